@@ -0,0 +1,43 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "grpc")]
+    grpc();
+}
+
+/// Server side of `proto/pumpfun.proto`, over the hand-written messages in `src/grpc.rs`
+#[cfg(feature = "grpc")]
+fn grpc() {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    let method = |name: &str, route: &str, input: &str, output: &str| {
+        Method::builder()
+            .name(name)
+            .route_name(route)
+            .input_type(format!("crate::grpc::{}", input))
+            .output_type(format!("crate::grpc::{}", output))
+            .codec_path("tonic_prost::ProstCodec")
+            .server_streaming()
+            .build()
+    };
+    let service = Service::builder()
+        .name("Pumpfun")
+        .package("pumpfun.v1")
+        .method(method(
+            "subscribe_tokens",
+            "SubscribeTokens",
+            "SubscribeTokensRequest",
+            "TokenEvent",
+        ))
+        .method(method(
+            "subscribe_trades",
+            "SubscribeTrades",
+            "SubscribeTradesRequest",
+            "TradeEvent",
+        ))
+        .build();
+
+    Builder::new()
+        .build_client(false)
+        .build_transport(false)
+        .compile(&[service]);
+}
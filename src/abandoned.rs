@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, info_span, warn, Instrument};
+
+use crate::config::AbandonedConfig;
+use crate::models::{Token, TokenSource};
+use crate::processor::Processor;
+
+/// Publishes `TokenAbandoned` and stops tracking pump.fun tokens still on their
+/// bonding curve that have not traded for `idle_secs` (counted from discovery
+/// for tokens that never traded).
+pub struct AbandonedSweeper {
+    processor: Processor,
+    interval: Duration,
+    idle: chrono::Duration,
+}
+
+impl AbandonedSweeper {
+    /// Start the sweep task
+    pub fn spawn(processor: Processor, config: &AbandonedConfig) {
+        let sweeper = Self {
+            processor,
+            interval: Duration::from_secs(config.interval_secs.max(1)),
+            idle: chrono::Duration::seconds(config.idle_secs as i64),
+        };
+        tokio::spawn(sweeper.run());
+    }
+
+    async fn run(self) {
+        let mut ticker = tokio::time::interval(self.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // The first tick fires right away, nothing is tracked yet
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            self.sweep().instrument(info_span!("abandoned_sweep")).await;
+        }
+    }
+
+    async fn sweep(&self) {
+        let now = Utc::now();
+        let bonding: Vec<Token> = self
+            .processor
+            .tracked_tokens()
+            .into_iter()
+            .filter(is_bonding)
+            .collect();
+        debug!("Checking {} bonding tokens for trades", bonding.len());
+
+        for token in bonding {
+            let last_trade_at = self.processor.last_trade(&token.mint_address).await;
+            let active_at = last_trade_at.unwrap_or(token.discovered_at);
+            if now - active_at < self.idle {
+                continue;
+            }
+            let mint = token.mint_address.clone();
+            if let Err(e) = self
+                .processor
+                .process_token_abandoned(token, last_trade_at)
+                .await
+            {
+                warn!("Failed to publish abandonment of {}: {:#}", mint, e);
+            }
+        }
+    }
+}
+
+/// Still trading (or not) on its pump.fun bonding curve
+fn is_bonding(token: &Token) -> bool {
+    token.source == TokenSource::Pumpfun
+        && token.pump_fun_bonding_curve.is_some()
+        && token.pumpswap_pool.is_none()
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, TimeZone, Utc};
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, warn};
+
+use crate::config::CandlesConfig;
+use crate::metrics;
+use crate::models::{Candle, Trade};
+use crate::processor::Processor;
+
+/// Closed candles are kept this long so late trades can be told apart from new ones
+const KEEP_CLOSED: chrono::Duration = chrono::Duration::minutes(5);
+
+/// Builds OHLCV candles of every configured length from the trades of tracked
+/// tokens. A candle is published once its period ended `close_delay_secs` ago;
+/// periods without trades have no candle.
+pub struct CandleAggregator {
+    intervals: Vec<u64>,
+    close_delay: chrono::Duration,
+    /// The latest candle per (mint, interval) and whether it was published
+    series: Mutex<HashMap<(String, u64), (Candle, bool)>>,
+}
+
+impl CandleAggregator {
+    pub fn new(config: &CandlesConfig) -> Self {
+        Self {
+            intervals: config.intervals_secs.clone(),
+            close_delay: chrono::Duration::seconds(config.close_delay_secs as i64),
+            series: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Add a trade priced at `price_sol`, returning the candles it closed early
+    pub fn add(
+        &self,
+        trade: &Trade,
+        price_sol: &BigDecimal,
+        volume_sol: &BigDecimal,
+    ) -> Vec<Candle> {
+        let mut series = self.series.lock().expect("candle lock poisoned");
+        let mut closed = Vec::new();
+        for &interval in &self.intervals {
+            let open_time = bucket(trade.at, interval);
+            let key = (trade.mint.clone(), interval);
+            match series.get_mut(&key) {
+                Some((candle, published))
+                    if candle.open_time > open_time
+                        || (candle.open_time == open_time && *published) =>
+                {
+                    debug!(
+                        "Late trade of {} for the {}s candle at {}",
+                        trade.mint, interval, open_time
+                    );
+                    metrics::DROPPED_MESSAGES
+                        .with_label_values(&["aggregator", "late_trade"])
+                        .inc();
+                }
+                Some((candle, _)) if candle.open_time == open_time => {
+                    candle.add(trade, price_sol, volume_sol);
+                }
+                // First trade of the mint or of a new period
+                _ => {
+                    let candle = Candle::open(trade, interval, open_time, price_sol, volume_sol);
+                    if let Some((previous, false)) = series.insert(key, (candle, false)) {
+                        closed.push(previous);
+                    }
+                }
+            }
+        }
+        closed
+    }
+
+    /// Candles whose period ended at least `close_delay_secs` before `now`
+    pub fn close(&self, now: DateTime<Utc>) -> Vec<Candle> {
+        let mut series = self.series.lock().expect("candle lock poisoned");
+        let mut closed = Vec::new();
+        series.retain(|_, (candle, published)| {
+            let end = candle.open_time + chrono::Duration::seconds(candle.interval_secs as i64);
+            if !*published && end + self.close_delay <= now {
+                *published = true;
+                closed.push(candle.clone());
+            }
+            !*published || end + KEEP_CLOSED > now
+        });
+        closed
+    }
+
+    /// Publish due candles every second
+    pub fn spawn_flusher(processor: Processor) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_secs(1));
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = processor.flush_candles().await {
+                    warn!("Failed to publish candles: {:#}", e);
+                }
+            }
+        });
+    }
+}
+
+impl Candle {
+    fn open(
+        trade: &Trade,
+        interval_secs: u64,
+        open_time: DateTime<Utc>,
+        price_sol: &BigDecimal,
+        volume_sol: &BigDecimal,
+    ) -> Self {
+        Self {
+            mint: trade.mint.clone(),
+            interval_secs,
+            open_time,
+            open: price_sol.clone(),
+            high: price_sol.clone(),
+            low: price_sol.clone(),
+            close: price_sol.clone(),
+            volume_sol: volume_sol.clone(),
+            volume_tokens: u128::from(trade.token_amount),
+            buys: u32::from(trade.is_buy),
+            sells: u32::from(!trade.is_buy),
+        }
+    }
+
+    fn add(&mut self, trade: &Trade, price_sol: &BigDecimal, volume_sol: &BigDecimal) {
+        if *price_sol > self.high {
+            self.high = price_sol.clone();
+        }
+        if *price_sol < self.low {
+            self.low = price_sol.clone();
+        }
+        self.close = price_sol.clone();
+        self.volume_sol += volume_sol;
+        self.volume_tokens += u128::from(trade.token_amount);
+        if trade.is_buy {
+            self.buys += 1;
+        } else {
+            self.sells += 1;
+        }
+    }
+}
+
+/// Start of the `interval` long period containing `at`
+fn bucket(at: DateTime<Utc>, interval: u64) -> DateTime<Utc> {
+    let interval = interval.max(1) as i64;
+    let start = at.timestamp().div_euclid(interval) * interval;
+    Utc.timestamp_opt(start, 0).single().unwrap_or(at)
+}
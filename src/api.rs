@@ -0,0 +1,291 @@
+use anyhow::{Context, Result};
+use axum::extract::{Path, Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::Response;
+use axum::routing::{get, post, put};
+use axum::{Json, Router};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use tracing::info;
+
+use crate::blacklist::CreatorBlacklist;
+
+use crate::config::{ApiConfig, FilterConfig};
+use crate::health::{HealthChecker, HealthReport};
+use crate::helius::{EnhancedTransaction, HeliusWebhook};
+use crate::metrics;
+use crate::processor::Processor;
+use crate::papertrade::PaperReport;
+use crate::positions::PositionsReport;
+use crate::trending::Mover;
+use crate::supervisor::{ListenerControls, ListenerStatus, StatusMap};
+
+#[derive(Clone)]
+struct AppState {
+    listener_status: StatusMap,
+    controls: ListenerControls,
+    health: Arc<HealthChecker>,
+    blacklist: Option<Arc<CreatorBlacklist>>,
+    helius: Option<Arc<HeliusWebhook>>,
+    processor: Processor,
+}
+
+pub async fn serve(
+    config: ApiConfig,
+    listener_status: StatusMap,
+    controls: ListenerControls,
+    health: Arc<HealthChecker>,
+    blacklist: Option<Arc<CreatorBlacklist>>,
+    helius: Option<Arc<HeliusWebhook>>,
+    processor: Processor,
+) -> Result<()> {
+    let mut app = Router::new()
+        .route("/metrics", get(metrics_handler))
+        .route("/status", get(status_handler))
+        .route("/trending", get(trending_handler))
+        .route("/papertrade", get(papertrade_handler))
+        .route("/positions", get(positions_handler))
+        .route("/healthz", get(healthz_handler))
+        .route("/readyz", get(readyz_handler))
+        .route("/webhooks/helius", post(helius_webhook_handler));
+
+    match config.admin_token.clone().filter(|token| !token.is_empty()) {
+        Some(token) => {
+            let admin = Router::new()
+                .route("/blacklist", get(blacklist_list_handler))
+                .route(
+                    "/blacklist/{wallet}",
+                    put(blacklist_flag_handler).delete(blacklist_unflag_handler),
+                )
+                .route("/listeners/{name}/pause", post(listener_pause_handler))
+                .route("/listeners/{name}/resume", post(listener_resume_handler))
+                .route(
+                    "/filters",
+                    get(filters_get_handler).patch(filters_patch_handler),
+                )
+                .route_layer(middleware::from_fn_with_state(Arc::new(token), require_admin));
+            app = app.nest("/admin", admin);
+        }
+        None => info!("api.admin_token not set, /admin routes disabled"),
+    }
+
+    let app = app.with_state(AppState {
+        listener_status,
+        controls,
+        health,
+        blacklist,
+        helius,
+        processor,
+    });
+
+    let addr = format!("{}:{}", config.host, config.port);
+    let listener = tokio::net::TcpListener::bind(&addr)
+        .await
+        .with_context(|| format!("Failed to bind API server on {}", addr))?;
+
+    info!("API server listening on {}", addr);
+    axum::serve(listener, app)
+        .await
+        .context("API server stopped")
+}
+
+async fn metrics_handler() -> String {
+    metrics::gather()
+}
+
+async fn status_handler(
+    State(state): State<AppState>,
+) -> Json<HashMap<&'static str, ListenerStatus>> {
+    Json(state.listener_status.read().await.clone())
+}
+
+async fn trending_handler(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<Mover>>, (StatusCode, String)> {
+    state.processor.top_movers().map(Json).ok_or((
+        StatusCode::NOT_FOUND,
+        "trending.enabled is off".to_string(),
+    ))
+}
+
+async fn papertrade_handler(
+    State(state): State<AppState>,
+) -> Result<Json<PaperReport>, (StatusCode, String)> {
+    state.processor.paper_report().map(Json).ok_or((
+        StatusCode::NOT_FOUND,
+        "papertrade.enabled is off".to_string(),
+    ))
+}
+
+async fn positions_handler(
+    State(state): State<AppState>,
+) -> Result<Json<PositionsReport>, (StatusCode, String)> {
+    state.processor.positions_report().map(Json).ok_or((
+        StatusCode::NOT_FOUND,
+        "execution.enabled is off".to_string(),
+    ))
+}
+
+async fn helius_webhook_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(transactions): Json<Vec<EnhancedTransaction>>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let helius = state.helius.as_ref().ok_or((
+        StatusCode::NOT_FOUND,
+        "helius.enabled is off".to_string(),
+    ))?;
+    let authorization = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    if !helius.authorized(authorization) {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid authorization".to_string()));
+    }
+    helius.ingest(transactions).await;
+    Ok(StatusCode::OK)
+}
+
+async fn healthz_handler(State(state): State<AppState>) -> (StatusCode, Json<HealthReport>) {
+    health_response(state.health.liveness().await)
+}
+
+async fn readyz_handler(State(state): State<AppState>) -> (StatusCode, Json<HealthReport>) {
+    health_response(state.health.readiness().await)
+}
+
+/// Reject admin requests without `Authorization: Bearer <api.admin_token>`
+async fn require_admin(
+    State(token): State<Arc<String>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|given| bool::from(given.as_bytes().ct_eq(token.as_bytes())));
+    if !authorized {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    Ok(next.run(request).await)
+}
+
+fn blacklist_of(state: &AppState) -> Result<&CreatorBlacklist, (StatusCode, String)> {
+    state.blacklist.as_deref().ok_or((
+        StatusCode::NOT_FOUND,
+        "blacklist.enabled is off".to_string(),
+    ))
+}
+
+fn internal_error(error: anyhow::Error) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", error))
+}
+
+async fn blacklist_list_handler(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<String>>, (StatusCode, String)> {
+    let members = blacklist_of(&state)?.members().await.map_err(internal_error)?;
+    Ok(Json(members))
+}
+
+async fn blacklist_flag_handler(
+    State(state): State<AppState>,
+    Path(wallet): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if Pubkey::from_str(&wallet).is_err() {
+        return Err((StatusCode::BAD_REQUEST, format!("{} is not a wallet address", wallet)));
+    }
+    let added = blacklist_of(&state)?
+        .flag(&wallet, "api")
+        .await
+        .map_err(internal_error)?;
+    if added {
+        info!("Blacklisted creator {} via the admin API", wallet);
+    }
+    Ok(if added { StatusCode::CREATED } else { StatusCode::OK })
+}
+
+async fn blacklist_unflag_handler(
+    State(state): State<AppState>,
+    Path(wallet): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let removed = blacklist_of(&state)?
+        .unflag(&wallet)
+        .await
+        .map_err(internal_error)?;
+    Ok(if removed { StatusCode::NO_CONTENT } else { StatusCode::NOT_FOUND })
+}
+
+async fn listener_pause_handler(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    set_paused(&state, &name, true)
+}
+
+async fn listener_resume_handler(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    set_paused(&state, &name, false)
+}
+
+/// 204 whether or not the listener was already in that state
+fn set_paused(
+    state: &AppState,
+    name: &str,
+    paused: bool,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let changed = state
+        .controls
+        .set_paused(name, paused)
+        .ok_or((StatusCode::NOT_FOUND, format!("No {} listener", name)))?;
+    if changed {
+        info!(
+            "{} the {} listener via the admin API",
+            if paused { "Pausing" } else { "Resuming" },
+            name
+        );
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn filters_get_handler(State(state): State<AppState>) -> Json<FilterConfig> {
+    Json(state.processor.filters())
+}
+
+/// Fields in the body replace the current ones, the others are kept
+async fn filters_patch_handler(
+    State(state): State<AppState>,
+    Json(changes): Json<serde_json::Map<String, serde_json::Value>>,
+) -> Result<Json<FilterConfig>, (StatusCode, String)> {
+    let bad_request = |e: anyhow::Error| (StatusCode::BAD_REQUEST, format!("{:#}", e));
+
+    let mut filters =
+        serde_json::to_value(state.processor.filters()).map_err(|e| internal_error(e.into()))?;
+    if let Some(current) = filters.as_object_mut() {
+        current.extend(changes);
+    }
+    let filters: FilterConfig =
+        serde_json::from_value(filters).map_err(|e| bad_request(e.into()))?;
+    state
+        .processor
+        .set_filters(filters.clone())
+        .map_err(bad_request)?;
+    info!("Filters changed via the admin API: {:?}", filters);
+    Ok(Json(filters))
+}
+
+fn health_response(report: HealthReport) -> (StatusCode, Json<HealthReport>) {
+    let code = if report.healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (code, Json(report))
+}
@@ -0,0 +1,487 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use solana_sdk::signature::Signature;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tracing::{info, warn, Instrument};
+
+use crate::abandoned::AbandonedSweeper;
+use crate::aggregator::CandleAggregator;
+use crate::authority::AuthorityMonitor;
+use crate::lifecycle::Lifecycle;
+use crate::liquidity::LiquidityRefresher;
+use crate::reenrich::Reenricher;
+use crate::volume::VolumeTracker;
+use crate::blacklist::CreatorBlacklist;
+use crate::config::{Config, DatabaseBackend};
+use crate::confirmation::ConfirmationTracker;
+use crate::cursor::SlotCursor;
+use crate::dedup::SignatureDedup;
+use crate::dlq::DeadLetterQueue;
+use crate::fixtures::{FixtureRecorder, RecordedLog};
+use crate::health::HealthChecker;
+use crate::leader::LeaderElection;
+use crate::positions::PositionMonitor;
+use crate::rpc_pool::{RpcPool, RpcTransport};
+use crate::sharding::Shards;
+use crate::sqlite::SqliteStore;
+use crate::token_cache::TokenInfoCache;
+use crate::supervisor::Supervisor;
+use crate::{
+    api, config_watch, dexscreener, event_bus, execution, funding, grpc, health, helius, honeypot, housekeeping_util, liquidity, listeners, processor, scoring, selftest, wash,
+};
+
+/// Everything between the RPC endpoints and the sinks, shared by `run` and `replay`
+struct Pipeline {
+    dedup: Arc<SignatureDedup>,
+    leader: Option<Arc<LeaderElection>>,
+    shards: Option<Arc<Shards>>,
+    processor: processor::Processor,
+    queue: event_bus::LogQueue,
+    supervisor: Supervisor,
+}
+
+impl Pipeline {
+    async fn build(config: &Config, transport: &RpcTransport) -> Result<Self> {
+        // Shared RPC endpoints for all listeners and helpers
+        let rpc = Arc::new(
+            RpcPool::with_transport(&config.network, &config.rate_limit, transport)?
+                .with_budget(&config.rpc_budget),
+        );
+
+        dexscreener::init(&config.dexscreener, &config.negative_cache)?;
+        let dedup = Arc::new(SignatureDedup::new(&config.dedup, &config.database).await?);
+
+        let blacklist = if config.blacklist.enabled {
+            Some(Arc::new(
+                CreatorBlacklist::new(&config.blacklist, &config.database.redis_url).await?,
+            ))
+        } else {
+            None
+        };
+
+        let lifecycle = if config.lifecycle.enabled {
+            Some(Arc::new(
+                Lifecycle::new(&config.lifecycle, &config.database.redis_url).await?,
+            ))
+        } else {
+            None
+        };
+
+        // With a standby instance running, only the one holding the lease publishes
+        let leader = if config.leader.enabled {
+            let leader = Arc::new(
+                LeaderElection::new(&config.leader, &config.database.redis_url).await?,
+            );
+            leader.spawn();
+            Some(leader)
+        } else {
+            None
+        };
+
+        // Or every instance publishes, but each only enriches the mints of its shards
+        let shards = if config.sharding.enabled {
+            let shards = Arc::new(Shards::new(&config.sharding, &config.database.redis_url).await?);
+            shards.spawn();
+            Some(shards)
+        } else {
+            None
+        };
+
+        let token_cache = Arc::new(
+            TokenInfoCache::new(&config.token_cache, &config.database.redis_url).await?,
+        );
+
+        // Create processor
+        let processor = processor::Processor::new(config.clone())
+            .await?
+            .with_funding(funding::tracer(config, &rpc))
+            .with_honeypot(honeypot::simulator(config, &rpc))
+            .with_wash(wash::detector(config, &rpc))
+            .with_lp_checker(liquidity::lp_checker(config, &rpc))
+            .with_sniper(execution::sniper(config, &rpc)?)
+            .with_blacklist(blacklist)
+            .with_lifecycle(lifecycle)
+            .with_leader(leader.clone())
+            .with_shards(shards.clone())
+            .with_token_cache(Some(token_cache));
+
+        // Authorities left on tracked tokens can still be revoked or abused
+        if config.authority_monitor.enabled {
+            AuthorityMonitor::spawn(rpc.clone(), processor.clone(), &config.authority_monitor);
+        }
+
+        if config.candles.enabled {
+            CandleAggregator::spawn_flusher(processor.clone());
+        }
+
+        if config.volume.enabled {
+            VolumeTracker::spawn_publisher(processor.clone(), &config.volume);
+        }
+
+        if config.abandoned.enabled {
+            AbandonedSweeper::spawn(processor.clone(), &config.abandoned);
+        }
+
+        if config.execution.enabled {
+            PositionMonitor::spawn(processor.clone(), &config.positions);
+        }
+
+        if config.liquidity.refresh {
+            LiquidityRefresher::spawn(rpc.clone(), processor.clone(), &config.liquidity);
+        }
+
+        if config.reenrich.enabled {
+            Reenricher::spawn(rpc.clone(), processor.clone(), &config.reenrich);
+        }
+
+        // Listeners queue their logs here, workers process them off the stream tasks
+        let queue = event_bus::LogQueue::new(&config.ingestion);
+
+        // Register enabled listeners
+        let mut supervisor = Supervisor::new(&config.ingestion).with_rpc(rpc.clone());
+
+        if config.ingestion.track_pumpfun {
+            // Fast path: provisional tokens from `processed`, settled once their creation confirms
+            let confirmations = if config.ingestion.fast_path {
+                Some(ConfirmationTracker::spawn(
+                    rpc.clone(),
+                    processor.clone(),
+                    config.ingestion.fast_path_confirm_commitment()?,
+                    Duration::from_secs(config.ingestion.fast_path_confirm_timeout_secs),
+                ))
+            } else {
+                None
+            };
+
+            supervisor.register(Arc::new(listeners::pumpfun::PumpFunListener::new(
+                config.clone(),
+                processor.clone(),
+                rpc.clone(),
+                dedup.clone(),
+                queue.clone(),
+                confirmations,
+            )));
+        }
+
+        if config.ingestion.track_launchlab {
+            supervisor.register(Arc::new(listeners::launchlab::LaunchLabListener::new(
+                config.clone(),
+                processor.clone(),
+                rpc.clone(),
+                dedup.clone(),
+                queue.clone(),
+            )));
+        }
+
+        if config.ingestion.track_orca {
+            supervisor.register(Arc::new(listeners::orca::OrcaWhirlpoolListener::new(
+                config.clone(),
+                processor.clone(),
+                rpc.clone(),
+                dedup.clone(),
+                queue.clone(),
+            )));
+        }
+
+        if config.ingestion.track_meteora {
+            for program in [
+                listeners::meteora::MeteoraProgram::Dlmm,
+                listeners::meteora::MeteoraProgram::Dbc,
+            ] {
+                supervisor.register(Arc::new(listeners::meteora::MeteoraListener::new(
+                    program,
+                    config.clone(),
+                    processor.clone(),
+                    rpc.clone(),
+                    dedup.clone(),
+                    queue.clone(),
+                    )));
+            }
+        }
+
+        if config.watchlist.enabled && !config.watchlist.wallets.is_empty() {
+            supervisor.register(Arc::new(listeners::watchlist::WatchlistListener::new(
+                config.clone(),
+                processor.clone(),
+                rpc.clone(),
+                queue.clone(),
+            )?));
+        }
+
+        if config.ingestion.track_onchain {
+            for program in [
+                listeners::onchain::MintProgram::SplToken,
+                listeners::onchain::MintProgram::Token2022,
+            ] {
+                supervisor.register(Arc::new(listeners::onchain::OnChainMintListener::new(
+                    program,
+                    config.clone(),
+                    processor.clone(),
+                    rpc.clone(),
+                    dedup.clone(),
+                    queue.clone(),
+                    )));
+            }
+        }
+
+        if config.ingestion.shared_subscription {
+            if let Some(router) = listeners::router::LogRouter::new(
+                config.clone(),
+                rpc.clone(),
+                queue.clone(),
+                &supervisor.handlers(),
+            ) {
+                supervisor.register(Arc::new(router));
+            }
+        }
+
+        Ok(Self {
+            dedup,
+            leader,
+            shards,
+            processor,
+            queue,
+            supervisor,
+        })
+    }
+}
+
+/// Subscribe, process and publish until a shutdown signal
+pub async fn run(config: Config, recorder: Option<Arc<FixtureRecorder>>) -> Result<()> {
+    housekeeping_util::spawn_log_cleaner(config.logging.clone());
+    info!("Starting Ingestion Service");
+    if config.self_test.enabled {
+        selftest::run(&config).await?;
+    }
+
+    let transport = match &recorder {
+        Some(recorder) => RpcTransport::Record(recorder.clone()),
+        None => RpcTransport::Http,
+    };
+    let Pipeline {
+        dedup,
+        leader,
+        shards,
+        processor,
+        queue,
+        supervisor,
+    } = Pipeline::build(&config, &transport).await?;
+
+    // Filter and notifier changes apply without a restart
+    if config.ingestion.hot_reload {
+        if let Err(e) = config_watch::spawn(config.clone(), processor.clone()) {
+            warn!("Config hot-reload disabled: {:#}", e);
+        }
+    }
+
+    let listener_status = supervisor.status();
+    let controls = supervisor.controls();
+    let health = Arc::new(HealthChecker::new(&config, listener_status.clone()).await?);
+    let blacklist = processor.blacklist();
+    // Pump.fun transactions pushed by Helius take the place of the WSS subscription
+    let helius = helius::webhook(&config, queue.clone()).await?;
+    // A dry run must not move the cursor of the production instance
+    let cursor = Arc::new(if config.dry_run {
+        SlotCursor::in_memory()
+    } else if config.database.backend == DatabaseBackend::Sqlite {
+        SlotCursor::sqlite(SqliteStore::open(&config.database.sqlite_path)?).await?
+    } else {
+        SlotCursor::load(&config.ingestion.cursor_path)?
+    });
+
+    // Lets consumers tell a quiet market from a dead feed
+    if config.events.heartbeat_secs > 0 {
+        health::spawn_heartbeat(
+            processor.clone(),
+            listener_status.clone(),
+            cursor.clone(),
+            Duration::from_secs(config.events.heartbeat_secs),
+        );
+    }
+
+    // Logs that fail processing are parked in Redis and retried in the background
+    let dlq = if config.dlq.enabled {
+        let dlq = Arc::new(
+            DeadLetterQueue::new(&config.dlq, &config.database.redis_url)
+                .await?
+                .with_timeout(config.ingestion.log_timeout()),
+        );
+        tokio::spawn(dlq.clone().run_retrier(supervisor.handlers(), dedup.clone()));
+        Some(dlq)
+    } else {
+        None
+    };
+
+    let mut workers = tokio::spawn(event_bus::run_workers(
+        queue.clone(),
+        supervisor.handlers(),
+        config.ingestion.workers,
+        config.ingestion.log_timeout(),
+        cursor.clone(),
+        dlq,
+        recorder,
+    ));
+
+    // Run  in parallel
+    tokio::select! {
+        result = supervisor.run() => {
+            tracing::error!("Listener supervisor stopped: {:?}", result);
+        }
+        _ = &mut workers => {
+            tracing::error!("Log workers stopped");
+        }
+        result = api::serve(
+            config.api.clone(),
+            listener_status,
+            controls,
+            health,
+            blacklist,
+            helius,
+            processor.clone(),
+        ) => {
+            tracing::error!("API server stopped: {:?}", result);
+        }
+        result = grpc::serve(config.grpc.clone(), processor.broadcast()) => {
+            tracing::error!("gRPC server stopped: {:?}", result);
+        }
+        _ = shutdown_signal() => {
+            info!("Shutdown signal received, draining");
+        }
+    }
+
+    // Subscriptions are gone with the supervisor; finish what was already received
+    let drain_timeout = Duration::from_secs(config.ingestion.shutdown_timeout_secs);
+    let deadline = tokio::time::Instant::now() + drain_timeout;
+
+    queue.close();
+    if tokio::time::timeout_at(deadline, workers).await.is_err() {
+        tracing::warn!("Log workers still busy after {:?}, abandoning the rest", drain_timeout);
+    }
+    let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+    if !processor.flush(remaining).await {
+        tracing::warn!("Sinks still had undelivered events at shutdown");
+    }
+    if let Err(e) = cursor.persist().await {
+        tracing::error!("Failed to save slot cursor: {:#}", e);
+    }
+    if let Some(leader) = leader {
+        leader.resign().await;
+    }
+    if let Some(shards) = shards {
+        shards.release().await;
+    }
+
+    info!("Ingestion Service stopped");
+    Ok(())
+}
+
+/// Resolves on Ctrl-C or, on Unix, SIGTERM (what systemd and Kubernetes send)
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+/// Run the pump.fun parser on a single transaction without publishing anything
+pub async fn inspect(config: &Config, signature: &str) -> Result<()> {
+    let signature: Signature = signature.parse().context("Invalid transaction signature")?;
+    let rpc = RpcPool::new(&config.network, &config.rate_limit)?;
+    dexscreener::init(&config.dexscreener, &config.negative_cache)?;
+
+    let token =
+        listeners::pumpfun::parse_creation(&rpc, config.programs.pump_fun_program(), &signature).await?;
+    let Some(mut token) = token else {
+        anyhow::bail!("{} is not a pump.fun token creation (or could not be fetched)", signature);
+    };
+    scoring::apply(&mut token);
+
+    println!("{}", serde_json::to_string_pretty(&token)?);
+    Ok(())
+}
+
+/// Hand every recorded log to the listener that received it, in file order,
+/// then wait for the sinks to deliver what was published
+pub async fn replay(config: &Config, file: &Path, transport: &RpcTransport) -> Result<()> {
+    // A one-off replay publishes and enriches everything, whatever other instances
+    // do, but never buys recorded tokens
+    let mut config = config.clone();
+    config.leader.enabled = false;
+    config.sharding.enabled = false;
+    config.execution.enabled = false;
+    let config = &config;
+    let pipeline = Pipeline::build(config, transport).await?;
+    let handlers = pipeline.supervisor.handlers();
+    let flush_timeout = Duration::from_secs(config.ingestion.shutdown_timeout_secs);
+
+    let input = tokio::fs::File::open(file)
+        .await
+        .with_context(|| format!("Failed to open {}", file.display()))?;
+    let mut lines = BufReader::new(input).lines();
+
+    let (mut replayed, mut failed) = (0usize, 0usize);
+    let mut line_no = 0usize;
+    while let Some(line) = lines.next_line().await? {
+        line_no += 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let recorded: RecordedLog = serde_json::from_str(&line)
+            .with_context(|| format!("{}:{}: not a recorded log", file.display(), line_no))?;
+        let Some(listener) = handlers.get(recorded.listener.as_str()) else {
+            warn!(
+                "{}:{}: listener {} is not enabled, skipping",
+                file.display(),
+                line_no,
+                recorded.listener
+            );
+            continue;
+        };
+
+        replayed += 1;
+        let span = event_bus::log_span(listener.name(), recorded.slot, &recorded.log.signature);
+        let handling = event_bus::handle_log(
+            listener.as_ref(),
+            recorded.slot,
+            &recorded.log,
+            config.ingestion.log_timeout(),
+        )
+        .instrument(span);
+        if let Err(e) = handling.await {
+            failed += 1;
+            warn!("{}:{} (slot {}): {:#}", file.display(), line_no, recorded.slot, e);
+        }
+    }
+
+    if !pipeline.processor.flush(flush_timeout).await {
+        warn!("Sinks still had undelivered events after {:?}", flush_timeout);
+    }
+    info!("Replayed {} logs from {} ({} failed)", replayed, file.display(), failed);
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use bigdecimal::BigDecimal;
+use solana_sdk::pubkey::Pubkey;
+use spl_token_2022::extension::StateWithExtensions;
+use spl_token_2022::state::AccountState;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, info, info_span, warn, Instrument};
+
+use crate::config::AuthorityMonitorConfig;
+use crate::models::Token;
+use crate::processor::Processor;
+use crate::rpc_pool::RpcPool;
+use crate::{metrics, scoring, token_helper};
+
+/// Re-reads the mints of tracked tokens that still have a mint or freeze
+/// authority, publishing `TokenUpdated` with a new score when an authority is
+/// revoked, supply is minted or top holders get frozen.
+pub struct AuthorityMonitor {
+    rpc: Arc<RpcPool>,
+    processor: Processor,
+    interval: Duration,
+}
+
+impl AuthorityMonitor {
+    /// Start the re-check task
+    pub fn spawn(rpc: Arc<RpcPool>, processor: Processor, config: &AuthorityMonitorConfig) {
+        let monitor = Self {
+            rpc,
+            processor,
+            interval: Duration::from_secs(config.interval_secs.max(1)),
+        };
+        tokio::spawn(monitor.run());
+    }
+
+    async fn run(self) {
+        let mut ticker = tokio::time::interval(self.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // The first tick fires right away, nothing is tracked yet
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self
+                .check_all()
+                .instrument(info_span!("authority_monitor"))
+                .await
+            {
+                warn!("Authority re-check failed: {:#}", e);
+            }
+        }
+    }
+
+    async fn check_all(&self) -> Result<()> {
+        // Once both authorities are gone nothing can change anymore
+        let watched: Vec<(Token, Pubkey)> = self
+            .processor
+            .tracked_tokens()
+            .into_iter()
+            .filter(|token| !token.mint_authority_disabled || !token.freeze_authority_disabled)
+            .filter_map(|token| {
+                let mint = Pubkey::from_str(&token.mint_address).ok()?;
+                Some((token, mint))
+            })
+            .collect();
+        debug!("Re-checking authorities of {} tokens", watched.len());
+
+        let keys: Vec<Pubkey> = watched.iter().map(|(_, mint)| *mint).collect();
+        let accounts = token_helper::load_accounts(&self.rpc, &keys).await?;
+        for ((before, mint), account) in watched.iter().zip(accounts) {
+            let Some(mint_data) =
+                account.and_then(|account| token_helper::parse_mint_account(mint, &account))
+            else {
+                continue;
+            };
+
+            let frozen_holders = if mint_data.freeze_authority.is_some() {
+                match self.frozen_holders(mint).await {
+                    Ok(frozen) => Some(frozen),
+                    Err(e) => {
+                        warn!("Failed to check frozen holders of {}: {:#}", mint, e);
+                        before.frozen_holders
+                    }
+                }
+            } else {
+                before.frozen_holders
+            };
+
+            let mut after = before.clone();
+            after.mint_authority_disabled = mint_data.mint_authority.is_none();
+            after.freeze_authority_disabled = mint_data.freeze_authority.is_none();
+            after.total_supply = BigDecimal::from(mint_data.supply);
+            after.frozen_holders = frozen_holders;
+
+            let changes = changes(before, &after);
+            if changes.is_empty() {
+                continue;
+            }
+            for change in &changes {
+                metrics::AUTHORITY_CHANGES
+                    .with_label_values(&[change])
+                    .inc();
+            }
+            info!("Authorities of {} changed: {}", mint, changes.join(", "));
+
+            // Apply to the latest state, other tasks may have updated the token meanwhile
+            let Some(mut token) = self.processor.tracked_token(&before.mint_address).await else {
+                continue;
+            };
+            token.mint_authority_disabled = after.mint_authority_disabled;
+            token.freeze_authority_disabled = after.freeze_authority_disabled;
+            token.total_supply = after.total_supply;
+            token.frozen_holders = after.frozen_holders;
+            scoring::apply(&mut token);
+            if let Err(e) = self.processor.process_token_updated(token).await {
+                warn!("Failed to publish authority change of {}: {:#}", mint, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Frozen accounts among the largest holders of `mint`
+    async fn frozen_holders(&self, mint: &Pubkey) -> Result<u32> {
+        let largest = self
+            .rpc
+            .call("getTokenLargestAccounts", |client| async move {
+                client.get_token_largest_accounts(mint).await
+            })
+            .await
+            .context("Failed to list token holders")?;
+        let keys: Vec<Pubkey> = largest
+            .iter()
+            .filter_map(|holder| Pubkey::from_str(&holder.address).ok())
+            .collect();
+
+        let frozen = token_helper::load_accounts(&self.rpc, &keys)
+            .await?
+            .into_iter()
+            .flatten()
+            .filter(|account| {
+                StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+                    .is_ok_and(|state| state.base.state == AccountState::Frozen)
+            })
+            .count();
+        Ok(frozen as u32)
+    }
+}
+
+/// Metric labels of what changed between two reads of a token
+fn changes(before: &Token, after: &Token) -> Vec<&'static str> {
+    let mut changes = Vec::new();
+    if !before.mint_authority_disabled && after.mint_authority_disabled {
+        changes.push("mint_revoked");
+    }
+    if !before.freeze_authority_disabled && after.freeze_authority_disabled {
+        changes.push("freeze_revoked");
+    }
+    if after.total_supply > before.total_supply {
+        changes.push("supply_minted");
+    }
+    if after.frozen_holders.unwrap_or(0) > before.frozen_holders.unwrap_or(0) {
+        changes.push("holders_frozen");
+    }
+    changes
+}
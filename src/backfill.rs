@@ -0,0 +1,200 @@
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+
+use crate::config::Config;
+use crate::listener_helpers;
+use crate::listeners::pumpfun;
+use crate::models::Token;
+use crate::processor::Processor;
+use crate::rpc_pool::RpcPool;
+use crate::scoring;
+
+/// `getSignaturesForAddress` page size (the RPC maximum)
+const PAGE_SIZE: usize = 1000;
+
+/// Which history to backfill; every bound is inclusive and optional,
+/// but at least one lower bound is needed to know where to stop
+#[derive(Debug, Clone)]
+pub struct BackfillRange {
+    pub from_slot: Option<u64>,
+    pub to_slot: Option<u64>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl BackfillRange {
+    /// The signature is older than the range, so every following page is too
+    fn is_before(&self, status: &RpcConfirmedTransactionStatusWithSignature) -> bool {
+        self.from_slot.is_some_and(|from| status.slot < from)
+            || self
+                .since
+                .zip(status.block_time)
+                .is_some_and(|(since, t)| t < since.timestamp())
+    }
+
+    fn contains(&self, status: &RpcConfirmedTransactionStatusWithSignature) -> bool {
+        !self.is_before(status)
+            && self.to_slot.is_none_or(|to| status.slot <= to)
+            && self
+                .until
+                .zip(status.block_time)
+                .is_none_or(|(until, t)| t <= until.timestamp())
+    }
+}
+
+/// Where backfilled tokens go
+pub enum BackfillOutput {
+    /// Through the processor: filters, scoring, notifiers and every sink
+    Publish(Box<Processor>),
+    /// Appended to an NDJSON file, nothing is published
+    File(tokio::fs::File),
+}
+
+impl BackfillOutput {
+    /// Append to `path`, creating it if needed
+    pub async fn file(path: &Path) -> Result<Self> {
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        Ok(Self::File(file))
+    }
+
+    async fn emit(&mut self, mut token: Token) -> Result<()> {
+        match self {
+            Self::Publish(processor) => processor.process_token_discovered(token).await,
+            Self::File(file) => {
+                scoring::apply(&mut token);
+                let line = format!("{}\n", serde_json::to_string(&token)?);
+                file.write_all(line.as_bytes()).await?;
+                Ok(())
+            }
+        }
+    }
+
+    async fn finish(&mut self, timeout: Duration) -> Result<()> {
+        match self {
+            Self::Publish(processor) => {
+                if !processor.flush(timeout).await {
+                    warn!("Sinks still had undelivered events after {:?}", timeout);
+                }
+            }
+            Self::File(file) => file.flush().await?,
+        }
+        Ok(())
+    }
+}
+
+/// Page through the pump.fun program's signatures from newest to oldest and
+/// run every create transaction in `range` through the regular parser
+pub async fn run(config: &Config, range: BackfillRange, mut output: BackfillOutput) -> Result<()> {
+    if range.from_slot.is_none() && range.since.is_none() {
+        anyhow::bail!("backfill needs --from-slot or --since to know where to stop");
+    }
+
+    let rpc = RpcPool::new(&config.network, &config.rate_limit)?.with_budget(&config.rpc_budget);
+    let program_id = config.programs.pump_fun_program();
+    let program = Pubkey::from_str(program_id).context("Invalid programs.pump_fun")?;
+    let commitment = rpc.commitment();
+
+    let mut before: Option<Signature> = None;
+    let (mut scanned, mut found) = (0usize, 0usize);
+
+    loop {
+        let page = rpc
+            .call("getSignaturesForAddress", |client| async move {
+                client
+                    .get_signatures_for_address_with_config(
+                        &program,
+                        GetConfirmedSignaturesForAddress2Config {
+                            before,
+                            until: None,
+                            limit: Some(PAGE_SIZE),
+                            commitment: Some(commitment),
+                        },
+                    )
+                    .await
+            })
+            .await
+            .context("Failed to page pump.fun signatures")?;
+
+        let Some(last) = page.last() else {
+            break;
+        };
+        before = Some(last.signature.parse().context("Invalid signature in page")?);
+        let reached_start = page.iter().any(|status| range.is_before(status));
+
+        let signatures: Vec<Signature> = page
+            .iter()
+            .filter(|status| status.err.is_none() && range.contains(status))
+            .filter_map(|status| status.signature.parse().ok())
+            .collect();
+        scanned += signatures.len();
+
+        // Transactions are fetched in parallel, tokens are emitted in page order
+        let tokens: Vec<Option<Token>> = stream::iter(signatures)
+            .map(|sig| {
+                let rpc = &rpc;
+                async move {
+                    match parse_signature(rpc, program_id, &sig).await {
+                        Ok(token) => token,
+                        Err(e) => {
+                            warn!("Backfill skipped {}: {:#}", sig, e);
+                            None
+                        }
+                    }
+                }
+            })
+            .buffered(config.ingestion.workers.max(1))
+            .collect()
+            .await;
+
+        for token in tokens.into_iter().flatten() {
+            found += 1;
+            output.emit(token).await?;
+        }
+
+        info!(
+            "Backfill: {} transactions scanned, {} tokens found, now at slot {}",
+            scanned, found, last.slot
+        );
+        if reached_start || page.len() < PAGE_SIZE {
+            break;
+        }
+    }
+
+    output
+        .finish(Duration::from_secs(config.ingestion.shutdown_timeout_secs))
+        .await?;
+
+    info!("Backfill finished: {} tokens from {} transactions", found, scanned);
+    Ok(())
+}
+
+/// Token created by `sig`, `None` for buys, sells and anything else
+async fn parse_signature(
+    rpc: &RpcPool,
+    program_id: &str,
+    sig: &Signature,
+) -> Result<Option<Token>> {
+    let Some(tx) = listener_helpers::fetch_transaction_with_retry(rpc, sig).await? else {
+        return Ok(None);
+    };
+    if !pumpfun::is_create_transaction(&tx, program_id) {
+        return Ok(None);
+    }
+    pumpfun::parse_transaction(rpc, program_id, &tx).await
+}
@@ -0,0 +1,168 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use bigdecimal::{BigDecimal, FromPrimitive, Zero};
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+
+use crate::config::{BirdeyeConfig, NegativeCacheConfig, RateLimitConfig};
+use crate::metrics;
+use crate::models::Token;
+use crate::negative_cache::NegativeCache;
+use crate::outbound;
+use crate::rate_limit::RateLimiter;
+
+/// Significant digits kept in prices
+const PRICE_DIGITS: u64 = 10;
+const LAMPORTS_DECIMALS: i64 = 9;
+
+/// What `/defi/token_overview` knows about a token
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenOverview {
+    /// USD
+    #[serde(default)]
+    pub price: Option<f64>,
+    /// USD
+    #[serde(default)]
+    pub liquidity: Option<f64>,
+    #[serde(default, alias = "mc")]
+    pub market_cap: Option<f64>,
+    #[serde(default)]
+    pub holder: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    #[serde(default)]
+    success: bool,
+    #[serde(default)]
+    data: Option<TokenOverview>,
+}
+
+impl TokenOverview {
+    /// Set the USD price, market cap and holder count of `token`, and its SOL
+    /// price and liquidity when `sol_usd` is known
+    pub fn apply(&self, token: &mut Token, sol_usd: Option<&BigDecimal>) {
+        let decimal = |value: Option<f64>| value.and_then(BigDecimal::from_f64);
+        if let Some(price_usd) = decimal(self.price) {
+            token.price_usd = Some(price_usd.with_prec(PRICE_DIGITS));
+        }
+        if let Some(market_cap_usd) = decimal(self.market_cap) {
+            token.market_cap_usd = Some(market_cap_usd.round(2));
+        }
+        if self.holder.is_some() {
+            token.holder_count = self.holder;
+        }
+
+        let Some(sol_usd) = sol_usd.filter(|sol_usd| !sol_usd.is_zero()) else {
+            return;
+        };
+        if let Some(price_usd) = decimal(self.price) {
+            token.price_sol = Some((price_usd / sol_usd).with_prec(PRICE_DIGITS));
+        }
+        if let Some(liquidity_usd) = decimal(self.liquidity) {
+            token.liquidity_sol = Some((liquidity_usd / sol_usd).round(LAMPORTS_DECIMALS));
+        }
+    }
+}
+
+/// Birdeye API client for tokens `delay_secs` old, held under
+/// `requests_per_sec`
+pub struct Birdeye {
+    http: Client,
+    url: String,
+    api_key: String,
+    delay: Duration,
+    limiter: RateLimiter,
+    misses: NegativeCache,
+}
+
+impl Birdeye {
+    pub fn new(config: &BirdeyeConfig, negative: &NegativeCacheConfig) -> Result<Self> {
+        let http = outbound::client_builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .context("Failed to build Birdeye HTTP client")?;
+        let host = reqwest::Url::parse(&config.url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_else(|| config.url.clone());
+        let limiter = RateLimiter::new(
+            &host,
+            &RateLimitConfig {
+                max_requests_per_sec: config.requests_per_sec,
+                min_requests_per_sec: config.requests_per_sec / 10.0,
+                ..RateLimitConfig::default()
+            },
+        );
+        Ok(Self {
+            http,
+            url: config.url.trim_end_matches('/').to_string(),
+            api_key: config.api_key.clone().unwrap_or_default(),
+            delay: Duration::from_secs(config.delay_secs),
+            limiter,
+            misses: NegativeCache::new("birdeye", negative),
+        })
+    }
+
+    /// How long after discovery tokens are looked up
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// The overview of `mint`, `None` when Birdeye does not know it (yet)
+    pub async fn overview(&self, mint: &str) -> Result<Option<TokenOverview>> {
+        if self.misses.contains(mint) {
+            return Ok(None);
+        }
+        let result = self.fetch(mint).await;
+        let outcome = match &result {
+            Ok(Some(_)) => "ok",
+            Ok(None) => "unknown",
+            Err(_) => "error",
+        };
+        if outcome != "ok" {
+            self.misses.insert(mint).await;
+        }
+        metrics::BIRDEYE_REQUESTS
+            .with_label_values(&[outcome])
+            .inc();
+        result
+    }
+
+    async fn fetch(&self, mint: &str) -> Result<Option<TokenOverview>> {
+        self.limiter.acquire().await;
+        let response = self
+            .http
+            .get(format!("{}/defi/token_overview", self.url))
+            .query(&[("address", mint)])
+            .header("X-API-KEY", &self.api_key)
+            .header("x-chain", "solana")
+            .send()
+            .await
+            .context("Birdeye request failed")?;
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok())
+                .map(Duration::from_secs);
+            self.limiter.on_rate_limited(retry_after);
+        } else {
+            self.limiter.on_success();
+        }
+
+        let response: Response = response
+            .error_for_status()
+            .context("Birdeye request failed")?
+            .json()
+            .await
+            .context("Invalid Birdeye response")?;
+        Ok(response
+            .data
+            .filter(|overview| response.success && overview.price.is_some()))
+    }
+}
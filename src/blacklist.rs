@@ -0,0 +1,85 @@
+use anyhow::{Context, Result};
+use redis::aio::ConnectionManager;
+use tracing::{info, warn};
+
+use crate::config::BlacklistConfig;
+use crate::metrics;
+
+/// Redis set of creator wallets known to rug; tokens they launch are scored
+/// `RiskLevel::High` right away.
+///
+/// Wallets are added through the admin API or, with `auto_flag`, when the
+/// creator of a tracked token dumps a large share of its supply.
+pub struct CreatorBlacklist {
+    conn: ConnectionManager,
+    key: String,
+}
+
+impl CreatorBlacklist {
+    pub async fn new(config: &BlacklistConfig, redis_url: &str) -> Result<Self> {
+        let client = redis::Client::open(redis_url).context("Invalid redis_url for blacklist")?;
+        let conn = ConnectionManager::new(client)
+            .await
+            .context("Failed to connect to Redis for blacklist")?;
+        info!("Creator blacklist kept in Redis set {}", config.key);
+
+        Ok(Self {
+            conn,
+            key: config.key.clone(),
+        })
+    }
+
+    /// Whether `wallet` is flagged; a Redis failure counts as not flagged
+    pub async fn contains(&self, wallet: &str) -> bool {
+        let mut conn = self.conn.clone();
+        let flagged: redis::RedisResult<bool> = redis::cmd("SISMEMBER")
+            .arg(&self.key)
+            .arg(wallet)
+            .query_async(&mut conn)
+            .await;
+        match flagged {
+            Ok(flagged) => flagged,
+            Err(e) => {
+                warn!("Blacklist lookup of {} failed: {}", wallet, e);
+                false
+            }
+        }
+    }
+
+    /// Flag `wallet`; `origin` ("api" or "rug") labels the metric.
+    /// Returns `false` if it already was flagged.
+    pub async fn flag(&self, wallet: &str, origin: &str) -> Result<bool> {
+        let mut conn = self.conn.clone();
+        let added: u32 = redis::cmd("SADD")
+            .arg(&self.key)
+            .arg(wallet)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to add wallet to the blacklist")?;
+        if added > 0 {
+            metrics::CREATORS_BLACKLISTED.with_label_values(&[origin]).inc();
+        }
+        Ok(added > 0)
+    }
+
+    /// Remove `wallet`, returns `false` if it was not flagged
+    pub async fn unflag(&self, wallet: &str) -> Result<bool> {
+        let mut conn = self.conn.clone();
+        let removed: u32 = redis::cmd("SREM")
+            .arg(&self.key)
+            .arg(wallet)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to remove wallet from the blacklist")?;
+        Ok(removed > 0)
+    }
+
+    pub async fn members(&self) -> Result<Vec<String>> {
+        let mut conn = self.conn.clone();
+        redis::cmd("SMEMBERS")
+            .arg(&self.key)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to read the blacklist")
+    }
+}
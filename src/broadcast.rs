@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use tokio::sync::broadcast;
+
+use crate::envelope;
+use crate::models::{Envelope, Trade};
+
+/// Published events and decoded trades, fanned out to consumers inside this
+/// process (the gRPC server). Nothing is kept without subscribers, and a
+/// subscriber more than `capacity` messages behind misses the oldest ones.
+#[derive(Clone)]
+pub struct Broadcast {
+    events: broadcast::Sender<Arc<Envelope>>,
+    trades: broadcast::Sender<Arc<ObservedTrade>>,
+}
+
+/// A trade with the transaction it was decoded from
+#[derive(Debug, Clone)]
+pub struct ObservedTrade {
+    pub trade: Trade,
+    pub slot: Option<u64>,
+    pub signature: Option<String>,
+}
+
+impl Broadcast {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            events: broadcast::channel(capacity.max(1)).0,
+            trades: broadcast::channel(capacity.max(1)).0,
+        }
+    }
+
+    pub fn subscribe_events(&self) -> broadcast::Receiver<Arc<Envelope>> {
+        self.events.subscribe()
+    }
+
+    pub fn subscribe_trades(&self) -> broadcast::Receiver<Arc<ObservedTrade>> {
+        self.trades.subscribe()
+    }
+
+    pub(crate) fn send_event(&self, envelope: &Envelope) {
+        if self.events.receiver_count() > 0 {
+            let _ = self.events.send(Arc::new(envelope.clone()));
+        }
+    }
+
+    pub(crate) fn send_trade(&self, trade: &Trade) {
+        if self.trades.receiver_count() > 0 {
+            let (slot, signature) = envelope::origin();
+            let _ = self.trades.send(Arc::new(ObservedTrade {
+                trade: trade.clone(),
+                slot,
+                signature,
+            }));
+        }
+    }
+}
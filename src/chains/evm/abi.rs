@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Context, Result};
+use bigdecimal::num_bigint::{BigInt, Sign};
+use sha3::{Digest, Keccak256};
+
+use super::EvmLog;
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+/// `topic0` of an event, e.g. `event_topic("Transfer(address,address,uint256)")`
+pub fn event_topic(signature: &str) -> String {
+    format!("0x{}", hex::encode(keccak256(signature.as_bytes())))
+}
+
+/// 4-byte selector of a function, e.g. `function_selector("decimals()")`
+pub fn function_selector(signature: &str) -> String {
+    format!("0x{}", hex::encode(&keccak256(signature.as_bytes())[..4]))
+}
+
+pub fn decode_hex(value: &str) -> Result<Vec<u8>> {
+    hex::decode(value.trim_start_matches("0x")).with_context(|| format!("Invalid hex {:?}", value))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbiType {
+    Address,
+    Uint,
+    Int,
+    Bool,
+    Bytes32,
+    String,
+    Bytes,
+}
+
+impl AbiType {
+    fn parse(name: &str) -> Result<Self> {
+        Ok(match name {
+            "address" => AbiType::Address,
+            "bool" => AbiType::Bool,
+            "bytes32" => AbiType::Bytes32,
+            "string" => AbiType::String,
+            "bytes" => AbiType::Bytes,
+            n if n.starts_with("uint") => AbiType::Uint,
+            n if n.starts_with("int") => AbiType::Int,
+            other => bail!("Unsupported ABI type {:?}", other),
+        })
+    }
+
+    fn is_dynamic(self) -> bool {
+        matches!(self, AbiType::String | AbiType::Bytes)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AbiValue {
+    Address(String),
+    Uint(BigInt),
+    Int(BigInt),
+    Bool(bool),
+    Bytes32([u8; 32]),
+    String(String),
+    Bytes(Vec<u8>),
+}
+
+impl AbiValue {
+    pub fn as_address(&self) -> Option<&str> {
+        match self {
+            AbiValue::Address(address) => Some(address),
+            _ => None,
+        }
+    }
+
+    pub fn as_uint(&self) -> Option<&BigInt> {
+        match self {
+            AbiValue::Uint(value) | AbiValue::Int(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EventParam {
+    pub name: String,
+    pub kind: AbiType,
+    pub indexed: bool,
+}
+
+/// An event definition parsed from its human readable form, e.g.
+/// `PairCreated(address indexed token0, address indexed token1, address pair, uint256)`
+#[derive(Debug, Clone)]
+pub struct EventAbi {
+    pub name: String,
+    pub inputs: Vec<EventParam>,
+    topic: String,
+}
+
+impl EventAbi {
+    pub fn parse(definition: &str) -> Result<Self> {
+        let definition = definition.trim().trim_start_matches("event ").trim();
+        let open = definition
+            .find('(')
+            .ok_or_else(|| anyhow!("Missing '(' in event {:?}", definition))?;
+        let body = definition[open + 1..]
+            .strip_suffix(')')
+            .ok_or_else(|| anyhow!("Missing ')' in event {:?}", definition))?;
+        let name = definition[..open].trim().to_string();
+
+        let mut inputs = Vec::new();
+        for (position, param) in body.split(',').map(str::trim).filter(|p| !p.is_empty()).enumerate() {
+            let parts: Vec<&str> = param.split_whitespace().collect();
+            let kind = AbiType::parse(parts[0])?;
+            let indexed = parts.contains(&"indexed");
+            let name = parts
+                .iter()
+                .skip(1)
+                .find(|p| **p != "indexed")
+                .map(|p| p.to_string())
+                .unwrap_or_else(|| format!("arg{}", position));
+            inputs.push(EventParam { name, kind, indexed });
+        }
+
+        let canonical: Vec<String> = body
+            .split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(|p| p.split_whitespace().next().unwrap_or_default().to_string())
+            .collect();
+        let topic = event_topic(&format!("{}({})", name, canonical.join(",")));
+
+        Ok(Self { name, inputs, topic })
+    }
+
+    /// `topic0` identifying this event in logs
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    pub fn matches(&self, log: &EvmLog) -> bool {
+        log.topics
+            .first()
+            .is_some_and(|t| t.eq_ignore_ascii_case(&self.topic))
+    }
+
+    /// Decode every parameter by name. Indexed dynamic values only carry their
+    /// hash and come back as `Bytes32`.
+    pub fn decode(&self, log: &EvmLog) -> Result<HashMap<String, AbiValue>> {
+        if !self.matches(log) {
+            bail!("Log is not a {} event", self.name);
+        }
+
+        let data = decode_hex(&log.data)?;
+        let mut topics = log.topics.iter().skip(1);
+        let mut head = 0usize;
+        let mut values = HashMap::with_capacity(self.inputs.len());
+
+        for param in &self.inputs {
+            let value = if param.indexed {
+                let topic = topics
+                    .next()
+                    .ok_or_else(|| anyhow!("{} log is missing topic for {}", self.name, param.name))?;
+                let word = to_word(&decode_hex(topic)?)?;
+                if param.kind.is_dynamic() {
+                    AbiValue::Bytes32(word)
+                } else {
+                    decode_word(param.kind, &word)
+                }
+            } else {
+                let word = to_word(word_at(&data, head)?)?;
+                head += 32;
+                if param.kind.is_dynamic() {
+                    decode_dynamic(param.kind, &data, &word)?
+                } else {
+                    decode_word(param.kind, &word)
+                }
+            };
+            values.insert(param.name.clone(), value);
+        }
+
+        Ok(values)
+    }
+}
+
+fn word_at(data: &[u8], offset: usize) -> Result<&[u8]> {
+    data.get(offset..offset + 32)
+        .ok_or_else(|| anyhow!("ABI data too short for word at {}", offset))
+}
+
+fn to_word(bytes: &[u8]) -> Result<[u8; 32]> {
+    bytes
+        .try_into()
+        .map_err(|_| anyhow!("Expected a 32 byte word, got {} bytes", bytes.len()))
+}
+
+fn decode_word(kind: AbiType, word: &[u8; 32]) -> AbiValue {
+    match kind {
+        AbiType::Address => AbiValue::Address(format!("0x{}", hex::encode(&word[12..]))),
+        AbiType::Uint => AbiValue::Uint(BigInt::from_bytes_be(Sign::Plus, word)),
+        AbiType::Int => AbiValue::Int(BigInt::from_signed_bytes_be(word)),
+        AbiType::Bool => AbiValue::Bool(word[31] != 0),
+        AbiType::Bytes32 | AbiType::String | AbiType::Bytes => AbiValue::Bytes32(*word),
+    }
+}
+
+fn decode_dynamic(kind: AbiType, data: &[u8], offset_word: &[u8; 32]) -> Result<AbiValue> {
+    let offset = word_to_usize(offset_word)?;
+    let length = word_to_usize(&to_word(word_at(data, offset)?)?)?;
+    let bytes = data
+        .get(offset + 32..offset + 32 + length)
+        .ok_or_else(|| anyhow!("ABI dynamic value out of bounds"))?
+        .to_vec();
+
+    Ok(match kind {
+        AbiType::String => AbiValue::String(String::from_utf8_lossy(&bytes).into_owned()),
+        _ => AbiValue::Bytes(bytes),
+    })
+}
+
+fn word_to_usize(word: &[u8; 32]) -> Result<usize> {
+    if word[..24].iter().any(|b| *b != 0) {
+        bail!("ABI offset does not fit in usize");
+    }
+    Ok(u64::from_be_bytes(word[24..].try_into()?) as usize)
+}
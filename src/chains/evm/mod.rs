@@ -0,0 +1,43 @@
+//! Shared plumbing for EVM chain listeners (BSC, Base): JSON-RPC providers with
+//! endpoint rotation, `eth_subscribe("logs")` with reconnects and ABI event
+//! decoding. This is the EVM counterpart of `listener_helpers`.
+
+// Nothing registers an EVM listener yet
+#![allow(dead_code)]
+
+pub mod abi;
+pub mod provider;
+pub mod subscription;
+
+use serde::{Deserialize, Serialize};
+
+/// A log entry as returned by `eth_getLogs` and `eth_subscribe("logs")`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvmLog {
+    pub address: String,
+    pub topics: Vec<String>,
+    pub data: String,
+    #[serde(default)]
+    pub block_number: Option<String>,
+    #[serde(default)]
+    pub transaction_hash: Option<String>,
+    #[serde(default)]
+    pub log_index: Option<String>,
+    /// Set when the log was removed by a reorg
+    #[serde(default)]
+    pub removed: bool,
+}
+
+impl EvmLog {
+    pub fn block_number(&self) -> Option<u64> {
+        self.block_number.as_deref().and_then(|n| parse_quantity(n).ok())
+    }
+}
+
+/// Parse a hex `QUANTITY` such as `"0x1b4"`
+pub fn parse_quantity(value: &str) -> anyhow::Result<u64> {
+    let digits = value.trim_start_matches("0x");
+    u64::from_str_radix(digits, 16)
+        .map_err(|e| anyhow::anyhow!("Invalid hex quantity {:?}: {}", value, e))
+}
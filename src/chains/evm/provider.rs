@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context, Result};
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use super::subscription::{self, LogFilter};
+use super::{parse_quantity, EvmLog};
+use crate::config::EvmChainConfig;
+use crate::metrics;
+use crate::outbound;
+use crate::retry::{self, CircuitBreaker, RetryPolicy};
+
+/// JSON-RPC client for one EVM chain.
+///
+/// Requests go to the current HTTP endpoint; on a transport or RPC error the
+/// provider moves on to the next one, so a failing endpoint is skipped until
+/// the others fail too. Endpoints that keep failing have their circuit opened
+/// and are left out of the rotation until it cools down.
+pub struct EvmProvider {
+    chain: String,
+    config: EvmChainConfig,
+    client: Client,
+    breakers: Vec<CircuitBreaker>,
+    current: AtomicUsize,
+    next_id: AtomicU64,
+}
+
+impl EvmProvider {
+    pub fn new(chain: &str, config: EvmChainConfig) -> Result<Self> {
+        if config.http_urls.is_empty() {
+            bail!("evm.{}.http_urls must list at least one endpoint", chain);
+        }
+
+        let client = outbound::client_builder()
+            .timeout(Duration::from_secs(config.request_timeout_secs))
+            .build()
+            .context("Failed to build EVM HTTP client")?;
+
+        let breakers = config
+            .http_urls
+            .iter()
+            .enumerate()
+            .map(|(i, _)| {
+                CircuitBreaker::new(
+                    format!("{} RPC endpoint #{}", chain, i),
+                    3,
+                    Duration::from_secs(60),
+                )
+            })
+            .collect();
+
+        Ok(Self {
+            chain: chain.to_string(),
+            config,
+            client,
+            breakers,
+            current: AtomicUsize::new(0),
+            next_id: AtomicU64::new(1),
+        })
+    }
+
+    pub fn chain(&self) -> &str {
+        &self.chain
+    }
+
+    pub fn config(&self) -> &EvmChainConfig {
+        &self.config
+    }
+
+    /// Send a JSON-RPC request, rotating through the endpoints until one answers.
+    /// If every endpoint fails the whole rotation is retried with backoff.
+    pub async fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let what = format!("{} {}", self.chain, method);
+        retry::retry(&RetryPolicy::default(), &what, |_| true, || {
+            self.request_once(method, &params)
+        })
+        .await
+    }
+
+    async fn request_once<T: DeserializeOwned>(&self, method: &str, params: &Value) -> Result<T> {
+        let urls = &self.config.http_urls;
+        let start = self.current.load(Ordering::Relaxed);
+        let now = Instant::now();
+        let mut last_error = None;
+
+        for offset in 0..urls.len() {
+            let index = (start + offset) % urls.len();
+            // Skip open circuits unless this is the last endpoint left to try
+            if !self.breakers[index].is_closed(now) && offset + 1 < urls.len() {
+                continue;
+            }
+            match self.send(&urls[index], method, params).await {
+                Ok(result) => {
+                    self.breakers[index].record_success();
+                    if index != start {
+                        info!("{} RPC switched to endpoint {}", self.chain, urls[index]);
+                        self.current.store(index, Ordering::Relaxed);
+                    }
+                    return serde_json::from_value(result)
+                        .with_context(|| format!("Unexpected {} response shape", method));
+                }
+                Err(e) => {
+                    self.breakers[index].record_failure();
+                    metrics::RPC_ERRORS.with_label_values(&[method]).inc();
+                    warn!("{} {} via {} failed: {:#}", self.chain, method, urls[index], e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| anyhow!("No {} endpoints configured", self.chain)))
+    }
+
+    async fn send(&self, url: &str, method: &str, params: &Value) -> Result<Value> {
+        let _timer = metrics::RPC_FETCH_SECONDS
+            .with_label_values(&[method])
+            .start_timer();
+
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id.fetch_add(1, Ordering::Relaxed),
+            "method": method,
+            "params": params,
+        });
+
+        let response: Value = self
+            .client
+            .post(url)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if let Some(error) = response.get("error") {
+            bail!("RPC error: {}", error);
+        }
+        response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| anyhow!("RPC response without result"))
+    }
+
+    pub async fn block_number(&self) -> Result<u64> {
+        let number: String = self.request("eth_blockNumber", json!([])).await?;
+        parse_quantity(&number)
+    }
+
+    pub async fn get_logs(&self, filter: &LogFilter, from_block: u64, to_block: u64) -> Result<Vec<EvmLog>> {
+        let mut params = filter.to_params();
+        params["fromBlock"] = json!(format!("0x{:x}", from_block));
+        params["toBlock"] = json!(format!("0x{:x}", to_block));
+        self.request("eth_getLogs", json!([params])).await
+    }
+
+    /// `eth_call` against the latest block, returning the raw hex output
+    pub async fn call(&self, to: &str, data: &str) -> Result<String> {
+        self.request("eth_call", json!([{ "to": to, "data": data }, "latest"]))
+            .await
+    }
+
+    /// Stream logs from this chain's websocket endpoint until `tx` is closed
+    pub async fn subscribe_logs(&self, filter: &LogFilter, tx: mpsc::Sender<EvmLog>) -> Result<()> {
+        subscription::subscribe_logs(
+            &self.config.wss_url,
+            filter,
+            tx,
+            Duration::from_secs(self.config.reconnect_max_secs),
+        )
+        .await
+    }
+
+    pub async fn get_transaction_receipt(&self, hash: &str) -> Result<Option<Value>> {
+        self.request("eth_getTransactionReceipt", json!([hash])).await
+    }
+}
+
+/// One provider per configured `[evm.<chain>]`
+#[derive(Default)]
+pub struct EvmProviderManager {
+    providers: HashMap<String, Arc<EvmProvider>>,
+}
+
+impl EvmProviderManager {
+    pub fn new(chains: &HashMap<String, EvmChainConfig>) -> Result<Self> {
+        let providers = chains
+            .iter()
+            .map(|(name, config)| {
+                info!(
+                    "EVM chain {} (chain id {}) with {} HTTP endpoint(s)",
+                    name,
+                    config.chain_id,
+                    config.http_urls.len()
+                );
+                Ok((name.clone(), Arc::new(EvmProvider::new(name, config.clone())?)))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+
+        Ok(Self { providers })
+    }
+
+    pub fn get(&self, chain: &str) -> Option<Arc<EvmProvider>> {
+        self.providers.get(chain).cloned()
+    }
+}
@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{info, warn};
+
+use super::EvmLog;
+
+/// Which logs to subscribe to: any of `addresses` (empty = all contracts) emitting
+/// any of the `topic0` event signatures (empty = all events)
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    pub addresses: Vec<String>,
+    pub topic0: Vec<String>,
+}
+
+impl LogFilter {
+    pub fn to_params(&self) -> Value {
+        let mut params = json!({});
+        if !self.addresses.is_empty() {
+            params["address"] = json!(self.addresses);
+        }
+        if !self.topic0.is_empty() {
+            params["topics"] = json!([self.topic0]);
+        }
+        params
+    }
+}
+
+/// Stream matching logs into `tx` over `eth_subscribe`, reconnecting with
+/// exponential backoff (capped at `reconnect_max`) whenever the socket drops.
+///
+/// Returns once the receiving side of `tx` is closed.
+pub async fn subscribe_logs(
+    wss_url: &str,
+    filter: &LogFilter,
+    tx: mpsc::Sender<EvmLog>,
+    reconnect_max: Duration,
+) -> Result<()> {
+    let mut attempt: u32 = 0;
+
+    loop {
+        match run_session(wss_url, filter, &tx).await {
+            Ok(received) => {
+                if tx.is_closed() {
+                    return Ok(());
+                }
+                warn!("EVM log subscription to {} closed", wss_url);
+                if received > 0 {
+                    attempt = 0;
+                }
+            }
+            Err(e) => warn!("EVM log subscription to {} failed: {:#}", wss_url, e),
+        }
+
+        let delay = Duration::from_secs(1u64 << attempt.min(6)).min(reconnect_max);
+        attempt = attempt.saturating_add(1);
+        info!("Reconnecting to {} in {:?}", wss_url, delay);
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// One websocket session; returns the number of logs delivered
+async fn run_session(wss_url: &str, filter: &LogFilter, tx: &mpsc::Sender<EvmLog>) -> Result<u64> {
+    let (mut socket, _) = connect_async(wss_url)
+        .await
+        .with_context(|| format!("Failed to connect to {}", wss_url))?;
+
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "eth_subscribe",
+        "params": ["logs", filter.to_params()],
+    });
+    socket.send(Message::text(request.to_string())).await?;
+
+    let mut subscription: Option<String> = None;
+    let mut received = 0u64;
+
+    while let Some(message) = socket.next().await {
+        let text = match message? {
+            Message::Text(text) => text,
+            Message::Close(frame) => {
+                info!("EVM websocket {} closed: {:?}", wss_url, frame);
+                break;
+            }
+            _ => continue,
+        };
+
+        let value: Value = serde_json::from_str(text.as_str()).context("Invalid JSON-RPC message")?;
+
+        if subscription.is_none() {
+            if let Some(error) = value.get("error") {
+                bail!("eth_subscribe rejected: {}", error);
+            }
+            let id = value
+                .get("result")
+                .and_then(Value::as_str)
+                .ok_or_else(|| anyhow!("Unexpected eth_subscribe reply: {}", value))?;
+            info!("Subscribed to EVM logs on {} ({})", wss_url, id);
+            subscription = Some(id.to_string());
+            continue;
+        }
+
+        if value.get("method").and_then(Value::as_str) != Some("eth_subscription") {
+            continue;
+        }
+        let Some(result) = value.get("params").and_then(|p| p.get("result")) else {
+            continue;
+        };
+
+        let log: EvmLog = match serde_json::from_value(result.clone()) {
+            Ok(log) => log,
+            Err(e) => {
+                warn!("Skipping malformed EVM log: {}", e);
+                continue;
+            }
+        };
+
+        received += 1;
+        if tx.send(log).await.is_err() {
+            break;
+        }
+    }
+
+    Ok(received)
+}
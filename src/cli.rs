@@ -0,0 +1,97 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand};
+
+#[derive(Debug, Parser)]
+#[command(name = "pumpfun_ingestion", version, about = "Solana token launch listener")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Report events and alerts on stdout instead of publishing them (like `dry_run = true`)
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+    /// Publish events as NDJSON on stdout only (no Redis needed), logging to stderr
+    #[arg(long, global = true)]
+    pub stdout: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Subscribe to the enabled listeners and publish tokens (the default)
+    Run {
+        /// Also write every log notification and RPC response to fixture files in this directory
+        #[arg(long)]
+        record: Option<PathBuf>,
+    },
+    /// Parse one pump.fun create transaction and print the resulting token as JSON
+    Inspect {
+        signature: String,
+    },
+    /// Process historical pump.fun transactions, newest first, back to --from-slot / --since
+    Backfill {
+        #[arg(long)]
+        from_slot: Option<u64>,
+        #[arg(long)]
+        to_slot: Option<u64>,
+        /// RFC 3339 time, e.g. 2025-01-01T00:00:00Z
+        #[arg(long)]
+        since: Option<DateTime<Utc>>,
+        #[arg(long)]
+        until: Option<DateTime<Utc>>,
+        /// Write the tokens to this NDJSON file instead of publishing them
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Rescore the tokens of published events with the current scoring and filters,
+    /// emitting the corrected events to `reprocess.channel`
+    Reprocess {
+        /// Redis stream to read (`output.mode = "stream"`)
+        #[arg(long, conflicts_with = "file", required_unless_present = "file")]
+        stream: Option<String>,
+        /// NDJSON event files instead, `.gz` too (file sink output, archive objects)
+        #[arg(long)]
+        file: Vec<PathBuf>,
+        /// RFC 3339 time, e.g. 2025-01-01T00:00:00Z
+        #[arg(long)]
+        since: Option<DateTime<Utc>>,
+        #[arg(long)]
+        until: Option<DateTime<Utc>>,
+        /// Channel or stream for the corrected events, instead of `reprocess.channel`
+        #[arg(long)]
+        channel: Option<String>,
+        /// Write the corrected events to this NDJSON file instead of Redis
+        #[arg(long, conflicts_with = "channel")]
+        output: Option<PathBuf>,
+        /// Only emit events whose score or risk level changed
+        #[arg(long)]
+        changed_only: bool,
+    },
+    /// Feed recorded log notifications (NDJSON) through the listeners and sinks
+    Replay {
+        #[arg(long)]
+        file: PathBuf,
+        /// Answer RPC requests from a recorded rpc.ndjson instead of the configured endpoints
+        #[arg(long)]
+        rpc_fixtures: Option<PathBuf>,
+    },
+    /// Check the configured endpoints, Redis and program ids, then exit
+    SelfTest,
+    /// Serve a canned RPC node (HTTP + websocket) from a `run --record` directory
+    #[cfg(feature = "test-utils")]
+    MockRpc {
+        #[arg(long)]
+        fixtures: Option<PathBuf>,
+        #[arg(long, default_value_t = 8899)]
+        http_port: u16,
+        #[arg(long, default_value_t = 8900)]
+        ws_port: u16,
+    },
+}
+
+impl Cli {
+    /// The subcommand to run, `run` when none was given
+    pub fn command(self) -> Command {
+        self.command.unwrap_or(Command::Run { record: None })
+    }
+}
@@ -0,0 +1,317 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use bigdecimal::{BigDecimal, ToPrimitive};
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::Serialize;
+use tokio::sync::Notify;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, error, info, warn};
+
+use crate::config::ClickHouseConfig;
+use crate::metrics;
+use crate::models::{Envelope, Trade};
+use crate::outbound;
+
+const TRADES_TABLE: &str = "pumpfun_trades";
+const TOKENS_TABLE: &str = "pumpfun_tokens";
+
+/// Batches every decoded trade and a snapshot of the token of every published
+/// event into ClickHouse, over its HTTP interface
+pub struct ClickHouseWriter {
+    client: Client,
+    config: ClickHouseConfig,
+    trades: Mutex<VecDeque<TradeRow>>,
+    tokens: Mutex<VecDeque<TokenRow>>,
+    /// Woken when a buffer holds `batch_size` rows
+    full: Notify,
+}
+
+#[derive(Serialize)]
+struct TradeRow {
+    at: String,
+    mint: String,
+    wallet: String,
+    is_buy: bool,
+    sol_amount: u64,
+    token_amount: u64,
+    slot: Option<u64>,
+    signature: Option<String>,
+}
+
+#[derive(Serialize)]
+struct TokenRow {
+    at: String,
+    seq: u64,
+    event: &'static str,
+    mint: String,
+    source: String,
+    creator: Option<String>,
+    name: Option<String>,
+    symbol: Option<String>,
+    created_at: String,
+    liquidity_sol: Option<f64>,
+    price_sol: Option<f64>,
+    price_usd: Option<f64>,
+    market_cap_usd: Option<f64>,
+    bonding_curve_progress: Option<f64>,
+    holder_count: Option<u32>,
+    top_10_holder_percentage: Option<f64>,
+    dev_buy_percentage: Option<f64>,
+    mint_authority_disabled: bool,
+    freeze_authority_disabled: bool,
+    pumpswap_pool: Option<String>,
+    score: Option<i32>,
+    risk_level: Option<String>,
+    creator_blacklisted: bool,
+    bundled: bool,
+}
+
+impl ClickHouseWriter {
+    /// Build the writer and start flushing it every `flush_interval_secs`
+    pub fn spawn(config: &ClickHouseConfig) -> Result<Arc<Self>> {
+        let client = outbound::client_builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .context("Failed to build ClickHouse HTTP client")?;
+        let writer = Arc::new(Self {
+            client,
+            config: config.clone(),
+            trades: Mutex::new(VecDeque::new()),
+            tokens: Mutex::new(VecDeque::new()),
+            full: Notify::new(),
+        });
+
+        let flusher = writer.clone();
+        tokio::spawn(async move {
+            let mut tables_created = !flusher.config.create_tables;
+            let interval = Duration::from_secs(flusher.config.flush_interval_secs.max(1));
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = flusher.full.notified() => {}
+                }
+                if !tables_created {
+                    match flusher.create_tables().await {
+                        Ok(()) => tables_created = true,
+                        Err(e) => {
+                            error!("Failed to create ClickHouse tables: {:#}", e);
+                            continue;
+                        }
+                    }
+                }
+                flusher.flush().await;
+            }
+        });
+
+        Ok(writer)
+    }
+
+    pub fn add_trade(&self, trade: &Trade) {
+        let (slot, signature) = crate::envelope::origin();
+        let row = TradeRow {
+            at: timestamp(trade.at),
+            mint: trade.mint.clone(),
+            wallet: trade.wallet.clone(),
+            is_buy: trade.is_buy,
+            sol_amount: trade.sol_amount,
+            token_amount: trade.token_amount,
+            slot,
+            signature,
+        };
+        self.buffer(&self.trades, row, TRADES_TABLE);
+    }
+
+    /// Snapshot the token carried by `envelope`, if any
+    pub fn add_event(&self, envelope: &Envelope) {
+        let Some(token) = envelope.payload.token() else {
+            return;
+        };
+        let row = TokenRow {
+            at: timestamp(envelope.emitted_at),
+            seq: envelope.seq,
+            event: envelope.event_type(),
+            mint: token.mint_address.clone(),
+            source: token.source.to_string(),
+            creator: token.creator.clone(),
+            name: token.name.clone(),
+            symbol: token.symbol.clone(),
+            created_at: timestamp(token.created_at),
+            liquidity_sol: float(&token.liquidity_sol),
+            price_sol: float(&token.price_sol),
+            price_usd: float(&token.price_usd),
+            market_cap_usd: float(&token.market_cap_usd),
+            bonding_curve_progress: float(&token.bonding_curve_progress),
+            holder_count: token.holder_count,
+            top_10_holder_percentage: float(&token.top_10_holder_percentage),
+            dev_buy_percentage: float(&token.dev_buy_percentage),
+            mint_authority_disabled: token.mint_authority_disabled,
+            freeze_authority_disabled: token.freeze_authority_disabled,
+            pumpswap_pool: token.pumpswap_pool.clone(),
+            score: token.score,
+            risk_level: token.risk_level.as_ref().map(ToString::to_string),
+            creator_blacklisted: token.creator_blacklisted,
+            bundled: token.bundled,
+        };
+        self.buffer(&self.tokens, row, TOKENS_TABLE);
+    }
+
+    fn buffer<T>(&self, rows: &Mutex<VecDeque<T>>, row: T, table: &str) {
+        let mut rows = rows.lock().expect("clickhouse lock poisoned");
+        if rows.len() >= self.config.max_buffered_rows {
+            metrics::DROPPED_MESSAGES
+                .with_label_values(&["clickhouse", "buffer_full"])
+                .inc();
+            debug!("ClickHouse buffer of {} is full, dropping a row", table);
+            return;
+        }
+        rows.push_back(row);
+        if rows.len() >= self.config.batch_size {
+            self.full.notify_one();
+        }
+    }
+
+    /// Insert every buffered row, in batches of `batch_size`; rows of a failed
+    /// insert are kept for the next flush
+    pub async fn flush(&self) {
+        self.flush_table(&self.trades, TRADES_TABLE).await;
+        self.flush_table(&self.tokens, TOKENS_TABLE).await;
+    }
+
+    async fn flush_table<T: Serialize>(&self, rows: &Mutex<VecDeque<T>>, table: &str) {
+        loop {
+            let batch: Vec<T> = {
+                let mut rows = rows.lock().expect("clickhouse lock poisoned");
+                let len = rows.len().min(self.config.batch_size.max(1));
+                rows.drain(..len).collect()
+            };
+            if batch.is_empty() {
+                return;
+            }
+            let count = batch.len();
+            if let Err(e) = self.insert(table, &batch).await {
+                warn!("Failed to insert {} rows into {}: {:#}", count, table, e);
+                metrics::PUBLISH_FAILURES
+                    .with_label_values(&["clickhouse"])
+                    .inc();
+                let mut rows = rows.lock().expect("clickhouse lock poisoned");
+                let room = self.config.max_buffered_rows.saturating_sub(rows.len());
+                for row in batch.into_iter().take(room).rev() {
+                    rows.push_front(row);
+                }
+                return;
+            }
+            metrics::CLICKHOUSE_ROWS
+                .with_label_values(&[table])
+                .inc_by(count as u64);
+            debug!("Inserted {} rows into {}", count, table);
+        }
+    }
+
+    async fn insert<T: Serialize>(&self, table: &str, rows: &[T]) -> Result<()> {
+        let mut body = Vec::new();
+        for row in rows {
+            serde_json::to_writer(&mut body, row)?;
+            body.push(b'\n');
+        }
+        let query = format!(
+            "INSERT INTO {}.{} FORMAT JSONEachRow",
+            self.config.database, table
+        );
+        self.query(&query, body).await
+    }
+
+    async fn create_tables(&self) -> Result<()> {
+        let db = &self.config.database;
+        self.query(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {db}.{TRADES_TABLE} (
+                    at DateTime64(3, 'UTC'),
+                    mint String,
+                    wallet String,
+                    is_buy Bool,
+                    sol_amount UInt64,
+                    token_amount UInt64,
+                    slot Nullable(UInt64),
+                    signature Nullable(String)
+                ) ENGINE = MergeTree
+                PARTITION BY toYYYYMM(at)
+                ORDER BY (mint, at)"
+            ),
+            Vec::new(),
+        )
+        .await?;
+        self.query(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {db}.{TOKENS_TABLE} (
+                    at DateTime64(3, 'UTC'),
+                    seq UInt64,
+                    event LowCardinality(String),
+                    mint String,
+                    source LowCardinality(String),
+                    creator Nullable(String),
+                    name Nullable(String),
+                    symbol Nullable(String),
+                    created_at DateTime64(3, 'UTC'),
+                    liquidity_sol Nullable(Float64),
+                    price_sol Nullable(Float64),
+                    price_usd Nullable(Float64),
+                    market_cap_usd Nullable(Float64),
+                    bonding_curve_progress Nullable(Float64),
+                    holder_count Nullable(UInt32),
+                    top_10_holder_percentage Nullable(Float64),
+                    dev_buy_percentage Nullable(Float64),
+                    mint_authority_disabled Bool,
+                    freeze_authority_disabled Bool,
+                    pumpswap_pool Nullable(String),
+                    score Nullable(Int32),
+                    risk_level Nullable(String),
+                    creator_blacklisted Bool,
+                    bundled Bool
+                ) ENGINE = MergeTree
+                PARTITION BY toYYYYMM(at)
+                ORDER BY (mint, at)"
+            ),
+            Vec::new(),
+        )
+        .await?;
+        info!("ClickHouse tables ready in {}", db);
+        Ok(())
+    }
+
+    async fn query(&self, query: &str, body: Vec<u8>) -> Result<()> {
+        let mut request = self
+            .client
+            .post(&self.config.url)
+            .query(&[("query", query)])
+            .body(body);
+        if let Some(user) = &self.config.user {
+            request = request.header("X-ClickHouse-User", user);
+        }
+        if let Some(password) = &self.config.password {
+            request = request.header("X-ClickHouse-Key", password);
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let message = response.text().await.unwrap_or_default();
+            bail!("ClickHouse returned {}: {}", status, message.trim());
+        }
+        Ok(())
+    }
+}
+
+/// The format ClickHouse parses into `DateTime64(3)`
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M:%S%.3f").to_string()
+}
+
+fn float(value: &Option<BigDecimal>) -> Option<f64> {
+    value.as_ref().and_then(ToPrimitive::to_f64)
+}
@@ -0,0 +1,69 @@
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use notify::{RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::config::{Config, CONFIG_DIR};
+use crate::processor::Processor;
+
+/// Editors save in several writes; let them settle before reloading
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Watch the config directory and hot-apply `[filters]` and `[notifiers]` to
+/// `processor`. Other sections are only read at startup, changing them logs a
+/// reminder to restart.
+pub fn spawn(current: Config, processor: Processor) -> Result<()> {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if event.is_ok_and(|event| !event.kind.is_access()) {
+            let _ = tx.send(());
+        }
+    })
+    .context("Failed to create config watcher")?;
+    watcher
+        .watch(Path::new(CONFIG_DIR), RecursiveMode::NonRecursive)
+        .with_context(|| format!("Failed to watch {}", CONFIG_DIR))?;
+    info!("Watching {}/ for config changes", CONFIG_DIR);
+
+    tokio::spawn(async move {
+        // Dropping the watcher stops the notifications
+        let _watcher = watcher;
+        let mut current = current;
+
+        while rx.recv().await.is_some() {
+            tokio::time::sleep(DEBOUNCE).await;
+            while rx.try_recv().is_ok() {}
+
+            let config = match Config::load() {
+                Ok(config) => config,
+                Err(e) => {
+                    warn!("Ignoring config change, the new config does not load: {:#}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = processor.reload(&config) {
+                warn!("Ignoring config change, keeping the current rules: {:#}", e);
+                continue;
+            }
+            info!("Config reloaded, new [filters] and [notifiers] settings are active");
+            warn_restart_required(&current, &config);
+            current = config;
+        }
+    });
+
+    Ok(())
+}
+
+fn warn_restart_required(old: &Config, new: &Config) {
+    let changed = [
+        ("network", old.network != new.network),
+        ("programs", old.programs != new.programs),
+        ("ingestion", old.ingestion != new.ingestion),
+    ];
+    for (section, _) in changed.iter().filter(|(_, changed)| *changed) {
+        warn!("[{}] changed, restart to apply it", section);
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use solana_commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use tracing::{debug, warn};
+
+use crate::processor::Processor;
+use crate::rpc_pool::RpcPool;
+
+/// `getSignatureStatuses` accepts at most this many signatures per call
+const MAX_SIGNATURES_PER_CALL: usize = 256;
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Follows provisional tokens published from `processed` notifications until
+/// their creation transaction reaches `target`, then publishes `TokenConfirmed`,
+/// or `TokenRetracted` if it failed or never landed within `timeout`.
+pub struct ConfirmationTracker {
+    rpc: Arc<RpcPool>,
+    processor: Processor,
+    target: CommitmentConfig,
+    timeout: Duration,
+    pending: Mutex<HashMap<Signature, Pending>>,
+}
+
+struct Pending {
+    mint: String,
+    since: Instant,
+}
+
+enum Outcome {
+    Confirmed,
+    Retracted(&'static str),
+}
+
+impl ConfirmationTracker {
+    /// Create the tracker and start its polling task
+    pub fn spawn(
+        rpc: Arc<RpcPool>,
+        processor: Processor,
+        target: CommitmentConfig,
+        timeout: Duration,
+    ) -> Arc<Self> {
+        let tracker = Arc::new(Self {
+            rpc,
+            processor,
+            target,
+            timeout,
+            pending: Mutex::new(HashMap::new()),
+        });
+        tokio::spawn(tracker.clone().run());
+        tracker
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Signature, Pending>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Watch the creation transaction of a token that was just published provisionally
+    pub fn track(&self, signature: Signature, mint: String) {
+        self.lock().insert(
+            signature,
+            Pending {
+                mint,
+                since: Instant::now(),
+            },
+        );
+    }
+
+    async fn run(self: Arc<Self>) {
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            // Nobody can hand us new tokens anymore
+            if Arc::strong_count(&self) == 1 {
+                return;
+            }
+
+            let signatures: Vec<Signature> = self.lock().keys().copied().collect();
+            for chunk in signatures.chunks(MAX_SIGNATURES_PER_CALL) {
+                self.check(chunk).await;
+            }
+        }
+    }
+
+    async fn check(&self, signatures: &[Signature]) {
+        let statuses = match self
+            .rpc
+            .call("getSignatureStatuses", |client| async move {
+                client.get_signature_statuses(signatures).await
+            })
+            .await
+        {
+            Ok(response) => response.value,
+            Err(e) => {
+                warn!("Failed to check provisional token signatures: {}", e);
+                return;
+            }
+        };
+
+        for (signature, status) in signatures.iter().zip(statuses) {
+            let outcome = match status {
+                Some(status) if status.err.is_some() => Some(Outcome::Retracted("transaction failed")),
+                Some(status) if status.satisfies_commitment(self.target) => Some(Outcome::Confirmed),
+                _ => {
+                    let expired = self
+                        .lock()
+                        .get(signature)
+                        .is_some_and(|p| p.since.elapsed() >= self.timeout);
+                    expired.then_some(Outcome::Retracted("transaction dropped"))
+                }
+            };
+
+            let Some(outcome) = outcome else {
+                continue;
+            };
+            let Some(pending) = self.lock().remove(signature) else {
+                continue;
+            };
+
+            // Tokens the filters rejected were never published, so there is nothing to settle
+            let Some(token) = self.processor.tracked_token(&pending.mint).await else {
+                debug!("Provisional token {} is no longer tracked", pending.mint);
+                continue;
+            };
+
+            let result = match outcome {
+                Outcome::Confirmed => self.processor.process_token_confirmed(token).await,
+                Outcome::Retracted(reason) => {
+                    self.processor.process_token_retracted(token, reason).await
+                }
+            };
+            if let Err(e) = result {
+                warn!("Failed to publish confirmation of {}: {:#}", signature, e);
+            }
+        }
+    }
+}
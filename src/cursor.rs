@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::sqlite::SqliteStore;
+
+/// Newest log a listener has handled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CursorPosition {
+    pub slot: u64,
+    pub signature: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Per-listener slot cursor, kept in memory and written to
+/// `ingestion.cursor_path` (or the SQLite database) on shutdown so the next
+/// run knows where we stopped.
+pub struct SlotCursor {
+    path: Option<PathBuf>,
+    sqlite: Option<SqliteStore>,
+    positions: Mutex<HashMap<String, CursorPosition>>,
+}
+
+impl SlotCursor {
+    /// Load the cursor file if there is one; an empty path disables persistence
+    pub fn load(path: &str) -> Result<Self> {
+        let path = (!path.is_empty()).then(|| PathBuf::from(path));
+
+        let positions = match &path {
+            Some(path) if path.exists() => {
+                let raw = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read cursor file {}", path.display()))?;
+                match serde_json::from_str(&raw) {
+                    Ok(positions) => positions,
+                    Err(e) => {
+                        warn!("Ignoring unreadable cursor file {}: {}", path.display(), e);
+                        HashMap::new()
+                    }
+                }
+            }
+            _ => HashMap::new(),
+        };
+
+        Ok(Self {
+            path,
+            sqlite: None,
+            positions: Mutex::new(positions),
+        })
+    }
+
+    /// Cursor kept in the `database.backend = "sqlite"` database
+    pub async fn sqlite(store: SqliteStore) -> Result<Self> {
+        let positions = store
+            .load_cursor()
+            .await
+            .context("Failed to read the slot cursor from SQLite")?;
+        Ok(Self {
+            path: None,
+            sqlite: Some(store),
+            positions: Mutex::new(positions),
+        })
+    }
+
+    /// Cursor that is never written to disk
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            sqlite: None,
+            positions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, CursorPosition>> {
+        self.positions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Advance `listener`'s cursor; older slots (handled out of order by other workers) are ignored
+    pub fn record(&self, listener: &str, slot: u64, signature: &str) {
+        let mut positions = self.lock();
+        if positions.get(listener).is_some_and(|p| p.slot > slot) {
+            return;
+        }
+        positions.insert(
+            listener.to_string(),
+            CursorPosition {
+                slot,
+                signature: signature.to_string(),
+                updated_at: Utc::now(),
+            },
+        );
+    }
+
+    pub fn get(&self, listener: &str) -> Option<CursorPosition> {
+        self.lock().get(listener).cloned()
+    }
+
+    /// Write the cursor to SQLite, or the cursor file atomically (temp file + rename)
+    pub async fn persist(&self) -> Result<()> {
+        let positions = self.lock().clone();
+        if let Some(store) = &self.sqlite {
+            store
+                .save_cursor(positions)
+                .await
+                .context("Failed to save the slot cursor to SQLite")?;
+            info!("Saved slot cursor to SQLite");
+            return Ok(());
+        }
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let json = serde_json::to_string_pretty(&positions)?;
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(dir)
+                .await
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, json)
+            .await
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        tokio::fs::rename(&tmp, path)
+            .await
+            .with_context(|| format!("Failed to move cursor file into {}", path.display()))?;
+
+        info!("Saved slot cursor to {}", path.display());
+        Ok(())
+    }
+}
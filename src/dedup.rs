@@ -0,0 +1,147 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use moka::future::Cache;
+use redis::aio::ConnectionManager;
+use tracing::{info, warn};
+
+use crate::config::{DatabaseBackend, DatabaseConfig, DedupConfig};
+use crate::sqlite::SqliteStore;
+
+const REDIS_KEY_PREFIX: &str = "dedup:sig:";
+/// Expired SQLite entries are deleted every this many new signatures
+const SQLITE_PRUNE_EVERY: u64 = 10_000;
+
+/// Remembers recently processed transaction signatures so the same log
+/// delivered twice (or replayed after a reconnect) is only handled once.
+///
+/// The in-memory cache always applies; when `use_redis` is set the check is
+/// also done with `SET NX` so several instances share the same view. With
+/// `database.backend = "sqlite"` signatures are kept in SQLite instead, so they
+/// survive restarts.
+pub struct SignatureDedup {
+    local: Cache<String, ()>,
+    redis: Option<ConnectionManager>,
+    sqlite: Option<SqliteStore>,
+    inserted: AtomicU64,
+    ttl: Duration,
+}
+
+impl SignatureDedup {
+    pub async fn new(config: &DedupConfig, database: &DatabaseConfig) -> Result<Self> {
+        let ttl = Duration::from_secs(config.ttl_secs);
+
+        let local = Cache::builder()
+            .max_capacity(config.max_entries)
+            .time_to_live(ttl)
+            .build();
+
+        let redis = if config.use_redis {
+            let client = redis::Client::open(database.redis_url.as_str())
+                .context("Invalid redis_url for dedup")?;
+            let manager = ConnectionManager::new(client)
+                .await
+                .context("Failed to connect to Redis for dedup")?;
+            info!("Signature dedup backed by Redis");
+            Some(manager)
+        } else {
+            None
+        };
+
+        let sqlite = match database.backend {
+            DatabaseBackend::Sqlite => {
+                info!("Signature dedup backed by SQLite");
+                Some(SqliteStore::open(&database.sqlite_path)?)
+            }
+            DatabaseBackend::Redis => None,
+        };
+
+        Ok(Self {
+            local,
+            redis,
+            sqlite,
+            inserted: AtomicU64::new(0),
+            ttl,
+        })
+    }
+
+    /// Returns `true` the first time a signature is seen within the TTL window
+    pub async fn first_seen(&self, signature: &str) -> bool {
+        let entry = self.local.entry(signature.to_string()).or_insert(()).await;
+        if !entry.is_fresh() {
+            return false;
+        }
+
+        if let Some(sqlite) = &self.sqlite {
+            return self.first_seen_sqlite(sqlite, signature).await;
+        }
+        let Some(redis) = &self.redis else {
+            return true;
+        };
+
+        let mut conn = redis.clone();
+        let result: redis::RedisResult<Option<String>> = redis::cmd("SET")
+            .arg(format!("{}{}", REDIS_KEY_PREFIX, signature))
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(self.ttl.as_secs())
+            .query_async(&mut conn)
+            .await;
+
+        match result {
+            Ok(reply) => reply.is_some(),
+            Err(e) => {
+                // Prefer a possible duplicate over silently losing a token
+                warn!("Redis dedup check failed for {}: {}", signature, e);
+                true
+            }
+        }
+    }
+
+    async fn first_seen_sqlite(&self, sqlite: &SqliteStore, signature: &str) -> bool {
+        if self
+            .inserted
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(SQLITE_PRUNE_EVERY)
+        {
+            if let Err(e) = sqlite.prune_signatures(self.ttl).await {
+                warn!("Failed to prune expired dedup entries: {:#}", e);
+            }
+        }
+        match sqlite.first_seen(signature, self.ttl).await {
+            Ok(first) => first,
+            Err(e) => {
+                warn!("SQLite dedup check failed for {}: {:#}", signature, e);
+                true
+            }
+        }
+    }
+
+    /// Drop a signature so it is processed again, e.g. when a failed log is retried
+    pub async fn forget(&self, signature: &str) {
+        self.local.invalidate(signature).await;
+
+        if let Some(sqlite) = &self.sqlite {
+            if let Err(e) = sqlite.forget(signature).await {
+                warn!(
+                    "Failed to clear SQLite dedup entry for {}: {:#}",
+                    signature, e
+                );
+            }
+        }
+
+        let Some(redis) = &self.redis else {
+            return;
+        };
+        let mut conn = redis.clone();
+        let result: redis::RedisResult<()> = redis::cmd("DEL")
+            .arg(format!("{}{}", REDIS_KEY_PREFIX, signature))
+            .query_async(&mut conn)
+            .await;
+        if let Err(e) = result {
+            warn!("Failed to clear Redis dedup entry for {}: {}", signature, e);
+        }
+    }
+}
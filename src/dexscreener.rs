@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use bigdecimal::BigDecimal;
+use moka::future::Cache;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{DexScreenerConfig, NegativeCacheConfig, RateLimitConfig};
+use crate::metrics;
+use crate::negative_cache::NegativeCache;
+use crate::outbound;
+use crate::rate_limit::RateLimiter;
+use crate::retry::{self, CircuitBreaker, RetryPolicy};
+use crate::token_helper::is_transient_http_error;
+
+const WSOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+static SHARED: OnceLock<DexScreener> = OnceLock::new();
+
+/// A pair from the `/tokens/v1/{chain}/{addresses}` endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pair {
+    pub chain_id: String,
+    pub dex_id: String,
+    #[serde(default)]
+    pub url: Option<String>,
+    pub pair_address: String,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    pub base_token: PairToken,
+    pub quote_token: PairToken,
+    /// Price of the base token in the quote token
+    #[serde(default)]
+    pub price_native: Option<BigDecimal>,
+    #[serde(default)]
+    pub price_usd: Option<BigDecimal>,
+    #[serde(default)]
+    pub liquidity: Option<Liquidity>,
+    #[serde(default)]
+    pub fdv: Option<BigDecimal>,
+    #[serde(default)]
+    pub market_cap: Option<BigDecimal>,
+    /// Unix milliseconds
+    #[serde(default)]
+    pub pair_created_at: Option<i64>,
+    /// USD volume keyed by window (`m5`, `h1`, `h6`, `h24`)
+    #[serde(default)]
+    pub volume: HashMap<String, f64>,
+    #[serde(default)]
+    pub txns: HashMap<String, Txns>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairToken {
+    pub address: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub symbol: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Liquidity {
+    #[serde(default)]
+    pub usd: Option<BigDecimal>,
+    #[serde(default)]
+    pub base: BigDecimal,
+    #[serde(default)]
+    pub quote: BigDecimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Txns {
+    #[serde(default)]
+    pub buys: u64,
+    #[serde(default)]
+    pub sells: u64,
+}
+
+impl Pair {
+    /// SOL on the quote side of a SOL pair
+    pub fn liquidity_sol(&self) -> Option<BigDecimal> {
+        if self.quote_token.address != WSOL_MINT {
+            return None;
+        }
+        self.liquidity
+            .as_ref()
+            .map(|liquidity| liquidity.quote.clone())
+    }
+
+    fn liquidity_usd(&self) -> BigDecimal {
+        self.liquidity
+            .as_ref()
+            .and_then(|liquidity| liquidity.usd.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Configure the client every caller shares; only the first call counts
+pub fn init(config: &DexScreenerConfig, negative: &NegativeCacheConfig) -> Result<()> {
+    let client = DexScreener::new(config, negative)?;
+    let _ = SHARED.set(client);
+    Ok(())
+}
+
+/// The shared client, with the default settings if `init` was never called
+pub fn shared() -> &'static DexScreener {
+    SHARED.get_or_init(|| {
+        DexScreener::new(
+            &DexScreenerConfig::default(),
+            &NegativeCacheConfig::default(),
+        )
+        .expect("DexScreener client can be built")
+    })
+}
+
+/// DexScreener API client held under its requests-per-minute cap, with the
+/// pairs of each mint cached for `cache_secs`, mints without pairs skipped for
+/// `negative_cache.ttl_secs` and a circuit breaker so an unreachable API costs
+/// one timeout instead of one per token
+pub struct DexScreener {
+    http: Client,
+    url: String,
+    limiter: RateLimiter,
+    breaker: CircuitBreaker,
+    cache: Cache<String, Arc<Vec<Pair>>>,
+    misses: NegativeCache,
+}
+
+impl DexScreener {
+    pub fn new(config: &DexScreenerConfig, negative: &NegativeCacheConfig) -> Result<Self> {
+        let http = outbound::client_builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .context("Failed to build DexScreener HTTP client")?;
+        let host = reqwest::Url::parse(&config.url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_else(|| config.url.clone());
+        let requests_per_sec = f64::from(config.requests_per_minute.max(1)) / 60.0;
+        let limiter = RateLimiter::new(
+            &host,
+            &RateLimitConfig {
+                max_requests_per_sec: requests_per_sec,
+                min_requests_per_sec: requests_per_sec / 10.0,
+                ..RateLimitConfig::default()
+            },
+        );
+        Ok(Self {
+            http,
+            url: config.url.trim_end_matches('/').to_string(),
+            limiter,
+            breaker: CircuitBreaker::new("DexScreener", 5, Duration::from_secs(60)),
+            cache: Cache::builder()
+                .max_capacity(config.cache_max_entries)
+                .time_to_live(Duration::from_secs(config.cache_secs.max(1)))
+                .build(),
+            misses: NegativeCache::new("dexscreener", negative),
+        })
+    }
+
+    /// Pairs of `mint` on `chain_id`, most liquid first
+    pub async fn pairs(&self, chain_id: &str, mint: &str) -> Result<Arc<Vec<Pair>>> {
+        let key = format!("{}:{}", chain_id, mint);
+        if let Some(pairs) = self.cache.get(&key).await {
+            metrics::DEXSCREENER_REQUESTS
+                .with_label_values(&["cache_hit"])
+                .inc();
+            return Ok(pairs);
+        }
+        if self.misses.contains(&key) {
+            return Ok(Arc::new(Vec::new()));
+        }
+        if !self.breaker.is_closed(Instant::now()) {
+            metrics::DEXSCREENER_REQUESTS
+                .with_label_values(&["circuit_open"])
+                .inc();
+            bail!("DexScreener circuit open");
+        }
+
+        let url = format!("{}/tokens/v1/{}/{}", self.url, chain_id, mint);
+        let response = retry::retry(
+            &RetryPolicy::default(),
+            "DexScreener request",
+            is_transient_http_error,
+            || self.get(&url),
+        )
+        .await;
+        let mut pairs: Vec<Pair> = match response {
+            Ok(response) => {
+                self.breaker.record_success();
+                match response.json().await {
+                    Ok(pairs) => pairs,
+                    Err(e) => {
+                        metrics::DEXSCREENER_REQUESTS
+                            .with_label_values(&["error"])
+                            .inc();
+                        self.misses.insert(&key).await;
+                        return Err(e).context("Invalid DexScreener response");
+                    }
+                }
+            }
+            Err(e) => {
+                if is_transient_http_error(&e) {
+                    self.breaker.record_failure();
+                }
+                metrics::DEXSCREENER_REQUESTS
+                    .with_label_values(&["error"])
+                    .inc();
+                self.misses.insert(&key).await;
+                return Err(e).context("DexScreener request failed");
+            }
+        };
+
+        pairs.sort_by_key(|pair| std::cmp::Reverse(pair.liquidity_usd()));
+        let pairs = Arc::new(pairs);
+        // A mint without a pair may get one any moment, so it is only
+        // skipped for the short negative TTL
+        if pairs.is_empty() {
+            metrics::DEXSCREENER_REQUESTS
+                .with_label_values(&["empty"])
+                .inc();
+            self.misses.insert(&key).await;
+        } else {
+            metrics::DEXSCREENER_REQUESTS
+                .with_label_values(&["ok"])
+                .inc();
+            self.cache.insert(key, pairs.clone()).await;
+        }
+        Ok(pairs)
+    }
+
+    /// The most liquid pair of `mint`, if it has any
+    pub async fn best_pair(&self, chain_id: &str, mint: &str) -> Result<Option<Pair>> {
+        Ok(self.pairs(chain_id, mint).await?.first().cloned())
+    }
+
+    async fn get(&self, url: &str) -> reqwest::Result<reqwest::Response> {
+        self.limiter.acquire().await;
+        let response = self.http.get(url).send().await?;
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok())
+                .map(Duration::from_secs);
+            self.limiter.on_rate_limited(retry_after);
+        } else {
+            self.limiter.on_success();
+        }
+        response.error_for_status()
+    }
+}
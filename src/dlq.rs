@@ -0,0 +1,367 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionManager;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_response::RpcLogsResponse;
+use tracing::{error, info, warn, Instrument};
+
+use crate::config::DlqConfig;
+use crate::dedup::SignatureDedup;
+use crate::error::{ErrorAction, ListenerError};
+use crate::listeners::TokenListener;
+use crate::{event_bus, metrics};
+use crate::retry::RetryPolicy;
+
+/// A log whose processing failed, waiting in Redis to be retried
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub listener: String,
+    pub slot: u64,
+    pub log: RpcLogsResponse,
+    /// Error of the last attempt
+    pub error: String,
+    pub attempts: u32,
+    pub failed_at: DateTime<Utc>,
+    pub next_attempt_at: DateTime<Utc>,
+}
+
+/// Redis dead-letter list for logs the workers failed to process.
+///
+/// Entries live in `dlq.key`; the retrier reprocesses them with exponential
+/// backoff and moves the ones that exhaust `max_attempts` to `{key}:failed`.
+pub struct DeadLetterQueue {
+    conn: ConnectionManager,
+    key: String,
+    failed_key: String,
+    max_attempts: u32,
+    retry_interval: Duration,
+    backoff: RetryPolicy,
+    /// Per retry, see [`DeadLetterQueue::with_timeout`]
+    timeout: Option<Duration>,
+}
+
+impl DeadLetterQueue {
+    pub async fn new(config: &DlqConfig, redis_url: &str) -> Result<Self> {
+        let client = redis::Client::open(redis_url).context("Invalid redis_url for dlq")?;
+        let conn = ConnectionManager::new(client)
+            .await
+            .context("Failed to connect to Redis for dlq")?;
+        info!("Failed logs go to dead-letter list {}", config.key);
+
+        Ok(Self {
+            conn,
+            key: config.key.clone(),
+            failed_key: format!("{}:failed", config.key),
+            max_attempts: config.max_attempts.max(1),
+            retry_interval: Duration::from_secs(config.retry_interval_secs.max(1)),
+            backoff: RetryPolicy {
+                max_attempts: config.max_attempts.max(1),
+                initial_backoff: Duration::from_secs(config.initial_backoff_secs),
+                max_backoff: Duration::from_secs(config.max_backoff_secs),
+            },
+            timeout: None,
+        })
+    }
+
+    /// Abandon retries still running after `timeout` (`ingestion.log_timeout_secs`)
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Dead-letter a log that just failed for the first time
+    pub async fn push(&self, listener: &str, slot: u64, log: RpcLogsResponse, error: &ListenerError) {
+        let now = Utc::now();
+        let letter = DeadLetter {
+            listener: listener.to_string(),
+            slot,
+            log,
+            error: format!("{:#}", error),
+            attempts: 1,
+            failed_at: now,
+            next_attempt_at: now + self.backoff.delay(1),
+        };
+
+        match self.requeue(&letter).await {
+            Ok(()) => metrics::DEAD_LETTERS
+                .with_label_values(&[listener, "queued"])
+                .inc(),
+            Err(e) => error!(
+                "Failed to dead-letter {} log {}: {:#}",
+                listener, letter.log.signature, e
+            ),
+        }
+    }
+
+    async fn requeue(&self, letter: &DeadLetter) -> Result<()> {
+        self.lpush(&self.key, letter).await
+    }
+
+    async fn lpush(&self, key: &str, letter: &DeadLetter) -> Result<()> {
+        let mut conn = self.conn.clone();
+        let _: () = redis::cmd("LPUSH")
+            .arg(key)
+            .arg(serde_json::to_string(letter)?)
+            .query_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn pop(&self) -> Result<Option<String>> {
+        let mut conn = self.conn.clone();
+        Ok(redis::cmd("RPOP").arg(&self.key).query_async(&mut conn).await?)
+    }
+
+    async fn len(&self) -> Result<usize> {
+        let mut conn = self.conn.clone();
+        Ok(redis::cmd("LLEN").arg(&self.key).query_async(&mut conn).await?)
+    }
+
+    /// Periodically reprocess due entries through the listener that received them.
+    /// The signature is forgotten by the dedup first, or the retry would be skipped as a duplicate.
+    pub async fn run_retrier(
+        self: Arc<Self>,
+        handlers: HashMap<&'static str, Arc<dyn TokenListener>>,
+        dedup: Arc<SignatureDedup>,
+    ) {
+        loop {
+            tokio::time::sleep(self.retry_interval).await;
+            if let Err(e) = self.retry_due(&handlers, &dedup).await {
+                warn!("Dead-letter retry pass failed: {:#}", e);
+            }
+        }
+    }
+
+    /// One pass over the list; every entry is popped at most once
+    async fn retry_due(
+        &self,
+        handlers: &HashMap<&'static str, Arc<dyn TokenListener>>,
+        dedup: &SignatureDedup,
+    ) -> Result<()> {
+        for _ in 0..self.len().await? {
+            let Some(raw) = self.pop().await? else {
+                break;
+            };
+            let mut letter: DeadLetter = match serde_json::from_str(&raw) {
+                Ok(letter) => letter,
+                Err(e) => {
+                    warn!("Discarding unreadable dead letter: {}", e);
+                    continue;
+                }
+            };
+
+            if letter.next_attempt_at > Utc::now() {
+                self.requeue(&letter).await?;
+                continue;
+            }
+
+            let Some(listener) = handlers.get(letter.listener.as_str()) else {
+                // Listener disabled since; keep the entry for when it comes back
+                self.requeue(&letter).await?;
+                continue;
+            };
+
+            dedup.forget(&letter.log.signature).await;
+            let span = event_bus::log_span(listener.name(), letter.slot, &letter.log.signature);
+            let handling = event_bus::handle_log(
+                listener.as_ref(),
+                letter.slot,
+                &letter.log,
+                self.timeout,
+            )
+            .instrument(span);
+            match handling.await {
+                Ok(()) => {
+                    info!(
+                        "Recovered dead-lettered {} log {} after {} attempts",
+                        letter.listener, letter.log.signature, letter.attempts
+                    );
+                    metrics::DEAD_LETTERS
+                        .with_label_values(&[listener.name(), "recovered"])
+                        .inc();
+                }
+                Err(e) => {
+                    letter.attempts += 1;
+                    letter.error = format!("{:#}", e);
+                    letter.failed_at = Utc::now();
+
+                    // Only transient failures are worth another attempt
+                    if letter.attempts >= self.max_attempts || e.action() != ErrorAction::Retry {
+                        warn!(
+                            "Giving up on {} log {} after {} attempts: {}",
+                            letter.listener, letter.log.signature, letter.attempts, letter.error
+                        );
+                        metrics::DEAD_LETTERS
+                            .with_label_values(&[listener.name(), "failed"])
+                            .inc();
+                        self.lpush(&self.failed_key, &letter).await?;
+                    } else {
+                        letter.next_attempt_at = letter.failed_at + self.backoff.delay(letter.attempts);
+                        self.requeue(&letter).await?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::str::FromStr;
+    use std::sync::Mutex;
+
+    use base64::Engine;
+    use solana_sdk::pubkey::Pubkey;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::*;
+    use crate::event_bus::LogQueue;
+    use crate::fixtures::tests as fixture;
+    use crate::listeners::pumpfun::idl::TRADE_EVENT;
+    use crate::listeners::pumpfun::PumpFunListener;
+    use crate::mock_rpc::MockRpcServer;
+    use crate::processor::Processor;
+    use crate::rpc_pool::RpcPool;
+
+    type List = Arc<Mutex<VecDeque<String>>>;
+
+    /// A Redis speaking just enough RESP for the list commands of the queue
+    async fn redis() -> Result<(String, List)> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("redis://{}", listener.local_addr()?);
+        let list = List::default();
+        let served = list.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, served.clone()));
+            }
+        });
+        Ok((url, list))
+    }
+
+    async fn serve(stream: TcpStream, list: List) -> Result<()> {
+        let (read, mut write) = stream.into_split();
+        let mut read = BufReader::new(read);
+        loop {
+            let mut args = Vec::new();
+            for _ in 0..header(&mut read, '*').await? {
+                let mut arg = vec![0; header(&mut read, '$').await? + 2];
+                read.read_exact(&mut arg).await?;
+                arg.truncate(arg.len() - 2);
+                args.push(String::from_utf8(arg)?);
+            }
+            let reply = {
+                let mut list = list.lock().unwrap();
+                match args
+                    .first()
+                    .map(|command| command.to_uppercase())
+                    .as_deref()
+                {
+                    Some("LPUSH") => {
+                        for value in &args[2..] {
+                            list.push_front(value.clone());
+                        }
+                        format!(":{}\r\n", list.len())
+                    }
+                    Some("RPOP") => match list.pop_back() {
+                        Some(value) => format!("${}\r\n{}\r\n", value.len(), value),
+                        None => "$-1\r\n".to_string(),
+                    },
+                    Some("LLEN") => format!(":{}\r\n", list.len()),
+                    _ => "+OK\r\n".to_string(),
+                }
+            };
+            write.write_all(reply.as_bytes()).await?;
+        }
+    }
+
+    /// The length after a `*` or `$` line
+    async fn header(
+        read: &mut BufReader<tokio::net::tcp::OwnedReadHalf>,
+        kind: char,
+    ) -> Result<usize> {
+        let mut line = String::new();
+        if read.read_line(&mut line).await? == 0 {
+            anyhow::bail!("connection closed");
+        }
+        line.trim_end()
+            .strip_prefix(kind)
+            .and_then(|len| len.parse().ok())
+            .with_context(|| format!("unexpected {:?}", line))
+    }
+
+    /// The creator's buy of the recorded mint, as pump.fun logs it
+    fn dev_buy() -> Result<String> {
+        let mut data = TRADE_EVENT.to_vec();
+        data.extend_from_slice(Pubkey::from_str(fixture::CREATE_MINT)?.as_ref());
+        data.extend_from_slice(&1_000_000_000u64.to_le_bytes());
+        data.extend_from_slice(&34_612_903_225_806u64.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.extend_from_slice(&1_760_000_000i64.to_le_bytes());
+        data.extend_from_slice(&31_000_000_000u64.to_le_bytes());
+        data.extend_from_slice(&1_038_387_096_774_194u64.to_le_bytes());
+        Ok(format!(
+            "Program data: {}",
+            base64::engine::general_purpose::STANDARD.encode(data)
+        ))
+    }
+
+    /// The trades of a log that failed after recording them are not recorded
+    /// again when the dead letter is retried
+    #[tokio::test]
+    async fn retry_records_trades_once() -> Result<()> {
+        let node = MockRpcServer::from_fixtures(&fixture::fixtures_dir())?
+            .with_failures("getTransaction", 1)
+            .spawn(0, 0)
+            .await?;
+        let (redis_url, list) = redis().await?;
+
+        let mut config = fixture::config()?;
+        config.network.rpc_http_url = node.http_url.clone();
+        config.network.rpc_http_urls = Vec::new();
+        config.dlq.enabled = true;
+        config.dlq.initial_backoff_secs = 0;
+        config.volume.enabled = true;
+        let rpc = Arc::new(RpcPool::new(&config.network, &config.rate_limit)?);
+        let processor = Processor::with_sinks(&config, Vec::new())?;
+        let mut trades = processor.broadcast().subscribe_trades();
+        let dedup = Arc::new(SignatureDedup::new(&config.dedup, &config.database).await?);
+        let dlq = DeadLetterQueue::new(&config.dlq, &redis_url).await?;
+        let queue = LogQueue::new(&config.ingestion);
+        let listener = Arc::new(PumpFunListener::new(
+            config,
+            processor,
+            rpc,
+            dedup.clone(),
+            queue,
+            None,
+        ));
+
+        let mut recorded = fixture::create_log()?;
+        let consumed = recorded.log.logs.len() - 2;
+        recorded.log.logs.insert(consumed, dev_buy()?);
+        let error = event_bus::handle_log(listener.as_ref(), recorded.slot, &recorded.log, None)
+            .await
+            .expect_err("getTransaction fails the first time");
+        dlq.push("pumpfun", recorded.slot, recorded.log, &error)
+            .await;
+        assert_eq!(list.lock().unwrap().len(), 1);
+
+        let handlers = HashMap::from([("pumpfun", listener as Arc<dyn TokenListener>)]);
+        dlq.retry_due(&handlers, &dedup).await?;
+        assert!(list.lock().unwrap().is_empty(), "the retry should succeed");
+
+        let trade = trades.try_recv()?;
+        assert_eq!(trade.signature.as_deref(), Some(fixture::CREATE_SIGNATURE));
+        assert!(trades.try_recv().is_err(), "trade recorded twice");
+        Ok(())
+    }
+}
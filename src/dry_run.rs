@@ -0,0 +1,54 @@
+use std::io::Write;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::json;
+use tracing::warn;
+
+use crate::models::{Envelope, Token};
+use crate::sinks::EventSink;
+
+/// Stands in for every sink and notifier in a dry run (`--dry-run` or
+/// `dry_run = true`): what would have been published or alerted is written to
+/// stdout as JSON lines, `{"report": "event", "event": ...}` and
+/// `{"report": "notification", "notifier": ..., "token": ...}`
+pub struct DryRunReporter;
+
+impl DryRunReporter {
+    /// Report an alert `notifier` would have sent about `token`
+    pub fn notification(notifier: &str, token: &Token) {
+        let line = json!({
+            "report": "notification",
+            "notifier": notifier,
+            "token": token,
+        });
+        if let Err(e) = write_line(&line) {
+            warn!(
+                "Failed to report {} alert for {}: {}",
+                notifier, token.mint_address, e
+            );
+        }
+    }
+}
+
+#[async_trait]
+impl EventSink for DryRunReporter {
+    fn name(&self) -> &str {
+        "dry_run"
+    }
+
+    async fn send(&self, event: &Envelope) -> Result<()> {
+        write_line(&json!({
+            "report": "event",
+            "event": event,
+        }))
+    }
+}
+
+fn write_line(line: &serde_json::Value) -> Result<()> {
+    let line = serde_json::to_string(line)?;
+    let mut stdout = std::io::stdout().lock();
+    writeln!(stdout, "{}", line)?;
+    stdout.flush()?;
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use redis::aio::ConnectionManager;
+use tracing::info;
+
+use crate::config::{DatabaseBackend, DatabaseConfig, SequenceConfig};
+use crate::models::{Envelope, Event, SCHEMA_VERSION};
+use crate::sqlite::SqliteStore;
+
+/// The log notification whose processing published an event
+#[derive(Debug, Clone)]
+struct Origin {
+    slot: u64,
+    signature: String,
+}
+
+tokio::task_local! {
+    static ORIGIN: Origin;
+}
+
+/// Run `handling` (a listener handling one log) so the events it publishes carry
+/// `slot` and `signature`; tasks it spawns publish without them
+pub async fn from_log<F: Future>(slot: u64, signature: &str, handling: F) -> F::Output {
+    let origin = Origin {
+        slot,
+        signature: signature.to_string(),
+    };
+    ORIGIN.scope(origin, handling).await
+}
+
+/// Slot and signature of the log being handled, if any
+pub fn origin() -> (Option<u64>, Option<String>) {
+    match ORIGIN.try_with(Origin::clone) {
+        Ok(origin) => (Some(origin.slot), Some(origin.signature)),
+        Err(_) => (None, None),
+    }
+}
+
+/// Numbers published events 1, 2, 3... so consumers can spot gaps and duplicates.
+///
+/// With `sequence.persist` the counter is a Redis key incremented per event,
+/// shared by every process publishing to the same Redis and kept across restarts
+/// (a row of the SQLite database with `database.backend = "sqlite"`);
+/// otherwise it lives in memory and starts over at 1.
+pub struct Sequencer {
+    redis: Option<(ConnectionManager, String)>,
+    sqlite: Option<(SqliteStore, String)>,
+    next: AtomicU64,
+}
+
+impl Sequencer {
+    pub fn in_memory() -> Self {
+        Self {
+            redis: None,
+            sqlite: None,
+            next: AtomicU64::new(1),
+        }
+    }
+
+    pub async fn new(config: &SequenceConfig, database: &DatabaseConfig) -> Result<Self> {
+        if !config.persist {
+            return Ok(Self::in_memory());
+        }
+        if database.backend == DatabaseBackend::Sqlite {
+            let store = SqliteStore::open(&database.sqlite_path)?;
+            info!("Event sequence numbers kept in SQLite under {}", config.key);
+            return Ok(Self {
+                sqlite: Some((store, config.key.clone())),
+                ..Self::in_memory()
+            });
+        }
+        let client = redis::Client::open(database.redis_url.as_str())
+            .context("Invalid redis_url for sequence")?;
+        let conn = ConnectionManager::new(client)
+            .await
+            .context("Failed to connect to Redis for sequence")?;
+        info!("Event sequence numbers kept in Redis under {}", config.key);
+        Ok(Self {
+            redis: Some((conn, config.key.clone())),
+            ..Self::in_memory()
+        })
+    }
+
+    async fn next(&self) -> Result<u64> {
+        if let Some((store, key)) = &self.sqlite {
+            return store
+                .next_sequence(key)
+                .await
+                .context("Failed to take an event sequence number");
+        }
+        let Some((conn, key)) = &self.redis else {
+            return Ok(self.next.fetch_add(1, Ordering::Relaxed));
+        };
+        let mut conn = conn.clone();
+        redis::cmd("INCR")
+            .arg(key)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to take an event sequence number")
+    }
+
+    /// `event` with the next sequence number and, when published while handling
+    /// a log, its slot and signature
+    pub async fn wrap(&self, event: Event) -> Result<Envelope> {
+        let (slot, signature) = origin();
+        Ok(Envelope {
+            version: SCHEMA_VERSION,
+            seq: self.next().await?,
+            slot,
+            signature,
+            emitted_at: Utc::now(),
+            payload: event,
+        })
+    }
+}
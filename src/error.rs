@@ -0,0 +1,147 @@
+use std::time::Duration;
+
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::rpc_custom_error::{
+    JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE, JSON_RPC_SERVER_ERROR_BLOCK_STATUS_NOT_AVAILABLE_YET,
+    JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_UNREACHABLE,
+    JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED,
+};
+use solana_client::rpc_request::RpcError;
+use thiserror::Error;
+
+use crate::{rate_limit, rpc_pool};
+
+/// What a caller should do about a failed log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorAction {
+    /// Try again later, e.g. through the dead-letter queue
+    Retry,
+    /// Nothing to do, the log simply did not yield a token
+    Skip,
+    /// Retrying will not help and someone should look at it
+    Alert,
+}
+
+/// Why turning a log into a token failed
+#[derive(Debug, Error)]
+pub enum EnrichError {
+    /// Network trouble, throttling or a node that is behind
+    #[error("{method} failed transiently: {source}")]
+    RpcTransient {
+        method: &'static str,
+        #[source]
+        source: ClientError,
+    },
+    /// The node rejected the request
+    #[error("{method} failed: {source}")]
+    RpcFatal {
+        method: &'static str,
+        #[source]
+        source: ClientError,
+    },
+    /// The transaction or account is not there (yet) or not what the parser expects
+    #[error("{0}")]
+    ParseMiss(String),
+}
+
+impl EnrichError {
+    /// Classify a failed RPC call
+    pub fn rpc(method: &'static str, source: ClientError) -> Self {
+        if is_missing(&source) {
+            Self::ParseMiss(format!("{} returned nothing: {}", method, source))
+        } else if is_transient(&source) {
+            Self::RpcTransient { method, source }
+        } else {
+            Self::RpcFatal { method, source }
+        }
+    }
+
+    pub fn action(&self) -> ErrorAction {
+        match self {
+            Self::RpcTransient { .. } => ErrorAction::Retry,
+            Self::RpcFatal { .. } => ErrorAction::Alert,
+            Self::ParseMiss(_) => ErrorAction::Skip,
+        }
+    }
+}
+
+/// Why a listener failed to handle a log
+#[derive(Debug, Error)]
+pub enum ListenerError {
+    #[error(transparent)]
+    Enrich(#[from] EnrichError),
+    /// A sink could not take the event, e.g. its queue was full
+    #[error("sink {sink} failed: {message}")]
+    SinkFailure { sink: String, message: String },
+    /// Processing outlived `ingestion.log_timeout_secs`
+    #[error("gave up after {0:?}")]
+    TimedOut(Duration),
+    /// Anything not classified above; retried like a transient failure
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl ListenerError {
+    pub fn action(&self) -> ErrorAction {
+        match self {
+            Self::Enrich(e) => e.action(),
+            Self::SinkFailure { .. } => ErrorAction::Alert,
+            Self::TimedOut(_) | Self::Other(_) => ErrorAction::Retry,
+        }
+    }
+
+    /// Label for `pumpfun_dropped_messages_total{reason}`
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Self::Enrich(EnrichError::RpcTransient { .. }) => "rpc_transient",
+            Self::Enrich(EnrichError::RpcFatal { .. }) => "rpc_fatal",
+            Self::Enrich(EnrichError::ParseMiss(_)) => "parse_miss",
+            Self::SinkFailure { .. } => "sink_failure",
+            Self::TimedOut(_) => "timeout",
+            Self::Other(_) => "process_error",
+        }
+    }
+}
+
+impl From<anyhow::Error> for ListenerError {
+    /// Keeps the classification of errors that were propagated with `?`
+    fn from(error: anyhow::Error) -> Self {
+        let error = match error.downcast::<ListenerError>() {
+            Ok(e) => return e,
+            Err(error) => error,
+        };
+        match error.downcast::<EnrichError>() {
+            Ok(e) => Self::Enrich(e),
+            Err(error) => Self::Other(error),
+        }
+    }
+}
+
+/// The node answered `null` where a value was expected (e.g. a transaction it has not indexed)
+pub fn is_missing(error: &ClientError) -> bool {
+    matches!(error.kind(), ClientErrorKind::SerdeJson(e) if e.is_data())
+}
+
+fn is_transient(error: &ClientError) -> bool {
+    if rpc_pool::is_endpoint_failure(error) || rate_limit::rate_limit_hint(error).is_some() {
+        return true;
+    }
+    matches!(
+        error.kind(),
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. })
+            if matches!(
+                *code,
+                JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE
+                    | JSON_RPC_SERVER_ERROR_BLOCK_STATUS_NOT_AVAILABLE_YET
+                    | JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_UNREACHABLE
+                    | JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED
+            )
+    )
+}
+
+/// The WSS connection under a subscription stopped delivering keepalive
+/// notifications (half-open) or closed; after a healthy session the supervisor
+/// resubscribes at once instead of backing off
+#[derive(Debug, Error)]
+#[error("WSS connection lost: {0}")]
+pub struct ConnectionLost(pub String);
@@ -0,0 +1,637 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use solana_client::rpc_response::RpcLogsResponse;
+use tokio::sync::Notify;
+use tokio::task::JoinSet;
+use tracing::{debug, error, field, info, info_span, Instrument, Span};
+use uuid::Uuid;
+
+use crate::config::{IngestionConfig, OverflowPolicy};
+use crate::cursor::SlotCursor;
+use crate::dlq::DeadLetterQueue;
+use crate::error::{ErrorAction, ListenerError};
+use crate::fixtures::FixtureRecorder;
+use crate::listeners::TokenListener;
+use crate::log_classifier::{LogClassifier, LogFlag};
+use crate::{envelope, metrics};
+
+/// A log notification waiting to be processed by the listener that received it
+pub struct LogJob {
+    pub listener: &'static str,
+    /// Slot of the notification, for the cursor
+    pub slot: u64,
+    pub log: RpcLogsResponse,
+    kind: LogKind,
+    enqueued_at: Instant,
+}
+
+/// What a log notification carries, as far as its instruction logs tell,
+/// for `queue_overflow = "drop_trades"` and the drop counters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LogKind {
+    Create,
+    Trade,
+    Other,
+}
+
+/// Launch and trade instructions of every listener's programs
+static KIND_CLASSIFIER: LazyLock<LogClassifier> = LazyLock::new(|| {
+    LogClassifier::builder()
+        .contains("Instruction: Create", LogFlag::Create)
+        .contains("Instruction: InitializeMint", LogFlag::Create)
+        .contains("Instruction: InitializePool", LogFlag::Create)
+        .contains("Instruction: InitializeV2", LogFlag::Create)
+        .contains("Instruction: InitializeWithToken2022", LogFlag::Create)
+        .contains("Instruction: InitializeVirtualPool", LogFlag::Create)
+        .contains("Instruction: InitializeLbPair", LogFlag::Create)
+        .contains(
+            "Instruction: InitializeCustomizablePermissionlessLbPair",
+            LogFlag::Create,
+        )
+        // Plain `Initialize` prefixes InitializeAccount3, which buys log too
+        .line("Program log: Instruction: Initialize", LogFlag::Create)
+        .contains("Instruction: Buy", LogFlag::Buy)
+        .contains("Instruction: Sell", LogFlag::Sell)
+        .contains("Instruction: Swap", LogFlag::Swap)
+        .contains("SwapEvent", LogFlag::Swap)
+        .build()
+});
+
+impl LogKind {
+    fn of(log: &RpcLogsResponse) -> Self {
+        let flags = KIND_CLASSIFIER.classify(&log.logs);
+        // Launches often buy in the same transaction, creation wins
+        if flags.contains(LogFlag::Create) {
+            Self::Create
+        } else if flags.is_trade() {
+            Self::Trade
+        } else {
+            Self::Other
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Create => "create",
+            Self::Trade => "trade",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// Bounded queue between the log subscriptions and the processing workers.
+///
+/// Listeners only push onto it, so a slow RPC enrichment or sink never holds
+/// up the WSS stream; what happens when it is full is set by `queue_overflow`.
+/// With `prioritize_creations`, queued creations are taken before anything else.
+#[derive(Clone)]
+pub struct LogQueue {
+    shared: Arc<Shared>,
+}
+
+/// The two priority tiers, sharing one capacity
+struct Jobs {
+    creations: VecDeque<LogJob>,
+    rest: VecDeque<LogJob>,
+    prioritize: bool,
+}
+
+impl Jobs {
+    fn len(&self) -> usize {
+        self.creations.len() + self.rest.len()
+    }
+
+    fn push_back(&mut self, job: LogJob) {
+        if self.prioritize && job.kind == LogKind::Create {
+            self.creations.push_back(job);
+        } else {
+            self.rest.push_back(job);
+        }
+    }
+
+    /// Next job for a worker, creations first
+    fn pop_front(&mut self) -> Option<LogJob> {
+        self.creations.pop_front().or_else(|| self.rest.pop_front())
+    }
+
+    /// Remove the job queued the longest, whatever its tier
+    fn pop_oldest(&mut self) -> Option<LogJob> {
+        match (self.creations.front(), self.rest.front()) {
+            (Some(creation), Some(other)) if creation.enqueued_at <= other.enqueued_at => {
+                self.creations.pop_front()
+            }
+            (Some(_), None) => self.creations.pop_front(),
+            _ => self.rest.pop_front(),
+        }
+    }
+
+    /// Remove the oldest queued non-creation, or the oldest job when only
+    /// creations are queued
+    fn pop_trade(&mut self) -> Option<LogJob> {
+        match self
+            .rest
+            .iter()
+            .position(|queued| queued.kind != LogKind::Create)
+        {
+            Some(index) => self.rest.remove(index),
+            None => self.pop_oldest(),
+        }
+    }
+}
+
+struct Shared {
+    jobs: Mutex<Jobs>,
+    capacity: usize,
+    policy: OverflowPolicy,
+    not_empty: Notify,
+    not_full: Notify,
+    closed: AtomicBool,
+}
+
+impl LogQueue {
+    pub fn new(config: &IngestionConfig) -> Self {
+        let capacity = config.queue_capacity.max(1);
+        Self {
+            shared: Arc::new(Shared {
+                jobs: Mutex::new(Jobs {
+                    creations: VecDeque::new(),
+                    rest: VecDeque::with_capacity(capacity),
+                    prioritize: config.prioritize_creations,
+                }),
+                capacity,
+                policy: config.queue_overflow,
+                not_empty: Notify::new(),
+                not_full: Notify::new(),
+                closed: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Jobs> {
+        self.shared.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Queue a log notification for `listener`, applying the overflow policy when full.
+    /// Logs pushed after [`LogQueue::close`] are discarded.
+    pub async fn push(&self, listener: &'static str, slot: u64, log: RpcLogsResponse) {
+        let kind = LogKind::of(&log);
+        let mut job = LogJob {
+            listener,
+            slot,
+            log,
+            kind,
+            enqueued_at: Instant::now(),
+        };
+
+        loop {
+            {
+                let mut jobs = self.lock();
+                if self.is_closed() {
+                    return;
+                }
+                if jobs.len() < self.shared.capacity {
+                    jobs.push_back(job);
+                    break;
+                }
+
+                match self.shared.policy {
+                    OverflowPolicy::DropNewest => {
+                        drop(jobs);
+                        record_drop(listener, kind);
+                        return;
+                    }
+                    OverflowPolicy::DropOldest => {
+                        evict(jobs.pop_oldest());
+                        jobs.push_back(job);
+                        break;
+                    }
+                    OverflowPolicy::DropTrades => {
+                        if kind != LogKind::Create {
+                            drop(jobs);
+                            record_drop(listener, kind);
+                            return;
+                        }
+                        evict(jobs.pop_trade());
+                        jobs.push_back(job);
+                        break;
+                    }
+                    OverflowPolicy::Block => {}
+                }
+            }
+
+            // Block: wait for a worker to make room, then try again
+            self.shared.not_full.notified().await;
+            job.enqueued_at = Instant::now();
+        }
+
+        metrics::LOG_QUEUE_DEPTH.with_label_values(&[listener]).inc();
+        self.shared.not_empty.notify_one();
+    }
+
+    /// Stop accepting logs; workers drain what is queued and then stop
+    pub fn close(&self) {
+        self.shared.closed.store(true, Ordering::Release);
+        self.shared.not_empty.notify_waiters();
+        self.shared.not_full.notify_waiters();
+    }
+
+    fn is_closed(&self) -> bool {
+        self.shared.closed.load(Ordering::Acquire)
+    }
+
+    /// Next queued job, waiting until one is available; `None` once closed and drained
+    pub async fn pop(&self) -> Option<LogJob> {
+        loop {
+            let notified = self.shared.not_empty.notified();
+            let next = self.lock().pop_front();
+            if let Some(job) = next {
+                metrics::LOG_QUEUE_DEPTH
+                    .with_label_values(&[job.listener])
+                    .dec();
+                metrics::LOG_QUEUE_WAIT_SECONDS
+                    .with_label_values(&[job.listener])
+                    .observe(job.enqueued_at.elapsed().as_secs_f64());
+                self.shared.not_full.notify_one();
+                return Some(job);
+            }
+            if self.is_closed() {
+                return None;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Account for a queued job dropped to make room
+fn evict(evicted: Option<LogJob>) {
+    if let Some(evicted) = evicted {
+        metrics::LOG_QUEUE_DEPTH
+            .with_label_values(&[evicted.listener])
+            .dec();
+        record_drop(evicted.listener, evicted.kind);
+    }
+}
+
+fn record_drop(listener: &str, kind: LogKind) {
+    metrics::DROPPED_MESSAGES
+        .with_label_values(&[listener, "queue_full"])
+        .inc();
+    metrics::LOG_QUEUE_DROPPED
+        .with_label_values(&[listener, kind.as_str()])
+        .inc();
+}
+
+/// Run `workers` tasks that take jobs off the queue and hand each one to the
+/// listener that received it, so a burst of launches is enriched in parallel.
+/// Returns once the queue is closed and drained (or every worker has stopped).
+pub async fn run_workers(
+    queue: LogQueue,
+    handlers: HashMap<&'static str, Arc<dyn TokenListener>>,
+    workers: usize,
+    timeout: Option<Duration>,
+    cursor: Arc<SlotCursor>,
+    dlq: Option<Arc<DeadLetterQueue>>,
+    recorder: Option<Arc<FixtureRecorder>>,
+) {
+    let handlers = Arc::new(handlers);
+    let mut tasks = JoinSet::new();
+    for _ in 0..workers.max(1) {
+        tasks.spawn(run_worker(
+            queue.clone(),
+            handlers.clone(),
+            timeout,
+            cursor.clone(),
+            dlq.clone(),
+            recorder.clone(),
+        ));
+    }
+    info!("Started {} log processing workers", workers.max(1));
+
+    while let Some(result) = tasks.join_next().await {
+        if let Err(e) = result {
+            error!("Log worker crashed: {}", e);
+        }
+    }
+}
+
+async fn run_worker(
+    queue: LogQueue,
+    handlers: Arc<HashMap<&'static str, Arc<dyn TokenListener>>>,
+    timeout: Option<Duration>,
+    cursor: Arc<SlotCursor>,
+    dlq: Option<Arc<DeadLetterQueue>>,
+    recorder: Option<Arc<FixtureRecorder>>,
+) {
+    while let Some(job) = queue.pop().await {
+        let Some(listener) = handlers.get(job.listener) else {
+            error!("No handler registered for {} logs", job.listener);
+            continue;
+        };
+        if let Some(recorder) = &recorder {
+            recorder.record_log(job.listener, job.slot, &job.log);
+        }
+
+        let span = log_span(job.listener, job.slot, &job.log.signature);
+        handle_job(job, listener.as_ref(), timeout, &cursor, dlq.as_deref())
+            .instrument(span)
+            .await;
+    }
+}
+
+async fn handle_job(
+    job: LogJob,
+    listener: &dyn TokenListener,
+    timeout: Option<Duration>,
+    cursor: &SlotCursor,
+    dlq: Option<&DeadLetterQueue>,
+) {
+    let signature = job.log.signature.as_str();
+    metrics::WORKERS_BUSY.inc();
+    let result = handle_log(listener, job.slot, &job.log, timeout).await;
+    metrics::WORKERS_BUSY.dec();
+    cursor.record(job.listener, job.slot, signature);
+
+    if let Err(e) = result {
+        metrics::DROPPED_MESSAGES
+            .with_label_values(&[job.listener, e.reason()])
+            .inc();
+        match e.action() {
+            ErrorAction::Skip => debug!("Skipped {} log {}: {}", job.listener, signature, e),
+            ErrorAction::Alert => error!("Error processing {} log {}: {}", job.listener, signature, e),
+            ErrorAction::Retry => {
+                error!("Error processing {} log {}: {}", job.listener, signature, e);
+                if let Some(dlq) = dlq {
+                    dlq.push(job.listener, job.slot, job.log, &e).await;
+                }
+            }
+        }
+    }
+}
+
+/// Hand `log` to `listener`, abandoning it once `timeout` has passed so a hung
+/// RPC or HTTP call cannot hold the worker
+pub async fn handle_log(
+    listener: &dyn TokenListener,
+    slot: u64,
+    log: &RpcLogsResponse,
+    timeout: Option<Duration>,
+) -> Result<(), ListenerError> {
+    let handling = envelope::from_log(slot, &log.signature, listener.handle_log(slot, log));
+    let Some(timeout) = timeout else {
+        return handling.await;
+    };
+    tokio::time::timeout(timeout, handling)
+        .await
+        .unwrap_or(Err(ListenerError::TimedOut(timeout)))
+}
+
+/// Span around everything done for one log notification, so each line it logs
+/// carries the same `trace_id`; `mint` is recorded once a token was parsed
+pub fn log_span(listener: &str, slot: u64, signature: &str) -> Span {
+    info_span!(
+        "log",
+        trace_id = %Uuid::new_v4().simple(),
+        listener,
+        slot,
+        signature,
+        mint = field::Empty,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    const LISTENER: &str = "pumpfun";
+
+    fn queue(capacity: usize, policy: OverflowPolicy, prioritize: bool) -> LogQueue {
+        let mut config = Config::load().expect("config loads").ingestion;
+        config.queue_capacity = capacity;
+        config.queue_overflow = policy;
+        config.prioritize_creations = prioritize;
+        LogQueue::new(&config)
+    }
+
+    fn log(signature: &str, instruction: &str) -> RpcLogsResponse {
+        RpcLogsResponse {
+            signature: signature.to_string(),
+            err: None,
+            logs: vec![format!("Program log: Instruction: {}", instruction)],
+        }
+    }
+
+    async fn push(queue: &LogQueue, signature: &str, instruction: &str) {
+        queue.push(LISTENER, 1, log(signature, instruction)).await;
+    }
+
+    /// Signatures left in the queue, in the order workers would take them
+    async fn drain(queue: &LogQueue) -> Vec<String> {
+        queue.close();
+        let mut signatures = Vec::new();
+        while let Some(job) = queue.pop().await {
+            signatures.push(job.log.signature);
+        }
+        signatures
+    }
+
+    /// The kind by the per-line rules the classifier replaced
+    fn per_line_kind(log: &RpcLogsResponse) -> LogKind {
+        let mut kind = LogKind::Other;
+        for line in &log.logs {
+            let Some(instruction) = line.strip_prefix("Program log: Instruction: ") else {
+                if line.contains("SwapEvent") {
+                    kind = LogKind::Trade;
+                }
+                continue;
+            };
+            let account_setup = instruction.starts_with("InitializeAccount")
+                || instruction.starts_with("InitializeImmutableOwner");
+            if (instruction.starts_with("Create") || instruction.starts_with("Initialize"))
+                && !account_setup
+            {
+                return LogKind::Create;
+            }
+            if instruction.starts_with("Buy")
+                || instruction.starts_with("Sell")
+                || instruction.starts_with("Swap")
+            {
+                kind = LogKind::Trade;
+            }
+        }
+        kind
+    }
+
+    #[test]
+    fn log_kind_matches_the_per_line_rules() {
+        // Instructions of the listened programs and the token programs they call
+        let instructions = [
+            "Create",
+            "CreateV2",
+            "CreateIdempotent",
+            "CreatePool",
+            "CreateMetadataAccountV3",
+            "Initialize",
+            "InitializeMint",
+            "InitializeMint2",
+            "InitializeAccount",
+            "InitializeAccount3",
+            "InitializeImmutableOwner",
+            "InitializePool",
+            "InitializeV2",
+            "InitializeWithToken2022",
+            "InitializeVirtualPoolWithSplToken",
+            "InitializeVirtualPoolWithToken2022",
+            "InitializeLbPair",
+            "InitializeLbPair2",
+            "InitializeCustomizablePermissionlessLbPair",
+            "Buy",
+            "BuyExactIn",
+            "Sell",
+            "SellExactIn",
+            "Swap",
+            "Swap2",
+            "SwapBaseInput",
+            "Transfer",
+            "TransferChecked",
+            "SyncNative",
+            "CloseAccount",
+            "Migrate",
+        ];
+        let mut logs: Vec<RpcLogsResponse> = instructions
+            .iter()
+            .map(|instruction| log(instruction, instruction))
+            .collect();
+        let lines = |lines: &[&str]| RpcLogsResponse {
+            signature: lines.join(" "),
+            err: None,
+            logs: lines.iter().map(|line| line.to_string()).collect(),
+        };
+        logs.extend([
+            lines(&["Program log: SwapEvent { amount_in: 1 }"]),
+            lines(&["Program 6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P invoke [1]"]),
+            lines(&[
+                "Program log: Instruction: InitializeAccount3",
+                "Program log: Instruction: Buy",
+            ]),
+            lines(&[
+                "Program log: Instruction: Buy",
+                "Program log: Instruction: Create",
+            ]),
+            lines(&[]),
+        ]);
+
+        for log in &logs {
+            assert_eq!(LogKind::of(log), per_line_kind(log), "{}", log.signature);
+        }
+    }
+
+    #[test]
+    fn only_launch_initializations_are_creations() {
+        // The per-line rules took any Initialize* for a launch
+        for instruction in [
+            "InitializePosition",
+            "InitializeBinArray",
+            "InitializeReward",
+        ] {
+            assert_eq!(LogKind::of(&log(instruction, instruction)), LogKind::Other);
+        }
+    }
+
+    #[tokio::test]
+    async fn drop_newest_discards_incoming_logs() {
+        let queue = queue(2, OverflowPolicy::DropNewest, false);
+        push(&queue, "a", "Buy").await;
+        push(&queue, "b", "Create").await;
+        push(&queue, "c", "Create").await;
+        assert_eq!(drain(&queue).await, ["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_evicts_the_head() {
+        let queue = queue(2, OverflowPolicy::DropOldest, false);
+        push(&queue, "a", "Create").await;
+        push(&queue, "b", "Buy").await;
+        push(&queue, "c", "Sell").await;
+        assert_eq!(drain(&queue).await, ["b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn drop_trades_keeps_creations() {
+        let queue = queue(2, OverflowPolicy::DropTrades, false);
+        push(&queue, "trade", "Buy").await;
+        push(&queue, "create", "Create").await;
+        // An incoming trade is dropped, an incoming creation evicts a trade
+        push(&queue, "late trade", "Sell").await;
+        push(&queue, "second create", "Create").await;
+        assert_eq!(drain(&queue).await, ["create", "second create"]);
+
+        // With only creations queued the oldest makes room
+        let queue = self::queue(2, OverflowPolicy::DropTrades, false);
+        push(&queue, "a", "Create").await;
+        push(&queue, "b", "Create").await;
+        push(&queue, "c", "Create").await;
+        assert_eq!(drain(&queue).await, ["b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn block_waits_for_room() {
+        let queue = queue(1, OverflowPolicy::Block, false);
+        push(&queue, "a", "Buy").await;
+
+        let blocked = tokio::spawn({
+            let queue = queue.clone();
+            async move { push(&queue, "b", "Buy").await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!blocked.is_finished());
+
+        let first = queue.pop().await.expect("queued log");
+        assert_eq!(first.log.signature, "a");
+        tokio::time::timeout(Duration::from_secs(1), blocked)
+            .await
+            .expect("push unblocked")
+            .expect("push task");
+        assert_eq!(drain(&queue).await, ["b"]);
+    }
+
+    #[tokio::test]
+    async fn creations_are_taken_first() {
+        let queue = queue(10, OverflowPolicy::DropOldest, true);
+        push(&queue, "buy", "Buy").await;
+        push(&queue, "create", "Create").await;
+        push(&queue, "other", "Withdraw").await;
+        push(&queue, "create and buy", "Create").await;
+        push(&queue, "sell", "Sell").await;
+        assert_eq!(
+            drain(&queue).await,
+            ["create", "create and buy", "buy", "other", "sell"]
+        );
+
+        // Without prioritize_creations the queue is first in, first out
+        let queue = self::queue(10, OverflowPolicy::DropOldest, false);
+        push(&queue, "buy", "Buy").await;
+        push(&queue, "create", "Create").await;
+        assert_eq!(drain(&queue).await, ["buy", "create"]);
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_evicts_across_tiers() {
+        let queue = queue(2, OverflowPolicy::DropOldest, true);
+        push(&queue, "create", "Create").await;
+        push(&queue, "buy", "Buy").await;
+        push(&queue, "sell", "Sell").await;
+        assert_eq!(drain(&queue).await, ["buy", "sell"]);
+    }
+
+    #[tokio::test]
+    async fn closed_queue_discards_pushes() {
+        let queue = queue(2, OverflowPolicy::Block, false);
+        queue.close();
+        push(&queue, "a", "Create").await;
+        assert!(queue.pop().await.is_none());
+    }
+}
@@ -0,0 +1,908 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use moka::future::Cache;
+use reqwest::Client;
+use solana_client::rpc_config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::Transaction;
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use tracing::{debug, info, warn};
+
+use crate::config::{Config, ExecutionConfig};
+use crate::listeners::pumpfun::idl::{BUY, SELL};
+use crate::metrics;
+use crate::models::{Position, Token, TokenSource};
+use crate::outbound;
+use crate::rpc_pool::RpcPool;
+use crate::{listener_helpers, token_helper};
+
+const FEE_PROGRAM: &str = "pfeeUxB6jkeY1Hxd7CsFCAjcbHA9rWtchMGdZ6VojVZ";
+const SYSTEM_PROGRAM: &str = "11111111111111111111111111111111";
+const COMPUTE_BUDGET_PROGRAM: &str = "ComputeBudget111111111111111111111111111111";
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+/// How often a sent transaction's status is checked
+const CONFIRM_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Bonding curve account: discriminator, virtual token / virtual SOL / real
+/// token / real SOL reserves and total supply (u64 each), complete, creator
+const CURVE_VIRTUAL_TOKEN_OFFSET: usize = 8;
+const CURVE_VIRTUAL_SOL_OFFSET: usize = 16;
+const CURVE_REAL_TOKEN_OFFSET: usize = 24;
+const CURVE_COMPLETE_OFFSET: usize = 48;
+const CURVE_CREATOR_OFFSET: usize = 49;
+/// Global account: discriminator, initialized, authority, fee recipient,
+/// initial reserves and supply, fee basis points, withdraw authority,
+/// enable migrate, pool migration fee, creator fee basis points
+const GLOBAL_FEE_BPS_OFFSET: usize = 105;
+const GLOBAL_CREATOR_FEE_BPS_OFFSET: usize = 154;
+
+/// The sniper for `config`, `None` unless `execution.enabled`
+pub fn sniper(config: &Config, rpc: &Arc<RpcPool>) -> Result<Option<Arc<Sniper>>> {
+    if !config.execution.enabled {
+        return Ok(None);
+    }
+    let program = Pubkey::from_str(config.programs.pump_fun_program())
+        .context("Invalid pump.fun program id")?;
+    Ok(Some(Arc::new(Sniper::new(rpc.clone(), config, program)?)))
+}
+
+/// Buys pump.fun tokens still on their bonding curve once they pass the
+/// filters with at least `execution.min_score`, and sells them again.
+///
+/// With `dry_run` (the default) a signed buy is only simulated and sells are
+/// only quoted; otherwise both are sent through the RPC endpoints, or the Jito
+/// block engine with a tip.
+pub struct Sniper {
+    rpc: Arc<RpcPool>,
+    http: Client,
+    config: ExecutionConfig,
+    /// Below the quoted proceeds a sell accepts, in basis points
+    sell_slippage_bps: u64,
+    program: Pubkey,
+    keypair: Option<Arc<Keypair>>,
+    /// Mints already bought (or tried), so a token is bought once
+    attempted: Cache<String, ()>,
+    /// When the buys of the last hour were made
+    recent: Mutex<VecDeque<DateTime<Utc>>>,
+}
+
+/// A buy that went through, or would have in a dry run
+#[derive(Debug, Clone)]
+pub struct Fill {
+    /// `None` in a dry run
+    pub signature: Option<String>,
+    /// Received, or quoted from the curve in a dry run
+    pub token_amount: u64,
+    /// Spent with fees, or `buy_sol` in a dry run
+    pub cost_lamports: u64,
+}
+
+/// A sell that went through, or would have in a dry run
+#[derive(Debug, Clone)]
+pub struct Sale {
+    /// `None` in a dry run
+    pub signature: Option<String>,
+    /// Received after fees, or quoted from the curve in a dry run
+    pub proceeds_lamports: u64,
+}
+
+/// What the buy and sell instructions need from a bonding curve
+struct Curve {
+    address: Pubkey,
+    token_program: Pubkey,
+    creator: Pubkey,
+    virtual_tokens: u64,
+    virtual_sol: u64,
+    real_tokens: u64,
+    /// Protocol and creator fee charged on top of buys and taken from sells
+    fee_bps: u64,
+}
+
+impl Curve {
+    /// Tokens `lamports` buy with fees, constant product on the virtual reserves
+    fn buy_quote(&self, lamports: u64) -> u64 {
+        let net = u128::from(lamports) * 10_000 / (10_000 + u128::from(self.fee_bps));
+        let tokens = u128::from(self.virtual_tokens) * net / (u128::from(self.virtual_sol) + net);
+        (tokens as u64).min(self.real_tokens)
+    }
+
+    /// Lamports `token_amount` sells for after fees
+    fn sell_quote(&self, token_amount: u64) -> u64 {
+        let gross = u128::from(self.virtual_sol) * u128::from(token_amount)
+            / (u128::from(self.virtual_tokens) + u128::from(token_amount));
+        let fees = (gross * u128::from(self.fee_bps)).div_ceil(10_000);
+        gross.saturating_sub(fees) as u64
+    }
+}
+
+impl Sniper {
+    pub fn new(rpc: Arc<RpcPool>, config: &Config, program: Pubkey) -> Result<Self> {
+        let execution = &config.execution;
+        let keypair = load_keypair(execution)?.map(Arc::new);
+        match (&keypair, execution.dry_run) {
+            (Some(keypair), true) => info!(
+                "Execution in dry run: buys by {} are simulated, not sent",
+                keypair.pubkey()
+            ),
+            (None, true) => info!("Execution in dry run without a keypair: buys are only logged"),
+            (Some(keypair), false) => warn!(
+                "Execution LIVE: {} buys {} SOL of tokens scoring {}+",
+                keypair.pubkey(),
+                execution.buy_sol,
+                execution.min_score
+            ),
+            (None, false) => bail!("execution.dry_run = false needs a keypair"),
+        }
+
+        Ok(Self {
+            rpc,
+            http: outbound::client_builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .context("Failed to build Jito HTTP client")?,
+            config: execution.clone(),
+            sell_slippage_bps: config.positions.slippage_bps,
+            program,
+            keypair,
+            attempted: Cache::builder()
+                .time_to_live(Duration::from_secs(24 * 3600))
+                .build(),
+            recent: Mutex::new(VecDeque::new()),
+        })
+    }
+
+    /// Whether `token` is one to buy
+    pub fn qualifies(&self, token: &Token) -> bool {
+        token.source == TokenSource::Pumpfun
+            && token
+                .score
+                .is_some_and(|score| score >= self.config.min_score)
+    }
+
+    /// Buy `token` unless it was bought before or `max_buys_per_hour` is
+    /// reached; `None` if nothing was (or would have been) bought
+    pub async fn snipe(&self, token: &Token) -> Option<Fill> {
+        let fresh = self
+            .attempted
+            .entry(token.mint_address.clone())
+            .or_insert(())
+            .await
+            .is_fresh();
+        if !fresh {
+            return None;
+        }
+        if !self.take_slot() {
+            // Claimed above; a later token event may still buy it
+            self.attempted.invalidate(&token.mint_address).await;
+            warn!(
+                "execution.max_buys_per_hour reached, not buying {}",
+                token.mint_address
+            );
+            metrics::SNIPES.with_label_values(&["rate_limited"]).inc();
+            return None;
+        }
+        match self.buy(token).await {
+            Ok(fill) => fill,
+            Err(e) => {
+                warn!("Buy of {} failed: {:#}", token.mint_address, e);
+                metrics::SNIPES.with_label_values(&["failed"]).inc();
+                None
+            }
+        }
+    }
+
+    /// Count a buy against `max_buys_per_hour`, `false` if none is left
+    fn take_slot(&self) -> bool {
+        let now = Utc::now();
+        let mut recent = self.recent.lock().expect("execution lock poisoned");
+        while recent
+            .front()
+            .is_some_and(|at| now - *at >= chrono::Duration::hours(1))
+        {
+            recent.pop_front();
+        }
+        if recent.len() >= self.config.max_buys_per_hour {
+            return false;
+        }
+        recent.push_back(now);
+        true
+    }
+
+    async fn buy(&self, token: &Token) -> Result<Option<Fill>> {
+        let mint = Pubkey::from_str(&token.mint_address).context("Invalid mint")?;
+        let Some(curve) = self.curve(&mint).await? else {
+            info!("Bonding curve of {} is complete, not buying", mint);
+            return Ok(None);
+        };
+        let lamports = (self.config.buy_sol * LAMPORTS_PER_SOL) as u64;
+        let token_amount = curve.buy_quote(lamports);
+        let max_sol_cost = lamports + lamports * self.config.slippage_bps / 10_000;
+        if token_amount == 0 {
+            bail!("Nothing left to buy on the curve");
+        }
+        let fill = Fill {
+            signature: None,
+            token_amount,
+            cost_lamports: lamports,
+        };
+
+        let Some(keypair) = &self.keypair else {
+            info!(
+                "Dry run: would buy {} tokens of {} ({}) for at most {} lamports, score {:?}",
+                token_amount,
+                token.symbol.as_deref().unwrap_or("UNKNOWN"),
+                mint,
+                max_sol_cost,
+                token.score
+            );
+            metrics::SNIPES.with_label_values(&["simulated"]).inc();
+            return Ok(Some(fill));
+        };
+        let user = keypair.pubkey();
+        let user_ata =
+            get_associated_token_address_with_program_id(&user, &mint, &curve.token_program);
+        let mut data = BUY.to_vec();
+        data.extend_from_slice(&token_amount.to_le_bytes());
+        data.extend_from_slice(&max_sol_cost.to_le_bytes());
+        // track_volume
+        data.push(0);
+        let transaction = self
+            .transaction(
+                keypair,
+                vec![
+                    create_ata_idempotent(&user, &user_ata, &mint, &curve.token_program),
+                    self.buy_instruction(&user, &user_ata, &mint, &curve, data)?,
+                ],
+            )
+            .await?;
+
+        if self.config.dry_run {
+            if !self.simulate(&transaction, &mint).await? {
+                return Ok(None);
+            }
+            info!(
+                "Dry run: buy of {} tokens of {} for at most {} lamports would succeed",
+                token_amount, mint, max_sol_cost
+            );
+            return Ok(Some(fill));
+        }
+        let signature = self.send(&transaction).await?;
+        metrics::SNIPES.with_label_values(&["sent"]).inc();
+        info!(
+            "Sent buy of {} tokens of {} for at most {} lamports: {}",
+            token_amount, mint, max_sol_cost, signature
+        );
+
+        let tx = self.confirm(&signature).await?;
+        let received =
+            listener_helpers::token_balance_gain(&tx, &user.to_string(), &mint.to_string());
+        if received == 0 {
+            bail!("Buy {} confirmed without tokens received", signature);
+        }
+        let spent = listener_helpers::fee_payer_balance_change(&tx)
+            .context("Buy transaction has no balances")?;
+        metrics::SNIPES.with_label_values(&["confirmed"]).inc();
+        let fill = Fill {
+            signature: Some(signature.to_string()),
+            token_amount: u64::try_from(received).unwrap_or(u64::MAX),
+            cost_lamports: u64::try_from(-spent).unwrap_or(0),
+        };
+        info!(
+            "Bought {} tokens of {} for {} lamports: {}",
+            fill.token_amount, mint, fill.cost_lamports, signature
+        );
+        Ok(Some(fill))
+    }
+
+    /// Sell all of `position` on its bonding curve; a dry-run position is only quoted
+    pub async fn sell(&self, position: &Position) -> Result<Sale> {
+        let mint = Pubkey::from_str(&position.mint).context("Invalid mint")?;
+        let curve = self
+            .curve(&mint)
+            .await?
+            .context("Bonding curve complete, sell on the pool instead")?;
+        let proceeds_lamports = curve.sell_quote(position.token_amount);
+        let min_sol_output =
+            proceeds_lamports - proceeds_lamports * self.sell_slippage_bps.min(10_000) / 10_000;
+
+        let keypair = match &self.keypair {
+            Some(keypair) if !position.dry_run => keypair,
+            _ => {
+                info!(
+                    "Dry run: would sell {} tokens of {} for about {} lamports",
+                    position.token_amount, mint, proceeds_lamports
+                );
+                return Ok(Sale {
+                    signature: None,
+                    proceeds_lamports,
+                });
+            }
+        };
+        let user = keypair.pubkey();
+        let mut data = SELL.to_vec();
+        data.extend_from_slice(&position.token_amount.to_le_bytes());
+        data.extend_from_slice(&min_sol_output.to_le_bytes());
+        let transaction = self
+            .transaction(
+                keypair,
+                vec![self.sell_instruction(&user, &mint, &curve, data)?],
+            )
+            .await?;
+        let signature = self.send(&transaction).await?;
+        info!(
+            "Sent sell of {} tokens of {} for at least {} lamports: {}",
+            position.token_amount, mint, min_sol_output, signature
+        );
+
+        let tx = self.confirm(&signature).await?;
+        let received = listener_helpers::fee_payer_balance_change(&tx)
+            .context("Sell transaction has no balances")?;
+        Ok(Sale {
+            signature: Some(signature.to_string()),
+            proceeds_lamports: u64::try_from(received).unwrap_or(0),
+        })
+    }
+
+    /// Wait for `signature` to reach `network.commitment` and fetch its
+    /// transaction; an error if it failed or did not land in time
+    async fn confirm(
+        &self,
+        signature: &Signature,
+    ) -> Result<EncodedConfirmedTransactionWithStatusMeta> {
+        let commitment = self.rpc.commitment();
+        let deadline = Instant::now() + Duration::from_secs(self.config.confirm_timeout_secs);
+        loop {
+            let status = self
+                .rpc
+                .call("getSignatureStatuses", |client| async move {
+                    client.get_signature_statuses(&[*signature]).await
+                })
+                .await
+                .map(|response| response.value.into_iter().next().flatten());
+            match status {
+                Ok(Some(status)) if status.err.is_some() => {
+                    bail!("Transaction {} failed: {:?}", signature, status.err)
+                }
+                Ok(Some(status)) if status.satisfies_commitment(commitment) => break,
+                Ok(_) => {}
+                Err(e) => debug!("Failed to check the status of {}: {}", signature, e),
+            }
+            if Instant::now() >= deadline {
+                bail!(
+                    "Transaction {} not {:?} after {}s",
+                    signature,
+                    commitment.commitment,
+                    self.config.confirm_timeout_secs
+                );
+            }
+            tokio::time::sleep(CONFIRM_POLL_INTERVAL).await;
+        }
+
+        listener_helpers::fetch_transaction_with_retry(&self.rpc, signature)
+            .await?
+            .with_context(|| format!("Confirmed transaction {} unavailable", signature))
+    }
+
+    /// The bonding curve of `mint` with the fees of the global account,
+    /// `None` once it is complete
+    async fn curve(&self, mint: &Pubkey) -> Result<Option<Curve>> {
+        let (address, _) =
+            Pubkey::find_program_address(&[b"bonding-curve", mint.as_ref()], &self.program);
+        let (global, _) = Pubkey::find_program_address(&[b"global"], &self.program);
+        let mut accounts =
+            token_helper::load_accounts(&self.rpc, &[*mint, address, global]).await?;
+        let (Some(mint_account), Some(curve), Some(global)) =
+            (accounts.remove(0), accounts.remove(0), accounts.remove(0))
+        else {
+            bail!("Mint, bonding curve or global account not found");
+        };
+
+        let data = &curve.data;
+        if data
+            .get(CURVE_COMPLETE_OFFSET)
+            .is_none_or(|complete| *complete != 0)
+        {
+            return Ok(None);
+        }
+        let creator = data
+            .get(CURVE_CREATOR_OFFSET..CURVE_CREATOR_OFFSET + 32)
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .map(Pubkey::new_from_array)
+            .context("Bonding curve account too short")?;
+        Ok(Some(Curve {
+            address,
+            token_program: mint_account.owner,
+            creator,
+            virtual_tokens: read_u64(data, CURVE_VIRTUAL_TOKEN_OFFSET, "Bonding curve")?,
+            virtual_sol: read_u64(data, CURVE_VIRTUAL_SOL_OFFSET, "Bonding curve")?,
+            real_tokens: read_u64(data, CURVE_REAL_TOKEN_OFFSET, "Bonding curve")?,
+            fee_bps: fee_bps(&global.data, &creator)?,
+        }))
+    }
+
+    /// `instructions` with the compute budget and Jito tip, signed by `keypair`
+    async fn transaction(
+        &self,
+        keypair: &Keypair,
+        instructions: Vec<Instruction>,
+    ) -> Result<Transaction> {
+        let user = keypair.pubkey();
+        let mut all = vec![
+            set_compute_unit_limit(self.config.compute_unit_limit),
+            set_compute_unit_price(self.config.priority_fee_micro_lamports),
+        ];
+        all.extend(instructions);
+        if let Some(tip) = self.config.jito_tip_lamports {
+            let tip_account = Pubkey::from_str(&self.config.jito_tip_account)
+                .context("Invalid execution.jito_tip_account")?;
+            all.push(transfer(&user, &tip_account, tip));
+        }
+
+        let blockhash = self
+            .rpc
+            .call("getLatestBlockhash", |client| async move {
+                client.get_latest_blockhash().await
+            })
+            .await
+            .context("Failed to get a recent blockhash")?;
+        Ok(Transaction::new_signed_with_payer(
+            &all,
+            Some(&user),
+            &[keypair],
+            blockhash,
+        ))
+    }
+
+    fn buy_instruction(
+        &self,
+        user: &Pubkey,
+        user_ata: &Pubkey,
+        mint: &Pubkey,
+        curve: &Curve,
+        data: Vec<u8>,
+    ) -> Result<Instruction> {
+        let pda = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &self.program).0;
+        let (fee_recipient, fee_config, fee_program) = self.fee_accounts()?;
+        Ok(Instruction {
+            program_id: self.program,
+            accounts: vec![
+                AccountMeta::new_readonly(pda(&[b"global"]), false),
+                AccountMeta::new(fee_recipient, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new(curve.address, false),
+                AccountMeta::new(curve_ata(curve, mint), false),
+                AccountMeta::new(*user_ata, false),
+                AccountMeta::new(*user, true),
+                AccountMeta::new_readonly(system_program(), false),
+                AccountMeta::new_readonly(curve.token_program, false),
+                AccountMeta::new(pda(&[b"creator-vault", curve.creator.as_ref()]), false),
+                AccountMeta::new_readonly(pda(&[b"__event_authority"]), false),
+                AccountMeta::new_readonly(self.program, false),
+                AccountMeta::new(pda(&[b"global_volume_accumulator"]), false),
+                AccountMeta::new(pda(&[b"user_volume_accumulator", user.as_ref()]), false),
+                AccountMeta::new_readonly(fee_config, false),
+                AccountMeta::new_readonly(fee_program, false),
+            ],
+            data,
+        })
+    }
+
+    fn sell_instruction(
+        &self,
+        user: &Pubkey,
+        mint: &Pubkey,
+        curve: &Curve,
+        data: Vec<u8>,
+    ) -> Result<Instruction> {
+        let pda = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &self.program).0;
+        let (fee_recipient, fee_config, fee_program) = self.fee_accounts()?;
+        let user_ata =
+            get_associated_token_address_with_program_id(user, mint, &curve.token_program);
+        // Unlike buy, creator_vault comes before the token program
+        Ok(Instruction {
+            program_id: self.program,
+            accounts: vec![
+                AccountMeta::new_readonly(pda(&[b"global"]), false),
+                AccountMeta::new(fee_recipient, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new(curve.address, false),
+                AccountMeta::new(curve_ata(curve, mint), false),
+                AccountMeta::new(user_ata, false),
+                AccountMeta::new(*user, true),
+                AccountMeta::new_readonly(system_program(), false),
+                AccountMeta::new(pda(&[b"creator-vault", curve.creator.as_ref()]), false),
+                AccountMeta::new_readonly(curve.token_program, false),
+                AccountMeta::new_readonly(pda(&[b"__event_authority"]), false),
+                AccountMeta::new_readonly(self.program, false),
+                AccountMeta::new_readonly(fee_config, false),
+                AccountMeta::new_readonly(fee_program, false),
+            ],
+            data,
+        })
+    }
+
+    /// The fee recipient, fee config and fee program of buys and sells
+    fn fee_accounts(&self) -> Result<(Pubkey, Pubkey, Pubkey)> {
+        let fee_program = Pubkey::from_str(FEE_PROGRAM).expect("valid fee program id");
+        let fee_recipient = Pubkey::from_str(&self.config.fee_recipient)
+            .context("Invalid execution.fee_recipient")?;
+        let (fee_config, _) =
+            Pubkey::find_program_address(&[b"fee_config", self.program.as_ref()], &fee_program);
+        Ok((fee_recipient, fee_config, fee_program))
+    }
+
+    /// Whether `transaction` would succeed
+    async fn simulate(&self, transaction: &Transaction, mint: &Pubkey) -> Result<bool> {
+        let commitment = self.rpc.commitment();
+        let simulation = self
+            .rpc
+            .call("simulateTransaction", |client| async move {
+                client
+                    .simulate_transaction_with_config(
+                        transaction,
+                        RpcSimulateTransactionConfig {
+                            commitment: Some(commitment),
+                            ..RpcSimulateTransactionConfig::default()
+                        },
+                    )
+                    .await
+            })
+            .await
+            .context("Failed to simulate the buy")?
+            .value;
+
+        match simulation.err {
+            None => {
+                metrics::SNIPES.with_label_values(&["simulated"]).inc();
+                Ok(true)
+            }
+            Some(error) => {
+                metrics::SNIPES
+                    .with_label_values(&["simulation_failed"])
+                    .inc();
+                warn!(
+                    "Dry run: buy of {} would fail: {:?}, logs: {:?}",
+                    mint, error, simulation.logs
+                );
+                Ok(false)
+            }
+        }
+    }
+
+    /// Through the Jito block engine with a tip set, the RPC endpoints otherwise
+    async fn send(&self, transaction: &Transaction) -> Result<Signature> {
+        match self.config.jito_tip_lamports {
+            Some(_) => self.send_jito(transaction).await,
+            None => self.send_rpc(transaction).await,
+        }
+    }
+
+    async fn send_rpc(&self, transaction: &Transaction) -> Result<Signature> {
+        self.rpc
+            .call("sendTransaction", |client| async move {
+                client
+                    .send_transaction_with_config(
+                        transaction,
+                        RpcSendTransactionConfig {
+                            skip_preflight: true,
+                            ..RpcSendTransactionConfig::default()
+                        },
+                    )
+                    .await
+            })
+            .await
+            .context("Failed to send the transaction")
+    }
+
+    async fn send_jito(&self, transaction: &Transaction) -> Result<Signature> {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(
+            bincode::serialize(transaction).context("Failed to serialize the transaction")?,
+        );
+        let response: serde_json::Value = self
+            .http
+            .post(&self.config.jito_url)
+            .json(&serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "sendTransaction",
+                "params": [encoded, {"encoding": "base64"}],
+            }))
+            .send()
+            .await
+            .context("Failed to reach the Jito block engine")?
+            .json()
+            .await
+            .context("Invalid Jito response")?;
+        if let Some(error) = response.get("error") {
+            bail!("Jito rejected the transaction: {}", error);
+        }
+        let signature = response
+            .get("result")
+            .and_then(|result| result.as_str())
+            .context("Jito response has no signature")?;
+        Signature::from_str(signature).context("Invalid signature from Jito")
+    }
+}
+
+fn load_keypair(config: &ExecutionConfig) -> Result<Option<Keypair>> {
+    let bytes = if let Some(secret) = &config.keypair {
+        bs58::decode(secret.trim())
+            .into_vec()
+            .context("execution.keypair is not base58")?
+    } else if let Some(path) = &config.keypair_path {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read execution.keypair_path {}", path))?;
+        serde_json::from_str::<Vec<u8>>(&json)
+            .with_context(|| format!("{} is not a keypair file", path))?
+    } else {
+        return Ok(None);
+    };
+    Keypair::try_from(bytes.as_slice())
+        .map(Some)
+        .map_err(|e| anyhow::anyhow!("Invalid execution keypair: {}", e))
+}
+
+fn read_u64(data: &[u8], offset: usize, account: &str) -> Result<u64> {
+    data.get(offset..offset + 8)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+        .with_context(|| format!("{} account too short", account))
+}
+
+/// Fee of a trade on a curve by `creator`, from the global account; curves
+/// without a creator pay no creator fee
+fn fee_bps(global: &[u8], creator: &Pubkey) -> Result<u64> {
+    let protocol = read_u64(global, GLOBAL_FEE_BPS_OFFSET, "Global")?;
+    if *creator == Pubkey::default() {
+        return Ok(protocol);
+    }
+    Ok(protocol + read_u64(global, GLOBAL_CREATOR_FEE_BPS_OFFSET, "Global")?)
+}
+
+fn curve_ata(curve: &Curve, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(&curve.address, mint, &curve.token_program)
+}
+
+fn system_program() -> Pubkey {
+    Pubkey::from_str(SYSTEM_PROGRAM).expect("valid system program id")
+}
+
+fn set_compute_unit_limit(units: u32) -> Instruction {
+    let mut data = vec![2];
+    data.extend_from_slice(&units.to_le_bytes());
+    Instruction {
+        program_id: Pubkey::from_str(COMPUTE_BUDGET_PROGRAM).expect("valid compute budget id"),
+        accounts: Vec::new(),
+        data,
+    }
+}
+
+fn set_compute_unit_price(micro_lamports: u64) -> Instruction {
+    let mut data = vec![3];
+    data.extend_from_slice(&micro_lamports.to_le_bytes());
+    Instruction {
+        program_id: Pubkey::from_str(COMPUTE_BUDGET_PROGRAM).expect("valid compute budget id"),
+        accounts: Vec::new(),
+        data,
+    }
+}
+
+fn create_ata_idempotent(
+    payer: &Pubkey,
+    ata: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: spl_associated_token_account::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*ata, false),
+            AccountMeta::new_readonly(*payer, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program(), false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+        // CreateIdempotent
+        data: vec![1],
+    }
+}
+
+fn transfer(from: &Pubkey, to: &Pubkey, lamports: u64) -> Instruction {
+    // SystemInstruction::Transfer
+    let mut data = 2u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&lamports.to_le_bytes());
+    Instruction {
+        program_id: system_program(),
+        accounts: vec![AccountMeta::new(*from, true), AccountMeta::new(*to, false)],
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::engine::general_purpose::STANDARD;
+    use serde_json::{json, Value};
+    use solana_program::program_option::COption;
+    use solana_sdk::hash::Hash;
+
+    use super::*;
+    use crate::fixtures::tests as fixture;
+    use crate::listener_helpers::build_token;
+    use crate::mock_rpc::{MockRpcHandle, MockRpcServer};
+    use crate::token_helper::{MintInfo, MintProgramType, TokenInfo};
+
+    /// A fresh pump.fun curve
+    fn curve(fee_bps: u64) -> Curve {
+        Curve {
+            address: Pubkey::new_unique(),
+            token_program: spl_token::id(),
+            creator: Pubkey::new_unique(),
+            virtual_tokens: 1_073_000_000_000_000,
+            virtual_sol: 30_000_000_000,
+            real_tokens: 793_100_000_000_000,
+            fee_bps,
+        }
+    }
+
+    #[test]
+    fn buy_quote_without_fees() {
+        assert_eq!(curve(0).buy_quote(1_000_000_000), 34_612_903_225_806);
+        assert_eq!(curve(0).buy_quote(0), 0);
+    }
+
+    #[test]
+    fn buy_quote_spends_the_fees_first() {
+        // 1 SOL with 1.25% on top leaves 987_654_320 lamports for the curve
+        assert_eq!(curve(125).buy_quote(1_000_000_000), 34_199_203_154_141);
+    }
+
+    #[test]
+    fn buy_quote_is_capped_by_real_reserves() {
+        assert_eq!(curve(125).buy_quote(u64::MAX), 793_100_000_000_000);
+    }
+
+    #[test]
+    fn sell_quote_without_fees() {
+        assert_eq!(curve(0).sell_quote(34_612_903_225_806), 937_499_999);
+        assert_eq!(curve(0).sell_quote(0), 0);
+    }
+
+    #[test]
+    fn sell_quote_deducts_the_fees_rounded_up() {
+        // 937_499_999 minus 11_718_750
+        assert_eq!(curve(125).sell_quote(34_612_903_225_806), 925_781_249);
+    }
+
+    #[test]
+    fn fee_bps_from_the_global_account() {
+        let mut global = vec![0; 200];
+        global[GLOBAL_FEE_BPS_OFFSET..GLOBAL_FEE_BPS_OFFSET + 8]
+            .copy_from_slice(&95u64.to_le_bytes());
+        global[GLOBAL_CREATOR_FEE_BPS_OFFSET..GLOBAL_CREATOR_FEE_BPS_OFFSET + 8]
+            .copy_from_slice(&30u64.to_le_bytes());
+
+        assert_eq!(fee_bps(&global, &Pubkey::new_unique()).unwrap(), 125);
+        assert_eq!(fee_bps(&global, &Pubkey::default()).unwrap(), 95);
+        assert!(fee_bps(&global[..100], &Pubkey::new_unique()).is_err());
+    }
+
+    /// An account as `getMultipleAccounts` returns it
+    fn account(owner: &Pubkey, data: &[u8]) -> Value {
+        json!({
+            "lamports": 1_000_000_000u64,
+            "owner": owner.to_string(),
+            "data": [STANDARD.encode(data), "base64"],
+            "executable": false,
+            "rentEpoch": 0,
+            "space": data.len(),
+        })
+    }
+
+    /// A node holding the curve of `mint` and accepting any transaction,
+    /// whose status is `status`
+    async fn node(mint: &Pubkey, status: Value) -> Result<MockRpcHandle> {
+        let program = Pubkey::from_str(fixture::config()?.programs.pump_fun_program())?;
+        let (curve, _) = Pubkey::find_program_address(&[b"bonding-curve", mint.as_ref()], &program);
+        let (global, _) = Pubkey::find_program_address(&[b"global"], &program);
+        let mut curve_data = vec![0; 81];
+        for (offset, value) in [
+            (CURVE_VIRTUAL_TOKEN_OFFSET, 1_073_000_000_000_000u64),
+            (CURVE_VIRTUAL_SOL_OFFSET, 30_000_000_000),
+            (CURVE_REAL_TOKEN_OFFSET, 793_100_000_000_000),
+        ] {
+            curve_data[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+        }
+        let context = |value| json!({ "context": { "slot": 1 }, "value": value });
+
+        MockRpcServer::new()
+            .with_account(&mint.to_string(), account(&spl_token::id(), &[0; 82]))
+            .with_account(&curve.to_string(), account(&program, &curve_data))
+            .with_account(&global.to_string(), account(&program, &[0; 200]))
+            .with_answer(
+                "getLatestBlockhash",
+                context(json!({
+                    "blockhash": Hash::new_unique().to_string(),
+                    "lastValidBlockHeight": 1_000,
+                })),
+            )
+            .with_answer("getSignatureStatuses", context(json!([status])))
+            .spawn(0, 0)
+            .await
+    }
+
+    /// A live sniper with a fresh keypair on `node`
+    fn sniper(node: &MockRpcHandle) -> Result<Sniper> {
+        let mut config = fixture::config()?;
+        config.network.rpc_http_url = node.http_url.clone();
+        config.network.rpc_http_urls = Vec::new();
+        config.execution.enabled = true;
+        config.execution.dry_run = false;
+        config.execution.keypair = Some(bs58::encode(Keypair::new().to_bytes()).into_string());
+        config.execution.jito_tip_lamports = None;
+        config.execution.confirm_timeout_secs = 1;
+        let rpc = Arc::new(RpcPool::new(&config.network, &config.rate_limit)?);
+        let program = Pubkey::from_str(config.programs.pump_fun_program())?;
+        Sniper::new(rpc, &config, program)
+    }
+
+    fn token(mint: &Pubkey) -> Token {
+        let mint_info = MintInfo {
+            program: MintProgramType::Token,
+            decimals: 6,
+            supply: 1_000_000_000_000_000,
+            mint_authority: COption::None,
+            freeze_authority: COption::None,
+            extensions: Default::default(),
+        };
+        let info = TokenInfo {
+            name: "Moon".to_string(),
+            symbol: "MOON".to_string(),
+            uri: None,
+            decimals: Some(6),
+            pair: None,
+        };
+        build_token(
+            mint,
+            TokenSource::Pumpfun,
+            None,
+            Utc::now(),
+            &mint_info,
+            info,
+        )
+    }
+
+    /// A buy sent but failed, or never landed, fills nothing and so opens no position
+    #[tokio::test]
+    async fn unconfirmed_buys_fill_nothing() -> Result<()> {
+        let failed = json!({
+            "slot": 1,
+            "confirmations": null,
+            "status": { "Err": { "InstructionError": [3, { "Custom": 6002 }] } },
+            "err": { "InstructionError": [3, { "Custom": 6002 }] },
+            "confirmationStatus": "confirmed",
+        });
+        let sent = metrics::SNIPES.with_label_values(&["sent"]);
+        let failures = metrics::SNIPES.with_label_values(&["failed"]);
+
+        for status in [failed, Value::Null] {
+            let mint = Pubkey::new_unique();
+            let node = node(&mint, status).await?;
+            let (sent_before, failed_before) = (sent.get(), failures.get());
+
+            assert!(sniper(&node)?.snipe(&token(&mint)).await.is_none());
+            assert_eq!(sent.get(), sent_before + 1);
+            assert_eq!(failures.get(), failed_before + 1);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,576 @@
+//! A small boolean expression language for the publish filter, e.g.
+//!
+//! ```text
+//! score > 60 && holder_count > 20 && !name_matches("test")
+//! ```
+//!
+//! Supports `&&`, `||`, `!`, unary `-`, parentheses, the comparisons
+//! `== != > >= < <=`, number/string/bool/`null` literals, token fields by name and the functions
+//! `name_matches(re)` / `symbol_matches(re)` (case-insensitive regex).
+
+use anyhow::{anyhow, bail, Context, Result};
+use bigdecimal::ToPrimitive;
+use regex::{Regex, RegexBuilder};
+
+use crate::models::Token;
+
+const FIELDS: &[&str] = &[
+    "mint",
+    "name",
+    "symbol",
+    "source",
+    "creator",
+    "decimals",
+    "score",
+    "risk_level",
+    "holder_count",
+    "top_10_holder_percentage",
+    "dev_buy_percentage",
+    "funding_source",
+    "creator_fresh_wallet",
+    "creator_blacklisted",
+    "impersonation_of",
+    "live_socials",
+    "sellable",
+    "frozen_holders",
+    "lp_burned",
+    "liquidity_locked",
+    "liquidity_sol",
+    "price_sol",
+    "price_usd",
+    "market_cap_usd",
+    "bonding_curve_progress",
+    "mint_authority_disabled",
+    "freeze_authority_disabled",
+    "has_transfer_hook",
+    "has_permanent_delegate",
+    "transfer_fee_bps",
+];
+
+/// A parsed filter expression, compiled once and evaluated per token
+#[derive(Debug)]
+pub struct FilterExpr {
+    source: String,
+    root: Expr,
+}
+
+impl FilterExpr {
+    pub fn parse(source: &str) -> Result<Self> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let root = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            bail!("Unexpected {:?} at end of filter expression", token);
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            root,
+        })
+    }
+
+    /// `true` when the token should be published. Type errors evaluate to `false`.
+    pub fn matches(&self, token: &Token) -> bool {
+        match eval(&self.root, token) {
+            Value::Bool(b) => b,
+            other => {
+                tracing::warn!(
+                    "Filter expression {:?} did not evaluate to a bool (got {:?})",
+                    self.source,
+                    other
+                );
+                false
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Expr {
+    Literal(Value),
+    Field(String),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, CmpOp, Box<Expr>),
+    NameMatches(Regex),
+    SymbolMatches(Regex),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Str(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Ident(String),
+    Number(f64),
+    Str(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Tok>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push(Tok::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Tok::RParen);
+                i += 1;
+            }
+            '"' | '\'' => {
+                let quote = c;
+                let start = i + 1;
+                let end = chars[start..]
+                    .iter()
+                    .position(|&ch| ch == quote)
+                    .map(|p| start + p)
+                    .ok_or_else(|| anyhow!("Unterminated string in filter expression"))?;
+                tokens.push(Tok::Str(chars[start..end].iter().collect()));
+                i = end + 1;
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                if text.matches('.').count() > 1 {
+                    bail!("Invalid number {:?} in filter expression", text);
+                }
+                let number = text
+                    .parse()
+                    .with_context(|| format!("Invalid number {:?} in filter expression", text))?;
+                tokens.push(Tok::Number(number));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                tokens.push(Tok::Ident(chars[start..i].iter().collect()));
+            }
+            _ => {
+                let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+                let op = match two.as_str() {
+                    "&&" => "&&",
+                    "||" => "||",
+                    "==" => "==",
+                    "!=" => "!=",
+                    ">=" => ">=",
+                    "<=" => "<=",
+                    _ => match c {
+                        '>' => ">",
+                        '<' => "<",
+                        '!' => "!",
+                        '-' => "-",
+                        _ => bail!("Unexpected character {:?} in filter expression", c),
+                    },
+                };
+                i += op.len();
+                tokens.push(Tok::Op(op));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Tok>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Tok> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Tok> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat_op(&mut self, op: &str) -> bool {
+        if matches!(self.peek(), Some(Tok::Op(o)) if *o == op) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: Tok) -> Result<()> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            other => bail!(
+                "Expected {:?} in filter expression, found {:?}",
+                expected,
+                other
+            ),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut left = self.parse_and()?;
+        while self.eat_op("||") {
+            let right = self.parse_and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut left = self.parse_unary()?;
+        while self.eat_op("&&") {
+            let right = self.parse_unary()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        if self.eat_op("!") {
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr> {
+        let left = self.parse_primary()?;
+
+        let op = match self.peek() {
+            Some(Tok::Op("==")) => CmpOp::Eq,
+            Some(Tok::Op("!=")) => CmpOp::Ne,
+            Some(Tok::Op(">")) => CmpOp::Gt,
+            Some(Tok::Op(">=")) => CmpOp::Ge,
+            Some(Tok::Op("<")) => CmpOp::Lt,
+            Some(Tok::Op("<=")) => CmpOp::Le,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+
+        let right = self.parse_primary()?;
+        Ok(Expr::Compare(Box::new(left), op, Box::new(right)))
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Tok::Number(n)) => Ok(Expr::Literal(Value::Number(n))),
+            Some(Tok::Str(s)) => Ok(Expr::Literal(Value::Str(s))),
+            Some(Tok::Op("-")) => Ok(Expr::Neg(Box::new(self.parse_primary()?))),
+            Some(Tok::LParen) => {
+                let inner = self.parse_or()?;
+                self.expect(Tok::RParen)?;
+                Ok(inner)
+            }
+            Some(Tok::Ident(ident)) => match ident.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                "name_matches" | "symbol_matches" => {
+                    self.expect(Tok::LParen)?;
+                    let pattern = match self.next() {
+                        Some(Tok::Str(pattern)) => pattern,
+                        other => bail!("{} expects a string argument, found {:?}", ident, other),
+                    };
+                    self.expect(Tok::RParen)?;
+
+                    let regex = RegexBuilder::new(&pattern)
+                        .case_insensitive(true)
+                        .build()
+                        .with_context(|| format!("Invalid regex {:?} in {}", pattern, ident))?;
+                    Ok(if ident == "name_matches" {
+                        Expr::NameMatches(regex)
+                    } else {
+                        Expr::SymbolMatches(regex)
+                    })
+                }
+                field if FIELDS.contains(&field) => Ok(Expr::Field(ident)),
+                _ => bail!(
+                    "Unknown field {:?} in filter expression, expected one of {}",
+                    ident,
+                    FIELDS.join(", ")
+                ),
+            },
+            other => bail!("Unexpected {:?} in filter expression", other),
+        }
+    }
+}
+
+fn eval(expr: &Expr, token: &Token) -> Value {
+    match expr {
+        Expr::Literal(value) => value.clone(),
+        Expr::Field(name) => field_value(name, token),
+        Expr::Not(inner) => match eval(inner, token) {
+            Value::Bool(b) => Value::Bool(!b),
+            _ => Value::Null,
+        },
+        Expr::Neg(inner) => match eval(inner, token) {
+            Value::Number(n) => Value::Number(-n),
+            _ => Value::Null,
+        },
+        Expr::And(left, right) => {
+            Value::Bool(truthy(eval(left, token)) && truthy(eval(right, token)))
+        }
+        Expr::Or(left, right) => {
+            Value::Bool(truthy(eval(left, token)) || truthy(eval(right, token)))
+        }
+        Expr::Compare(left, op, right) => {
+            Value::Bool(compare(&eval(left, token), *op, &eval(right, token)))
+        }
+        Expr::NameMatches(regex) => {
+            Value::Bool(token.name.as_deref().is_some_and(|n| regex.is_match(n)))
+        }
+        Expr::SymbolMatches(regex) => {
+            Value::Bool(token.symbol.as_deref().is_some_and(|s| regex.is_match(s)))
+        }
+    }
+}
+
+fn truthy(value: Value) -> bool {
+    matches!(value, Value::Bool(true))
+}
+
+/// Comparisons against `null` (missing data) are false, except `== null` / `!= null`
+fn compare(left: &Value, op: CmpOp, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => match op {
+            CmpOp::Eq => a == b,
+            CmpOp::Ne => a != b,
+            CmpOp::Gt => a > b,
+            CmpOp::Ge => a >= b,
+            CmpOp::Lt => a < b,
+            CmpOp::Le => a <= b,
+        },
+        (Value::Str(a), Value::Str(b)) => match op {
+            CmpOp::Eq => a.eq_ignore_ascii_case(b),
+            CmpOp::Ne => !a.eq_ignore_ascii_case(b),
+            _ => false,
+        },
+        _ => match op {
+            CmpOp::Eq => left == right,
+            CmpOp::Ne => left != right,
+            _ => false,
+        },
+    }
+}
+
+fn field_value(name: &str, token: &Token) -> Value {
+    let opt_str = |v: Option<&String>| v.map(|s| Value::Str(s.clone())).unwrap_or(Value::Null);
+    let opt_num = |v: Option<f64>| v.map(Value::Number).unwrap_or(Value::Null);
+
+    match name {
+        "mint" => Value::Str(token.mint_address.clone()),
+        "name" => opt_str(token.name.as_ref()),
+        "symbol" => opt_str(token.symbol.as_ref()),
+        "source" => Value::Str(token.source.to_string()),
+        "creator" => opt_str(token.creator.as_ref()),
+        "decimals" => Value::Number(f64::from(token.decimals)),
+        "score" => opt_num(token.score.map(f64::from)),
+        "risk_level" => token
+            .risk_level
+            .as_ref()
+            .map(|r| Value::Str(r.to_string()))
+            .unwrap_or(Value::Null),
+        "holder_count" => opt_num(token.holder_count.map(f64::from)),
+        "top_10_holder_percentage" => opt_num(
+            token
+                .top_10_holder_percentage
+                .as_ref()
+                .and_then(|v| v.to_f64()),
+        ),
+        "dev_buy_percentage" => opt_num(token.dev_buy_percentage.as_ref().and_then(|v| v.to_f64())),
+        "funding_source" => token
+            .creator_funding
+            .as_ref()
+            .map(|f| Value::Str(f.source.to_string()))
+            .unwrap_or(Value::Null),
+        "creator_fresh_wallet" => token
+            .creator_funding
+            .as_ref()
+            .map(|f| Value::Bool(f.fresh_wallet))
+            .unwrap_or(Value::Null),
+        "creator_blacklisted" => Value::Bool(token.creator_blacklisted),
+        "impersonation_of" => opt_str(token.impersonation_of.as_ref()),
+        "lp_burned" => token.lp_burned.map(Value::Bool).unwrap_or(Value::Null),
+        "liquidity_locked" => token
+            .liquidity_locked
+            .map(Value::Bool)
+            .unwrap_or(Value::Null),
+        "frozen_holders" => opt_num(token.frozen_holders.map(f64::from)),
+        "sellable" => token.sellable.map(Value::Bool).unwrap_or(Value::Null),
+        "live_socials" => {
+            Value::Number(token.socials.iter().filter(|link| link.live).count() as f64)
+        }
+        "price_sol" => opt_num(token.price_sol.as_ref().and_then(|v| v.to_f64())),
+        "price_usd" => opt_num(token.price_usd.as_ref().and_then(|v| v.to_f64())),
+        "market_cap_usd" => opt_num(token.market_cap_usd.as_ref().and_then(|v| v.to_f64())),
+        "bonding_curve_progress" => opt_num(
+            token
+                .bonding_curve_progress
+                .as_ref()
+                .and_then(|v| v.to_f64()),
+        ),
+        "liquidity_sol" => opt_num(token.liquidity_sol.as_ref().and_then(|v| v.to_f64())),
+        "mint_authority_disabled" => Value::Bool(token.mint_authority_disabled),
+        "freeze_authority_disabled" => Value::Bool(token.freeze_authority_disabled),
+        "has_transfer_hook" => Value::Bool(token.has_transfer_hook),
+        "has_permanent_delegate" => Value::Bool(token.has_permanent_delegate),
+        "transfer_fee_bps" => opt_num(token.transfer_fee_bps.map(f64::from)),
+        _ => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use solana_program::program_option::COption;
+    use solana_sdk::pubkey::Pubkey;
+
+    use super::*;
+    use crate::listener_helpers::build_token;
+    use crate::models::TokenSource;
+    use crate::token_helper::{MintInfo, MintProgramType, TokenInfo};
+
+    fn token(name: &str, symbol: &str, score: Option<i32>) -> Token {
+        let mint = MintInfo {
+            program: MintProgramType::Token,
+            decimals: 6,
+            supply: 1_000_000_000_000_000,
+            mint_authority: COption::None,
+            freeze_authority: COption::None,
+            extensions: Default::default(),
+        };
+        let info = TokenInfo {
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            uri: None,
+            decimals: Some(6),
+            pair: None,
+        };
+        let mut token = build_token(
+            &Pubkey::new_unique(),
+            TokenSource::Pumpfun,
+            None,
+            Utc::now(),
+            &mint,
+            info,
+        );
+        token.score = score;
+        token
+    }
+
+    fn matches(source: &str, token: &Token) -> bool {
+        FilterExpr::parse(source).unwrap().matches(token)
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let token = token("Moon", "MOON", Some(10));
+        assert!(matches(
+            "score > 50 && decimals == 9 || decimals == 6",
+            &token
+        ));
+        assert!(!matches(
+            "score > 50 && (decimals == 9 || decimals == 6)",
+            &token
+        ));
+        assert!(matches(
+            "decimals == 6 || score > 50 && decimals == 9",
+            &token
+        ));
+    }
+
+    #[test]
+    fn not_negates_comparisons_and_groups() {
+        let token = token("Moon", "MOON", Some(10));
+        assert!(matches("!score > 50", &token));
+        assert!(!matches("!(score > 5 && decimals == 6)", &token));
+        assert!(matches("!!mint_authority_disabled", &token));
+    }
+
+    #[test]
+    fn missing_fields_only_equal_null() {
+        let token = token("Moon", "MOON", None);
+        assert!(matches("score == null", &token));
+        assert!(!matches("score != null", &token));
+        assert!(!matches("score > 0", &token));
+        assert!(!matches("score <= 0", &token));
+        assert!(matches(
+            "dev_buy_percentage == null && holder_count != null",
+            &token
+        ));
+    }
+
+    #[test]
+    fn regex_functions_ignore_case() {
+        let token = token("Test Token", "PEPE2", Some(10));
+        assert!(matches("name_matches(\"^test\")", &token));
+        assert!(matches("symbol_matches('pepe')", &token));
+        assert!(!matches("!name_matches(\"token$\")", &token));
+        assert!(!matches("symbol_matches(\"^doge\")", &token));
+    }
+
+    #[test]
+    fn negative_literals() {
+        let token = token("Moon", "MOON", Some(-3));
+        assert!(matches("score > -5", &token));
+        assert!(matches("score == -3", &token));
+        assert!(matches("-score >= 3 && -(score) < 3.5", &token));
+        assert!(!matches("score < -3.5", &token));
+    }
+
+    #[test]
+    fn strings_compare_case_insensitively() {
+        let token = token("Moon", "MOON", Some(10));
+        assert!(matches("symbol == 'moon' && source == 'PUMPFUN'", &token));
+        assert!(!matches("name > 'a'", &token));
+    }
+
+    #[test]
+    fn malformed_expressions_are_rejected() {
+        for source in [
+            "score > 1.2.3",
+            "score >",
+            "score > 5 &&",
+            "(score > 5",
+            "score > 5)",
+            "score = 5",
+            "score > 'unterminated",
+            "unknown_field > 5",
+            "name_matches(5)",
+            "name_matches(\"(\")",
+            "score > 5 6",
+            "- > 5",
+        ] {
+            assert!(FilterExpr::parse(source).is_err(), "{} parsed", source);
+        }
+    }
+}
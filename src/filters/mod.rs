@@ -0,0 +1,135 @@
+mod expr;
+
+use std::collections::HashSet;
+
+use anyhow::{Context, Result};
+use bigdecimal::{BigDecimal, FromPrimitive, Zero};
+use regex::{Regex, RegexBuilder};
+
+use crate::config::FilterConfig;
+use crate::models::Token;
+
+use self::expr::FilterExpr;
+
+/// Compiled form of `[filters]`, applied by the processor before publishing
+pub struct TokenFilter {
+    creator_allowlist: HashSet<String>,
+    creator_blocklist: HashSet<String>,
+    name_blocklist: Vec<Regex>,
+    min_decimals: Option<u8>,
+    require_mint_authority_disabled: bool,
+    min_liquidity_sol: Option<BigDecimal>,
+    min_market_cap_usd: Option<BigDecimal>,
+    min_score: Option<i32>,
+    expression: Option<FilterExpr>,
+}
+
+impl TokenFilter {
+    pub fn new(config: &FilterConfig) -> Result<Self> {
+        let name_blocklist = config
+            .name_blocklist
+            .iter()
+            .map(|pattern| {
+                RegexBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .with_context(|| format!("Invalid filters.name_blocklist pattern {:?}", pattern))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let min_liquidity_sol = match config.min_liquidity_sol {
+            Some(value) => Some(
+                BigDecimal::from_f64(value)
+                    .with_context(|| format!("Invalid filters.min_liquidity_sol {}", value))?,
+            ),
+            None => None,
+        };
+
+        let min_market_cap_usd = match config.min_market_cap_usd {
+            Some(value) => Some(
+                BigDecimal::from_f64(value)
+                    .with_context(|| format!("Invalid filters.min_market_cap_usd {}", value))?,
+            ),
+            None => None,
+        };
+
+        let expression = config
+            .expression
+            .as_deref()
+            .filter(|source| !source.trim().is_empty())
+            .map(|source| {
+                FilterExpr::parse(source)
+                    .with_context(|| format!("Invalid filters.expression {:?}", source))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            creator_allowlist: config.creator_allowlist.iter().cloned().collect(),
+            creator_blocklist: config.creator_blocklist.iter().cloned().collect(),
+            name_blocklist,
+            min_decimals: config.min_decimals,
+            require_mint_authority_disabled: config.require_mint_authority_disabled,
+            min_liquidity_sol,
+            min_market_cap_usd,
+            min_score: config.min_score,
+            expression,
+        })
+    }
+
+    /// Returns the name of the first rule the token fails, or `None` if it passes
+    pub fn check(&self, token: &Token) -> Option<&'static str> {
+        let creator = token.creator.as_deref();
+
+        if !self.creator_allowlist.is_empty()
+            && !creator.is_some_and(|c| self.creator_allowlist.contains(c))
+        {
+            return Some("creator_not_allowlisted");
+        }
+        if creator.is_some_and(|c| self.creator_blocklist.contains(c)) {
+            return Some("creator_blocklisted");
+        }
+
+        let name = token.name.as_deref().unwrap_or_default();
+        let symbol = token.symbol.as_deref().unwrap_or_default();
+        if self
+            .name_blocklist
+            .iter()
+            .any(|re| re.is_match(name) || re.is_match(symbol))
+        {
+            return Some("name_blocklisted");
+        }
+
+        if self.min_decimals.is_some_and(|min| token.decimals < min) {
+            return Some("min_decimals");
+        }
+
+        if self.require_mint_authority_disabled && !token.mint_authority_disabled {
+            return Some("mint_authority_enabled");
+        }
+
+        if let Some(min) = &self.min_liquidity_sol {
+            let zero = BigDecimal::zero();
+            if token.liquidity_sol.as_ref().unwrap_or(&zero) < min {
+                return Some("min_liquidity");
+            }
+        }
+
+        if let Some(min) = &self.min_market_cap_usd {
+            if token.market_cap_usd.as_ref().is_none_or(|cap| cap < min) {
+                return Some("min_market_cap");
+            }
+        }
+
+        if let Some(min) = self.min_score {
+            if token.score.is_none_or(|score| score < min) {
+                return Some("min_score");
+            }
+        }
+
+        if self.expression.as_ref().is_some_and(|expr| !expr.matches(token)) {
+            return Some("expression");
+        }
+
+        None
+    }
+}
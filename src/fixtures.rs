@@ -0,0 +1,289 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use solana_client::client_error::{ClientError, ClientErrorKind, Result as ClientResult};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_request::RpcRequest;
+use solana_client::rpc_response::RpcLogsResponse;
+use solana_client::rpc_sender::{RpcSender, RpcTransportStats};
+use tracing::{info, warn};
+
+/// Log notifications of a recording, one [`RecordedLog`] per line
+pub const LOGS_FILE: &str = "logs.ndjson";
+/// HTTP RPC exchanges of a recording, one [`RecordedCall`] per line
+pub const RPC_FILE: &str = "rpc.ndjson";
+
+/// A log notification as a listener received it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordedLog {
+    pub listener: String,
+    #[serde(default)]
+    pub slot: u64,
+    pub log: RpcLogsResponse,
+}
+
+/// One RPC request and the result the node answered with
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordedCall {
+    pub method: String,
+    pub params: Value,
+    pub result: Value,
+}
+
+/// Writes what a `run --record <dir>` session receives into fixture files
+pub struct FixtureRecorder {
+    logs: Mutex<File>,
+    rpc: Mutex<File>,
+}
+
+impl FixtureRecorder {
+    pub fn create(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let open = |name: &str| {
+            let path = dir.join(name);
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .with_context(|| format!("Failed to open {}", path.display()))
+        };
+
+        info!("Recording fixtures to {}", dir.display());
+        Ok(Self {
+            logs: Mutex::new(open(LOGS_FILE)?),
+            rpc: Mutex::new(open(RPC_FILE)?),
+        })
+    }
+
+    pub fn record_log(&self, listener: &str, slot: u64, log: &RpcLogsResponse) {
+        let entry = RecordedLog {
+            listener: listener.to_string(),
+            slot,
+            log: log.clone(),
+        };
+        append(&self.logs, &entry);
+    }
+
+    fn record_call(&self, method: &str, params: &Value, result: &Value) {
+        let entry = RecordedCall {
+            method: method.to_string(),
+            params: params.clone(),
+            result: result.clone(),
+        };
+        append(&self.rpc, &entry);
+    }
+}
+
+fn append<T: Serialize>(file: &Mutex<File>, entry: &T) {
+    let line = match serde_json::to_string(entry) {
+        Ok(line) => line,
+        Err(e) => {
+            warn!("Failed to serialize fixture: {}", e);
+            return;
+        }
+    };
+    let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+    if let Err(e) = writeln!(file, "{}", line) {
+        warn!("Failed to write fixture: {}", e);
+    }
+}
+
+/// Transport that forwards to a real node and records every successful exchange
+pub struct RecordingSender {
+    inner: RpcClient,
+    recorder: Arc<FixtureRecorder>,
+}
+
+impl RecordingSender {
+    pub fn new(inner: RpcClient, recorder: Arc<FixtureRecorder>) -> Self {
+        Self { inner, recorder }
+    }
+}
+
+#[async_trait]
+impl RpcSender for RecordingSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        let result: Value = self.inner.send(request, params.clone()).await?;
+        self.recorder.record_call(&request.to_string(), &params, &result);
+        Ok(result)
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
+
+/// Transport answering from a recorded `rpc.ndjson`, for deterministic offline runs.
+///
+/// Requests are matched on method and params. When the same request was
+/// recorded several times (e.g. `getTransaction` returning null before the
+/// transaction landed), the answers are replayed in order and the last one repeats.
+/// Clones share the recording, so every pool endpoint can get one.
+#[derive(Clone)]
+pub struct ReplaySender {
+    calls: Arc<Mutex<HashMap<String, VecDeque<Value>>>>,
+}
+
+impl ReplaySender {
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+
+        let mut calls: HashMap<String, VecDeque<Value>> = HashMap::new();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let call: RecordedCall = serde_json::from_str(&line)
+                .with_context(|| format!("{}:{}: not a recorded call", path.display(), index + 1))?;
+            calls
+                .entry(call_key(&call.method, &call.params))
+                .or_default()
+                .push_back(call.result);
+        }
+
+        info!("Loaded {} recorded RPC requests from {}", calls.len(), path.display());
+        Ok(Self {
+            calls: Arc::new(Mutex::new(calls)),
+        })
+    }
+
+    /// Next recorded result for this request, if it was recorded at all
+    pub fn answer(&self, method: &str, params: &Value) -> Option<Value> {
+        let mut calls = self.calls.lock().unwrap_or_else(|e| e.into_inner());
+        calls.get_mut(&call_key(method, params)).and_then(|answers| {
+            if answers.len() > 1 {
+                answers.pop_front()
+            } else {
+                answers.front().cloned()
+            }
+        })
+    }
+}
+
+/// Params are built by the same client code on record and replay, so equal requests serialize alike
+fn call_key(method: &str, params: &Value) -> String {
+    format!("{} {}", method, params)
+}
+
+#[async_trait]
+impl RpcSender for ReplaySender {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        let method = request.to_string();
+        self.answer(&method, &params).ok_or_else(|| {
+            ClientError::new_with_request(
+                ClientErrorKind::Custom(format!(
+                    "no recorded response for {}",
+                    call_key(&method, &params)
+                )),
+                request,
+            )
+        })
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        RpcTransportStats::default()
+    }
+
+    fn url(&self) -> String {
+        "replay".to_string()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    use bigdecimal::BigDecimal;
+    use chrono::DateTime;
+
+    use super::*;
+    use crate::config::Config;
+    use crate::listeners::pumpfun;
+    use crate::models::TokenSource;
+    use crate::rpc_pool::{RpcPool, RpcTransport};
+
+    /// A `run --record` of one pump.fun creation: the create log notification and
+    /// the `getTransaction` / `getMultipleAccounts` answers it takes to parse it
+    pub(crate) const CREATE_FIXTURES: &str = "tests/fixtures/pumpfun_create";
+    pub(crate) const CREATE_SIGNATURE: &str =
+        "5VfydnLu4XwV2H2dLHPv22JxhLbYJruaM9YTaGY4UkmPAPHsGJ2jSGEBwQ6zT3SM6kVZHGQaE8A7sBzpfAe3h1Qm";
+    pub(crate) const CREATE_MINT: &str = "7Ro9jWbvyQ3HAyhSVXdCA7nt4TfMBuTYL9rXdRAqpump";
+
+    pub(crate) fn fixtures_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join(CREATE_FIXTURES)
+    }
+
+    /// `config/config.toml` with nothing left that needs Redis
+    pub(crate) fn config() -> Result<Config> {
+        let mut config = Config::load()?;
+        config.dedup.use_redis = false;
+        config.sequence.persist = false;
+        config.ingestion.fast_path = false;
+        Ok(config)
+    }
+
+    /// The recorded log notification
+    pub(crate) fn create_log() -> Result<RecordedLog> {
+        let path = fixtures_dir().join(LOGS_FILE);
+        let raw = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let line = raw.lines().next().context("No recorded log")?;
+        Ok(serde_json::from_str(line)?)
+    }
+
+    /// The recorded create transaction parses into its token from the recorded
+    /// RPC answers alone; DexScreener metadata stays best-effort
+    #[tokio::test]
+    async fn parses_recorded_creation() -> Result<()> {
+        let config = config()?;
+        let transport = RpcTransport::Replay(ReplaySender::load(&fixtures_dir().join(RPC_FILE))?);
+        let rpc = RpcPool::with_transport(&config.network, &config.rate_limit, &transport)?;
+
+        let recorded = create_log()?;
+        assert_eq!(recorded.log.signature, CREATE_SIGNATURE);
+        let signature = recorded.log.signature.parse()?;
+        let token = pumpfun::parse_creation(&rpc, config.programs.pump_fun_program(), &signature)
+            .await?
+            .context("Recorded creation parsed to no token")?;
+
+        assert_eq!(token.mint_address, CREATE_MINT);
+        assert_eq!(token.source, TokenSource::Pumpfun);
+        assert_eq!(
+            token.creator.as_deref(),
+            Some("9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin")
+        );
+        assert_eq!(
+            token.created_at,
+            DateTime::from_timestamp(1_760_000_000, 0).unwrap()
+        );
+        assert_eq!(token.decimals, 6);
+        assert_eq!(
+            token.total_supply,
+            BigDecimal::from_str("1000000000000000")?
+        );
+        assert!(token.mint_authority_disabled);
+        assert!(token.freeze_authority_disabled);
+        assert_eq!(
+            token
+                .pump_fun_bonding_curve
+                .map(|curve| curve.to_string())
+                .as_deref(),
+            Some("4i6aur3AVaFFaGxdCUpDqfK9k5TsivGkEWTCqjGS1d89")
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use futures::{stream, StreamExt};
+use moka::future::Cache;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::{UiInstruction, UiParsedInstruction};
+use tracing::{debug, instrument, warn};
+
+use crate::config::{Config, FundingConfig};
+use crate::listener_helpers;
+use crate::models::{CreatorFunding, FundingSource};
+use crate::rpc_pool::RpcPool;
+
+const SYSTEM_PROGRAM: &str = "11111111111111111111111111111111";
+/// Wallets traced at once by [`FundingTracer::funders`]
+const FUNDERS_CONCURRENCY: usize = 4;
+/// Traders come back across launches, their funder is remembered this long
+const FUNDERS_TTL: Duration = Duration::from_secs(3600);
+const FUNDERS_MAX_ENTRIES: u64 = 50_000;
+
+/// The tracer for `config`, `None` unless `funding.enabled`
+pub fn tracer(config: &Config, rpc: &Arc<RpcPool>) -> Option<Arc<FundingTracer>> {
+    config
+        .funding
+        .enabled
+        .then(|| Arc::new(FundingTracer::new(rpc.clone(), config)))
+}
+
+/// Finds out where a token creator's SOL came from: walks the wallet's recent
+/// transactions, newest first, back to the last incoming SOL transfer and
+/// classifies its sender against the configured CEX, bridge and deployer wallets
+pub struct FundingTracer {
+    rpc: Arc<RpcPool>,
+    config: FundingConfig,
+    cex: HashSet<String>,
+    bridges: HashSet<String>,
+    deployers: HashSet<String>,
+    /// Creators traced before; funding a new wallet from one of them is a deployer handoff
+    seen_creators: Cache<String, ()>,
+    /// Funders of traders, see [`FundingTracer::funders`]
+    funders: Cache<String, Option<String>>,
+}
+
+impl FundingTracer {
+    pub fn new(rpc: Arc<RpcPool>, config: &Config) -> Self {
+        let funding = &config.funding;
+        Self {
+            rpc,
+            cex: funding.cex_wallets.iter().cloned().collect(),
+            bridges: funding.bridge_wallets.iter().cloned().collect(),
+            deployers: funding.deployer_wallets.iter().cloned().collect(),
+            config: funding.clone(),
+            seen_creators: Cache::builder()
+                .max_capacity(config.ingestion.tracked_token_max_entries)
+                .time_to_live(Duration::from_secs(config.ingestion.tracked_token_ttl_secs))
+                .build(),
+            funders: Cache::builder()
+                .max_capacity(FUNDERS_MAX_ENTRIES)
+                .time_to_live(FUNDERS_TTL)
+                .build(),
+        }
+    }
+
+    /// Trace `creator`, `None` if its history could not be read
+    #[instrument(name = "funding", skip_all, fields(creator = %creator))]
+    pub async fn trace(&self, creator: &str) -> Option<CreatorFunding> {
+        let funding = match self.walk(creator).await {
+            Ok(funding) => Some(funding),
+            Err(e) => {
+                warn!("Funding trace of {} failed: {:#}", creator, e);
+                None
+            }
+        };
+        self.seen_creators.insert(creator.to_string(), ()).await;
+        funding
+    }
+
+    /// The funder of each of `wallets` (see [`FundingTracer::funder`]) up to
+    /// `max_lookups` of them, in order; the rest are left out
+    pub async fn funders(
+        &self,
+        wallets: impl IntoIterator<Item = String>,
+        max_lookups: usize,
+    ) -> HashMap<String, Option<String>> {
+        stream::iter(wallets.into_iter().take(max_lookups))
+            .map(|wallet| async move {
+                let funder = self
+                    .funders
+                    .get_with(wallet.clone(), self.funder(&wallet))
+                    .await;
+                (wallet, funder)
+            })
+            .buffer_unordered(FUNDERS_CONCURRENCY)
+            .collect()
+            .await
+    }
+
+    /// The last sender of SOL to `wallet`, `None` when it is an exchange or a
+    /// bridge, which fund unrelated wallets alike
+    pub async fn funder(&self, wallet: &str) -> Option<String> {
+        match self.walk(wallet).await {
+            Ok(funding) => match funding.source {
+                FundingSource::Cex | FundingSource::Bridge => None,
+                _ => funding.funder,
+            },
+            Err(e) => {
+                debug!("Funding trace of {} failed: {:#}", wallet, e);
+                None
+            }
+        }
+    }
+
+    async fn walk(&self, creator: &str) -> Result<CreatorFunding> {
+        let wallet = Pubkey::from_str(creator).context("Invalid creator wallet")?;
+        let limit = self.config.max_signatures;
+        let commitment = self.rpc.commitment();
+
+        let history = self
+            .rpc
+            .call("getSignaturesForAddress", |client| async move {
+                client
+                    .get_signatures_for_address_with_config(
+                        &wallet,
+                        GetConfirmedSignaturesForAddress2Config {
+                            before: None,
+                            until: None,
+                            limit: Some(limit),
+                            commitment: Some(commitment),
+                        },
+                    )
+                    .await
+            })
+            .await
+            .context("Failed to list creator signatures")?;
+
+        // A full page means the wallet is older than what we look at
+        let first_seen = history
+            .last()
+            .filter(|_| history.len() < limit)
+            .and_then(|status| status.block_time)
+            .and_then(|ts| DateTime::from_timestamp(ts, 0));
+        let fresh_wallet = first_seen.is_some_and(|first_seen| {
+            Utc::now() - first_seen <= chrono::Duration::hours(self.config.fresh_wallet_hours)
+        });
+
+        for status in history.iter().filter(|status| status.err.is_none()) {
+            let sig = status.signature.parse().context("Invalid signature in history")?;
+            let Some(tx) = listener_helpers::fetch_transaction_with_retry(&self.rpc, &sig).await?
+            else {
+                continue;
+            };
+
+            let Some((funder, lamports)) = incoming_transfer(&tx, creator) else {
+                continue;
+            };
+            debug!("{} funded {} with {} lamports", funder, creator, lamports);
+            return Ok(CreatorFunding {
+                source: self.classify(&funder),
+                funder: Some(funder),
+                lamports,
+                funded_at: status.block_time.and_then(|ts| DateTime::from_timestamp(ts, 0)),
+                fresh_wallet,
+            });
+        }
+
+        Ok(CreatorFunding {
+            source: FundingSource::Unknown,
+            funder: None,
+            lamports: 0,
+            funded_at: None,
+            fresh_wallet,
+        })
+    }
+
+    fn classify(&self, funder: &str) -> FundingSource {
+        if self.cex.contains(funder) {
+            FundingSource::Cex
+        } else if self.bridges.contains(funder) {
+            FundingSource::Bridge
+        } else if self.deployers.contains(funder) || self.seen_creators.contains_key(funder) {
+            FundingSource::Deployer
+        } else {
+            FundingSource::Unknown
+        }
+    }
+}
+
+/// Sender and amount of the largest System Program transfer to `wallet` in `tx`
+fn incoming_transfer(
+    tx: &solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta,
+    wallet: &str,
+) -> Option<(String, u64)> {
+    listener_helpers::all_instructions(tx)
+        .into_iter()
+        .filter_map(|instr| match instr {
+            UiInstruction::Parsed(UiParsedInstruction::Parsed(pi))
+                if pi.program_id == SYSTEM_PROGRAM
+                    && pi
+                        .parsed
+                        .get("type")
+                        .and_then(|t| t.as_str())
+                        .is_some_and(|t| t == "transfer" || t == "transferWithSeed") =>
+            {
+                let info = pi.parsed.get("info")?;
+                let destination = info.get("destination")?.as_str()?;
+                let source = info.get("source")?.as_str()?;
+                let lamports = info.get("lamports")?.as_u64()?;
+                (destination == wallet && source != wallet).then(|| (source.to_string(), lamports))
+            }
+            _ => None,
+        })
+        .max_by_key(|(_, lamports)| *lamports)
+}
@@ -0,0 +1,293 @@
+use anyhow::Result;
+
+use crate::broadcast::Broadcast;
+use crate::config::GrpcConfig;
+
+#[cfg(feature = "grpc")]
+pub use server::{SubscribeTokensRequest, SubscribeTradesRequest, TokenEvent, TradeEvent};
+
+/// Stream events and trades to gRPC clients (`proto/pumpfun.proto`) until the
+/// server fails; never returns with `grpc.enabled` off
+#[cfg(feature = "grpc")]
+pub async fn serve(config: GrpcConfig, broadcast: Broadcast) -> Result<()> {
+    if !config.enabled {
+        return std::future::pending().await;
+    }
+    server::serve(config, broadcast).await
+}
+
+#[cfg(not(feature = "grpc"))]
+pub async fn serve(config: GrpcConfig, _broadcast: Broadcast) -> Result<()> {
+    if config.enabled {
+        anyhow::bail!(
+            "gRPC server ({}) configured but the binary was built without the `grpc` feature",
+            config.bind_addr
+        );
+    }
+    std::future::pending().await
+}
+
+#[cfg(feature = "grpc")]
+mod server {
+    use std::net::SocketAddr;
+    use std::pin::Pin;
+    use std::sync::Arc;
+
+    use anyhow::{Context, Result};
+    use futures::{Stream, StreamExt};
+    use tokio::sync::broadcast::{self, error::RecvError};
+    use tonic::{Request, Response, Status};
+    use tracing::{info, warn};
+
+    use crate::broadcast::{Broadcast, ObservedTrade};
+    use crate::config::GrpcConfig;
+    use crate::metrics;
+    use crate::models::Envelope;
+
+    include!(concat!(env!("OUT_DIR"), "/pumpfun.v1.Pumpfun.rs"));
+
+    use pumpfun_server::{Pumpfun, PumpfunServer};
+
+    /// Empty lists match everything
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SubscribeTokensRequest {
+        #[prost(string, repeated, tag = "1")]
+        pub event_types: Vec<String>,
+        #[prost(string, repeated, tag = "2")]
+        pub sources: Vec<String>,
+        #[prost(string, repeated, tag = "3")]
+        pub mints: Vec<String>,
+        #[prost(string, repeated, tag = "4")]
+        pub creators: Vec<String>,
+        #[prost(int32, optional, tag = "5")]
+        pub min_score: Option<i32>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TokenEvent {
+        #[prost(uint64, tag = "1")]
+        pub seq: u64,
+        #[prost(string, tag = "2")]
+        pub event_type: String,
+        #[prost(uint64, optional, tag = "3")]
+        pub slot: Option<u64>,
+        #[prost(string, optional, tag = "4")]
+        pub signature: Option<String>,
+        #[prost(int64, tag = "5")]
+        pub emitted_at_ms: i64,
+        #[prost(string, tag = "6")]
+        pub mint: String,
+        #[prost(string, optional, tag = "7")]
+        pub source: Option<String>,
+        /// The whole envelope, as published to the other sinks
+        #[prost(string, tag = "8")]
+        pub json: String,
+    }
+
+    /// Empty lists match everything
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SubscribeTradesRequest {
+        #[prost(string, repeated, tag = "1")]
+        pub mints: Vec<String>,
+        #[prost(string, repeated, tag = "2")]
+        pub wallets: Vec<String>,
+        #[prost(uint64, optional, tag = "3")]
+        pub min_sol_lamports: Option<u64>,
+        #[prost(bool, optional, tag = "4")]
+        pub is_buy: Option<bool>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TradeEvent {
+        #[prost(string, tag = "1")]
+        pub mint: String,
+        #[prost(string, tag = "2")]
+        pub wallet: String,
+        #[prost(bool, tag = "3")]
+        pub is_buy: bool,
+        #[prost(uint64, tag = "4")]
+        pub sol_lamports: u64,
+        #[prost(uint64, tag = "5")]
+        pub token_amount: u64,
+        #[prost(int64, tag = "6")]
+        pub at_ms: i64,
+        #[prost(uint64, optional, tag = "7")]
+        pub slot: Option<u64>,
+        #[prost(string, optional, tag = "8")]
+        pub signature: Option<String>,
+        #[prost(string, optional, tag = "9")]
+        pub router: Option<String>,
+    }
+
+    impl SubscribeTokensRequest {
+        fn matches(&self, envelope: &Envelope) -> bool {
+            let event = &envelope.payload;
+            let token = event.token();
+            let source = event
+                .source()
+                .map(|source| source.to_string().to_lowercase());
+            allows(&self.event_types, Some(event.event_type()))
+                && allows(&self.sources, source.as_deref())
+                && allows(&self.mints, Some(event.mint()))
+                && allows(
+                    &self.creators,
+                    token.and_then(|token| token.creator.as_deref()),
+                )
+                && self.min_score.is_none_or(|min| {
+                    token
+                        .and_then(|token| token.score)
+                        .is_some_and(|score| score >= min)
+                })
+        }
+    }
+
+    impl SubscribeTradesRequest {
+        fn matches(&self, observed: &ObservedTrade) -> bool {
+            let trade = &observed.trade;
+            allows(&self.mints, Some(&trade.mint))
+                && allows(&self.wallets, Some(&trade.wallet))
+                && self
+                    .min_sol_lamports
+                    .is_none_or(|min| trade.sol_amount >= min)
+                && self.is_buy.is_none_or(|is_buy| trade.is_buy == is_buy)
+        }
+    }
+
+    fn allows(accepted: &[String], value: Option<&str>) -> bool {
+        accepted.is_empty()
+            || value.is_some_and(|value| accepted.iter().any(|accepted| accepted == value))
+    }
+
+    fn token_event(envelope: &Envelope) -> Option<TokenEvent> {
+        let json = match serde_json::to_string(envelope) {
+            Ok(json) => json,
+            Err(e) => {
+                warn!("Failed to serialize {} event: {}", envelope.event_type(), e);
+                return None;
+            }
+        };
+        Some(TokenEvent {
+            seq: envelope.seq,
+            event_type: envelope.event_type().to_string(),
+            slot: envelope.slot,
+            signature: envelope.signature.clone(),
+            emitted_at_ms: envelope.emitted_at.timestamp_millis(),
+            mint: envelope.payload.mint().to_string(),
+            source: envelope
+                .payload
+                .source()
+                .map(|source| source.to_string().to_lowercase()),
+            json,
+        })
+    }
+
+    fn trade_event(observed: &ObservedTrade) -> TradeEvent {
+        let trade = &observed.trade;
+        TradeEvent {
+            mint: trade.mint.clone(),
+            wallet: trade.wallet.clone(),
+            is_buy: trade.is_buy,
+            sol_lamports: trade.sol_amount,
+            token_amount: trade.token_amount,
+            at_ms: trade.at.timestamp_millis(),
+            slot: observed.slot,
+            signature: observed.signature.clone(),
+            router: trade.router.clone(),
+        }
+    }
+
+    type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+    /// A client's receiver, counted in `pumpfun_grpc_subscribers` while it is open
+    struct Subscription<T> {
+        rx: broadcast::Receiver<T>,
+        method: &'static str,
+    }
+
+    impl<T> Drop for Subscription<T> {
+        fn drop(&mut self) {
+            metrics::GRPC_SUBSCRIBERS
+                .with_label_values(&[self.method])
+                .dec();
+        }
+    }
+
+    /// What `rx` receives from now on; a client too slow to keep up skips what it missed
+    fn receive<T: Clone + Send + 'static>(
+        rx: broadcast::Receiver<T>,
+        method: &'static str,
+    ) -> impl Stream<Item = T> + Send {
+        metrics::GRPC_SUBSCRIBERS.with_label_values(&[method]).inc();
+        futures::stream::unfold(Subscription { rx, method }, |mut subscription| async move {
+            loop {
+                match subscription.rx.recv().await {
+                    Ok(item) => return Some((item, subscription)),
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!(
+                            "gRPC {} subscriber fell behind, skipping {} messages",
+                            subscription.method, missed
+                        );
+                        metrics::DROPPED_MESSAGES
+                            .with_label_values(&["grpc", "lagged"])
+                            .inc_by(missed);
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+
+    struct PumpfunService {
+        broadcast: Broadcast,
+    }
+
+    #[tonic::async_trait]
+    impl Pumpfun for PumpfunService {
+        type SubscribeTokensStream = ResponseStream<TokenEvent>;
+        type SubscribeTradesStream = ResponseStream<TradeEvent>;
+
+        async fn subscribe_tokens(
+            &self,
+            request: Request<SubscribeTokensRequest>,
+        ) -> Result<Response<Self::SubscribeTokensStream>, Status> {
+            let filter = request.into_inner();
+            let events = receive(self.broadcast.subscribe_events(), "subscribe_tokens").filter_map(
+                move |envelope: Arc<Envelope>| {
+                    let event = filter
+                        .matches(&envelope)
+                        .then(|| token_event(&envelope))
+                        .flatten();
+                    async move { event.map(Ok) }
+                },
+            );
+            Ok(Response::new(Box::pin(events)))
+        }
+
+        async fn subscribe_trades(
+            &self,
+            request: Request<SubscribeTradesRequest>,
+        ) -> Result<Response<Self::SubscribeTradesStream>, Status> {
+            let filter = request.into_inner();
+            let trades = receive(self.broadcast.subscribe_trades(), "subscribe_trades").filter_map(
+                move |observed: Arc<ObservedTrade>| {
+                    let trade = filter.matches(&observed).then(|| trade_event(&observed));
+                    async move { trade.map(Ok) }
+                },
+            );
+            Ok(Response::new(Box::pin(trades)))
+        }
+    }
+
+    pub async fn serve(config: GrpcConfig, broadcast: Broadcast) -> Result<()> {
+        let addr: SocketAddr = config
+            .bind_addr
+            .parse()
+            .with_context(|| format!("Invalid grpc.bind_addr {}", config.bind_addr))?;
+        info!("gRPC server listening on {}", addr);
+        tonic::transport::Server::builder()
+            .add_service(PumpfunServer::new(PumpfunService { broadcast }))
+            .serve(addr)
+            .await
+            .context("gRPC server stopped")
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionManager;
+use serde::Serialize;
+use tokio::time::MissedTickBehavior;
+use tracing::warn;
+
+use crate::config::{Config, SinkConfig};
+use crate::cursor::SlotCursor;
+use crate::models::ListenerHeartbeat;
+use crate::processor::Processor;
+use crate::supervisor::{ListenerState, StatusMap};
+
+/// Time of the last log notification received by each listener
+static LAST_LOG: LazyLock<Mutex<HashMap<String, DateTime<Utc>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Note that `listener`'s subscription just delivered a notification
+pub fn record_log(listener: &str) {
+    let mut last = LAST_LOG.lock().unwrap_or_else(|e| e.into_inner());
+    match last.get_mut(listener) {
+        Some(at) => *at = Utc::now(),
+        None => {
+            last.insert(listener.to_string(), Utc::now());
+        }
+    }
+}
+
+fn last_log(listener: &str) -> Option<DateTime<Utc>> {
+    LAST_LOG
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(listener)
+        .copied()
+}
+
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    pub healthy: bool,
+    pub listeners: BTreeMap<&'static str, ListenerHealth>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redis: Option<CheckResult>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListenerHealth {
+    pub healthy: bool,
+    /// Subscription is up (not backing off after an error)
+    pub connected: bool,
+    pub last_event_at: Option<DateTime<Utc>>,
+    /// Seconds since the last notification, or since the subscription started if none arrived yet
+    pub last_event_age_secs: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckResult {
+    pub healthy: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Liveness and readiness checks behind `/healthz` and `/readyz`
+pub struct HealthChecker {
+    status: StatusMap,
+    redis: Option<ConnectionManager>,
+    ready_max_event_age: Duration,
+    live_max_event_age: Duration,
+}
+
+impl HealthChecker {
+    pub async fn new(config: &Config, status: StatusMap) -> Result<Self> {
+        let uses_redis = config.dedup.use_redis
+            || config.sinks.iter().any(|sink| matches!(sink, SinkConfig::Redis));
+
+        let redis = if uses_redis {
+            let client = redis::Client::open(config.database.redis_url.as_str())
+                .context("Invalid redis_url for health checks")?;
+            Some(
+                ConnectionManager::new(client)
+                    .await
+                    .context("Failed to connect to Redis for health checks")?,
+            )
+        } else {
+            None
+        };
+
+        Ok(Self {
+            status,
+            redis,
+            ready_max_event_age: Duration::from_secs(config.api.ready_max_event_age_secs),
+            live_max_event_age: Duration::from_secs(config.api.live_max_event_age_secs),
+        })
+    }
+
+    /// Alive unless every listener has been silent for `live_max_event_age_secs`,
+    /// i.e. the process is up but wedged and should be restarted
+    pub async fn liveness(&self) -> HealthReport {
+        let listeners = self.listeners(self.live_max_event_age).await;
+        let healthy = self.live_max_event_age.is_zero()
+            || listeners.is_empty()
+            || listeners.values().any(|l| l.healthy);
+
+        HealthReport {
+            healthy,
+            listeners,
+            redis: None,
+        }
+    }
+
+    /// Ready when every subscription is connected and recently active, and Redis answers
+    pub async fn readiness(&self) -> HealthReport {
+        let listeners = self.listeners(self.ready_max_event_age).await;
+        let redis = match &self.redis {
+            Some(redis) => Some(ping(redis).await),
+            None => None,
+        };
+
+        let healthy = !listeners.is_empty()
+            && listeners.values().all(|l| l.healthy)
+            && redis.as_ref().is_none_or(|r| r.healthy);
+
+        HealthReport {
+            healthy,
+            listeners,
+            redis,
+        }
+    }
+
+    async fn listeners(&self, max_event_age: Duration) -> BTreeMap<&'static str, ListenerHealth> {
+        let now = Utc::now();
+        let max_age = max_event_age.as_secs() as i64;
+
+        self.status
+            .read()
+            .await
+            .iter()
+            // Paused on purpose, neither alive nor dead
+            .filter(|(_, status)| !matches!(status.state, ListenerState::Paused))
+            .map(|(name, status)| {
+                let connected = matches!(status.state, ListenerState::Running);
+                let last_event_at = last_log(name);
+                // Before the first notification, count from when the subscription started
+                let reference = last_event_at.map_or(status.since, |at| at.max(status.since));
+                let age = (now - reference).num_seconds().max(0);
+
+                // Watched wallets may well not trade for hours
+                let expects_events = *name != "watchlist";
+                let health = ListenerHealth {
+                    healthy: connected && (!expects_events || max_age == 0 || age <= max_age),
+                    connected,
+                    last_event_at,
+                    last_event_age_secs: age,
+                };
+                (*name, health)
+            })
+            .collect()
+    }
+}
+
+/// Publish a `ListenerStatus` for every listener each `interval`: the newest
+/// slot it handled, how long it has been silent and whether it is subscribed
+pub fn spawn_heartbeat(
+    processor: Processor,
+    status: StatusMap,
+    cursor: Arc<SlotCursor>,
+    interval: Duration,
+) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            let now = Utc::now();
+            let heartbeats: Vec<ListenerHeartbeat> = status
+                .read()
+                .await
+                .iter()
+                .map(|(name, status)| {
+                    let reference = last_log(name).map_or(status.since, |at| at.max(status.since));
+                    ListenerHeartbeat {
+                        source: name.to_string(),
+                        last_slot: cursor.get(name).map(|position| position.slot),
+                        lag_ms: (now - reference).num_milliseconds().max(0),
+                        connected: matches!(status.state, ListenerState::Running),
+                    }
+                })
+                .collect();
+            for heartbeat in heartbeats {
+                let source = heartbeat.source.clone();
+                if let Err(e) = processor.process_listener_status(heartbeat).await {
+                    warn!("Failed to publish heartbeat of {}: {:#}", source, e);
+                }
+            }
+        }
+    });
+}
+
+async fn ping(redis: &ConnectionManager) -> CheckResult {
+    let mut conn = redis.clone();
+    let result = tokio::time::timeout(
+        Duration::from_secs(2),
+        redis::cmd("PING").query_async::<_, String>(&mut conn),
+    )
+    .await;
+
+    match result {
+        Ok(Ok(_)) => CheckResult {
+            healthy: true,
+            error: None,
+        },
+        Ok(Err(e)) => CheckResult {
+            healthy: false,
+            error: Some(e.to_string()),
+        },
+        Err(_) => CheckResult {
+            healthy: false,
+            error: Some("PING timed out".to_string()),
+        },
+    }
+}
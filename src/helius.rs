@@ -0,0 +1,244 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_response::RpcLogsResponse;
+use subtle::ConstantTimeEq;
+use tracing::{debug, info};
+
+use crate::config::{Config, HeliusConfig};
+use crate::event_bus::LogQueue;
+use crate::listeners::pumpfun::idl::{
+    BUY, CREATE, CREATE_POOL, CREATE_V2, EVENT_IX_TAG, PUMP_AMM_PROGRAM, SELL,
+};
+use crate::{health, metrics, outbound};
+
+/// The pump.fun listener handles what the webhook delivers
+const LISTENER: &str = "pumpfun";
+
+/// One transaction of a Helius enhanced-transactions webhook delivery
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnhancedTransaction {
+    pub signature: String,
+    #[serde(default)]
+    pub slot: u64,
+    #[serde(default, rename = "type")]
+    pub transaction_type: Option<String>,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub transaction_error: Option<serde_json::Value>,
+    #[serde(default)]
+    pub instructions: Vec<EnhancedInstruction>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnhancedInstruction {
+    pub program_id: String,
+    /// Base58
+    #[serde(default)]
+    pub data: String,
+    #[serde(default)]
+    pub accounts: Vec<String>,
+    #[serde(default)]
+    pub inner_instructions: Vec<EnhancedInstruction>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Webhook {
+    #[serde(rename = "webhookID", default, skip_serializing)]
+    webhook_id: String,
+    #[serde(rename = "webhookURL")]
+    webhook_url: String,
+    transaction_types: Vec<String>,
+    account_addresses: Vec<String>,
+    webhook_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    auth_header: Option<String>,
+}
+
+/// The webhook receiver for `config`, `None` unless `helius.enabled`; registers
+/// the webhook with Helius first when `helius.register` is set
+pub async fn webhook(config: &Config, queue: LogQueue) -> Result<Option<Arc<HeliusWebhook>>> {
+    let helius = &config.helius;
+    if !helius.enabled {
+        return Ok(None);
+    }
+    if helius.register {
+        register(helius, &config.programs.pump_fun).await?;
+    }
+    Ok(Some(Arc::new(HeliusWebhook {
+        queue,
+        auth_token: helius.auth_token.clone().unwrap_or_default(),
+        programs: config.programs.pump_fun.clone(),
+    })))
+}
+
+/// Create the webhook for `programs`, or update the one already pointing at
+/// `helius.webhook_url`
+async fn register(config: &HeliusConfig, programs: &[String]) -> Result<()> {
+    let api_key = config
+        .api_key
+        .as_deref()
+        .context("helius.api_key not set")?;
+    let webhook_url = config
+        .webhook_url
+        .clone()
+        .context("helius.webhook_url not set")?;
+    let http = outbound::client_builder()
+        .timeout(Duration::from_secs(15))
+        .build()
+        .context("Failed to build Helius HTTP client")?;
+    let webhooks_url = format!("{}/v0/webhooks", config.api_url.trim_end_matches('/'));
+
+    let existing: Vec<Webhook> = http
+        .get(&webhooks_url)
+        .query(&[("api-key", api_key)])
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .context("Failed to list Helius webhooks")?
+        .json()
+        .await
+        .context("Invalid Helius webhook list")?;
+    let webhook = Webhook {
+        webhook_id: String::new(),
+        webhook_url: webhook_url.clone(),
+        transaction_types: config.transaction_types.clone(),
+        account_addresses: programs.to_vec(),
+        webhook_type: "enhanced".to_string(),
+        auth_header: config.auth_token.clone(),
+    };
+
+    let request = match existing
+        .iter()
+        .find(|existing| existing.webhook_url == webhook_url)
+    {
+        Some(existing) => {
+            info!(
+                "Updating Helius webhook {} for {}",
+                existing.webhook_id, webhook_url
+            );
+            http.put(format!("{}/{}", webhooks_url, existing.webhook_id))
+        }
+        None => {
+            info!("Registering a Helius webhook for {}", webhook_url);
+            http.post(&webhooks_url)
+        }
+    };
+    let response = request
+        .query(&[("api-key", api_key)])
+        .json(&webhook)
+        .send()
+        .await
+        .context("Failed to reach the Helius API")?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        bail!("Helius refused the webhook ({}): {}", status, body);
+    }
+    Ok(())
+}
+
+/// Turns webhook deliveries into the log notifications the pump.fun listener
+/// gets over WSS, so they go through the same queue, dedup and parsers
+pub struct HeliusWebhook {
+    queue: LogQueue,
+    auth_token: String,
+    programs: Vec<String>,
+}
+
+impl HeliusWebhook {
+    /// Whether a delivery's `Authorization` header is the configured token
+    pub fn authorized(&self, header: Option<&str>) -> bool {
+        header.is_some_and(|header| bool::from(header.as_bytes().ct_eq(self.auth_token.as_bytes())))
+    }
+
+    /// Queue the successful pump.fun transactions of a delivery
+    pub async fn ingest(&self, transactions: Vec<EnhancedTransaction>) {
+        health::record_log(LISTENER);
+        for transaction in transactions {
+            if transaction.transaction_error.is_some() {
+                metrics::DROPPED_MESSAGES
+                    .with_label_values(&[LISTENER, "failed_transaction"])
+                    .inc();
+                continue;
+            }
+            debug!(
+                "Helius {} transaction {} from {}",
+                transaction.transaction_type.as_deref().unwrap_or("UNKNOWN"),
+                transaction.signature,
+                transaction.source.as_deref().unwrap_or("UNKNOWN")
+            );
+            let slot = transaction.slot;
+            let log = self.to_log(transaction);
+            if !self
+                .programs
+                .iter()
+                .any(|program| log.logs.iter().any(|line| line.contains(program.as_str())))
+            {
+                continue;
+            }
+            self.queue.push(LISTENER, slot, log).await;
+        }
+    }
+
+    /// The log lines the parsers look for: invocations with their instruction
+    /// names, and the events pump.fun emits through self-CPI as `Program data:`
+    fn to_log(&self, transaction: EnhancedTransaction) -> RpcLogsResponse {
+        let mut logs = Vec::new();
+        for instruction in &transaction.instructions {
+            self.log_instruction(instruction, 1, &mut logs);
+        }
+        RpcLogsResponse {
+            signature: transaction.signature,
+            err: None,
+            logs,
+        }
+    }
+
+    fn log_instruction(
+        &self,
+        instruction: &EnhancedInstruction,
+        depth: usize,
+        logs: &mut Vec<String>,
+    ) {
+        let program = &instruction.program_id;
+        logs.push(format!("Program {} invoke [{}]", program, depth));
+        let data = bs58::decode(&instruction.data)
+            .into_vec()
+            .unwrap_or_default();
+        let is_pump_fun = self.programs.contains(program);
+        if is_pump_fun && data.starts_with(&EVENT_IX_TAG) {
+            logs.push(format!(
+                "Program data: {}",
+                base64::engine::general_purpose::STANDARD.encode(&data[EVENT_IX_TAG.len()..])
+            ));
+        } else if let Some(name) = instruction_name(is_pump_fun, program, &data) {
+            logs.push(format!("Program log: Instruction: {}", name));
+        }
+        for inner in &instruction.inner_instructions {
+            self.log_instruction(inner, depth + 1, logs);
+        }
+        logs.push(format!("Program {} success", program));
+    }
+}
+
+fn instruction_name(is_pump_fun: bool, program: &str, data: &[u8]) -> Option<&'static str> {
+    let discriminator: [u8; 8] = data.get(..8)?.try_into().ok()?;
+    if is_pump_fun {
+        return match discriminator {
+            CREATE => Some("Create"),
+            CREATE_V2 => Some("CreateV2"),
+            BUY => Some("Buy"),
+            SELL => Some("Sell"),
+            _ => None,
+        };
+    }
+    (program == PUMP_AMM_PROGRAM && discriminator == CREATE_POOL).then_some("CreatePool")
+}
@@ -0,0 +1,208 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::extension::StateWithExtensions;
+use spl_token_2022::offchain::create_transfer_checked_instruction_with_extra_metas;
+use tracing::{debug, instrument, warn};
+
+use crate::config::{Config, HoneypotConfig};
+use crate::models::Token;
+use crate::rpc_pool::RpcPool;
+use crate::{metrics, token_helper};
+
+/// Mint extensions that can stop holders from moving their tokens
+const BLOCKING_EXTENSIONS: &[&str] = &["Pausable", "NonTransferable", "DefaultAccountState"];
+
+/// The simulator for `config`, `None` unless `honeypot.enabled`
+pub fn simulator(config: &Config, rpc: &Arc<RpcPool>) -> Option<Arc<SellSimulator>> {
+    config
+        .honeypot
+        .enabled
+        .then(|| Arc::new(SellSimulator::new(rpc.clone(), &config.honeypot)))
+}
+
+/// Checks that a holder can actually sell a token by simulating a small sell:
+/// a `TransferChecked` from a holder back into the largest token account, the
+/// pool vault for fresh launches.
+///
+/// Transfer hooks, paused or non-transferable mints and frozen accounts make
+/// that transfer fail. The transaction is never signed nor sent, the node runs
+/// it with `sigVerify` off.
+pub struct SellSimulator {
+    rpc: Arc<RpcPool>,
+    config: HoneypotConfig,
+}
+
+impl SellSimulator {
+    pub fn new(rpc: Arc<RpcPool>, config: &HoneypotConfig) -> Self {
+        Self {
+            rpc,
+            config: config.clone(),
+        }
+    }
+
+    /// Only tokens with an extension or authority that could block sells are simulated
+    pub fn is_suspicious(token: &Token) -> bool {
+        !token.freeze_authority_disabled
+            || token.has_transfer_hook
+            || token.has_permanent_delegate
+            || token.transfer_fee_bps.is_some()
+            || token
+                .mint_extensions
+                .iter()
+                .any(|ext| BLOCKING_EXTENSIONS.contains(&ext.as_str()))
+    }
+
+    /// Whether the simulated sell went through, `None` if it could not be run
+    #[instrument(name = "honeypot", skip_all, fields(mint = %token.mint_address))]
+    pub async fn sellable(&self, token: &Token) -> Option<bool> {
+        let result = match self.simulate(token).await {
+            Ok(sellable) => sellable,
+            Err(e) => {
+                warn!("Sell simulation of {} failed: {:#}", token.mint_address, e);
+                None
+            }
+        };
+        let label = match result {
+            Some(true) => "sellable",
+            Some(false) => "blocked",
+            None => "inconclusive",
+        };
+        metrics::SELL_SIMULATIONS.with_label_values(&[label]).inc();
+        result
+    }
+
+    async fn simulate(&self, token: &Token) -> Result<Option<bool>> {
+        let mint = Pubkey::from_str(&token.mint_address).context("Invalid mint")?;
+        // The creator pays the (never charged) fee, it is the one wallet sure to hold SOL
+        let Some(payer) = token.creator.as_deref() else {
+            return Ok(None);
+        };
+        let payer = Pubkey::from_str(payer).context("Invalid creator wallet")?;
+
+        let largest = self
+            .rpc
+            .call("getTokenLargestAccounts", |client| async move {
+                client.get_token_largest_accounts(&mint).await
+            })
+            .await
+            .context("Failed to list token holders")?;
+        let holders: Vec<(Pubkey, u64)> = largest
+            .iter()
+            .filter_map(|holder| {
+                Some((
+                    Pubkey::from_str(&holder.address).ok()?,
+                    holder.amount.amount.parse().ok()?,
+                ))
+            })
+            .filter(|(_, amount)| *amount > 0)
+            .collect();
+
+        let Some(mint_account) = token_helper::load_accounts(&self.rpc, &[mint])
+            .await?
+            .remove(0)
+        else {
+            return Ok(None);
+        };
+        let token_program = mint_account.owner;
+
+        // Sell from the creator's dev buy if there is one, otherwise as the second
+        // largest holder, into the largest account
+        let creator_account =
+            get_associated_token_address_with_program_id(&payer, &mint, &token_program);
+        let Some(&(destination, _)) = holders.first() else {
+            return Ok(None);
+        };
+        let Some(&(source, balance)) = holders
+            .iter()
+            .find(|(account, _)| *account == creator_account && *account != destination)
+            .or_else(|| holders.get(1))
+        else {
+            return Ok(None);
+        };
+
+        let Some(source_account) = token_helper::load_accounts(&self.rpc, &[source])
+            .await?
+            .remove(0)
+        else {
+            return Ok(None);
+        };
+        let seller =
+            StateWithExtensions::<spl_token_2022::state::Account>::unpack(&source_account.data)
+                .context("Invalid token account")?
+                .base
+                .owner;
+
+        let amount = (balance as u128 * u128::from(self.config.sell_bps) / 10_000).max(1) as u64;
+        let rpc = self.rpc.clone();
+        let instruction = create_transfer_checked_instruction_with_extra_metas(
+            &token_program,
+            &source,
+            &mint,
+            &destination,
+            &seller,
+            &[],
+            amount,
+            token.decimals,
+            |address| {
+                let rpc = rpc.clone();
+                async move {
+                    let mut accounts = token_helper::load_accounts(&rpc, &[address]).await?;
+                    Ok(accounts.remove(0).map(|account| account.data))
+                }
+            },
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to build the sell: {}", e))?;
+
+        let transaction = Transaction::new_unsigned(Message::new(&[instruction], Some(&payer)));
+        let commitment = self.rpc.commitment();
+        let simulation = self
+            .rpc
+            .call("simulateTransaction", |client| {
+                let transaction = &transaction;
+                async move {
+                    client
+                        .simulate_transaction_with_config(
+                            transaction,
+                            RpcSimulateTransactionConfig {
+                                sig_verify: false,
+                                replace_recent_blockhash: true,
+                                commitment: Some(commitment),
+                                ..RpcSimulateTransactionConfig::default()
+                            },
+                        )
+                        .await
+                }
+            })
+            .await
+            .context("Failed to simulate the sell")?
+            .value;
+
+        match simulation.err.map(TransactionError::from) {
+            None => Ok(Some(true)),
+            // The token program (or its transfer hook) refused the transfer
+            Some(TransactionError::InstructionError(_, error)) => {
+                debug!(
+                    "Sell of {} by {} blocked: {:?}, logs: {:?}",
+                    token.mint_address, seller, error, simulation.logs
+                );
+                Ok(Some(false))
+            }
+            // Anything else is about our transaction, not the token
+            Some(error) => {
+                debug!(
+                    "Sell simulation of {} not run: {}",
+                    token.mint_address, error
+                );
+                Ok(None)
+            }
+        }
+    }
+}
@@ -1,86 +1,73 @@
 use std::fs;
-use std::fs::OpenOptions;
-use std::io::{self, Write};
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use anyhow::{Context, Result};
 use chrono::Utc;
-use tracing::{info, error,Level};
-use tracing_subscriber::{fmt, layer::SubscriberExt, EnvFilter, Registry};
-use tracing_subscriber::fmt::{time, MakeWriter};
-use flexi_logger::{Criterion, Cleanup, FileSpec, Logger, Naming};
+use tracing::{debug, info, error};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{fmt, layer::SubscriberExt, EnvFilter, Layer, Registry};
 use tokio::time::sleep;
 use std::time::Duration as StdDuration;
-use anyhow::__private::not;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_subscriber::util::SubscriberInitExt;
-
-// Wrapper around Arc<Mutex<File>> to implement Write
-type FileHandle = Arc<Mutex<std::fs::File>>;
-const LOG_FILE_DIRS: &str ="/var/logs/pumpfun_ingestion";
-struct LogFileWrapper(FileHandle);
-
-impl Write for LogFileWrapper {
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        let mut file = self.0.lock().unwrap();
-        file.write(buf)
-    }
-
-    fn flush(&mut self) -> io::Result<()> {
-        let mut file = self.0.lock().unwrap();
-        file.flush()
-    }
-}
-
-// Implement MakeWriter for our wrapper
-struct LogMakeWriter(FileHandle);
-
-impl<'a> MakeWriter<'a> for LogMakeWriter {
-    type Writer = LogFileWrapper;
-
-    fn make_writer(&'a self) -> Self::Writer {
-        LogFileWrapper(self.0.clone())
-    }
-}
-
-
-
-pub fn init_logging() {
-    let log_path = format!("{}/app.log",LOG_FILE_DIRS);
-
-    // Ensure parent directory exists
-    if let Some(parent) = std::path::Path::new(&log_path).parent() {
-        fs::create_dir_all(parent)
-            .expect("Failed to create log directory");
-    }
-
-    // Initialize rolling log file manually
-    let file = OpenOptions::new()
-        .append(true)
-        .create(true)
-        .open(log_path)
-        .expect("Failed to create log file");
-
-    let file_handle = Arc::new(Mutex::new(file));
-
-    // stdout layer
+use crate::config::{LogFormat, LogRotation, LoggingConfig, TelemetryConfig};
+use crate::telemetry::{self, TelemetryGuard};
+
+/// How often retention and the size cap are enforced
+const LOG_CLEAN_INTERVAL: StdDuration = StdDuration::from_secs(60 * 60);
+
+pub fn init_logging(config: &LoggingConfig, telemetry: &TelemetryConfig) -> Result<TelemetryGuard> {
+    fs::create_dir_all(&config.directory)
+        .with_context(|| format!("Failed to create log directory {}", config.directory))?;
+
+    let rotation = match config.rotation {
+        LogRotation::Hourly => Rotation::HOURLY,
+        LogRotation::Daily => Rotation::DAILY,
+        LogRotation::Never => Rotation::NEVER,
+    };
+    let file = RollingFileAppender::builder()
+        .rotation(rotation)
+        .filename_prefix(&config.file_name)
+        .build(&config.directory)
+        .with_context(|| format!("Failed to open log file in {}", config.directory))?;
+
+    // console layer, on stderr when stdout carries events
+    let console = if config.stderr {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
     let stdout_layer = fmt::layer()
-        .with_writer(std::io::stdout)
+        .with_writer(console)
         .with_target(false)
         .with_level(true);
 
-    // file layer using MakeWriter wrapper
+    // file layer, rotated by tracing-appender
     let file_layer = fmt::layer()
-        .with_writer(LogMakeWriter(file_handle.clone()))
+        .with_writer(file)
+        .with_ansi(false)
         .with_target(false)
         .with_level(true);
 
+    let layers = match config.format {
+        LogFormat::Pretty => vec![stdout_layer.boxed(), file_layer.boxed()],
+        LogFormat::Json => vec![stdout_layer.json().boxed(), file_layer.json().boxed()],
+    };
+
+    // OTLP export, when enabled
+    let (otlp_layer, guard) = telemetry::otlp_layer(telemetry)?;
+
     // Combine layers
     Registry::default()
         .with(EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into()))
-        .with(stdout_layer)
-        .with(file_layer)
+        .with(layers)
+        .with(otlp_layer)
         .init();
 
-    info!("Logging initialized");
+    info!("Logging initialized, writing to {}", config.directory);
+    if telemetry.enabled {
+        info!("Exporting spans to {}", telemetry.otlp_endpoint);
+    }
+    Ok(guard)
 }
 
 pub fn clean_old_logs(dir: &str, max_age_days: i64) {
@@ -103,14 +90,14 @@ pub fn clean_old_logs(dir: &str, max_age_days: i64) {
                     Ok(entry) => {
                         let path = entry.path();
                         any_files = true;
-                        info!("Found file: {}", path.display());
+                        debug!("Found file: {}", path.display());
                         if path.is_file() {
                             match fs::metadata(&path) {
                                 Ok(metadata) => match metadata.modified() {
                                     Ok(modified) => {
                                         let modified_time = chrono::DateTime::<Utc>::from(modified);
                                         let age_days = now.signed_duration_since(modified_time).num_days();
-                                        info!("File {} is {} days old", path.display(), age_days);
+                                        debug!("File {} is {} days old", path.display(), age_days);
                                         if age_days >= max_age_days {
                                             match fs::remove_file(&path) {
                                                 Ok(_) => info!("Deleted old log: {}", path.display()),
@@ -136,12 +123,51 @@ pub fn clean_old_logs(dir: &str, max_age_days: i64) {
     }
 }
 
-pub fn spawn_log_cleaner(max_age_days: i64) {
+/// Delete the oldest files in `dir` until it holds at most `max_bytes`; the newest file is always kept
+pub fn enforce_max_size(dir: &str, max_bytes: u64) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            error!("Failed to read directory {}: {}", dir, e);
+            return;
+        }
+    };
+
+    let mut files: Vec<(PathBuf, std::time::SystemTime, u64)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+            Some((entry.path(), metadata.modified().ok()?, metadata.len()))
+        })
+        .collect();
+    files.sort_by_key(|(_, modified, _)| *modified);
+
+    let mut total: u64 = files.iter().map(|(_, _, len)| len).sum();
+    for (path, _, len) in files.iter().take(files.len().saturating_sub(1)) {
+        if total <= max_bytes {
+            break;
+        }
+        match fs::remove_file(path) {
+            Ok(_) => {
+                total -= len;
+                info!("Deleted log {} to stay under {} bytes", path.display(), max_bytes);
+            }
+            Err(e) => error!("Failed to delete {}: {}", path.display(), e),
+        }
+    }
+}
+
+pub fn spawn_log_cleaner(config: LoggingConfig) {
     tokio::spawn(async move {
         loop {
-            info!("Running log cleaner for: {}",&LOG_FILE_DIRS);
-            clean_old_logs(&LOG_FILE_DIRS,max_age_days);
-            sleep(StdDuration::from_secs(60 * 60 * 24)).await;
+            info!("Running log cleaner for: {}", config.directory);
+            if config.retention_days > 0 {
+                clean_old_logs(&config.directory, config.retention_days);
+            }
+            if config.max_size_mb > 0 {
+                enforce_max_size(&config.directory, config.max_size_mb * 1024 * 1024);
+            }
+            sleep(LOG_CLEAN_INTERVAL).await;
         }
     });
-}
\ No newline at end of file
+}
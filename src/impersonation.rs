@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use moka::future::Cache;
+
+use crate::config::{ImpersonationConfig, ProtectedToken};
+use crate::models::Token;
+
+/// Symbols shorter than this are too generic to compare
+const MIN_SYMBOL_LEN: usize = 2;
+/// Names shorter than this only match exactly
+const MIN_FUZZY_NAME_LEN: usize = 4;
+
+/// Flags new tokens whose name or symbol copies a well-known token, or one that
+/// graduated recently, after folding unicode confusables (`Ᏼоnk` is `bonk`)
+pub struct ImpersonationDetector {
+    enabled: bool,
+    min_similarity: f64,
+    protected: Vec<Target>,
+    /// Recently graduated tokens keyed by mint
+    trending: Cache<String, Target>,
+}
+
+#[derive(Clone)]
+struct Target {
+    label: String,
+    name: String,
+    symbol: String,
+}
+
+impl Target {
+    fn new(name: &str, symbol: &str) -> Self {
+        Self {
+            label: format!("{} ({})", name, symbol),
+            name: normalize(name),
+            symbol: normalize(symbol),
+        }
+    }
+}
+
+impl ImpersonationDetector {
+    pub fn new(config: &ImpersonationConfig) -> Self {
+        Self {
+            enabled: config.enabled,
+            min_similarity: config.min_similarity,
+            protected: config
+                .tokens
+                .iter()
+                .map(|ProtectedToken { name, symbol }| Target::new(name, symbol))
+                .collect(),
+            trending: Cache::builder()
+                .max_capacity(10_000)
+                .time_to_live(Duration::from_secs(config.trending_ttl_secs))
+                .build(),
+        }
+    }
+
+    /// Remember a token that just graduated, so copies of it launched next are caught
+    pub async fn add_trending(&self, token: &Token) {
+        if !self.enabled {
+            return;
+        }
+        if let (Some(name), Some(symbol)) = (&token.name, &token.symbol) {
+            self.trending
+                .insert(token.mint_address.clone(), Target::new(name, symbol))
+                .await;
+        }
+    }
+
+    /// The token `token` pretends to be, as "Name (SYMBOL)"
+    pub fn check(&self, token: &Token) -> Option<String> {
+        if !self.enabled {
+            return None;
+        }
+        let name = token.name.as_deref().map(normalize).unwrap_or_default();
+        let symbol = token.symbol.as_deref().map(normalize).unwrap_or_default();
+
+        let trending = self
+            .trending
+            .iter()
+            .filter(|(mint, _)| mint.as_str() != token.mint_address)
+            .map(|(_, target)| target);
+        self.protected
+            .iter()
+            .cloned()
+            .chain(trending)
+            .find(|target| self.matches(target, &name, &symbol))
+            .map(|target| target.label)
+    }
+
+    fn matches(&self, target: &Target, name: &str, symbol: &str) -> bool {
+        if symbol.len() >= MIN_SYMBOL_LEN && symbol == target.symbol {
+            return true;
+        }
+        if name.is_empty() || target.name.is_empty() {
+            return false;
+        }
+        name == target.name
+            || (name.len() >= MIN_FUZZY_NAME_LEN
+                && strsim::normalized_levenshtein(name, &target.name) >= self.min_similarity)
+    }
+}
+
+/// Lowercase ASCII look-alike of `text` without spaces or punctuation
+fn normalize(text: &str) -> String {
+    let cured = decancer::cure(text, decancer::Options::default())
+        .map(|cured| cured.to_string())
+        .unwrap_or_else(|_| text.to_lowercase());
+    cured.chars().filter(|c| c.is_alphanumeric()).collect()
+}
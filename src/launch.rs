@@ -0,0 +1,345 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use solana_sdk::pubkey::Pubkey;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::extension::StateWithExtensions;
+
+use crate::config::LaunchConfig;
+use crate::models::{LaunchBuy, LaunchBuyer, LaunchSnapshot};
+use crate::rpc_pool::RpcPool;
+use crate::{listener_helpers, token_helper};
+
+/// A launch is closed this many slots after its window, so buys that workers
+/// handle slightly out of order still make it in
+const SETTLE_SLOTS: u64 = 4;
+
+/// Collects the buys in the first slots after each pump.fun launch and sets
+/// bundled launches and snipers apart from organic buyers.
+///
+/// Slots only advance with the logs the listener handles, so a launch is
+/// closed by the first log seen after its window has settled.
+pub struct LaunchTracker {
+    config: LaunchConfig,
+    launches: Mutex<HashMap<String, Launch>>,
+    latest_slot: AtomicU64,
+}
+
+struct Launch {
+    creator: Option<String>,
+    creation_slot: u64,
+    /// Buys inside the creation transaction itself
+    creation_buys: usize,
+    buys: Vec<LaunchBuy>,
+    /// Its window closed and `WindowClosed` was handed out
+    window_closed: bool,
+}
+
+/// What [`LaunchTracker::record`] found out about a launch
+pub enum LaunchUpdate {
+    /// The snipe window closed: `sniper_count` and `bundled` are final
+    WindowClosed(LaunchSnapshot),
+    /// The snapshot to publish with `launch.snapshot_event`: at the window, or
+    /// once `launch.snapshot_buys` buys are in. The launch is no longer followed.
+    Snapshot(LaunchSnapshot),
+}
+
+impl LaunchTracker {
+    pub fn new(config: &LaunchConfig) -> Self {
+        let mut config = config.clone();
+        // Launches are only followed past their window for the event
+        if !config.snapshot_event {
+            config.snapshot_buys = 0;
+        }
+        Self {
+            config,
+            launches: Mutex::new(HashMap::new()),
+            latest_slot: AtomicU64::new(0),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Launch>> {
+        self.launches.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Follow a token created at `slot`, starting with the buys of its creation transaction
+    pub fn start(&self, mint: &str, creator: Option<String>, slot: u64, buys: Vec<LaunchBuy>) {
+        self.lock().entry(mint.to_string()).or_insert(Launch {
+            creator,
+            creation_slot: slot,
+            creation_buys: buys.len(),
+            buys,
+            window_closed: false,
+        });
+    }
+
+    /// Add the buys of a log seen at `slot` to their launches and return what
+    /// changed since: closed windows, then snapshots due
+    pub fn record(&self, slot: u64, buys: Vec<(String, LaunchBuy)>) -> Vec<LaunchUpdate> {
+        let latest = self.latest_slot.fetch_max(slot, Ordering::Relaxed).max(slot);
+        let window = self.config.window_slots;
+        let first_buys = self.config.snapshot_buys;
+
+        let mut launches = self.lock();
+        for (mint, buy) in buys {
+            if let Some(launch) = launches.get_mut(&mint) {
+                if buy.slot <= launch.creation_slot + window || launch.buys.len() < first_buys {
+                    launch.buys.push(buy);
+                }
+            }
+        }
+
+        let mut updates = Vec::new();
+        for (mint, launch) in launches.iter_mut() {
+            if !launch.window_closed && latest > launch.creation_slot + window + SETTLE_SLOTS {
+                launch.window_closed = true;
+                updates.push(LaunchUpdate::WindowClosed(self.snapshot(mint, launch)));
+            }
+        }
+        let due: Vec<String> = launches
+            .iter()
+            .filter(|(_, launch)| {
+                launch.window_closed
+                    && (launch.buys.len() >= first_buys
+                        || latest > launch.creation_slot + self.config.snapshot_max_slots)
+            })
+            .map(|(mint, _)| mint.clone())
+            .collect();
+        for mint in due {
+            if let Some(launch) = launches.remove(&mint) {
+                updates.push(LaunchUpdate::Snapshot(self.snapshot(&mint, &launch)));
+            }
+        }
+        updates
+    }
+
+    fn snapshot(&self, mint: &str, launch: &Launch) -> LaunchSnapshot {
+        let mut buys = launch.buys.clone();
+        buys.sort_by_key(|buy| buy.slot);
+
+        let creator = launch.creator.as_deref();
+        let window_end = launch.creation_slot + self.config.window_slots;
+        let others = |in_creation_slot: bool| {
+            buys.iter()
+                .filter(|buy| buy.slot <= window_end)
+                .filter(|buy| Some(buy.wallet.as_str()) != creator)
+                .filter(|buy| !in_creation_slot || buy.slot == launch.creation_slot)
+                .map(|buy| buy.wallet.as_str())
+                .collect::<HashSet<_>>()
+                .len()
+        };
+        let sniper_count = others(false);
+        let bundled =
+            launch.creation_buys > 1 || others(true) >= self.config.bundle_min_buyers.max(1);
+
+        let mut buyers: Vec<LaunchBuyer> = Vec::new();
+        for buy in &buys {
+            match buyers.iter_mut().find(|buyer| buyer.wallet == buy.wallet) {
+                Some(buyer) => buyer.token_amount += buy.token_amount,
+                None => buyers.push(LaunchBuyer {
+                    wallet: buy.wallet.clone(),
+                    token_amount: buy.token_amount,
+                    holding_percentage: None,
+                    funder: None,
+                }),
+            }
+        }
+
+        LaunchSnapshot {
+            mint: mint.to_string(),
+            creator: launch.creator.clone(),
+            creation_slot: launch.creation_slot,
+            window_slots: self.config.window_slots,
+            buys,
+            sniper_count: sniper_count as u32,
+            bundled,
+            buyers,
+            same_funder_buyers: None,
+            dev_holds: None,
+        }
+    }
+}
+
+/// Fill in the share of the supply each buyer holds and whether the creator
+/// still holds some, from their associated token accounts
+pub async fn load_holdings(rpc: &RpcPool, snapshot: &mut LaunchSnapshot) -> Result<()> {
+    let mint = Pubkey::from_str(&snapshot.mint).context("Invalid mint")?;
+    let Some(mint_account) = token_helper::load_accounts(rpc, &[mint]).await?.remove(0) else {
+        return Ok(());
+    };
+    let Some(mint_data) = token_helper::parse_mint_account(&mint, &mint_account) else {
+        return Ok(());
+    };
+
+    let mut wallets: Vec<String> = snapshot
+        .buyers
+        .iter()
+        .map(|buyer| buyer.wallet.clone())
+        .collect();
+    if let Some(creator) = &snapshot.creator {
+        if !wallets.contains(creator) {
+            wallets.push(creator.clone());
+        }
+    }
+    let accounts = wallets
+        .iter()
+        .map(|wallet| {
+            let owner = Pubkey::from_str(wallet).context("Invalid buyer wallet")?;
+            Ok(get_associated_token_address_with_program_id(
+                &owner,
+                &mint,
+                &mint_account.owner,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let loaded = token_helper::load_accounts(rpc, &accounts).await?;
+    let balances: HashMap<&str, u64> = wallets
+        .iter()
+        .zip(loaded)
+        .map(|(wallet, account)| {
+            // A closed or never opened account holds nothing
+            let amount = account
+                .and_then(|account| {
+                    StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+                        .ok()
+                        .map(|state| state.base.amount)
+                })
+                .unwrap_or(0);
+            (wallet.as_str(), amount)
+        })
+        .collect();
+
+    for buyer in &mut snapshot.buyers {
+        buyer.holding_percentage = balances.get(buyer.wallet.as_str()).and_then(|amount| {
+            listener_helpers::supply_percentage(u128::from(*amount), mint_data.supply)
+        });
+    }
+    snapshot.dev_holds = snapshot
+        .creator
+        .as_deref()
+        .and_then(|creator| balances.get(creator))
+        .map(|amount| *amount > 0);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINT: &str = "mint";
+
+    fn tracker(snapshot_buys: usize) -> LaunchTracker {
+        LaunchTracker::new(&LaunchConfig {
+            window_slots: 2,
+            bundle_min_buyers: 3,
+            snapshot_event: true,
+            snapshot_buys,
+            snapshot_max_slots: 50,
+        })
+    }
+
+    fn buy(wallet: &str, slot: u64) -> LaunchBuy {
+        LaunchBuy {
+            wallet: wallet.to_string(),
+            slot,
+            sol_amount: 1_000_000_000,
+            token_amount: 1_000_000,
+            usd_amount: None,
+        }
+    }
+
+    fn record(tracker: &LaunchTracker, slot: u64, wallets: &[&str]) -> Vec<LaunchUpdate> {
+        let buys = wallets
+            .iter()
+            .map(|wallet| (MINT.to_string(), buy(wallet, slot)))
+            .collect();
+        tracker.record(slot, buys)
+    }
+
+    fn wallets(snapshot: &LaunchSnapshot) -> Vec<&str> {
+        snapshot
+            .buys
+            .iter()
+            .map(|buy| buy.wallet.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn snapshot_waits_for_the_first_buys() {
+        let tracker = tracker(3);
+        tracker.start(MINT, Some("dev".to_string()), 100, vec![buy("dev", 100)]);
+        assert!(record(&tracker, 101, &["sniper"]).is_empty());
+
+        // The window settles with two buys, one short of the snapshot
+        let updates = record(&tracker, 107, &[]);
+        let [LaunchUpdate::WindowClosed(window)] = updates.as_slice() else {
+            panic!("only the window should close");
+        };
+        assert_eq!(wallets(window), ["dev", "sniper"]);
+        assert_eq!(window.sniper_count, 1);
+
+        let updates = record(&tracker, 108, &["late"]);
+        let [LaunchUpdate::Snapshot(snapshot)] = updates.as_slice() else {
+            panic!("the third buy should take the snapshot");
+        };
+        assert_eq!(wallets(snapshot), ["dev", "sniper", "late"]);
+        assert_eq!(snapshot.sniper_count, 1);
+        assert_eq!(snapshot.buyers.len(), 3);
+
+        // No longer followed
+        assert!(record(&tracker, 109, &["after"]).is_empty());
+    }
+
+    #[test]
+    fn snapshot_with_enough_buys_comes_with_the_window() {
+        let tracker = tracker(2);
+        tracker.start(MINT, None, 100, Vec::new());
+        assert!(record(&tracker, 101, &["a", "b"]).is_empty());
+
+        let updates = record(&tracker, 107, &[]);
+        let [LaunchUpdate::WindowClosed(_), LaunchUpdate::Snapshot(snapshot)] = updates.as_slice()
+        else {
+            panic!("window and snapshot expected");
+        };
+        assert_eq!(wallets(snapshot), ["a", "b"]);
+    }
+
+    #[test]
+    fn snapshot_gives_up_waiting_after_max_slots() {
+        let tracker = tracker(10);
+        tracker.start(MINT, None, 100, Vec::new());
+        record(&tracker, 101, &["a"]);
+        assert!(matches!(
+            record(&tracker, 107, &["b"]).as_slice(),
+            [LaunchUpdate::WindowClosed(_)]
+        ));
+        assert!(record(&tracker, 150, &["c"]).is_empty());
+
+        let updates = record(&tracker, 151, &[]);
+        let [LaunchUpdate::Snapshot(snapshot)] = updates.as_slice() else {
+            panic!("snapshot expected past snapshot_max_slots");
+        };
+        assert_eq!(wallets(snapshot), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn buys_after_the_window_are_ignored_without_the_event() {
+        let tracker = LaunchTracker::new(&LaunchConfig {
+            window_slots: 2,
+            snapshot_buys: 5,
+            ..LaunchConfig::default()
+        });
+        tracker.start(MINT, None, 100, Vec::new());
+        record(&tracker, 101, &["a"]);
+        let updates = record(&tracker, 107, &["late"]);
+        let [LaunchUpdate::WindowClosed(window), LaunchUpdate::Snapshot(_)] = updates.as_slice()
+        else {
+            panic!("window and snapshot expected");
+        };
+        assert_eq!(wallets(window), ["a"]);
+    }
+}
@@ -0,0 +1,166 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use redis::aio::ConnectionManager;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use crate::config::LeaderConfig;
+use crate::metrics;
+
+/// Renews the lease if we hold it, takes it if nobody does; returns the holder
+const CAMPAIGN_SCRIPT: &str = r#"
+local holder = redis.call('GET', KEYS[1])
+if holder == ARGV[1] then
+    redis.call('PEXPIRE', KEYS[1], ARGV[2])
+    return holder
+elseif not holder then
+    redis.call('SET', KEYS[1], ARGV[1], 'PX', ARGV[2])
+    return ARGV[1]
+end
+return holder
+"#;
+
+const RELEASE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+/// Lets several instances run side by side while only one publishes.
+///
+/// The leader holds the Redis key `leader.key` with a `lease_secs` expiry and
+/// renews it every `renew_interval_secs`; when it stops renewing, the first
+/// standby to find the key gone takes over. A leader that cannot reach Redis
+/// steps down before its lease could have expired.
+pub struct LeaderElection {
+    conn: ConnectionManager,
+    key: String,
+    id: String,
+    lease: Duration,
+    renew_interval: Duration,
+    leader: AtomicBool,
+    /// Held during each campaign, set once resigned
+    resigned: Mutex<bool>,
+}
+
+impl LeaderElection {
+    pub async fn new(config: &LeaderConfig, redis_url: &str) -> Result<Self> {
+        let client = redis::Client::open(redis_url).context("Invalid redis_url for leader")?;
+        let conn = ConnectionManager::new(client)
+            .await
+            .context("Failed to connect to Redis for leader election")?;
+        let id = config.instance_id.clone().unwrap_or_else(default_instance_id);
+        info!("Leader election on {} as {}", config.key, id);
+        metrics::LEADER.set(0);
+
+        Ok(Self {
+            conn,
+            key: config.key.clone(),
+            id,
+            lease: Duration::from_secs(config.lease_secs),
+            renew_interval: Duration::from_secs(config.renew_interval_secs),
+            leader: AtomicBool::new(false),
+            resigned: Mutex::new(false),
+        })
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.leader.load(Ordering::Relaxed)
+    }
+
+    /// Campaign for the lease until [`LeaderElection::resign`]
+    pub fn spawn(self: &Arc<Self>) {
+        let election = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(election.renew_interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut lease_until = None;
+            loop {
+                ticker.tick().await;
+                let resigned = election.resigned.lock().await;
+                if *resigned {
+                    return;
+                }
+                let attempt = Instant::now();
+                match election.campaign().await {
+                    Ok(holder) if holder == election.id => {
+                        lease_until = Some(attempt + election.lease);
+                        election.set_leader(true, &holder);
+                    }
+                    Ok(holder) => {
+                        lease_until = None;
+                        election.set_leader(false, &holder);
+                    }
+                    Err(e) => {
+                        warn!("Leader election on {} failed: {:#}", election.key, e);
+                        // The lease may run out before the next attempt
+                        let next_attempt = Instant::now() + election.renew_interval;
+                        if lease_until.is_some_and(|until| next_attempt >= until) {
+                            lease_until = None;
+                            election.set_leader(false, "unknown");
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    async fn campaign(&self) -> Result<String> {
+        let mut conn = self.conn.clone();
+        redis::cmd("EVAL")
+            .arg(CAMPAIGN_SCRIPT)
+            .arg(1)
+            .arg(&self.key)
+            .arg(&self.id)
+            .arg(self.lease.as_millis() as u64)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to campaign for leadership")
+    }
+
+    fn set_leader(&self, leader: bool, holder: &str) {
+        if self.leader.swap(leader, Ordering::Relaxed) == leader {
+            return;
+        }
+        metrics::LEADER.set(leader as i64);
+        if leader {
+            info!("{} is now the leader, publishing events", self.id);
+        } else {
+            warn!("{} lost leadership to {}, standing by", self.id, holder);
+        }
+    }
+
+    /// Stop campaigning and hand the lease over right away instead of letting
+    /// it expire
+    pub async fn resign(&self) {
+        let mut resigned = self.resigned.lock().await;
+        *resigned = true;
+        if !self.leader.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        metrics::LEADER.set(0);
+        let mut conn = self.conn.clone();
+        let released: redis::RedisResult<u32> = redis::cmd("EVAL")
+            .arg(RELEASE_SCRIPT)
+            .arg(1)
+            .arg(&self.key)
+            .arg(&self.id)
+            .query_async(&mut conn)
+            .await;
+        match released {
+            Ok(_) => info!("{} resigned leadership", self.id),
+            Err(e) => warn!("Failed to release leadership, it expires in time: {}", e),
+        }
+    }
+}
+
+/// The hostname (a pod name on Kubernetes) and process id
+pub(crate) fn default_instance_id() -> String {
+    let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "instance".to_string());
+    format!("{}-{}", host, std::process::id())
+}
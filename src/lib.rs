@@ -0,0 +1,81 @@
+//! Solana token launch listener.
+//!
+//! The `pumpfun_ingestion` binary subscribes to the configured launchpads and
+//! publishes every discovered token to the configured sinks. The same
+//! listeners can be embedded in another program, e.g.
+//! [`PumpFunListener::into_stream`] yields events in-process without Redis.
+
+mod listener_helpers;
+mod log_classifier;
+pub mod listeners;
+pub mod config;
+pub mod config_watch;
+pub mod processor;
+mod token_helper;
+pub mod housekeeping_util;
+mod metrics;
+pub mod api;
+pub mod supervisor;
+pub mod scoring;
+pub mod dedup;
+pub mod sqlite;
+pub mod sinks;
+pub mod dry_run;
+pub mod envelope;
+pub mod clickhouse;
+pub mod broadcast;
+pub mod grpc;
+pub mod helius;
+pub mod dexscreener;
+pub mod birdeye;
+pub mod rugcheck;
+mod negative_cache;
+pub mod token_cache;
+mod notifiers;
+mod filters;
+mod impersonation;
+mod socials;
+mod price;
+mod chains;
+pub mod rpc_pool;
+pub mod outbound;
+pub mod rpc_budget;
+mod rate_limit;
+mod retry;
+pub mod event_bus;
+pub mod cursor;
+pub mod health;
+pub mod telemetry;
+pub mod confirmation;
+pub mod launch;
+pub mod funding;
+pub mod honeypot;
+pub mod wash;
+pub mod authority;
+pub mod liquidity;
+pub mod reenrich;
+pub mod blacklist;
+pub mod lifecycle;
+pub mod volume;
+pub mod trending;
+pub mod papertrade;
+pub mod execution;
+pub mod positions;
+pub mod dlq;
+pub mod leader;
+pub mod sharding;
+pub mod error;
+pub mod abandoned;
+pub mod aggregator;
+pub mod app;
+pub mod backfill;
+pub mod reprocess;
+pub mod selftest;
+pub mod fixtures;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod mock_rpc;
+pub mod models;
+
+pub use config::Config;
+pub use listeners::pumpfun::PumpFunListener;
+pub use models::{Event, Token};
@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use moka::future::Cache;
+use redis::aio::ConnectionManager;
+use tracing::info;
+
+use crate::config::LifecycleConfig;
+use crate::models::{TokenState, TokenStateChange};
+
+/// Where each published token is in its life, kept in Redis (one key per mint,
+/// expiring after `ttl_secs`) so a restart picks up where it left off.
+///
+/// States only move forward; `Abandoned` and `Rugged` are final.
+pub struct Lifecycle {
+    conn: ConnectionManager,
+    key_prefix: String,
+    ttl: Duration,
+    /// Read-through copy of the Redis keys
+    states: Cache<String, TokenState>,
+}
+
+impl Lifecycle {
+    pub async fn new(config: &LifecycleConfig, redis_url: &str) -> Result<Self> {
+        let client = redis::Client::open(redis_url).context("Invalid redis_url for lifecycle")?;
+        let conn = ConnectionManager::new(client)
+            .await
+            .context("Failed to connect to Redis for lifecycle")?;
+        info!("Token lifecycle kept in Redis under {}*", config.key_prefix);
+
+        let ttl = Duration::from_secs(config.ttl_secs);
+        Ok(Self {
+            conn,
+            key_prefix: config.key_prefix.clone(),
+            ttl,
+            states: Cache::builder()
+                .max_capacity(100_000)
+                .time_to_live(ttl)
+                .build(),
+        })
+    }
+
+    pub async fn state(&self, mint: &str) -> Result<Option<TokenState>> {
+        if let Some(state) = self.states.get(mint).await {
+            return Ok(Some(state));
+        }
+        let mut conn = self.conn.clone();
+        let stored: Option<String> = redis::cmd("GET")
+            .arg(format!("{}{}", self.key_prefix, mint))
+            .query_async(&mut conn)
+            .await
+            .context("Failed to read token state")?;
+        let state = stored.and_then(|state| state.parse().ok());
+        if let Some(state) = state {
+            self.states.insert(mint.to_string(), state).await;
+        }
+        Ok(state)
+    }
+
+    /// Move `mint` to `to`, `None` if that is not a step forward from where it is
+    pub async fn advance(&self, mint: &str, to: TokenState) -> Result<Option<TokenStateChange>> {
+        let from = self.state(mint).await?;
+        if from.is_some_and(|from| !from.can_become(to)) {
+            return Ok(None);
+        }
+
+        let mut conn = self.conn.clone();
+        let _: () = redis::cmd("SET")
+            .arg(format!("{}{}", self.key_prefix, mint))
+            .arg(to.to_string())
+            .arg("EX")
+            .arg(self.ttl.as_secs())
+            .query_async(&mut conn)
+            .await
+            .context("Failed to store token state")?;
+        self.states.insert(mint.to_string(), to).await;
+
+        Ok(Some(TokenStateChange {
+            mint: mint.to_string(),
+            from,
+            to,
+            at: Utc::now(),
+        }))
+    }
+}
@@ -0,0 +1,435 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use bigdecimal::{BigDecimal, ToPrimitive};
+use chrono::Utc;
+use solana_sdk::pubkey::Pubkey;
+use spl_token_2022::extension::StateWithExtensions;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, info_span, instrument, warn, Instrument};
+
+use crate::config::{Config, LiquidityConfig, LpConfig};
+use crate::models::Token;
+use crate::processor::Processor;
+use crate::rpc_pool::RpcPool;
+use crate::{scoring, token_helper};
+
+/// Offsets in a pump.fun `BondingCurve` account: discriminator, virtual token,
+/// virtual SOL, real token and real SOL reserves
+const CURVE_VIRTUAL_TOKEN_OFFSET: usize = 8;
+const CURVE_VIRTUAL_SOL_OFFSET: usize = 8 + 8;
+const CURVE_REAL_TOKEN_OFFSET: usize = 8 + 8 * 2;
+const CURVE_REAL_SOL_OFFSET: usize = 8 + 8 * 3;
+/// Real token reserves of a new pump.fun curve, the tokens sold before graduation
+const CURVE_INITIAL_REAL_TOKENS: u64 = 793_100_000_000_000;
+const LAMPORTS_DECIMALS: i64 = 9;
+/// Significant digits kept in prices, which are often far below one lamport per token
+const PRICE_DIGITS: u64 = 10;
+
+/// Offset of `lp_mint` in a PumpSwap `Pool` account: discriminator, bump, index,
+/// creator, base mint and quote mint come first
+const POOL_LP_MINT_OFFSET: usize = 8 + 1 + 2 + 32 * 3;
+/// `pool_base_token_account` and `pool_quote_token_account` (the token and WSOL
+/// vaults of pump.fun pools) follow `lp_mint`
+const POOL_BASE_VAULT_OFFSET: usize = POOL_LP_MINT_OFFSET + 32;
+const POOL_QUOTE_VAULT_OFFSET: usize = POOL_LP_MINT_OFFSET + 32 * 2;
+/// `lp_supply` follows `lp_mint` and the two pool vaults
+const POOL_LP_SUPPLY_OFFSET: usize = POOL_LP_MINT_OFFSET + 32 * 3;
+
+/// The checker for `config`, `None` unless `lp.enabled`
+pub fn lp_checker(config: &Config, rpc: &Arc<RpcPool>) -> Option<Arc<LpChecker>> {
+    config
+        .lp
+        .enabled
+        .then(|| Arc::new(LpChecker::new(rpc.clone(), &config.lp)))
+}
+
+/// What happened to the LP tokens minted when a pool was seeded
+#[derive(Debug, Clone, Copy)]
+pub struct LpStatus {
+    /// At least `lp.min_percentage` of the LP supply was burned
+    pub burned: bool,
+    /// At least `lp.min_percentage` of the LP supply was burned or sits in a locker program
+    pub locked: bool,
+}
+
+/// Checks whether a PumpSwap pool's LP tokens were burned or handed to a
+/// known locker program, so the liquidity can no longer be pulled
+pub struct LpChecker {
+    rpc: Arc<RpcPool>,
+    delay: Duration,
+    min_percentage: f64,
+    lockers: HashSet<Pubkey>,
+}
+
+impl LpChecker {
+    pub fn new(rpc: Arc<RpcPool>, config: &LpConfig) -> Self {
+        Self {
+            rpc,
+            delay: Duration::from_secs(config.check_delay_secs),
+            min_percentage: config.min_percentage,
+            lockers: config
+                .locker_programs
+                .iter()
+                .filter_map(|program| Pubkey::from_str(program).ok())
+                .collect(),
+        }
+    }
+
+    /// How long after pool creation to check, so burns and locks have landed
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// LP status of `pool`, `None` if it is not a PumpSwap pool (yet)
+    #[instrument(name = "lp", skip_all, fields(pool = %pool))]
+    pub async fn check(&self, pool: &Pubkey) -> Result<Option<LpStatus>> {
+        let Some(pool_account) = token_helper::load_accounts(&self.rpc, &[*pool])
+            .await?
+            .remove(0)
+        else {
+            return Ok(None);
+        };
+        let Some((lp_mint, issued)) = decode_pool_lp(&pool_account.data) else {
+            return Ok(None);
+        };
+        if issued == 0 {
+            return Ok(None);
+        }
+
+        let Some(mint_data) = token_helper::load_accounts(&self.rpc, &[lp_mint])
+            .await?
+            .remove(0)
+            .and_then(|account| token_helper::parse_mint_account(&lp_mint, &account))
+        else {
+            return Ok(None);
+        };
+        let burned = issued.saturating_sub(mint_data.supply);
+        let locked = self.locked_amount(&lp_mint).await?;
+
+        let percentage = |amount: u64| amount as f64 * 100.0 / issued as f64;
+        debug!(
+            "LP {}: {:.2}% burned, {:.2}% locked",
+            lp_mint,
+            percentage(burned),
+            percentage(locked)
+        );
+        Ok(Some(LpStatus {
+            burned: percentage(burned) >= self.min_percentage,
+            locked: percentage(burned.saturating_add(locked)) >= self.min_percentage,
+        }))
+    }
+
+    /// LP tokens among the largest holders whose owner is an account of a locker program
+    async fn locked_amount(&self, lp_mint: &Pubkey) -> Result<u64> {
+        let largest = self
+            .rpc
+            .call("getTokenLargestAccounts", |client| async move {
+                client.get_token_largest_accounts(lp_mint).await
+            })
+            .await
+            .context("Failed to list LP holders")?;
+        let keys: Vec<Pubkey> = largest
+            .iter()
+            .filter_map(|holder| Pubkey::from_str(&holder.address).ok())
+            .collect();
+
+        let holdings: Vec<(Pubkey, u64)> = token_helper::load_accounts(&self.rpc, &keys)
+            .await?
+            .into_iter()
+            .flatten()
+            .filter_map(|account| {
+                let state =
+                    StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+                        .ok()?;
+                Some((state.base.owner, state.base.amount))
+            })
+            .collect();
+        if holdings.is_empty() {
+            return Ok(0);
+        }
+
+        // Lockers hold LP tokens in accounts owned by their own PDAs
+        let owners: Vec<Pubkey> = holdings.iter().map(|(owner, _)| *owner).collect();
+        let owner_accounts = token_helper::load_accounts(&self.rpc, &owners).await?;
+        Ok(holdings
+            .iter()
+            .zip(owner_accounts)
+            .filter(|(_, owner)| {
+                owner
+                    .as_ref()
+                    .is_some_and(|owner| self.lockers.contains(&owner.owner))
+            })
+            .map(|((_, amount), _)| *amount)
+            .sum())
+    }
+}
+
+/// LP mint and the LP supply the pool has issued; burning LP tokens lowers the
+/// mint supply but not this
+fn decode_pool_lp(data: &[u8]) -> Option<(Pubkey, u64)> {
+    let lp_mint = data.get(POOL_LP_MINT_OFFSET..POOL_LP_MINT_OFFSET + 32)?;
+    let lp_supply = data.get(POOL_LP_SUPPLY_OFFSET..POOL_LP_SUPPLY_OFFSET + 8)?;
+    Some((
+        Pubkey::try_from(lp_mint).ok()?,
+        u64::from_le_bytes(lp_supply.try_into().ok()?),
+    ))
+}
+
+/// SOL liquidity and token price of a pump.fun bonding curve or PumpSwap pool
+#[derive(Debug, Clone)]
+pub struct Reserves {
+    pub liquidity_sol: BigDecimal,
+    /// SOL per whole token
+    pub price_sol: Option<BigDecimal>,
+    pub curve_progress: BigDecimal,
+}
+
+impl Reserves {
+    /// From a bonding curve account; the curve prices off its virtual reserves
+    pub fn from_curve(data: &[u8], decimals: u8) -> Option<Self> {
+        Some(Self {
+            liquidity_sol: lamports_to_sol(read_u64(data, CURVE_REAL_SOL_OFFSET)?),
+            price_sol: price_sol(
+                read_u64(data, CURVE_VIRTUAL_SOL_OFFSET)?,
+                read_u64(data, CURVE_VIRTUAL_TOKEN_OFFSET)?,
+                decimals,
+            ),
+            curve_progress: curve_progress(read_u64(data, CURVE_REAL_TOKEN_OFFSET)?),
+        })
+    }
+
+    /// From the raw balances of a pool's token and SOL vaults
+    fn from_pool(token_amount: u64, lamports: u64, decimals: u8) -> Self {
+        Self {
+            liquidity_sol: lamports_to_sol(lamports),
+            price_sol: price_sol(lamports, token_amount, decimals),
+            curve_progress: BigDecimal::from(100),
+        }
+    }
+
+    pub fn apply(self, token: &mut Token) {
+        token.liquidity_sol = Some(self.liquidity_sol);
+        token.price_sol = self.price_sol;
+        token.bonding_curve_progress = Some(self.curve_progress);
+    }
+}
+
+fn price_sol(lamports: u64, token_amount: u64, decimals: u8) -> Option<BigDecimal> {
+    (token_amount > 0).then(|| {
+        (lamports_to_sol(lamports) / BigDecimal::new(token_amount.into(), i64::from(decimals)))
+            .with_prec(PRICE_DIGITS)
+    })
+}
+
+/// Percentage of the curve's real token reserves sold
+fn curve_progress(real_tokens: u64) -> BigDecimal {
+    let sold = CURVE_INITIAL_REAL_TOKENS.saturating_sub(real_tokens);
+    (BigDecimal::from(sold) * BigDecimal::from(100) / BigDecimal::from(CURVE_INITIAL_REAL_TOKENS))
+        .round(2)
+}
+
+fn lamports_to_sol(lamports: u64) -> BigDecimal {
+    BigDecimal::new(lamports.into(), LAMPORTS_DECIMALS)
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    let bytes = data.get(offset..offset + 8)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Option<Pubkey> {
+    Pubkey::try_from(data.get(offset..offset + 32)?).ok()
+}
+
+/// Reserves of one PumpSwap pool, `None` if it is not one (yet)
+pub async fn pool_reserves(rpc: &RpcPool, pool: &Pubkey, decimals: u8) -> Result<Option<Reserves>> {
+    let Some(pool) = token_helper::load_accounts(rpc, &[*pool]).await?.remove(0) else {
+        return Ok(None);
+    };
+    let (Some(base), Some(quote)) = (
+        read_pubkey(&pool.data, POOL_BASE_VAULT_OFFSET),
+        read_pubkey(&pool.data, POOL_QUOTE_VAULT_OFFSET),
+    ) else {
+        return Ok(None);
+    };
+    let balances: Vec<Option<u64>> = token_helper::load_accounts(rpc, &[base, quote])
+        .await?
+        .into_iter()
+        .map(|vault| {
+            StateWithExtensions::<spl_token_2022::state::Account>::unpack(&vault?.data)
+                .ok()
+                .map(|state| state.base.amount)
+        })
+        .collect();
+    Ok(match balances[..] {
+        [Some(token_amount), Some(lamports)] => {
+            Some(Reserves::from_pool(token_amount, lamports, decimals))
+        }
+        _ => None,
+    })
+}
+
+/// Keeps `liquidity_sol` and `price_sol` of recently discovered pump.fun tokens
+/// current: from the bonding curve while bonding, from the PumpSwap pool's
+/// vaults once graduated. A token is published again (`TokenUpdated`) when
+/// either moved by at least `min_change_percentage`.
+pub struct LiquidityRefresher {
+    rpc: Arc<RpcPool>,
+    processor: Processor,
+    config: LiquidityConfig,
+}
+
+impl LiquidityRefresher {
+    /// Start the refresh task
+    pub fn spawn(rpc: Arc<RpcPool>, processor: Processor, config: &LiquidityConfig) {
+        let refresher = Self {
+            rpc,
+            processor,
+            config: config.clone(),
+        };
+        tokio::spawn(refresher.run());
+    }
+
+    async fn run(self) {
+        let mut ticker =
+            tokio::time::interval(Duration::from_secs(self.config.interval_secs.max(1)));
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self
+                .refresh_all()
+                .instrument(info_span!("liquidity_refresh"))
+                .await
+            {
+                warn!("Liquidity refresh failed: {:#}", e);
+            }
+        }
+    }
+
+    async fn refresh_all(&self) -> Result<()> {
+        let since = Utc::now() - chrono::Duration::seconds(self.config.window_secs as i64);
+        let recent: Vec<Token> = self
+            .processor
+            .tracked_tokens()
+            .into_iter()
+            .filter(|token| token.discovered_at >= since)
+            .collect();
+
+        let (graduated, bonding): (Vec<Token>, Vec<Token>) = recent
+            .into_iter()
+            .partition(|token| token.pumpswap_pool.is_some());
+
+        let pools: Vec<(Token, Pubkey)> = graduated
+            .into_iter()
+            .filter_map(|token| {
+                let pool = Pubkey::from_str(token.pumpswap_pool.as_deref()?).ok()?;
+                Some((token, pool))
+            })
+            .collect();
+        let keys: Vec<Pubkey> = pools.iter().map(|(_, pool)| *pool).collect();
+        let vaults: Vec<Option<(Pubkey, Pubkey)>> = token_helper::load_accounts(&self.rpc, &keys)
+            .await?
+            .into_iter()
+            .map(|pool| {
+                let data = pool?.data;
+                Some((
+                    read_pubkey(&data, POOL_BASE_VAULT_OFFSET)?,
+                    read_pubkey(&data, POOL_QUOTE_VAULT_OFFSET)?,
+                ))
+            })
+            .collect();
+
+        let (tokens, vaults): (Vec<&Token>, Vec<(Pubkey, Pubkey)>) = pools
+            .iter()
+            .zip(vaults)
+            .filter_map(|((token, _), vaults)| Some((token, vaults?)))
+            .unzip();
+        let vault_keys: Vec<Pubkey> = vaults
+            .iter()
+            .flat_map(|(base, quote)| [*base, *quote])
+            .collect();
+        let balances: Vec<Option<u64>> = token_helper::load_accounts(&self.rpc, &vault_keys)
+            .await?
+            .into_iter()
+            .map(|vault| {
+                let vault = vault?;
+                StateWithExtensions::<spl_token_2022::state::Account>::unpack(&vault.data)
+                    .ok()
+                    .map(|state| state.base.amount)
+            })
+            .collect();
+        for (token, balances) in tokens.into_iter().zip(balances.chunks(2)) {
+            let reserves = match balances {
+                [Some(token_amount), Some(lamports)] => Some(Reserves::from_pool(
+                    *token_amount,
+                    *lamports,
+                    token.decimals,
+                )),
+                _ => None,
+            };
+            self.update(token, reserves).await;
+        }
+
+        let curves: Vec<(Token, Pubkey)> = bonding
+            .into_iter()
+            .filter_map(|token| {
+                let curve = token.pump_fun_bonding_curve?;
+                Some((token, curve))
+            })
+            .collect();
+        let keys: Vec<Pubkey> = curves.iter().map(|(_, curve)| *curve).collect();
+        let accounts = token_helper::load_accounts(&self.rpc, &keys).await?;
+        for ((token, _), curve) in curves.iter().zip(accounts) {
+            let reserves =
+                curve.and_then(|curve| Reserves::from_curve(&curve.data, token.decimals));
+            self.update(token, reserves).await;
+        }
+        Ok(())
+    }
+
+    /// Publish `reserves` for `before` if they moved enough
+    async fn update(&self, before: &Token, reserves: Option<Reserves>) {
+        let Some(reserves) = reserves else {
+            return;
+        };
+        if !self.changed(before.liquidity_sol.as_ref(), Some(&reserves.liquidity_sol))
+            && !self.changed(before.price_sol.as_ref(), reserves.price_sol.as_ref())
+        {
+            return;
+        }
+        // Apply to the latest state, other tasks may have updated the token meanwhile
+        let Some(mut token) = self.processor.tracked_token(&before.mint_address).await else {
+            return;
+        };
+        debug!(
+            "Liquidity of {}: {:?} -> {} SOL",
+            token.mint_address, token.liquidity_sol, reserves.liquidity_sol
+        );
+        reserves.apply(&mut token);
+        scoring::apply(&mut token);
+        if let Err(e) = self.processor.process_token_updated(token).await {
+            warn!(
+                "Failed to publish liquidity of {}: {:#}",
+                before.mint_address, e
+            );
+        }
+    }
+
+    fn changed(&self, before: Option<&BigDecimal>, after: Option<&BigDecimal>) -> bool {
+        let Some(after) = after.and_then(|after| after.to_f64()) else {
+            return false;
+        };
+        let Some(before) = before.and_then(|before| before.to_f64()) else {
+            return true;
+        };
+        if before == 0.0 {
+            return after != 0.0;
+        }
+        ((after - before) / before).abs() * 100.0 >= self.config.min_change_percentage
+    }
+}
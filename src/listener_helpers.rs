@@ -1,11 +1,32 @@
-use std::sync::Arc;
-use solana_client::rpc_client::RpcClient;
+use std::time::Duration;
+use anyhow::bail;
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, Utc};
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::rpc_custom_error::JSON_RPC_SERVER_ERROR_UNSUPPORTED_TRANSACTION_VERSION;
+use solana_client::rpc_request::RpcError;
 use solana_client::rpc_config::RpcTransactionConfig;
-use solana_commitment_config::CommitmentConfig;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::rpc_response::{Response, RpcLogsResponse, SlotInfo};
 use solana_sdk::signature::Signature;
-use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding};
-use tokio::sync::Semaphore;
-use tracing::warn;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction, UiInstruction, UiMessage,
+    UiParsedInstruction, UiPartiallyDecodedInstruction, UiTransactionEncoding,
+    UiTransactionTokenBalance,
+};
+use solana_sdk::pubkey::Pubkey;
+use tracing::{info, warn};
+use crate::error::{self, ConnectionLost, EnrichError};
+use crate::{health, metrics};
+use crate::retry::{self, RetryPolicy};
+use crate::rpc_pool::RpcPool;
+use crate::models::{Token, TokenSource};
+use crate::dexscreener::Pair;
+use crate::token_helper::{MintInfo, TokenInfo};
 
 // pub async fn fetch_transaction_with_retry(
 //     rpc: &RpcClient,
@@ -62,78 +83,412 @@ use tracing::warn;
 //         }
 //     }
 
-pub async fn fetch_transaction_with_retry(
-    rpc: &RpcClient,
-    sig: &Signature,
-    limiter: Arc<Semaphore>,
-) -> anyhow::Result<Option<EncodedConfirmedTransactionWithStatusMeta>> {
-    // Acquire 1 permit — this limits concurrent RPC calls.
-    let _permit = limiter.acquire_owned().await?;
-
-    // Helper to detect version mismatch
-    let is_version_error = |msg: &str| {
-        msg.contains("Transaction version")
-            || msg.contains("not supported by the requesting client")
+/// Stand in for a listener's own subscription while `ingestion.shared_subscription`
+/// routes its logs; the supervisor sees it running
+pub async fn routed(listener: &str) -> anyhow::Result<()> {
+    info!("{} logs come from the shared subscription", listener);
+    std::future::pending().await
+}
+
+/// What solana-client hands back to end a subscription
+type UnsubscribeFn = Box<dyn FnOnce() -> BoxFuture<'static, ()> + Send>;
+
+/// Slot notifications on the same WSS connection as a listener's log
+/// subscriptions. They arrive every ~400ms whatever the programs do, so their
+/// silence means the socket is half-open: solana-client pings the server but
+/// never checks for the pong.
+pub struct Keepalive<'a> {
+    slots: Option<(BoxStream<'a, SlotInfo>, UnsubscribeFn)>,
+    timeout: Duration,
+}
+
+impl<'a> Keepalive<'a> {
+    /// Subscribe to slots unless `ingestion.keepalive_timeout_secs` is 0; a
+    /// node refusing the subscription only disables the keepalive
+    pub async fn subscribe(pubsub: &'a PubsubClient, listener: &str, timeout_secs: u64) -> Self {
+        let timeout = Duration::from_secs(timeout_secs);
+        if timeout.is_zero() {
+            return Self {
+                slots: None,
+                timeout,
+            };
+        }
+        let slots = match pubsub.slot_subscribe().await {
+            Ok(slots) => Some(slots),
+            Err(e) => {
+                warn!(
+                    "{}: slot subscription refused, WSS keepalive disabled: {}",
+                    listener, e
+                );
+                None
+            }
+        };
+        Self { slots, timeout }
+    }
+
+    /// Resolves with the reason once the connection looks dead
+    async fn lost(&mut self) -> String {
+        let Some((slots, _)) = &mut self.slots else {
+            return std::future::pending().await;
+        };
+        loop {
+            match tokio::time::timeout(self.timeout, slots.next()).await {
+                Ok(Some(_)) => continue,
+                Ok(None) => return "the connection closed".to_string(),
+                Err(_) => return format!("no slot notification for {:?}", self.timeout),
+            }
+        }
+    }
+
+    pub async fn close(self) {
+        if let Some((_, unsubscribe)) = self.slots {
+            unsubscribe().await;
+        }
+    }
+}
+
+/// Next message of a logs subscription with its slot, `None` once the stream has ended.
+///
+/// With a non-zero `silence_timeout` this doubles as a watchdog: if nothing
+/// arrives for that long the subscription is assumed dead and an error is
+/// returned, so the caller unsubscribes and the supervisor reconnects. A
+/// [`ConnectionLost`] error is returned as soon as `keepalive` stops.
+pub async fn next_log<S>(
+    stream: &mut S,
+    keepalive: &mut Keepalive<'_>,
+    listener: &str,
+    silence_timeout: Duration,
+) -> anyhow::Result<Option<(u64, RpcLogsResponse)>>
+where
+    S: Stream<Item = Response<RpcLogsResponse>> + Unpin,
+{
+    tokio::select! {
+        next = next_notification(stream, listener, silence_timeout) => next,
+        reason = keepalive.lost() => {
+            metrics::WSS_KEEPALIVE_FAILURES
+                .with_label_values(&[listener])
+                .inc();
+            Err(ConnectionLost(reason).into())
+        }
+    }
+}
+
+async fn next_notification<S>(
+    stream: &mut S,
+    listener: &str,
+    silence_timeout: Duration,
+) -> anyhow::Result<Option<(u64, RpcLogsResponse)>>
+where
+    S: Stream<Item = Response<RpcLogsResponse>> + Unpin,
+{
+    let split = |response: Response<RpcLogsResponse>| {
+        health::record_log(listener);
+        (response.context.slot, response.value)
     };
 
+    if silence_timeout.is_zero() {
+        return Ok(stream.next().await.map(split));
+    }
+
+    match tokio::time::timeout(silence_timeout, stream.next()).await {
+        Ok(next) => Ok(next.map(split)),
+        Err(_) => {
+            metrics::STREAM_STALLS.with_label_values(&[listener]).inc();
+            bail!("No logs received for {:?}, resubscribing", silence_timeout)
+        }
+    }
+}
+
+/// `getTransaction` through the endpoint pool, retried with backoff while the
+/// node has not indexed the transaction yet
+async fn pooled_get_transaction(
+    rpc: &RpcPool,
+    sig: &Signature,
+    config: RpcTransactionConfig,
+) -> solana_client::client_error::Result<EncodedConfirmedTransactionWithStatusMeta> {
+    retry::retry(
+        &RetryPolicy::default(),
+        "getTransaction",
+        error::is_missing,
+        || {
+            rpc.call("getTransaction", |client| async move {
+                client.get_transaction_with_config(sig, config).await
+            })
+        },
+    )
+    .await
+}
+
+/// The endpoint cannot encode this transaction version with the requested config
+fn is_version_error(error: &ClientError) -> bool {
+    matches!(
+        error.kind(),
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. })
+            if *code == JSON_RPC_SERVER_ERROR_UNSUPPORTED_TRANSACTION_VERSION
+    )
+}
+
+/// `None` if the transaction is not available (yet), RPC failures are classified
+/// so callers can tell a retryable outage from a request that will never work
+pub async fn fetch_transaction_with_retry(
+    rpc: &RpcPool,
+    sig: &Signature,
+) -> Result<Option<EncodedConfirmedTransactionWithStatusMeta>, EnrichError> {
     // ---- Attempt 1: default config
     let attempt_default = RpcTransactionConfig {
         encoding: Some(UiTransactionEncoding::JsonParsed),
         max_supported_transaction_version: None,
-        commitment: Some(CommitmentConfig::confirmed()),
+        commitment: Some(rpc.commitment()),
     };
 
-    match rpc.get_transaction_with_config(sig, attempt_default.clone()) {
+    match pooled_get_transaction(rpc, sig, attempt_default).await {
         Ok(tx) => return Ok(Some(tx)),
-        Err(err) => {
-            let msg = err.to_string();
-            if !is_version_error(&msg) {
-                if msg.contains("invalid type: null") {
-                    warn!("Transaction {} not found or not yet finalized", sig);
-                } else {
-                    warn!("Failed to fetch transaction {}: {}", sig, msg);
-                }
-                return Ok(None);
-            }
-        }
+        Err(err) if !is_version_error(&err) => return transaction_unavailable(sig, err),
+        Err(_) => {}
     }
 
     // ---- Attempt 2: allow version 0
     let attempt_v0 = RpcTransactionConfig {
         encoding: Some(UiTransactionEncoding::JsonParsed),
         max_supported_transaction_version: Some(0),
-        commitment: Some(CommitmentConfig::confirmed()),
-        ..Default::default()
+        commitment: Some(rpc.commitment()),
     };
 
-    match rpc.get_transaction_with_config(sig, attempt_v0) {
+    match pooled_get_transaction(rpc, sig, attempt_v0).await {
         Ok(tx) => return Ok(Some(tx)),
-        Err(err) => {
-            let msg = err.to_string();
-            if !is_version_error(&msg) {
-                warn!("Retry (v0) failed for transaction {}: {}", sig, msg);
-                return Ok(None);
-            }
-        }
+        Err(err) if !is_version_error(&err) => return transaction_unavailable(sig, err),
+        Err(_) => {}
     }
 
     // ---- Attempt 3: fallback None explicitly
     let attempt_none = RpcTransactionConfig {
         encoding: Some(UiTransactionEncoding::JsonParsed),
         max_supported_transaction_version: None,
-        commitment: Some(CommitmentConfig::confirmed()),
+        commitment: Some(rpc.commitment()),
     };
 
-    match rpc.get_transaction_with_config(sig, attempt_none) {
+    match pooled_get_transaction(rpc, sig, attempt_none).await {
         Ok(tx) => Ok(Some(tx)),
-        Err(err) => {
+        Err(err) if is_version_error(&err) => {
+            metrics::RPC_ERRORS.with_label_values(&["getTransaction"]).inc();
             warn!(
                 "RPC endpoint does not support versioned fetch for {}. Error: {}",
                 sig, err
             );
             Ok(None)
         }
+        Err(err) => transaction_unavailable(sig, err),
+    }
+}
+
+fn transaction_unavailable(
+    sig: &Signature,
+    err: ClientError,
+) -> Result<Option<EncodedConfirmedTransactionWithStatusMeta>, EnrichError> {
+    metrics::RPC_ERRORS.with_label_values(&["getTransaction"]).inc();
+    match EnrichError::rpc("getTransaction", err) {
+        EnrichError::ParseMiss(_) => {
+            warn!("Transaction {} not found or not yet finalized", sig);
+            Ok(None)
+        }
+        e => Err(e),
+    }
+}
+
+/// When the transaction landed: its block time, or the time of its slot if the
+/// node did not return one, or now as a last resort
+pub async fn transaction_time(
+    rpc: &RpcPool,
+    tx: &EncodedConfirmedTransactionWithStatusMeta,
+) -> DateTime<Utc> {
+    let block_time = match tx.block_time {
+        Some(ts) => Some(ts),
+        None => {
+            let slot = tx.slot;
+            rpc.call("getBlockTime", |client| async move { client.get_block_time(slot).await })
+                .await
+                .ok()
+        }
+    };
+
+    block_time
+        .and_then(|ts| DateTime::from_timestamp(ts, 0))
+        .unwrap_or_else(Utc::now)
+}
+
+/// Find the first instruction of `program_id` whose data starts with one of the given
+/// Anchor discriminators, looking at top-level instructions first and then CPIs
+/// (launchpad front-ends often invoke the underlying program from their own).
+pub fn find_program_instruction<'a>(
+    tx: &'a EncodedConfirmedTransactionWithStatusMeta,
+    program_id: &str,
+    discriminators: &[[u8; 8]],
+) -> Option<&'a UiPartiallyDecodedInstruction> {
+    all_instructions(tx).into_iter().find_map(|instr| match instr {
+        UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(decoded))
+            if decoded.program_id == program_id
+                && has_discriminator(&decoded.data, discriminators) =>
+        {
+            Some(decoded)
+        }
+        _ => None,
+    })
+}
+
+/// Top-level instructions followed by every inner (CPI) instruction
+pub fn all_instructions(tx: &EncodedConfirmedTransactionWithStatusMeta) -> Vec<&UiInstruction> {
+    let mut instructions: Vec<&UiInstruction> = Vec::new();
+    if let EncodedTransaction::Json(ui_tx) = &tx.transaction.transaction {
+        if let UiMessage::Parsed(parsed_msg) = &ui_tx.message {
+            instructions.extend(parsed_msg.instructions.iter());
+        }
     }
+    if let Some(meta) = &tx.transaction.meta {
+        if let OptionSerializer::Some(inner) = &meta.inner_instructions {
+            instructions.extend(inner.iter().flat_map(|set| set.instructions.iter()));
+        }
+    }
+    instructions
+}
+
+/// Distinct mints of every token account the transaction touched
+pub fn token_balance_mints(tx: &EncodedConfirmedTransactionWithStatusMeta) -> Vec<String> {
+    let mut mints: Vec<String> = Vec::new();
+    if let Some(meta) = &tx.transaction.meta {
+        if let OptionSerializer::Some(balances) = &meta.post_token_balances {
+            for balance in balances {
+                if !mints.contains(&balance.mint) {
+                    mints.push(balance.mint.clone());
+                }
+            }
+        }
+    }
+    mints
+}
+
+/// Program log lines recorded in the transaction meta
+pub fn log_messages(tx: &EncodedConfirmedTransactionWithStatusMeta) -> Option<&[String]> {
+    match &tx.transaction.meta.as_ref()?.log_messages {
+        OptionSerializer::Some(logs) => Some(logs),
+        _ => None,
+    }
+}
+
+/// Raw amount of `mint` that token accounts owned by `owner` gained in `tx`
+pub fn token_balance_gain(
+    tx: &EncodedConfirmedTransactionWithStatusMeta,
+    owner: &str,
+    mint: &str,
+) -> u128 {
+    let Some(meta) = &tx.transaction.meta else {
+        return 0;
+    };
+    let held = |balances: &OptionSerializer<Vec<UiTransactionTokenBalance>>| -> u128 {
+        let OptionSerializer::Some(balances) = balances else {
+            return 0;
+        };
+        balances
+            .iter()
+            .filter(|balance| {
+                balance.mint == mint
+                    && matches!(&balance.owner, OptionSerializer::Some(o) if o == owner)
+            })
+            .filter_map(|balance| balance.ui_token_amount.amount.parse::<u128>().ok())
+            .sum()
+    };
+    held(&meta.post_token_balances).saturating_sub(held(&meta.pre_token_balances))
+}
 
+/// Lamports the fee payer gained in `tx` (negative when it spent), fees included
+pub fn fee_payer_balance_change(tx: &EncodedConfirmedTransactionWithStatusMeta) -> Option<i128> {
+    let meta = tx.transaction.meta.as_ref()?;
+    let before = *meta.pre_balances.first()?;
+    let after = *meta.post_balances.first()?;
+    Some(i128::from(after) - i128::from(before))
+}
 
-}
\ No newline at end of file
+/// `amount` as a percentage of `supply` (both raw), `None` for an empty supply
+pub fn supply_percentage(amount: u128, supply: u64) -> Option<BigDecimal> {
+    if supply == 0 {
+        return None;
+    }
+    Some((BigDecimal::from(amount) * BigDecimal::from(100) / BigDecimal::from(supply)).round(2))
+}
+
+fn has_discriminator(data: &str, discriminators: &[[u8; 8]]) -> bool {
+    bs58::decode(data).into_vec().is_ok_and(|bytes| {
+        discriminators
+            .iter()
+            .any(|discriminator| bytes.starts_with(discriminator))
+    })
+}
+
+/// Build a freshly discovered token from its on-chain mint and fetched metadata.
+/// Venue specific fields (pools, bonding curves) are left for the caller to fill in.
+pub fn build_token(
+    mint: &Pubkey,
+    source: TokenSource,
+    creator: Option<String>,
+    created_at: DateTime<Utc>,
+    mint_data: &MintInfo,
+    token_info: TokenInfo,
+) -> Token {
+    let pair = token_info.pair.as_ref();
+    Token {
+        mint_address: mint.to_string(),
+        created_at,
+        discovered_at: Utc::now(),
+        source,
+        creator,
+        name: Some(token_info.name),
+        symbol: Some(token_info.symbol),
+        decimals: mint_data.decimals,
+        total_supply: BigDecimal::from(mint_data.supply),
+        holder_count: Some(0),
+        top_10_holder_percentage: Some(BigDecimal::zero()),
+        dev_buy_percentage: None,
+        liquidity_sol: pair.and_then(Pair::liquidity_sol),
+        price_sol: None,
+        price_usd: pair.and_then(|pair| pair.price_usd.clone()),
+        market_cap_usd: pair.and_then(|pair| pair.market_cap.clone()),
+        bonding_curve_progress: None,
+        king_of_the_hill_at: None,
+        liquidity_locked: None,
+        lp_burned: None,
+        mint_authority_disabled: mint_data.mint_authority.is_none(),
+        freeze_authority_disabled: mint_data.freeze_authority.is_none(),
+        mint_extensions: mint_data
+            .extensions
+            .types
+            .iter()
+            .map(|t| format!("{:?}", t))
+            .collect(),
+        transfer_fee_bps: mint_data.extensions.transfer_fee_basis_points,
+        has_transfer_hook: mint_data.extensions.transfer_hook_program.is_some(),
+        has_permanent_delegate: mint_data.extensions.permanent_delegate.is_some(),
+        raydium_pool: None,
+        pump_fun_bonding_curve: None,
+        launchlab_pool: None,
+        orca_pool: None,
+        meteora_pool: None,
+        pumpswap_pool: None,
+        four_meme_pool: None,
+        base_pair: None,
+        bsc_pair: None,
+        score: None,
+        risk_level: None,
+        creator_funding: None,
+        creator_blacklisted: false,
+        impersonation_of: None,
+        frozen_holders: None,
+        sellable: None,
+        wash_score: None,
+        metadata_uri: token_info.uri,
+        socials: Vec::new(),
+        bundled: false,
+        sniper_count: None,
+        provisional: false,
+        activity: None,
+        rugcheck: None,
+    }
+}
@@ -0,0 +1,247 @@
+use crate::processor::Processor;
+use crate::{listener_helpers, metrics, token_helper};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+
+use crate::config::Config;
+use crate::dedup::SignatureDedup;
+use crate::event_bus::LogQueue;
+use crate::error::ListenerError;
+use crate::listeners::TokenListener;
+use crate::models::{Token, TokenSource};
+use crate::rpc_pool::RpcPool;
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient,
+    rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+    rpc_response::RpcLogsResponse,
+};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// Anchor discriminators of the LaunchLab instructions that create a new launch
+const INITIALIZE_DISCRIMINATORS: [[u8; 8]; 3] = [
+    [175, 175, 109, 31, 13, 152, 155, 237], // initialize
+    [67, 153, 175, 39, 218, 16, 38, 32],    // initialize_v2
+    [37, 190, 126, 222, 44, 154, 171, 17],  // initialize_with_token_2022
+];
+
+// Account positions shared by every initialize variant
+const CREATOR_ACCOUNT_INDEX: usize = 1;
+const POOL_STATE_ACCOUNT_INDEX: usize = 5;
+const BASE_MINT_ACCOUNT_INDEX: usize = 6;
+
+const CREATE_LOG_LINES: [&str; 3] = [
+    "Program log: Instruction: Initialize",
+    "Program log: Instruction: InitializeV2",
+    "Program log: Instruction: InitializeWithToken2022",
+];
+
+/// Raydium LaunchLab (letsbonk.fun) bonding-curve launches
+pub struct LaunchLabListener {
+    config: Config,
+    processor: Processor,
+    rpc: Arc<RpcPool>,
+    dedup: Arc<SignatureDedup>,
+    queue: LogQueue,
+}
+
+impl LaunchLabListener {
+    pub fn new(
+        config: Config,
+        processor: Processor,
+        rpc: Arc<RpcPool>,
+        dedup: Arc<SignatureDedup>,
+        queue: LogQueue,
+    ) -> Self {
+        Self {
+            config,
+            processor,
+            rpc,
+            dedup,
+            queue,
+        }
+    }
+
+    async fn listen(&self) -> Result<()> {
+        if self.config.ingestion.shared_subscription {
+            return listener_helpers::routed(self.name()).await;
+        }
+        let pubsub = PubsubClient::new(self.rpc.wss_url(self.name())).await?;
+        let mut keepalive = listener_helpers::Keepalive::subscribe(
+            &pubsub,
+            self.name(),
+            self.config.ingestion.keepalive_timeout_secs,
+        )
+        .await;
+
+        let program = Pubkey::from_str(&self.config.programs.launchlab)
+            .context("Invalid programs.launchlab")?;
+
+        let (mut stream, unsubscribe) = pubsub
+            .logs_subscribe(
+                RpcTransactionLogsFilter::Mentions(vec![program.to_string()]),
+                RpcTransactionLogsConfig {
+                    commitment: Some(self.rpc.commitment()),
+                },
+            )
+            .await?;
+
+        info!("Subscribed to Raydium LaunchLab program");
+
+        let silence_timeout =
+            Duration::from_secs(self.config.ingestion.stream_silence_timeout_secs);
+
+        let outcome = loop {
+            let next = listener_helpers::next_log(
+                &mut stream,
+                &mut keepalive,
+                self.name(),
+                silence_timeout,
+            )
+            .await;
+            let (slot, rpc_log) = match next {
+                Ok(Some(next)) => next,
+                other => break other.map(|_| ()),
+            };
+
+            if self.accepts(&rpc_log) {
+                self.queue.push(self.name(), slot, rpc_log).await;
+            }
+        };
+
+        unsubscribe().await;
+        keepalive.close().await;
+        outcome
+    }
+
+    async fn process_log(&self, log: &RpcLogsResponse, program: &Pubkey) -> Result<()> {
+        if log.err.is_some() {
+            return Ok(());
+        }
+
+        let is_create = log
+            .logs
+            .iter()
+            .any(|line| CREATE_LOG_LINES.contains(&line.as_str()));
+
+        if !is_create {
+            if log.logs.iter().any(|l| l.contains("Instruction: Buy")) {
+                debug!("Detected LaunchLab Buy: {}", log.signature);
+            }
+            if log.logs.iter().any(|l| l.contains("Instruction: Sell")) {
+                debug!("Detected LaunchLab Sell: {}", log.signature);
+            }
+            return Ok(());
+        }
+
+        // The mint is only known from the transaction, so launches are sharded by signature
+        if !self.processor.owns_shard(&log.signature) {
+            metrics::DROPPED_MESSAGES
+                .with_label_values(&["launchlab", "other_shard"])
+                .inc();
+            return Ok(());
+        }
+
+        if !self.dedup.first_seen(&log.signature).await {
+            metrics::DROPPED_MESSAGES
+                .with_label_values(&["launchlab", "duplicate"])
+                .inc();
+            return Ok(());
+        }
+
+        info!("Detected new LaunchLab launch: {}", log.signature);
+
+        if let Some(token) = self.parse_launch(log, program).await? {
+            self.processor.process_token_discovered(token).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn parse_launch(&self, log: &RpcLogsResponse, program: &Pubkey) -> Result<Option<Token>> {
+        let sig = log
+            .signature
+            .parse()
+            .context("Failed to parse transaction signature for launchlab listener")?;
+        let rpc = self.rpc.as_ref();
+
+        let tx = match listener_helpers::fetch_transaction_with_retry(rpc, &sig)
+            .await?
+        {
+            Some(tx) => tx,
+            None => {
+                metrics::DROPPED_MESSAGES
+                    .with_label_values(&["launchlab", "tx_unavailable"])
+                    .inc();
+                return Ok(None);
+            }
+        };
+
+        let Some(initialize) = listener_helpers::find_program_instruction(
+            &tx,
+            &program.to_string(),
+            &INITIALIZE_DISCRIMINATORS,
+        )
+        .filter(|ix| ix.accounts.len() > BASE_MINT_ACCOUNT_INDEX) else {
+            metrics::DROPPED_MESSAGES
+                .with_label_values(&["launchlab", "mint_not_found"])
+                .inc();
+            return Ok(None);
+        };
+
+        let mint = Pubkey::from_str(&initialize.accounts[BASE_MINT_ACCOUNT_INDEX])
+            .context("Invalid LaunchLab base mint account")?;
+        let creator = initialize.accounts.get(CREATOR_ACCOUNT_INDEX).cloned();
+        let pool_state = initialize.accounts.get(POOL_STATE_ACCOUNT_INDEX).cloned();
+
+        // The initialize transaction is the creation itself, so its block time is the launch time
+        let created_at = listener_helpers::transaction_time(rpc, &tx).await;
+
+        let mint_str = mint.to_string();
+        let (mint_data, token_info) = tokio::join!(
+            token_helper::load_mint_info(rpc, &mint),
+            self.processor.token_info("launchlab", &mint_str),
+        );
+        let mint_data = match mint_data? {
+            Some(m) => m,
+            None => return Ok(None),
+        };
+        let token_info = token_info?;
+
+        let mut token = listener_helpers::build_token(
+            &mint,
+            TokenSource::LaunchLab,
+            creator,
+            created_at,
+            &mint_data,
+            token_info,
+        );
+        token.launchlab_pool = pool_state;
+
+        Ok(Some(token))
+    }
+}
+
+#[async_trait]
+impl TokenListener for LaunchLabListener {
+    fn name(&self) -> &'static str {
+        "launchlab"
+    }
+
+    async fn run(&self) -> Result<()> {
+        self.listen().await
+    }
+
+    fn programs(&self) -> Vec<String> {
+        vec![self.config.programs.launchlab.clone()]
+    }
+
+    async fn handle_log(&self, _slot: u64, log: &RpcLogsResponse) -> Result<(), ListenerError> {
+        let program = Pubkey::from_str(&self.config.programs.launchlab)
+            .context("Invalid programs.launchlab")?;
+        Ok(self.process_log(log, &program).await?)
+    }
+}
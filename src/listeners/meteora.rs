@@ -0,0 +1,343 @@
+use crate::processor::Processor;
+use crate::{listener_helpers, metrics, token_helper};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+
+use crate::config::Config;
+use crate::dedup::SignatureDedup;
+use crate::event_bus::LogQueue;
+use crate::error::ListenerError;
+use crate::listeners::TokenListener;
+use crate::models::TokenSource;
+use crate::rpc_pool::RpcPool;
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient,
+    rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+    rpc_response::RpcLogsResponse,
+};
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+
+// DLMM: lb_pair is account 0, token_mint_x / token_mint_y are accounts 2 and 3
+const DLMM_INITIALIZE_PAIR: [[u8; 8]; 4] = [
+    [45, 154, 237, 210, 221, 15, 166, 92],   // initialize_lb_pair
+    [73, 59, 36, 120, 237, 83, 108, 198],    // initialize_lb_pair2
+    [46, 39, 41, 135, 111, 183, 200, 64],    // initialize_customizable_permissionless_lb_pair
+    [243, 73, 129, 126, 51, 19, 241, 107],   // initialize_customizable_permissionless_lb_pair2
+];
+const DLMM_PAIR_ACCOUNTS: (usize, usize, usize) = (0, 2, 3);
+
+// DBC: creator, base_mint and pool are accounts 2, 3 and 5
+const DBC_INITIALIZE_POOL: [[u8; 8]; 2] = [
+    [140, 85, 215, 176, 102, 54, 104, 79],   // initialize_virtual_pool_with_spl_token
+    [169, 118, 51, 78, 145, 110, 220, 155],  // initialize_virtual_pool_with_token2022
+];
+const DBC_CREATOR_ACCOUNT_INDEX: usize = 2;
+const DBC_BASE_MINT_ACCOUNT_INDEX: usize = 3;
+const DBC_POOL_ACCOUNT_INDEX: usize = 5;
+
+// DBC graduation into a DAMM pool: the new pool is account 4
+const DBC_MIGRATE: [[u8; 8]; 2] = [
+    [27, 1, 48, 22, 180, 63, 118, 217],      // migrate_meteora_damm
+    [156, 169, 230, 103, 53, 228, 80, 64],   // migration_damm_v2
+];
+const DBC_MIGRATED_POOL_ACCOUNT_INDEX: usize = 4;
+
+const WRAPPED_SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+/// Which Meteora program a listener instance watches
+#[derive(Debug, Clone, Copy)]
+pub enum MeteoraProgram {
+    /// DLMM pairs, linked to tracked tokens
+    Dlmm,
+    /// Dynamic bonding curve launches and their migrations to DAMM
+    Dbc,
+}
+
+pub struct MeteoraListener {
+    program: MeteoraProgram,
+    config: Config,
+    processor: Processor,
+    rpc: Arc<RpcPool>,
+    dedup: Arc<SignatureDedup>,
+    queue: LogQueue,
+}
+
+impl MeteoraListener {
+    pub fn new(
+        program: MeteoraProgram,
+        config: Config,
+        processor: Processor,
+        rpc: Arc<RpcPool>,
+        dedup: Arc<SignatureDedup>,
+        queue: LogQueue,
+    ) -> Self {
+        Self {
+            program,
+            config,
+            processor,
+            rpc,
+            dedup,
+            queue,
+        }
+    }
+
+    fn program_id(&self) -> &str {
+        match self.program {
+            MeteoraProgram::Dlmm => &self.config.programs.meteora_dlmm,
+            MeteoraProgram::Dbc => &self.config.programs.meteora_dbc,
+        }
+    }
+
+    async fn listen(&self) -> Result<()> {
+        if self.config.ingestion.shared_subscription {
+            return listener_helpers::routed(self.name()).await;
+        }
+        let pubsub = PubsubClient::new(self.rpc.wss_url(self.name())).await?;
+        let mut keepalive = listener_helpers::Keepalive::subscribe(
+            &pubsub,
+            self.name(),
+            self.config.ingestion.keepalive_timeout_secs,
+        )
+        .await;
+
+        let program = Pubkey::from_str(self.program_id())
+            .with_context(|| format!("Invalid Meteora {:?} program id", self.program))?;
+
+        let (mut stream, unsubscribe) = pubsub
+            .logs_subscribe(
+                RpcTransactionLogsFilter::Mentions(vec![program.to_string()]),
+                RpcTransactionLogsConfig {
+                    commitment: Some(self.rpc.commitment()),
+                },
+            )
+            .await?;
+
+        info!("Subscribed to Meteora {:?} program", self.program);
+
+        let silence_timeout =
+            Duration::from_secs(self.config.ingestion.stream_silence_timeout_secs);
+
+        let outcome = loop {
+            let next = listener_helpers::next_log(
+                &mut stream,
+                &mut keepalive,
+                self.name(),
+                silence_timeout,
+            )
+            .await;
+            let (slot, rpc_log) = match next {
+                Ok(Some(next)) => next,
+                other => break other.map(|_| ()),
+            };
+
+            if self.accepts(&rpc_log) {
+                self.queue.push(self.name(), slot, rpc_log).await;
+            }
+        };
+
+        unsubscribe().await;
+        keepalive.close().await;
+        outcome
+    }
+
+    fn is_launch(&self, log: &RpcLogsResponse) -> bool {
+        matches!(self.program, MeteoraProgram::Dbc)
+            && log
+                .logs
+                .iter()
+                .any(|line| line.starts_with("Program log: Instruction: InitializeVirtualPool"))
+    }
+
+    fn is_candidate(&self, log: &RpcLogsResponse) -> bool {
+        log.logs.iter().any(|line| {
+            let Some(instruction) = line.strip_prefix("Program log: Instruction: ") else {
+                return false;
+            };
+            match self.program {
+                MeteoraProgram::Dlmm => instruction.starts_with("InitializeLbPair")
+                    || instruction.starts_with("InitializeCustomizablePermissionlessLbPair"),
+                MeteoraProgram::Dbc => instruction.starts_with("InitializeVirtualPool")
+                    || instruction.starts_with("MigrateMeteoraDamm")
+                    || instruction.starts_with("MigrationDammV2"),
+            }
+        })
+    }
+
+    async fn process_log(&self, log: &RpcLogsResponse) -> Result<()> {
+        if log.err.is_some() || !self.is_candidate(log) {
+            return Ok(());
+        }
+
+        // Launches are sharded by signature, their mint is only known from the
+        // transaction; pool links need the token and run on every instance
+        if self.is_launch(log) && !self.processor.owns_shard(&log.signature) {
+            metrics::DROPPED_MESSAGES
+                .with_label_values(&[self.name(), "other_shard"])
+                .inc();
+            return Ok(());
+        }
+
+        if !self.dedup.first_seen(&log.signature).await {
+            metrics::DROPPED_MESSAGES
+                .with_label_values(&[self.name(), "duplicate"])
+                .inc();
+            return Ok(());
+        }
+
+        let sig = log
+            .signature
+            .parse()
+            .context("Failed to parse transaction signature for meteora listener")?;
+
+        let tx = match listener_helpers::fetch_transaction_with_retry(
+            self.rpc.as_ref(),
+            &sig,
+        )
+        .await?
+        {
+            Some(tx) => tx,
+            None => {
+                metrics::DROPPED_MESSAGES
+                    .with_label_values(&[self.name(), "tx_unavailable"])
+                    .inc();
+                return Ok(());
+            }
+        };
+
+        match self.program {
+            MeteoraProgram::Dlmm => self.handle_dlmm(&tx).await,
+            MeteoraProgram::Dbc => self.handle_dbc(&tx).await,
+        }
+    }
+
+    async fn handle_dlmm(&self, tx: &EncodedConfirmedTransactionWithStatusMeta) -> Result<()> {
+        let (pair, mint_x, mint_y) = DLMM_PAIR_ACCOUNTS;
+        let Some(ix) =
+            listener_helpers::find_program_instruction(tx, self.program_id(), &DLMM_INITIALIZE_PAIR)
+                .filter(|ix| ix.accounts.len() > mint_y)
+        else {
+            metrics::DROPPED_MESSAGES
+                .with_label_values(&[self.name(), "pool_not_found"])
+                .inc();
+            return Ok(());
+        };
+
+        let mints = [ix.accounts[mint_x].clone(), ix.accounts[mint_y].clone()];
+        self.link_pool(&ix.accounts[pair], &mints).await
+    }
+
+    async fn handle_dbc(&self, tx: &EncodedConfirmedTransactionWithStatusMeta) -> Result<()> {
+        if let Some(ix) =
+            listener_helpers::find_program_instruction(tx, self.program_id(), &DBC_INITIALIZE_POOL)
+                .filter(|ix| ix.accounts.len() > DBC_POOL_ACCOUNT_INDEX)
+        {
+            let mint = Pubkey::from_str(&ix.accounts[DBC_BASE_MINT_ACCOUNT_INDEX])
+                .context("Invalid DBC base mint account")?;
+            let creator = ix.accounts.get(DBC_CREATOR_ACCOUNT_INDEX).cloned();
+            let pool = ix.accounts[DBC_POOL_ACCOUNT_INDEX].clone();
+
+            info!("Detected new Meteora DBC launch {} (pool {})", mint, pool);
+            return self.discover_dbc_launch(tx, mint, creator, pool).await;
+        }
+
+        if let Some(ix) = listener_helpers::find_program_instruction(tx, self.program_id(), &DBC_MIGRATE)
+            .filter(|ix| ix.accounts.len() > DBC_MIGRATED_POOL_ACCOUNT_INDEX)
+        {
+            // The migration's account list differs between DAMM versions, so take the
+            // mints from the balances the migration touched instead
+            let mints = listener_helpers::token_balance_mints(tx);
+            return self
+                .link_pool(&ix.accounts[DBC_MIGRATED_POOL_ACCOUNT_INDEX], &mints)
+                .await;
+        }
+
+        metrics::DROPPED_MESSAGES
+            .with_label_values(&[self.name(), "pool_not_found"])
+            .inc();
+        Ok(())
+    }
+
+    async fn discover_dbc_launch(
+        &self,
+        tx: &EncodedConfirmedTransactionWithStatusMeta,
+        mint: Pubkey,
+        creator: Option<String>,
+        pool: String,
+    ) -> Result<()> {
+        let rpc = self.rpc.as_ref();
+
+        let created_at = listener_helpers::transaction_time(rpc, tx).await;
+
+        let mint_str = mint.to_string();
+        let (mint_data, token_info) = tokio::join!(
+            token_helper::load_mint_info(rpc, &mint),
+            self.processor.token_info(self.name(), &mint_str),
+        );
+        let mint_data = match mint_data? {
+            Some(m) => m,
+            None => return Ok(()),
+        };
+        let token_info = token_info?;
+
+        let mut token = listener_helpers::build_token(
+            &mint,
+            TokenSource::Meteora,
+            creator,
+            created_at,
+            &mint_data,
+            token_info,
+        );
+        token.meteora_pool = Some(pool);
+
+        self.processor.process_token_discovered(token).await
+    }
+
+    /// Attach `pool` to whichever of `mints` is a tracked token
+    async fn link_pool(&self, pool: &str, mints: &[String]) -> Result<()> {
+        for mint in mints.iter().filter(|m| m.as_str() != WRAPPED_SOL_MINT) {
+            let Some(mut token) = self.processor.tracked_token(mint).await else {
+                continue;
+            };
+            if token.meteora_pool.as_deref() == Some(pool) {
+                continue;
+            }
+
+            info!("Meteora pool {} created for tracked token {}", pool, mint);
+            token.meteora_pool = Some(pool.to_string());
+            self.processor.process_token_updated(token).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TokenListener for MeteoraListener {
+    fn name(&self) -> &'static str {
+        match self.program {
+            MeteoraProgram::Dlmm => "meteora_dlmm",
+            MeteoraProgram::Dbc => "meteora_dbc",
+        }
+    }
+
+    async fn run(&self) -> Result<()> {
+        self.listen().await
+    }
+
+    fn programs(&self) -> Vec<String> {
+        vec![self.program_id().to_string()]
+    }
+
+    fn accepts(&self, log: &RpcLogsResponse) -> bool {
+        log.err.is_none() && self.is_candidate(log)
+    }
+
+    async fn handle_log(&self, _slot: u64, log: &RpcLogsResponse) -> Result<(), ListenerError> {
+        Ok(self.process_log(log).await?)
+    }
+}
@@ -1 +1,41 @@
+pub mod launchlab;
+pub mod meteora;
+pub mod onchain;
+pub mod orca;
 pub mod pumpfun;
+pub mod router;
+pub mod watchlist;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use solana_client::rpc_response::RpcLogsResponse;
+
+use crate::error::ListenerError;
+
+/// A source of token events that can be run by the supervisor.
+///
+/// `run` holds a single subscription session and returns when that session
+/// ends (error or closed stream); restarting is left to the supervisor.
+/// The session only queues log notifications; `handle_log` is called for each
+/// of them, with the slot it was notified at, by the event bus workers; the log
+/// is only borrowed so it can still be dead-lettered without a copy.
+#[async_trait]
+pub trait TokenListener: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn run(&self) -> Result<()>;
+
+    async fn handle_log(&self, slot: u64, log: &RpcLogsResponse) -> Result<(), ListenerError>;
+
+    /// Programs whose notifications the shared subscription routes to this
+    /// listener (`ingestion.shared_subscription`); empty when it keeps a
+    /// subscription of its own
+    fn programs(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Whether a notification of one of its programs is worth queueing
+    fn accepts(&self, log: &RpcLogsResponse) -> bool {
+        log.err.is_none()
+    }
+}
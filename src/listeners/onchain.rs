@@ -0,0 +1,329 @@
+use crate::processor::Processor;
+use crate::{listener_helpers, metrics, token_helper};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+
+use crate::config::Config;
+use crate::dedup::SignatureDedup;
+use crate::event_bus::LogQueue;
+use crate::error::ListenerError;
+use crate::listeners::TokenListener;
+use crate::models::TokenSource;
+use crate::rpc_pool::RpcPool;
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient,
+    rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+    rpc_response::RpcLogsResponse,
+};
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction, UiInstruction, UiMessage,
+    UiParsedInstruction,
+};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+/// Which token program a listener instance watches
+#[derive(Debug, Clone, Copy)]
+pub enum MintProgram {
+    SplToken,
+    Token2022,
+}
+
+/// Picks up mints created directly through the token programs, i.e. launches
+/// that did not go through a launchpad we already listen to.
+pub struct OnChainMintListener {
+    program: MintProgram,
+    config: Config,
+    processor: Processor,
+    rpc: Arc<RpcPool>,
+    dedup: Arc<SignatureDedup>,
+    queue: LogQueue,
+    excluded_programs: Vec<String>,
+    /// Start of the current one-minute window and mints accepted in it
+    window: Mutex<(Instant, u32)>,
+}
+
+impl OnChainMintListener {
+    pub fn new(
+        program: MintProgram,
+        config: Config,
+        processor: Processor,
+        rpc: Arc<RpcPool>,
+        dedup: Arc<SignatureDedup>,
+        queue: LogQueue,
+    ) -> Self {
+        let mut excluded_programs = config.programs.pump_fun.clone();
+        excluded_programs.extend([
+            config.programs.launchlab.clone(),
+            config.programs.meteora_dbc.clone(),
+        ]);
+        excluded_programs.extend(config.onchain.exclude_programs.iter().cloned());
+
+        Self {
+            program,
+            config,
+            processor,
+            rpc,
+            dedup,
+            queue,
+            excluded_programs,
+            window: Mutex::new((Instant::now(), 0)),
+        }
+    }
+
+    fn program_id(&self) -> &str {
+        match self.program {
+            MintProgram::SplToken => &self.config.programs.token_program,
+            MintProgram::Token2022 => &self.config.programs.token_2022_program,
+        }
+    }
+
+    async fn listen(&self) -> Result<()> {
+        if self.config.ingestion.shared_subscription {
+            return listener_helpers::routed(self.name()).await;
+        }
+        let pubsub = PubsubClient::new(self.rpc.wss_url(self.name())).await?;
+        let mut keepalive = listener_helpers::Keepalive::subscribe(
+            &pubsub,
+            self.name(),
+            self.config.ingestion.keepalive_timeout_secs,
+        )
+        .await;
+
+        let program = Pubkey::from_str(self.program_id())
+            .with_context(|| format!("Invalid {:?} program id", self.program))?;
+
+        let (mut stream, unsubscribe) = pubsub
+            .logs_subscribe(
+                RpcTransactionLogsFilter::Mentions(vec![program.to_string()]),
+                RpcTransactionLogsConfig {
+                    commitment: Some(self.rpc.commitment()),
+                },
+            )
+            .await?;
+
+        info!("Subscribed to {:?} program for new mints", self.program);
+
+        let silence_timeout =
+            Duration::from_secs(self.config.ingestion.stream_silence_timeout_secs);
+
+        let outcome = loop {
+            let next = listener_helpers::next_log(
+                &mut stream,
+                &mut keepalive,
+                self.name(),
+                silence_timeout,
+            )
+            .await;
+            let (slot, rpc_log) = match next {
+                Ok(Some(next)) => next,
+                other => break other.map(|_| ()),
+            };
+
+            if self.accepts(&rpc_log) {
+                self.queue.push(self.name(), slot, rpc_log).await;
+            }
+        };
+
+        unsubscribe().await;
+        keepalive.close().await;
+        outcome
+    }
+
+    async fn process_log(&self, log: &RpcLogsResponse) -> Result<()> {
+        if log.err.is_some()
+            || !log
+                .logs
+                .iter()
+                .any(|l| l.starts_with("Program log: Instruction: InitializeMint"))
+        {
+            return Ok(());
+        }
+
+        // Launchpad mints are handled (with richer data) by their own listeners
+        if log
+            .logs
+            .iter()
+            .any(|l| self.excluded_programs.iter().any(|p| l.contains(p.as_str())))
+        {
+            return Ok(());
+        }
+
+        // The mint is only known from the transaction, so mints are sharded by signature
+        if !self.processor.owns_shard(&log.signature) {
+            metrics::DROPPED_MESSAGES
+                .with_label_values(&[self.name(), "other_shard"])
+                .inc();
+            return Ok(());
+        }
+
+        if !self.dedup.first_seen(&log.signature).await {
+            metrics::DROPPED_MESSAGES
+                .with_label_values(&[self.name(), "duplicate"])
+                .inc();
+            return Ok(());
+        }
+
+        if !self.try_acquire() {
+            debug!("On-chain mint rate limit reached, skipping {}", log.signature);
+            metrics::DROPPED_MESSAGES
+                .with_label_values(&[self.name(), "rate_limited"])
+                .inc();
+            return Ok(());
+        }
+
+        let sig = log
+            .signature
+            .parse()
+            .context("Failed to parse transaction signature for onchain listener")?;
+        let rpc = self.rpc.as_ref();
+
+        let tx = match listener_helpers::fetch_transaction_with_retry(rpc, &sig)
+            .await?
+        {
+            Some(tx) => tx,
+            None => {
+                metrics::DROPPED_MESSAGES
+                    .with_label_values(&[self.name(), "tx_unavailable"])
+                    .inc();
+                return Ok(());
+            }
+        };
+
+        let (mints, creator) = initialized_mints(&tx);
+        if mints.is_empty() {
+            metrics::DROPPED_MESSAGES
+                .with_label_values(&[self.name(), "mint_not_found"])
+                .inc();
+            return Ok(());
+        }
+
+        let created_at = listener_helpers::transaction_time(rpc, &tx).await;
+
+        for mint in mints {
+            let mint_data = match token_helper::load_mint_info(rpc, &mint).await? {
+                Some(m) => m,
+                None => continue,
+            };
+            let token_info = self
+                .processor
+                .token_info("onchain", &mint.to_string())
+                .await?;
+
+            info!("Detected new on-chain mint: {}", mint);
+            let token = listener_helpers::build_token(
+                &mint,
+                TokenSource::OnChain,
+                creator.clone(),
+                created_at,
+                &mint_data,
+                token_info,
+            );
+            self.processor.process_token_discovered(token).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Fixed one-minute window limit on how many mints get enriched
+    fn try_acquire(&self) -> bool {
+        let max = self.config.onchain.max_mints_per_minute;
+        let mut window = self.window.lock().unwrap_or_else(|e| e.into_inner());
+
+        if window.0.elapsed() >= Duration::from_secs(60) {
+            *window = (Instant::now(), 0);
+        }
+        if window.1 >= max {
+            return false;
+        }
+        window.1 += 1;
+        true
+    }
+}
+
+/// Mints initialized by the transaction (top level or CPI), plus its fee payer
+fn initialized_mints(tx: &EncodedConfirmedTransactionWithStatusMeta) -> (Vec<Pubkey>, Option<String>) {
+    let mut instructions: Vec<&UiInstruction> = Vec::new();
+    let mut creator = None;
+
+    if let EncodedTransaction::Json(ui_tx) = &tx.transaction.transaction {
+        if let UiMessage::Parsed(parsed_msg) = &ui_tx.message {
+            creator = parsed_msg
+                .account_keys
+                .iter()
+                .find(|account| account.signer)
+                .map(|account| account.pubkey.clone());
+            instructions.extend(parsed_msg.instructions.iter());
+        }
+    }
+    if let Some(meta) = &tx.transaction.meta {
+        if let OptionSerializer::Some(inner) = &meta.inner_instructions {
+            instructions.extend(inner.iter().flat_map(|set| set.instructions.iter()));
+        }
+    }
+
+    let mut mints = Vec::new();
+    for instr in instructions {
+        if let UiInstruction::Parsed(UiParsedInstruction::Parsed(pi)) = instr {
+            if pi.program != "spl-token" && pi.program != "spl-token-2022" {
+                continue;
+            }
+            let is_initialize_mint = pi
+                .parsed
+                .get("type")
+                .and_then(|t| t.as_str())
+                .is_some_and(|t| t.starts_with("initializeMint"));
+            if !is_initialize_mint {
+                continue;
+            }
+
+            if let Some(mint) = pi
+                .parsed
+                .get("info")
+                .and_then(|info| info.get("mint"))
+                .and_then(|m| m.as_str())
+                .and_then(|m| Pubkey::from_str(m).ok())
+            {
+                if !mints.contains(&mint) {
+                    mints.push(mint);
+                }
+            }
+        }
+    }
+
+    (mints, creator)
+}
+
+#[async_trait]
+impl TokenListener for OnChainMintListener {
+    fn name(&self) -> &'static str {
+        match self.program {
+            MintProgram::SplToken => "onchain_spl_token",
+            MintProgram::Token2022 => "onchain_token_2022",
+        }
+    }
+
+    async fn run(&self) -> Result<()> {
+        self.listen().await
+    }
+
+    fn programs(&self) -> Vec<String> {
+        vec![self.program_id().to_string()]
+    }
+
+    fn accepts(&self, log: &RpcLogsResponse) -> bool {
+        log.err.is_none()
+            && log
+                .logs
+                .iter()
+                .any(|l| l.starts_with("Program log: Instruction: InitializeMint"))
+    }
+
+    async fn handle_log(&self, _slot: u64, log: &RpcLogsResponse) -> Result<(), ListenerError> {
+        Ok(self.process_log(log).await?)
+    }
+}
@@ -0,0 +1,220 @@
+use crate::processor::Processor;
+use crate::{listener_helpers, metrics};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+
+use crate::config::Config;
+use crate::dedup::SignatureDedup;
+use crate::event_bus::LogQueue;
+use crate::error::ListenerError;
+use crate::listeners::TokenListener;
+use crate::rpc_pool::RpcPool;
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient,
+    rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+    rpc_response::RpcLogsResponse,
+};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+
+const INITIALIZE_POOL: [u8; 8] = [95, 180, 10, 172, 84, 174, 232, 40];
+const INITIALIZE_POOL_V2: [u8; 8] = [207, 45, 87, 242, 27, 63, 204, 67];
+
+/// Positions of (mint A, mint B, whirlpool) in each initialize variant
+const INITIALIZE_POOL_ACCOUNTS: (usize, usize, usize) = (1, 2, 4);
+const INITIALIZE_POOL_V2_ACCOUNTS: (usize, usize, usize) = (1, 2, 6);
+
+const CREATE_LOG_LINES: [&str; 2] = [
+    "Program log: Instruction: InitializePool",
+    "Program log: Instruction: InitializePoolV2",
+];
+
+/// Links new Orca Whirlpools to tokens we already published and emits `TokenUpdated`
+pub struct OrcaWhirlpoolListener {
+    config: Config,
+    processor: Processor,
+    rpc: Arc<RpcPool>,
+    dedup: Arc<SignatureDedup>,
+    queue: LogQueue,
+}
+
+impl OrcaWhirlpoolListener {
+    pub fn new(
+        config: Config,
+        processor: Processor,
+        rpc: Arc<RpcPool>,
+        dedup: Arc<SignatureDedup>,
+        queue: LogQueue,
+    ) -> Self {
+        Self {
+            config,
+            processor,
+            rpc,
+            dedup,
+            queue,
+        }
+    }
+
+    async fn listen(&self) -> Result<()> {
+        if self.config.ingestion.shared_subscription {
+            return listener_helpers::routed(self.name()).await;
+        }
+        let pubsub = PubsubClient::new(self.rpc.wss_url(self.name())).await?;
+        let mut keepalive = listener_helpers::Keepalive::subscribe(
+            &pubsub,
+            self.name(),
+            self.config.ingestion.keepalive_timeout_secs,
+        )
+        .await;
+
+        let program = Pubkey::from_str(&self.config.programs.orca_whirlpool)
+            .context("Invalid programs.orca_whirlpool")?;
+
+        let (mut stream, unsubscribe) = pubsub
+            .logs_subscribe(
+                RpcTransactionLogsFilter::Mentions(vec![program.to_string()]),
+                RpcTransactionLogsConfig {
+                    commitment: Some(self.rpc.commitment()),
+                },
+            )
+            .await?;
+
+        info!("Subscribed to Orca Whirlpool program");
+
+        let silence_timeout =
+            Duration::from_secs(self.config.ingestion.stream_silence_timeout_secs);
+
+        let outcome = loop {
+            let next = listener_helpers::next_log(
+                &mut stream,
+                &mut keepalive,
+                self.name(),
+                silence_timeout,
+            )
+            .await;
+            let (slot, rpc_log) = match next {
+                Ok(Some(next)) => next,
+                other => break other.map(|_| ()),
+            };
+
+            if self.accepts(&rpc_log) {
+                self.queue.push(self.name(), slot, rpc_log).await;
+            }
+        };
+
+        unsubscribe().await;
+        keepalive.close().await;
+        outcome
+    }
+
+    async fn process_log(&self, log: &RpcLogsResponse, program: &Pubkey) -> Result<()> {
+        if log.err.is_some()
+            || !log
+                .logs
+                .iter()
+                .any(|line| CREATE_LOG_LINES.contains(&line.as_str()))
+        {
+            return Ok(());
+        }
+
+        if !self.dedup.first_seen(&log.signature).await {
+            metrics::DROPPED_MESSAGES
+                .with_label_values(&["orca", "duplicate"])
+                .inc();
+            return Ok(());
+        }
+
+        let sig = log
+            .signature
+            .parse()
+            .context("Failed to parse transaction signature for orca listener")?;
+
+        let tx = match listener_helpers::fetch_transaction_with_retry(
+            self.rpc.as_ref(),
+            &sig,
+        )
+        .await?
+        {
+            Some(tx) => tx,
+            None => {
+                metrics::DROPPED_MESSAGES
+                    .with_label_values(&["orca", "tx_unavailable"])
+                    .inc();
+                return Ok(());
+            }
+        };
+
+        let program_id = program.to_string();
+        let accounts = [
+            (INITIALIZE_POOL, INITIALIZE_POOL_ACCOUNTS),
+            (INITIALIZE_POOL_V2, INITIALIZE_POOL_V2_ACCOUNTS),
+        ]
+        .into_iter()
+        .find_map(|(discriminator, (mint_a, mint_b, pool))| {
+            listener_helpers::find_program_instruction(&tx, &program_id, &[discriminator])
+                .filter(|ix| ix.accounts.len() > pool)
+                .map(|ix| {
+                    (
+                        ix.accounts[mint_a].clone(),
+                        ix.accounts[mint_b].clone(),
+                        ix.accounts[pool].clone(),
+                    )
+                })
+        });
+
+        let Some((mint_a, mint_b, whirlpool)) = accounts else {
+            metrics::DROPPED_MESSAGES
+                .with_label_values(&["orca", "pool_not_found"])
+                .inc();
+            return Ok(());
+        };
+
+        // Whirlpools order mints by key, so the tracked token can be on either side
+        for mint in [&mint_a, &mint_b] {
+            let Some(mut token) = self.processor.tracked_token(mint).await else {
+                continue;
+            };
+            if token.orca_pool.as_deref() == Some(whirlpool.as_str()) {
+                continue;
+            }
+
+            info!("Orca Whirlpool {} created for tracked token {}", whirlpool, mint);
+            token.orca_pool = Some(whirlpool.clone());
+            self.processor.process_token_updated(token).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TokenListener for OrcaWhirlpoolListener {
+    fn name(&self) -> &'static str {
+        "orca"
+    }
+
+    async fn run(&self) -> Result<()> {
+        self.listen().await
+    }
+
+    fn programs(&self) -> Vec<String> {
+        vec![self.config.programs.orca_whirlpool.clone()]
+    }
+
+    fn accepts(&self, log: &RpcLogsResponse) -> bool {
+        log.err.is_none()
+            && log
+                .logs
+                .iter()
+                .any(|line| CREATE_LOG_LINES.contains(&line.as_str()))
+    }
+
+    async fn handle_log(&self, _slot: u64, log: &RpcLogsResponse) -> Result<(), ListenerError> {
+        let program = Pubkey::from_str(&self.config.programs.orca_whirlpool)
+            .context("Invalid programs.orca_whirlpool")?;
+        Ok(self.process_log(log, &program).await?)
+    }
+}
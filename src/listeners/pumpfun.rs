@@ -1,14 +1,31 @@
+pub mod idl;
+
 use crate::processor::Processor;
-use crate::{listener_helpers, token_helper};
+use crate::{funding, honeypot, liquidity, listener_helpers, metrics, token_helper, wash};
 use anyhow::{Context, Result};
-use bigdecimal::{BigDecimal, FromPrimitive, Zero};
-use chrono::TimeZone;
-use futures::StreamExt;
-use std::clone;
-
-use crate::config::Config;
-use crate::models::{Token, TokenSource};
-use solana_client::rpc_client::RpcClient;
+use async_trait::async_trait;
+use bigdecimal::ToPrimitive;
+use chrono::{DateTime, Utc};
+
+use crate::config::{Config, TradeAttributionConfig};
+use crate::confirmation::ConfirmationTracker;
+use crate::cursor::SlotCursor;
+use crate::dedup::SignatureDedup;
+use crate::event_bus::{self, LogQueue};
+use crate::error::ListenerError;
+use crate::listeners::TokenListener;
+use crate::aggregator::CandleAggregator;
+use crate::launch::{self, LaunchTracker, LaunchUpdate};
+use crate::log_classifier::{LogClassifier, LogFlag};
+use crate::liquidity::Reserves;
+use crate::models::{Envelope, LaunchBuy, LaunchSnapshot, Token, TokenSource, TokenState, Trade};
+use crate::rpc_pool::RpcPool;
+use crate::sinks::channel::{ChannelSink, EventStream};
+use crate::supervisor::Supervisor;
+use crate::volume::VolumeTracker;
+use futures::Stream;
+use moka::future::Cache;
+use idl::{CreateEvent, Events, Instructions, TradeEvent, PUMP_AMM_PROGRAM};
 use solana_client::{
     nonblocking::pubsub_client::PubsubClient,
     rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter},
@@ -16,260 +33,956 @@ use solana_client::{
 };
 use solana_commitment_config::CommitmentConfig;
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
 use solana_transaction_status::{
     EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction, UiInstruction, UiMessage,
     UiParsedInstruction,
 };
+use std::collections::HashSet;
 use std::str::FromStr;
 use std::sync::Arc;
-use tokio::sync::Semaphore;
-use tracing::{error, info, warn};
+use std::time::Duration;
+use tracing::{debug, field, info, info_span, warn, Instrument};
+
+const CREATE_POOL_LOG: &str = "Program log: Instruction: CreatePool";
+
+const ATA_PROGRAM: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
+const WSOL_MINT: &str = "So11111111111111111111111111111111111111112";
 
 pub struct PumpFunListener {
     config: Config,
     processor: Processor,
-    limiter: Arc<Semaphore>,
+    rpc: Arc<RpcPool>,
+    dedup: Arc<SignatureDedup>,
+    queue: LogQueue,
+    /// Set in fast path mode: logs arrive at `processed` and tokens are published provisionally
+    confirmations: Option<Arc<ConfirmationTracker>>,
+    launches: LaunchTracker,
+    classifier: LogClassifier,
+    /// Logs whose trades and launch buys are recorded but whose token is not
+    /// published yet, so a dead-letter retry doesn't record them again
+    recorded: Cache<String, ()>,
 }
 
 impl PumpFunListener {
-    pub fn new(config: Config, processor: Processor, limiter: Arc<Semaphore>) -> Self {
+    pub fn new(
+        config: Config,
+        processor: Processor,
+        rpc: Arc<RpcPool>,
+        dedup: Arc<SignatureDedup>,
+        queue: LogQueue,
+        confirmations: Option<Arc<ConfirmationTracker>>,
+    ) -> Self {
         Self {
+            launches: LaunchTracker::new(&config.launch),
+            classifier: classifier(config.programs.pump_fun_program()),
+            recorded: Cache::builder()
+                .max_capacity(100_000)
+                .time_to_live(Duration::from_secs(24 * 3600))
+                .build(),
             config,
             processor,
-            limiter,
+            rpc,
+            dedup,
+            queue,
+            confirmations,
         }
     }
 
-    pub async fn start(&self) -> Result<()> {
-        info!("Starting Pump.fun listener");
-
-        loop {
-            if let Err(e) = self.listen().await {
-                error!("Pump.fun listener error: {}", e);
-                tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
-            }
+    /// Run this listener on its own and yield what it publishes as a stream,
+    /// in place of the configured sinks, numbered from 1 in memory. Dropping the
+    /// stream stops the listener.
+    pub fn into_stream(mut self) -> Result<impl Stream<Item = Envelope>> {
+        let (sink, rx) = ChannelSink::new(self.config.sink_options.queue_size);
+        // No shared subscription runs to route logs to it
+        self.config.ingestion.shared_subscription = false;
+        self.processor = Processor::with_sinks(&self.config, vec![Arc::new(sink)])?
+            .with_funding(funding::tracer(&self.config, &self.rpc))
+            .with_honeypot(honeypot::simulator(&self.config, &self.rpc))
+            .with_wash(wash::detector(&self.config, &self.rpc))
+            .with_lp_checker(liquidity::lp_checker(&self.config, &self.rpc))
+            .with_blacklist(self.processor.blacklist())
+            .with_lifecycle(self.processor.lifecycle());
+        if self.config.candles.enabled {
+            CandleAggregator::spawn_flusher(self.processor.clone());
+        }
+        if self.config.volume.enabled {
+            VolumeTracker::spawn_publisher(self.processor.clone(), &self.config.volume);
         }
+        if self.confirmations.is_some() {
+            self.confirmations = Some(ConfirmationTracker::spawn(
+                self.rpc.clone(),
+                self.processor.clone(),
+                self.config.ingestion.fast_path_confirm_commitment()?,
+                Duration::from_secs(self.config.ingestion.fast_path_confirm_timeout_secs),
+            ));
+        }
+
+        let queue = self.queue.clone();
+        let workers = self.config.ingestion.workers;
+        let timeout = self.config.ingestion.log_timeout();
+        let mut supervisor = Supervisor::new(&self.config.ingestion).with_rpc(self.rpc.clone());
+        supervisor.register(Arc::new(self));
+        let handlers = supervisor.handlers();
+
+        let tasks = vec![
+            tokio::spawn(async move {
+                if let Err(e) = supervisor.run().await {
+                    warn!("Pump.fun stream stopped: {}", e);
+                }
+            }),
+            tokio::spawn(event_bus::run_workers(
+                queue,
+                handlers,
+                workers,
+                timeout,
+                Arc::new(SlotCursor::in_memory()),
+                None,
+                None,
+            )),
+        ];
+
+        Ok(EventStream::new(rx, tasks))
     }
 
     async fn listen(&self) -> Result<()> {
-        let pubsub = PubsubClient::new(&self.config.network.rpc_wss_url).await?;
+        if self.config.helius.enabled {
+            info!("Pump.fun transactions come from the Helius webhook, not subscribing");
+            return std::future::pending().await;
+        }
+        // The fast path needs its `processed` subscription
+        if self.config.ingestion.shared_subscription && self.confirmations.is_none() {
+            return listener_helpers::routed(self.name()).await;
+        }
+        let pubsub = PubsubClient::new(self.rpc.wss_url(self.name())).await?;
+        let mut keepalive = listener_helpers::Keepalive::subscribe(
+            &pubsub,
+            self.name(),
+            self.config.ingestion.keepalive_timeout_secs,
+        )
+        .await;
+
+        let programs = self
+            .config
+            .programs
+            .pump_fun
+            .iter()
+            .map(|program| Pubkey::from_str(program).map(|p| p.to_string()))
+            .collect::<Result<Vec<_>, _>>()
+            .context("Invalid programs.pump_fun")?;
+        let commitment = if self.confirmations.is_some() {
+            CommitmentConfig::processed()
+        } else {
+            self.rpc.commitment()
+        };
 
-        let pumpfun_pubkey = Pubkey::from_str(&self.config.programs.pump_fun)?;
+        // `Mentions` takes a single program, so subscribe once per program and
+        // multiplex the notifications over this one connection
+        let mut streams = Vec::with_capacity(programs.len());
+        let mut unsubscribes = Vec::with_capacity(programs.len());
+        for program in &programs {
+            let (stream, unsubscribe) = pubsub
+                .logs_subscribe(
+                    RpcTransactionLogsFilter::Mentions(vec![program.clone()]),
+                    RpcTransactionLogsConfig {
+                        commitment: Some(commitment),
+                    },
+                )
+                .await?;
+            streams.push(stream);
+            unsubscribes.push(unsubscribe);
+        }
+        let mut stream = futures::stream::select_all(streams);
 
-        let (mut stream, unsubscribe) = pubsub
-            .logs_subscribe(
-                RpcTransactionLogsFilter::Mentions(vec![pumpfun_pubkey.to_string()]),
-                // RpcTransactionLogsFilter::All,
-                RpcTransactionLogsConfig {
-                    commitment: Some(CommitmentConfig::confirmed()),
-                },
-            )
-            .await?;
+        info!(
+            "Subscribed to {} Pump.fun program(s){}",
+            programs.len(),
+            if self.confirmations.is_some() { " (fast path)" } else { "" }
+        );
 
-        info!("Subscribed to Pump.fun program");
+        let silence_timeout =
+            Duration::from_secs(self.config.ingestion.stream_silence_timeout_secs);
 
-        while let Some(result) = stream.next().await {
-            let rpc_log: RpcLogsResponse = result.value;
+        let outcome = loop {
+            let next = listener_helpers::next_log(
+                &mut stream,
+                &mut keepalive,
+                self.name(),
+                silence_timeout,
+            )
+            .await;
+            let (slot, rpc_log) = match next {
+                Ok(Some(next)) => next,
+                other => break other.map(|_| ()),
+            };
+
+            // A transaction touching several programs arrives once per subscription,
+            // the copies are dropped by dedup
+            if self.accepts(&rpc_log) {
+                self.queue.push(self.name(), slot, rpc_log).await;
+            }
+        };
 
-            if rpc_log
-                .logs
-                .iter()
-                .any(|l| l.contains(&pumpfun_pubkey.to_string()))
-            {
-                if let Err(e) = self.process_log(rpc_log.clone()).await {
-                    // error!("Pump.fun process_log error: {:?}", rpc_log);
-                    error!("Error processing Pump.fun log: {}", e);
+        for unsubscribe in unsubscribes {
+            unsubscribe().await;
+        }
+        keepalive.close().await;
+        outcome
+    }
+
+    pub async fn process_log(&self, slot: u64, log: &RpcLogsResponse) -> Result<()> {
+        let events = Events::decode(&log.logs, self.config.programs.pump_fun_program());
+        // Everything about a mint (launch, trades, lifecycle) stays on the instance
+        // owning its shard; logs naming no mint, like pool creations, go to all
+        if self.processor.is_sharded() {
+            if let Some(mint) = shard_mint(&events) {
+                if !self.processor.owns_shard(&mint.to_string()) {
+                    metrics::DROPPED_MESSAGES
+                        .with_label_values(&["pumpfun", "other_shard"])
+                        .inc();
+                    return Ok(());
                 }
             }
         }
 
-        unsubscribe().await;
-        Ok(())
-    }
+        if !self.dedup.first_seen(&log.signature).await {
+            metrics::DROPPED_MESSAGES
+                .with_label_values(&["pumpfun", "duplicate"])
+                .inc();
+            return Ok(());
+        }
+        events.report_undecoded();
 
-    pub async fn process_log(&self, log: RpcLogsResponse) -> Result<()> {
+        let flags = self.classifier.classify(&log.logs);
         // Check if transaction succeeded
-        let is_success = log.logs.iter().any(|l| l.contains("success"));
-        if !is_success {
+        if !flags.contains(LogFlag::Success) {
             return Ok(());
         }
 
-        // Detect buy/sell instructions
-        let is_buy = log.logs.iter().any(|l| l.contains("Instruction: Buy"));
-        let is_sell = log.logs.iter().any(|l| l.contains("Instruction: Sell"));
-
-        if is_buy {
-            info!("Detected Pump.fun Buy: {:?}", &log.signature);
+        // PumpSwap pools (including migrations off the bonding curve) are linked to
+        // their token; the create parser only handles bonding curve transactions
+        if flags.contains(LogFlag::PumpAmm) && flags.contains(LogFlag::CreatePool) {
+            return self.process_pool_creation(log).await;
         }
-        if is_sell {
-            info!("Detected Pump.fun Sell: {:?}", &log.signature);
+        if !flags.contains(LogFlag::PumpFun) {
+            debug!("Skipping {}: no pump.fun bonding curve instruction", log.signature);
+            return Ok(());
         }
 
-        // Detect token creation
-        let is_create = log.logs.iter().any(|line| {
-            line.contains("InitializeMint")
-                || line.contains("InitializeMint2")
-                || line.contains("CreateMetadataAccount")
-                || line.contains("CreateMetadataAccountV3")
-                || line.contains("Instruction: Create")
-                || line.contains("master_edition")
-                || line.contains("InitializeAccount3")
+        if !self.recorded.contains_key(&log.signature) {
+            self.record_events(slot, log, &events).await?;
+            // Kept until the log is done, past a failure or `log_timeout_secs`
+            if self.config.dlq.enabled {
+                self.recorded.insert(log.signature.clone(), ()).await;
+            }
+        }
+        let trades = &events.trades;
+        let creation = events.creation();
+        let creation_buys = creation.map_or(0, |event| {
+            trades
+                .iter()
+                .filter(|trade| trade.is_buy && trade.mint == event.mint)
+                .count()
         });
 
-        // Detect swap events
-        let is_swap = log
-            .logs
-            .iter()
-            .any(|l| l.contains("Instruction: SwapTob") || l.contains("SwapEvent"));
-        if is_swap {
+        // The events decode whatever the instruction logs say
+        if flags.contains(LogFlag::Buy) || trades.iter().any(|trade| trade.is_buy) {
+            info!("Detected Pump.fun Buy: {:?}", &log.signature);
+        }
+        if flags.contains(LogFlag::Sell) || trades.iter().any(|trade| !trade.is_buy) {
+            info!("Detected Pump.fun Sell: {:?}", &log.signature);
+        }
+        if flags.contains(LogFlag::Swap) {
             info!("Detected Pump.fun Swap: {:?}", &log.signature);
         }
-        if !is_create {
-            return Ok(());
+        if flags.contains(LogFlag::Create) || creation.is_some() {
+            self.publish_creation(log, creation, trades, creation_buys).await?;
         }
+        self.recorded.invalidate(&log.signature).await;
+        Ok(())
+    }
 
+    /// Trades, launch buys, lifecycle transitions and rugs of a log, recorded
+    /// once per signature
+    async fn record_events(&self, slot: u64, log: &RpcLogsResponse, events: &Events) -> Result<()> {
+        // Early buys of recent launches, and the launch itself when this creates a token
+        let trades = &events.trades;
+        self.track_launches(slot, events.creation(), trades).await?;
+        if self.config.tracks_trades() {
+            for trade in self.attribute_trades(log, trades).await {
+                self.processor.process_trade(&trade).await?;
+            }
+        }
+        if self.config.lifecycle.enabled {
+            self.track_lifecycle(events).await?;
+        }
+        if self.config.lifecycle.enabled
+            || (self.config.blacklist.enabled && self.config.blacklist.auto_flag)
+        {
+            self.detect_rugs(trades).await?;
+        }
+        Ok(())
+    }
+
+    /// Parse the token a log creates and publish it, provisionally in fast path mode
+    async fn publish_creation(
+        &self,
+        log: &RpcLogsResponse,
+        creation: Option<&CreateEvent>,
+        trades: &[TradeEvent],
+        creation_buys: usize,
+    ) -> Result<()> {
         info!("Detected new Pump.fun token: {}", log.signature);
         // info!("Full logs for debugging: {:?}", &log.logs);
 
-        let token = self.parse_pumpfun_creation(&log).await?;
+        if let Some(confirmations) = &self.confirmations {
+            let provisional = match creation {
+                Some(event) => self.parse_provisional_creation(event, trades).await?,
+                None => None,
+            };
+            if let Some(mut token) = provisional {
+                token.bundled = creation_buys > 1;
+                let sig = log
+                    .signature
+                    .parse()
+                    .context("Failed to parse transaction signature for pumfun listener")?;
+                let mint = token.mint_address.clone();
+                self.processor.process_token_discovered(token).await?;
+                confirmations.track(sig, mint);
+                return Ok(());
+            }
+        }
+
+        let token = self.parse_pumpfun_creation(log).await?;
         info!("Pump.fun parsed token: {:?}", token);
 
-        if let Some(token) = token {
+        if let Some(mut token) = token {
+            token.bundled = creation_buys > 1;
             self.processor.process_token_discovered(token).await?;
         }
 
         Ok(())
     }
 
+    /// Hand the buys of a log seen at `slot` to the launch tracker, start following
+    /// `creation`, and publish the launches whose window has closed
+    async fn track_launches(
+        &self,
+        slot: u64,
+        creation: Option<&CreateEvent>,
+        trades: &[TradeEvent],
+    ) -> Result<()> {
+        let buys = |mint: Option<&Pubkey>| {
+            trades
+                .iter()
+                .filter(|trade| trade.is_buy && mint.is_none_or(|mint| trade.mint == *mint))
+                .map(|trade| {
+                    (
+                        trade.mint.to_string(),
+                        LaunchBuy {
+                            wallet: trade.user.to_string(),
+                            slot,
+                            sol_amount: trade.sol_amount,
+                            token_amount: trade.token_amount,
+                            usd_amount: None,
+                        },
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let closed = self.launches.record(slot, buys(None));
+        if let Some(event) = creation {
+            let creation_buys = buys(Some(&event.mint)).into_iter().map(|(_, buy)| buy);
+            self.launches.start(
+                &event.mint.to_string(),
+                Some(event.user.to_string()),
+                slot,
+                creation_buys.collect(),
+            );
+        }
+
+        for update in closed {
+            let span = info_span!("launch", mint = field::Empty);
+            self.publish_launch(update).instrument(span).await?;
+        }
+        Ok(())
+    }
+
+    /// Tracked tokens trading on their curve are bonding, completed curves graduated
+    async fn track_lifecycle(&self, events: &Events) -> Result<()> {
+        let traded: HashSet<Pubkey> = events.trades.iter().map(|trade| trade.mint).collect();
+        for mint in traded {
+            self.processor
+                .transition(&mint.to_string(), TokenState::Bonding)
+                .await?;
+        }
+        for event in &events.completes {
+            self.processor
+                .transition(&event.mint.to_string(), TokenState::Graduated)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Blacklist creators dumping a large share of a tracked token's supply,
+    /// and mark the token rugged
+    async fn detect_rugs(&self, trades: &[TradeEvent]) -> Result<()> {
+        for trade in trades.iter().filter(|trade| !trade.is_buy) {
+            let Some(token) = self.processor.tracked_token(&trade.mint.to_string()).await else {
+                continue;
+            };
+            if token.creator.as_deref() != Some(trade.user.to_string().as_str()) {
+                continue;
+            }
+            let sold = token
+                .total_supply
+                .to_u64()
+                .and_then(|supply| {
+                    listener_helpers::supply_percentage(u128::from(trade.token_amount), supply)
+                })
+                .and_then(|percentage| percentage.to_f64())
+                .unwrap_or(0.0);
+            if sold >= self.config.blacklist.rug_sell_percentage {
+                if self.config.blacklist.enabled && self.config.blacklist.auto_flag {
+                    let reason = format!("creator sold {:.2}% of the supply", sold);
+                    self.processor.flag_rugger(&token, &reason).await?;
+                }
+                self.processor
+                    .transition(&token.mint_address, TokenState::Rugged)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Set `bundled` and `sniper_count` on a closed launch's token, if it was
+    /// published, and publish snapshots with `launch.snapshot_event`
+    async fn publish_launch(&self, update: LaunchUpdate) -> Result<()> {
+        match update {
+            LaunchUpdate::WindowClosed(snapshot) => {
+                let Some(mut token) = self.processor.tracked_token(&snapshot.mint).await else {
+                    return Ok(());
+                };
+                token.bundled = snapshot.bundled;
+                token.sniper_count = Some(snapshot.sniper_count);
+                self.processor.process_token_updated(token).await
+            }
+            LaunchUpdate::Snapshot(snapshot) if self.config.launch.snapshot_event => {
+                self.spawn_launch_snapshot(snapshot);
+                Ok(())
+            }
+            LaunchUpdate::Snapshot(_) => Ok(()),
+        }
+    }
+
+    /// Read the holdings of the buyers off the log path, then publish the snapshot
+    fn spawn_launch_snapshot(&self, mut snapshot: LaunchSnapshot) {
+        let rpc = self.rpc.clone();
+        let processor = self.processor.clone();
+        tokio::spawn(
+            async move {
+                if let Err(e) = launch::load_holdings(&rpc, &mut snapshot).await {
+                    warn!(
+                        "Holdings of the buyers of {} unavailable: {:#}",
+                        snapshot.mint, e
+                    );
+                }
+                let mint = snapshot.mint.clone();
+                if let Err(e) = processor.process_launch_snapshot(snapshot).await {
+                    warn!("Failed to publish launch snapshot of {}: {:#}", mint, e);
+                }
+            }
+            .in_current_span(),
+        );
+    }
+
     pub async fn parse_pumpfun_creation(&self, log: &RpcLogsResponse) -> Result<Option<Token>> {
         let sig = log
             .signature
             .parse()
             .context("Failed to parse transaction signature for pumfun listener")?;
-        let rpc = RpcClient::new_with_commitment(
-            &self.config.network.rpc_http_url,
-            CommitmentConfig::confirmed(),
-        );
-
-        // 1️⃣ Fetch the transaction with retry logic
-        let tx_opt: Option<EncodedConfirmedTransactionWithStatusMeta> =
-            listener_helpers::fetch_transaction_with_retry(&rpc, &sig, self.limiter.clone())
-                .await?;
+        parse_creation(self.rpc.as_ref(), self.config.programs.pump_fun_program(), &sig).await
+    }
 
-        let tx = match tx_opt {
-            Some(tx) => tx,
-            None => return Ok(None),
-        };
+    /// The trades of a log tagged with the router they went through; with
+    /// `trade_attribution.resolve_fee_payer` routed ones are credited to the fee payer
+    async fn attribute_trades(&self, log: &RpcLogsResponse, events: &[TradeEvent]) -> Vec<Trade> {
+        let attribution = &self.config.trade_attribution;
+        let mut trades: Vec<Trade> = events
+            .iter()
+            .map(|event| event.to_trade(attribution))
+            .collect();
+        for program in events
+            .iter()
+            .filter_map(|event| event.invoked_by.as_deref())
+        {
+            let router = attribution
+                .routers
+                .get(program)
+                .map_or("other", String::as_str);
+            metrics::ROUTED_TRADES.with_label_values(&[router]).inc();
+        }
+        if !attribution.resolve_fee_payer || trades.iter().all(|trade| trade.router.is_none()) {
+            return trades;
+        }
 
-        // 2️⃣ Extract mint address from instructions
-        let mut mint_address: Option<Pubkey> = None;
-
-        if let EncodedTransaction::Json(ui_tx) = &tx.transaction.transaction {
-            if let UiMessage::Parsed(parsed_msg) = &ui_tx.message {
-                for instr in &parsed_msg.instructions {
-                    if let UiInstruction::Parsed(UiParsedInstruction::Parsed(pi)) = instr {
-                        if pi.program == "spl-associated-token-account"
-                            && pi.program_id == "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL"
-                            && pi
-                                .parsed
-                                .get("type")
-                                .unwrap_or(&serde_json::Value::String("".into()))
-                                == "createIdempotent"
-                        {
-                            if let Some(info) = pi.parsed.get("info") {
-                                if let Some(mint_str) = info.get("mint").and_then(|m| m.as_str()) {
-                                    if let Ok(pubkey) = Pubkey::from_str(mint_str) {
-                                        mint_address = Some(pubkey);
-                                        break;
-                                    }
-                                }
-                            }
-                        }
+        let payer = match log.signature.parse() {
+            Ok(sig) => {
+                match listener_helpers::fetch_transaction_with_retry(self.rpc.as_ref(), &sig).await
+                {
+                    Ok(tx) => tx.as_ref().and_then(fee_payer),
+                    Err(e) => {
+                        warn!(
+                            "Failed to resolve the fee payer of {}: {:#}",
+                            log.signature, e
+                        );
+                        None
                     }
                 }
             }
+            Err(_) => None,
+        };
+        if let Some(payer) = payer {
+            for trade in trades.iter_mut().filter(|trade| trade.router.is_some()) {
+                trade.wallet = payer.clone();
+            }
         }
+        trades
+    }
 
-        let mint = match mint_address {
-            Some(m) => m,
-            None => return Ok(None),
+    /// Link a new PumpSwap pool to the tracked token it trades and emit `TokenUpdated`
+    async fn process_pool_creation(&self, log: &RpcLogsResponse) -> Result<()> {
+        let sig = log
+            .signature
+            .parse()
+            .context("Failed to parse transaction signature for pumfun listener")?;
+
+        let Some(tx) = listener_helpers::fetch_transaction_with_retry(self.rpc.as_ref(), &sig).await?
+        else {
+            metrics::DROPPED_MESSAGES
+                .with_label_values(&["pumpfun", "tx_unavailable"])
+                .inc();
+            return Ok(());
         };
 
-        if mint.to_string() == "So11111111111111111111111111111111111111112" {
-            return Ok(None); // skip system accounts
-        }
-        // 3️⃣ Fetch mint creation transaction (with retry)
-        let first_sig_opt = {
-            let mut sig_opt: Option<_> = None;
-            for attempt in 1..=3 {
-                match rpc.get_signatures_for_address(&mint) {
-                    Ok(mut sigs) if !sigs.is_empty() => {
-                        sig_opt = sigs.last().cloned();
-                        break;
-                    }
-                    Ok(_) => warn!(
-                        "No signatures found for mint {} (attempt {}/3)",
-                        mint, attempt
-                    ),
-                    Err(err) => warn!("Failed get_signatures_for_address {}: {}", mint, err),
-                }
-                tokio::time::sleep(std::time::Duration::from_millis(200)).await;
-            }
-            sig_opt
+        let instructions = Instructions::decode(&tx, self.config.programs.pump_fun_program());
+        instructions.report_undecoded();
+        let Some((pool, mint)) = instructions
+            .pool_creation()
+            .map(|(_, accounts)| (accounts.pool.to_string(), accounts.base_mint.to_string()))
+        else {
+            metrics::DROPPED_MESSAGES
+                .with_label_values(&["pumpfun", "pool_not_found"])
+                .inc();
+            return Ok(());
         };
 
-        let created_at = if let Some(sig_info) = first_sig_opt {
-            let tx_opt = listener_helpers::fetch_transaction_with_retry(
-                &rpc,
-                &sig_info.signature.parse()?,
-                self.limiter.clone(),
-            )
+        let Some(mut token) = self.processor.tracked_token(&mint).await else {
+            return Ok(());
+        };
+        if token.pumpswap_pool.as_deref() == Some(pool.as_str()) {
+            return Ok(());
+        }
+
+        info!("PumpSwap pool {} created for tracked token {}", pool, mint);
+        self.processor.check_lp(&mint, &pool);
+        token.pumpswap_pool = Some(pool.clone());
+        self.processor.note_graduated(&token).await;
+        self.processor.process_token_updated(token.clone()).await?;
+        self.processor.process_token_graduated(token, &pool).await?;
+        self.processor.transition(&mint, TokenState::PoolListed).await
+    }
+
+    /// Token straight from the CreateEvent of a `processed` notification, without
+    /// waiting for `getTransaction` (which only serves confirmed transactions).
+    /// `None` when the mint account is not readable yet, the regular path is used then.
+    async fn parse_provisional_creation(
+        &self,
+        event: &CreateEvent,
+        trades: &[TradeEvent],
+    ) -> Result<Option<Token>> {
+        let accounts = token_helper::load_accounts_with_commitment(
+            self.rpc.as_ref(),
+            &[event.mint, event.bonding_curve],
+            CommitmentConfig::processed(),
+        )
+        .await?;
+        let Some(mint_data) = accounts
+            .first()
+            .and_then(Option::as_ref)
+            .and_then(|account| token_helper::parse_mint_account(&event.mint, account))
+        else {
+            return Ok(None);
+        };
+
+        let mut token = listener_helpers::build_token(
+            &event.mint,
+            TokenSource::Pumpfun,
+            Some(event.user.to_string()),
+            chrono::Utc::now(),
+            &mint_data,
+            token_helper::TokenInfo {
+                name: event.name.clone(),
+                symbol: event.symbol.clone(),
+                uri: Some(event.uri.clone()).filter(|uri| !uri.is_empty()),
+                decimals: Some(mint_data.decimals),
+                pair: None,
+            },
+        );
+        if let Some(curve) = accounts.get(1).and_then(Option::as_ref) {
+            token.pump_fun_bonding_curve = Some(event.bonding_curve);
+            if let Some(reserves) = Reserves::from_curve(&curve.data, mint_data.decimals) {
+                reserves.apply(&mut token);
+            }
+        }
+        token.dev_buy_percentage = listener_helpers::supply_percentage(
+            bought_in_trades(trades, &event.mint, &event.user),
+            mint_data.supply,
+        );
+        token.provisional = true;
+        Ok(Some(token))
+    }
+}
+
+/// Flags `process_log` looks at, for the configured bonding curve program
+fn classifier(pump_fun_program: &str) -> LogClassifier {
+    LogClassifier::builder()
+        .contains("success", LogFlag::Success)
+        .contains(pump_fun_program, LogFlag::PumpFun)
+        .contains(PUMP_AMM_PROGRAM, LogFlag::PumpAmm)
+        .line(CREATE_POOL_LOG, LogFlag::CreatePool)
+        .contains("Instruction: Buy", LogFlag::Buy)
+        .contains("Instruction: Sell", LogFlag::Sell)
+        .contains("Instruction: SwapTob", LogFlag::Swap)
+        .contains("SwapEvent", LogFlag::Swap)
+        .contains("InitializeMint", LogFlag::Create)
+        .contains("CreateMetadataAccount", LogFlag::Create)
+        .contains("Instruction: Create", LogFlag::Create)
+        .contains("master_edition", LogFlag::Create)
+        .contains("InitializeAccount3", LogFlag::Create)
+        .build()
+}
+
+/// Whether `program` shows up in the log lines, e.g. `Program <id> invoke [1]`
+pub(crate) fn mentions(log: &RpcLogsResponse, program: &str) -> bool {
+    log.logs.iter().any(|line| line.contains(program))
+}
+
+impl TradeEvent {
+    pub fn to_trade(&self, attribution: &TradeAttributionConfig) -> Trade {
+        Trade {
+            mint: self.mint.to_string(),
+            wallet: self.user.to_string(),
+            sol_amount: self.sol_amount,
+            token_amount: self.token_amount,
+            is_buy: self.is_buy,
+            at: DateTime::from_timestamp(self.timestamp, 0).unwrap_or_else(Utc::now),
+            router: self
+                .invoked_by
+                .as_deref()
+                .map(|program| attribution.router(program)),
+        }
+    }
+}
+
+/// The mint a log is about: the one it creates, else the first one it trades
+fn shard_mint(events: &Events) -> Option<Pubkey> {
+    events
+        .creation()
+        .map(|event| event.mint)
+        .or_else(|| events.trades.first().map(|trade| trade.mint))
+}
+
+/// Tokens `user` bought of `mint` according to the TradeEvents of a log
+fn bought_in_trades(trades: &[TradeEvent], mint: &Pubkey, user: &Pubkey) -> u128 {
+    trades
+        .iter()
+        .filter(|trade| trade.is_buy && trade.mint == *mint && trade.user == *user)
+        .map(|trade| u128::from(trade.token_amount))
+        .sum()
+}
+
+/// Fetch a pump.fun create transaction and build its token, `None` if it is not one
+pub async fn parse_creation(
+    rpc: &RpcPool,
+    program_id: &str,
+    sig: &Signature,
+) -> Result<Option<Token>> {
+    // 1️⃣ Fetch the transaction with retry logic
+    let tx_opt: Option<EncodedConfirmedTransactionWithStatusMeta> =
+        listener_helpers::fetch_transaction_with_retry(rpc, sig)
             .await?;
 
-            match tx_opt {
-                Some(tx) => tx.block_time.map(|ts| chrono::Utc.timestamp(ts, 0)),
-                None => None,
+    let tx = match tx_opt {
+        Some(tx) => tx,
+        None => {
+            metrics::DROPPED_MESSAGES
+                .with_label_values(&["pumpfun", "tx_unavailable"])
+                .inc();
+            return Ok(None);
+        }
+    };
+
+    parse_transaction(rpc, program_id, &tx).await
+}
+
+/// Whether `tx` runs the pump.fun Create instruction (top level or CPI),
+/// as opposed to a buy or sell that merely opens a token account
+pub fn is_create_transaction(
+    tx: &EncodedConfirmedTransactionWithStatusMeta,
+    program_id: &str,
+) -> bool {
+    Instructions::decode(tx, program_id).creation().is_some()
+}
+
+/// Build the token of an already fetched create transaction
+pub async fn parse_transaction(
+    rpc: &RpcPool,
+    program_id: &str,
+    tx: &EncodedConfirmedTransactionWithStatusMeta,
+) -> Result<Option<Token>> {
+    // 2️⃣ Extract mint address and creator, from the create instruction when it decodes
+    let instructions = Instructions::decode(tx, program_id);
+    instructions.report_undecoded();
+    let create = instructions.creation();
+    let creator = match &create {
+        Some((_, accounts)) => Some(accounts.user.to_string()),
+        None => fee_payer(tx),
+    };
+    let mint = create.as_ref().map(|(_, accounts)| accounts.mint);
+    let mint = match mint.or_else(|| extract_created_mint(tx)) {
+        Some(m) => m,
+        None => {
+            metrics::DROPPED_MESSAGES
+                .with_label_values(&["pumpfun", "mint_not_found"])
+                .inc();
+            return Ok(None);
+        }
+    };
+
+    // 3️⃣ Enrich concurrently: created_at comes from the creation transaction itself,
+    //    the mint and bonding curve accounts share one getMultipleAccounts call,
+    //    and metadata is fetched from DexScreener meanwhile
+    let bonding_curve = bonding_curve_address(&mint, program_id)?;
+    let mint_str = mint.to_string();
+    let keys = [mint, bonding_curve];
+    let (created_at, accounts, token_info) = tokio::join!(
+        listener_helpers::transaction_time(rpc, tx),
+        token_helper::load_accounts(rpc, &keys),
+        token_helper::fetch_token_info("pumpfun", &mint_str, "solana"),
+    );
+    let token_info = token_info?;
+
+    let accounts = match accounts {
+        Ok(accounts) => accounts,
+        Err(e) => {
+            warn!("{:#}", e);
+            return Ok(None);
+        }
+    };
+    let mint_data = match accounts
+        .first()
+        .and_then(Option::as_ref)
+        .and_then(|account| token_helper::parse_mint_account(&mint, account))
+    {
+        Some(m) => m,
+        None => return Ok(None),
+    };
+
+    // The creator's initial buy is bundled into the create transaction
+    let dev_buy = creator
+        .as_deref()
+        .map(|creator| listener_helpers::token_balance_gain(tx, creator, &mint_str))
+        .unwrap_or(0);
+
+    let mut token = listener_helpers::build_token(
+        &mint,
+        TokenSource::Pumpfun,
+        creator,
+        created_at,
+        &mint_data,
+        token_info,
+    );
+    token.dev_buy_percentage = listener_helpers::supply_percentage(dev_buy, mint_data.supply);
+    token.metadata_uri = listener_helpers::log_messages(tx)
+        .and_then(|logs| Events::decode(logs, program_id).creates.into_iter().next())
+        .map(|event| event.uri)
+        .or_else(|| create.map(|(args, _)| args.uri.clone()))
+        .filter(|uri| !uri.is_empty());
+    if let Some(curve) = accounts.get(1).and_then(Option::as_ref) {
+        token.pump_fun_bonding_curve = Some(bonding_curve);
+        if let Some(reserves) = Reserves::from_curve(&curve.data, mint_data.decimals) {
+            reserves.apply(&mut token);
+        }
+    }
+    Ok(Some(token))
+}
+
+/// PDA holding the pump.fun bonding curve of `mint`
+fn bonding_curve_address(mint: &Pubkey, program_id: &str) -> Result<Pubkey> {
+    let program = Pubkey::from_str(program_id).context("Invalid programs.pump_fun")?;
+    Ok(Pubkey::find_program_address(&[b"bonding-curve", mint.as_ref()], &program).0)
+}
+
+/// The fee payer of a transaction: the creator of a create transaction whose
+/// create instruction did not decode, the trader behind a routed trade
+fn fee_payer(tx: &EncodedConfirmedTransactionWithStatusMeta) -> Option<String> {
+    match &tx.transaction.transaction {
+        EncodedTransaction::Json(ui_tx) => match &ui_tx.message {
+            UiMessage::Parsed(parsed_msg) => parsed_msg
+                .account_keys
+                .iter()
+                .find(|account| account.signer)
+                .map(|account| account.pubkey.clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Mint created by a pump.fun create transaction whose create instruction did not
+/// decode.
+///
+/// Looks at associated token account creations (top level and CPI) first, then
+/// at the mints in `postTokenBalances`. Wrapped SOL is never the new mint.
+fn extract_created_mint(tx: &EncodedConfirmedTransactionWithStatusMeta) -> Option<Pubkey> {
+    let is_candidate = |mint: &str| mint != WSOL_MINT;
+
+    let from_ata = listener_helpers::all_instructions(tx)
+        .into_iter()
+        .find_map(|instr| match instr {
+            UiInstruction::Parsed(UiParsedInstruction::Parsed(pi))
+                if pi.program_id == ATA_PROGRAM
+                    && pi
+                        .parsed
+                        .get("type")
+                        .and_then(|t| t.as_str())
+                        .is_some_and(|t| t == "create" || t == "createIdempotent") =>
+            {
+                pi.parsed
+                    .get("info")
+                    .and_then(|info| info.get("mint"))
+                    .and_then(|m| m.as_str())
+                    .filter(|m| is_candidate(m))
+                    .map(str::to_string)
             }
-        } else {
-            None
-        };
-        let created_at = created_at.unwrap_or(chrono::Utc::now());
+            _ => None,
+        });
+
+    let mint = from_ata.or_else(|| {
+        listener_helpers::token_balance_mints(tx)
+            .into_iter()
+            .find(|m| is_candidate(m))
+    })?;
+
+    Pubkey::from_str(&mint).ok()
+}
 
-        // 4️⃣ Load mint info and token metadata
-        let mint_data = token_helper::load_mint_info(&rpc, &mint)?;
-        let token_info =
-            token_helper::fetch_token_info("pumpfun", &mint.to_string(), "solana").await?;
+#[async_trait]
+impl TokenListener for PumpFunListener {
+    fn name(&self) -> &'static str {
+        "pumpfun"
+    }
+
+    async fn run(&self) -> Result<()> {
+        self.listen().await
+    }
+
+    fn programs(&self) -> Vec<String> {
+        if self.config.helius.enabled || self.confirmations.is_some() {
+            return Vec::new();
+        }
+        self.config.programs.pump_fun.clone()
+    }
 
-        let mint_data = match mint_data {
-            Some(m) => m,
-            None => return Ok(None),
+    fn accepts(&self, log: &RpcLogsResponse) -> bool {
+        self.config
+            .programs
+            .pump_fun
+            .iter()
+            .any(|program| mentions(log, program))
+    }
+
+    async fn handle_log(&self, slot: u64, log: &RpcLogsResponse) -> Result<(), ListenerError> {
+        Ok(self.process_log(slot, log).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_classifier::LogFlags;
+
+    const PUMP_FUN: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
+
+    /// Lines of pump.fun creations, trades and migrations and of the programs they call
+    const LINES: &[&str] = &[
+        "Program ComputeBudget111111111111111111111111111111 invoke [1]",
+        "Program ComputeBudget111111111111111111111111111111 success",
+        "Program 6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P invoke [1]",
+        "Program log: Instruction: Create",
+        "Program log: Instruction: CreateV2",
+        "Program log: Instruction: InitializeMint2",
+        "Program log: Instruction: InitializeAccount3",
+        "Program log: Instruction: InitializeImmutableOwner",
+        "Program log: Instruction: CreateMetadataAccountV3",
+        "Program log: Creating master_edition",
+        "Program log: Instruction: Buy",
+        "Program log: Instruction: Sell",
+        "Program log: Instruction: Migrate",
+        "Program log: Instruction: CreatePool",
+        "Program log: Instruction: CreatePoolV2",
+        "Program log: Instruction: SwapTob",
+        "Program log: SwapEvent { amount_in: 1 }",
+        "Program log: Instruction: Transfer",
+        "Program data: vdt/007mYe5XD5Rn8AQAAOQFGQsAAAAA",
+        "Program pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA invoke [2]",
+        "Program pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA consumed 5000 of 200000 compute units",
+        "Program 6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P success",
+        "Program 6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P failed: custom program error: 0x1772",
+    ];
+
+    /// The flags of the per-line scan the classifier replaced
+    fn scan(logs: &[String]) -> LogFlags {
+        let mut flags = LogFlags::default();
+        let mut set = |flag, found: bool| {
+            if found {
+                flags.insert(flag);
+            }
         };
+        for line in logs {
+            set(LogFlag::Success, line.contains("success"));
+            set(LogFlag::PumpFun, line.contains(PUMP_FUN));
+            set(LogFlag::PumpAmm, line.contains(PUMP_AMM_PROGRAM));
+            set(LogFlag::CreatePool, line == CREATE_POOL_LOG);
+            set(LogFlag::Buy, line.contains("Instruction: Buy"));
+            set(LogFlag::Sell, line.contains("Instruction: Sell"));
+            set(
+                LogFlag::Swap,
+                line.contains("Instruction: SwapTob") || line.contains("SwapEvent"),
+            );
+            set(
+                LogFlag::Create,
+                line.contains("InitializeMint")
+                    || line.contains("CreateMetadataAccount")
+                    || line.contains("Instruction: Create")
+                    || line.contains("master_edition")
+                    || line.contains("InitializeAccount3"),
+            );
+        }
+        flags
+    }
 
-        Ok(Some(Token {
-            mint_address: mint.to_string(),
-            created_at,
-            discovered_at: chrono::Utc::now(),
-            source: TokenSource::Pumpfun,
-            name: Some(token_info.name),
-            symbol: Some(token_info.symbol),
-            decimals: mint_data.decimals,
-            total_supply: BigDecimal::from(mint_data.supply),
-            holder_count: Some(0),
-            top_10_holder_percentage: Some(BigDecimal::zero()),
-            liquidity_sol: Some(BigDecimal::zero()),
-            liquidity_locked: Some(false),
-            lp_burned: Some(false),
-            mint_authority_disabled: mint_data.mint_authority.is_none(),
-            freeze_authority_disabled: mint_data.freeze_authority.is_none(),
-            raydium_pool: None,
-            pump_fun_bonding_curve: None,
-            orca_pool: None,
-            meteora_pool: None,
-            four_meme_pool: None,
-            base_pair: None,
-            bsc_pair: None,
-            score: None,
-            risk_level: None,
-        }))
+    #[test]
+    fn classifier_matches_the_per_line_scan() {
+        let classifier = classifier(PUMP_FUN);
+        for line in LINES {
+            let logs = vec![line.to_string()];
+            assert_eq!(classifier.classify(&logs), scan(&logs), "{}", line);
+        }
+        let logs: Vec<String> = LINES.iter().map(|line| line.to_string()).collect();
+        assert_eq!(classifier.classify(&logs), scan(&logs));
+        assert_eq!(classifier.classify(&[]), LogFlags::default());
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,1166 @@
+//! pump.fun bonding curve and PumpSwap AMM instructions, their accounts and the
+//! events they emit, written after the programs' Anchor IDLs. Decoding goes by
+//! program id, discriminator and borsh layout, so it keeps working whatever the
+//! programs log as text.
+//!
+//! Upgrades only ever appended fields, so each layout is tried newest first and an
+//! older transaction, which ends before the added fields, falls back to the layout
+//! of its time; fields appended after the newest layout below are left unread.
+//! Discriminators of a known program that match nothing, or that no layout fits,
+//! are logged once and counted in `pumpfun_idl_undecoded_total`.
+
+use borsh::BorshDeserialize;
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, UiInstruction, UiParsedInstruction,
+};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::{LazyLock, Mutex};
+use tracing::{debug, warn};
+
+use crate::{listener_helpers, metrics};
+
+/// PumpSwap AMM, where pump.fun tokens trade once their bonding curve completes
+pub const PUMP_AMM_PROGRAM: &str = "pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA";
+
+/// Instruction discriminators, `sha256("global:<name>")[..8]`
+pub const CREATE: [u8; 8] = [24, 30, 200, 40, 5, 28, 7, 119];
+pub const CREATE_V2: [u8; 8] = [214, 144, 76, 236, 95, 139, 49, 180];
+pub const BUY: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
+pub const BUY_EXACT_SOL_IN: [u8; 8] = [56, 252, 116, 8, 158, 223, 205, 95];
+pub const SELL: [u8; 8] = [51, 230, 133, 164, 1, 127, 131, 173];
+pub const WITHDRAW: [u8; 8] = [183, 18, 70, 156, 148, 109, 161, 34];
+pub const CREATE_POOL: [u8; 8] = [233, 146, 209, 142, 207, 104, 64, 188];
+/// Prefix of the self-CPI instructions Anchor programs emit events with
+/// (`emit_cpi!`), followed by the event's own discriminator and data
+pub const EVENT_IX_TAG: [u8; 8] = [228, 69, 165, 46, 81, 203, 154, 29];
+
+/// Event discriminators, `sha256("event:<Name>")[..8]`
+pub const CREATE_EVENT: [u8; 8] = [27, 114, 169, 77, 222, 235, 99, 118];
+pub const TRADE_EVENT: [u8; 8] = [189, 219, 127, 211, 78, 230, 97, 238];
+pub const COMPLETE_EVENT: [u8; 8] = [95, 114, 97, 156, 212, 46, 152, 8];
+
+/// A program whose IDL the decoder knows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Program {
+    /// The bonding curve program, `programs.pump_fun_program()`
+    PumpFun,
+    PumpAmm,
+}
+
+impl Program {
+    pub fn of(program_id: &str, pump_fun_program: &str) -> Option<Self> {
+        if program_id == pump_fun_program {
+            Some(Self::PumpFun)
+        } else if program_id == PUMP_AMM_PROGRAM {
+            Some(Self::PumpAmm)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::PumpFun => "pump_fun",
+            Self::PumpAmm => "pump_amm",
+        }
+    }
+
+    /// Every instruction of the IDL, including those nothing here decodes
+    fn instructions(self) -> &'static [&'static str] {
+        match self {
+            Self::PumpFun => &[
+                "initialize",
+                "set_params",
+                "create",
+                "create_v2",
+                "buy",
+                "buy_exact_sol_in",
+                "sell",
+                "withdraw",
+                "migrate",
+                "extend_account",
+                "collect_creator_fee",
+                "set_creator",
+                "admin_set_creator",
+                "set_metaplex_creator",
+                "update_global_authority",
+                "admin_set_idl_authority",
+                "admin_update_token_incentives",
+                "claim_token_incentives",
+                "init_user_volume_accumulator",
+                "sync_user_volume_accumulator",
+                "close_user_volume_accumulator",
+            ],
+            Self::PumpAmm => &[
+                "create_config",
+                "update_fee_config",
+                "update_admin",
+                "disable",
+                "create_pool",
+                "deposit",
+                "withdraw",
+                "buy",
+                "buy_exact_quote_in",
+                "sell",
+                "extend_account",
+                "collect_coin_creator_fee",
+                "set_coin_creator",
+                "admin_set_coin_creator",
+                "admin_update_token_incentives",
+                "claim_token_incentives",
+                "init_user_volume_accumulator",
+                "sync_user_volume_accumulator",
+                "close_user_volume_accumulator",
+            ],
+        }
+    }
+
+    /// Every event of the IDL
+    fn events(self) -> &'static [&'static str] {
+        match self {
+            Self::PumpFun => &[
+                "CreateEvent",
+                "TradeEvent",
+                "CompleteEvent",
+                "SetParamsEvent",
+                "CompletePumpAmmMigrationEvent",
+                "ExtendAccountEvent",
+                "CollectCreatorFeeEvent",
+                "SetCreatorEvent",
+                "AdminSetCreatorEvent",
+                "SetMetaplexCreatorEvent",
+                "UpdateGlobalAuthorityEvent",
+                "AdminSetIdlAuthorityEvent",
+                "AdminUpdateTokenIncentivesEvent",
+                "ClaimTokenIncentivesEvent",
+                "InitUserVolumeAccumulatorEvent",
+                "SyncUserVolumeAccumulatorEvent",
+                "CloseUserVolumeAccumulatorEvent",
+            ],
+            Self::PumpAmm => &[
+                "CreateConfigEvent",
+                "UpdateFeeConfigEvent",
+                "UpdateAdminEvent",
+                "DisableEvent",
+                "CreatePoolEvent",
+                "DepositEvent",
+                "WithdrawEvent",
+                "BuyEvent",
+                "SellEvent",
+                "ExtendAccountEvent",
+                "CollectCoinCreatorFeeEvent",
+                "SetBondingCurveCoinCreatorEvent",
+                "SetMetaplexCoinCreatorEvent",
+                "AdminSetCoinCreatorEvent",
+                "AdminUpdateTokenIncentivesEvent",
+                "ClaimTokenIncentivesEvent",
+                "InitUserVolumeAccumulatorEvent",
+                "SyncUserVolumeAccumulatorEvent",
+                "CloseUserVolumeAccumulatorEvent",
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Kind {
+    Instruction,
+    Event,
+}
+
+impl Kind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Instruction => "instruction",
+            Self::Event => "event",
+        }
+    }
+}
+
+/// A discriminator scoped to the program and namespace it belongs to
+type Key = (Program, Kind, [u8; 8]);
+
+/// Names of every discriminator of the known programs
+static NAMES: LazyLock<HashMap<Key, &'static str>> = LazyLock::new(|| {
+    let mut names = HashMap::new();
+    for program in [Program::PumpFun, Program::PumpAmm] {
+        for name in program.instructions() {
+            let key = (program, Kind::Instruction, discriminator("global", name));
+            names.insert(key, *name);
+        }
+        for name in program.events() {
+            names.insert((program, Kind::Event, discriminator("event", name)), *name);
+        }
+    }
+    names
+});
+
+fn discriminator(namespace: &str, name: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("{}:{}", namespace, name));
+    let mut discriminator = [0; 8];
+    discriminator.copy_from_slice(&hash[..8]);
+    discriminator
+}
+
+/// An instruction or event of a known program that did not decode
+#[derive(Debug, Clone, Copy)]
+struct Undecoded {
+    program: Program,
+    kind: Kind,
+    discriminator: [u8; 8],
+}
+
+/// Discriminators already logged, so an unknown one warns once
+static REPORTED: LazyLock<Mutex<HashSet<Key>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+
+fn report(undecoded: &[Undecoded]) {
+    for item in undecoded {
+        metrics::IDL_UNDECODED
+            .with_label_values(&[item.program.as_str(), item.kind.as_str()])
+            .inc();
+        let key = (item.program, item.kind, item.discriminator);
+        let first = REPORTED
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key);
+        let what = match NAMES.get(&key) {
+            Some(name) => format!(
+                "{} {} in a layout no version fits",
+                item.kind.as_str(),
+                name
+            ),
+            None => format!(
+                "{} with unknown discriminator {:?}",
+                item.kind.as_str(),
+                item.discriminator
+            ),
+        };
+        if first {
+            warn!(
+                "Undecoded {} {}, the IDL may have changed",
+                item.program.as_str(),
+                what
+            );
+        } else {
+            debug!("Undecoded {} {}", item.program.as_str(), what);
+        }
+    }
+}
+
+/// Decode the newest layout `N` when the data holds it, else the original `O`
+fn versioned<T, N, O>(mut data: &[u8]) -> Option<T>
+where
+    N: BorshDeserialize + Into<T>,
+    O: BorshDeserialize + Into<T>,
+{
+    if let Ok(newest) = N::deserialize(&mut &data[..]) {
+        return Some(newest.into());
+    }
+    O::deserialize(&mut data).ok().map(Into::into)
+}
+
+/// Arguments of `create` (SPL Token mint) and `create_v2` (Token-2022 mint)
+#[derive(Debug, Clone)]
+pub struct CreateArgs {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    /// Passed since creator fees; older creations credit the signer
+    pub creator: Option<Pubkey>,
+}
+
+#[derive(BorshDeserialize)]
+struct CreateArgsV1 {
+    name: String,
+    symbol: String,
+    uri: String,
+}
+
+#[derive(BorshDeserialize)]
+struct CreateArgsV2 {
+    v1: CreateArgsV1,
+    creator: Pubkey,
+}
+
+impl From<CreateArgsV1> for CreateArgs {
+    fn from(v1: CreateArgsV1) -> Self {
+        Self {
+            name: v1.name,
+            symbol: v1.symbol,
+            uri: v1.uri,
+            creator: None,
+        }
+    }
+}
+
+impl From<CreateArgsV2> for CreateArgs {
+    fn from(v2: CreateArgsV2) -> Self {
+        Self {
+            creator: Some(v2.creator),
+            ..v2.v1.into()
+        }
+    }
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct BuyArgs {
+    /// Tokens to buy
+    pub amount: u64,
+    /// Lamports the buy may cost at most, fees included
+    pub max_sol_cost: u64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct BuyExactSolInArgs {
+    /// Lamports to spend, fees included
+    pub spendable_sol_in: u64,
+    /// Tokens the buy must bring in at least
+    pub min_tokens_out: u64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct SellArgs {
+    /// Tokens to sell
+    pub amount: u64,
+    /// Lamports the sell must bring in at least
+    pub min_sol_output: u64,
+}
+
+/// Arguments of PumpSwap `create_pool`
+#[derive(Debug, Clone)]
+pub struct CreatePoolArgs {
+    pub index: u16,
+    pub base_amount_in: u64,
+    pub quote_amount_in: u64,
+    /// Passed since coin creator fees
+    pub coin_creator: Option<Pubkey>,
+}
+
+#[derive(BorshDeserialize)]
+struct CreatePoolArgsV1 {
+    index: u16,
+    base_amount_in: u64,
+    quote_amount_in: u64,
+}
+
+#[derive(BorshDeserialize)]
+struct CreatePoolArgsV2 {
+    v1: CreatePoolArgsV1,
+    coin_creator: Pubkey,
+}
+
+impl From<CreatePoolArgsV1> for CreatePoolArgs {
+    fn from(v1: CreatePoolArgsV1) -> Self {
+        Self {
+            index: v1.index,
+            base_amount_in: v1.base_amount_in,
+            quote_amount_in: v1.quote_amount_in,
+            coin_creator: None,
+        }
+    }
+}
+
+impl From<CreatePoolArgsV2> for CreatePoolArgs {
+    fn from(v2: CreatePoolArgsV2) -> Self {
+        Self {
+            coin_creator: Some(v2.coin_creator),
+            ..v2.v1.into()
+        }
+    }
+}
+
+/// A decoded instruction of a known program
+#[derive(Debug, Clone)]
+pub enum Instruction {
+    Create(CreateArgs),
+    CreateV2(CreateArgs),
+    Buy(BuyArgs),
+    BuyExactSolIn(BuyExactSolInArgs),
+    Sell(SellArgs),
+    /// Moves the liquidity of a completed curve out for migration, no arguments
+    Withdraw,
+    /// A PumpSwap pool, created by a migration or by hand
+    CreatePool(CreatePoolArgs),
+}
+
+impl Instruction {
+    /// `None` for instructions of `program` nothing here decodes and for data no
+    /// layout of the instruction fits
+    pub fn decode(program: Program, data: &[u8]) -> Option<Self> {
+        let (discriminator, mut args) = data.split_first_chunk::<8>()?;
+        let args = &mut args;
+        Some(match (program, *discriminator) {
+            (Program::PumpFun, CREATE) => {
+                Self::Create(versioned::<_, CreateArgsV2, CreateArgsV1>(args)?)
+            }
+            (Program::PumpFun, CREATE_V2) => {
+                Self::CreateV2(versioned::<_, CreateArgsV2, CreateArgsV1>(args)?)
+            }
+            (Program::PumpFun, BUY) => Self::Buy(BuyArgs::deserialize(args).ok()?),
+            (Program::PumpFun, BUY_EXACT_SOL_IN) => {
+                Self::BuyExactSolIn(BuyExactSolInArgs::deserialize(args).ok()?)
+            }
+            (Program::PumpFun, SELL) => Self::Sell(SellArgs::deserialize(args).ok()?),
+            (Program::PumpFun, WITHDRAW) => Self::Withdraw,
+            (Program::PumpAmm, CREATE_POOL) => {
+                Self::CreatePool(versioned::<_, CreatePoolArgsV2, CreatePoolArgsV1>(args)?)
+            }
+            _ => return None,
+        })
+    }
+
+    /// Whether `decode` has layouts for `discriminator`
+    fn decodes(program: Program, discriminator: [u8; 8]) -> bool {
+        matches!(
+            (program, discriminator),
+            (
+                Program::PumpFun,
+                CREATE | CREATE_V2 | BUY | BUY_EXACT_SOL_IN | SELL | WITHDRAW
+            ) | (Program::PumpAmm, CREATE_POOL)
+        )
+    }
+
+    /// Whether the IDL of `program` has `discriminator`, or it is an `emit_cpi!` event
+    fn is_known(program: Program, discriminator: [u8; 8]) -> bool {
+        discriminator == EVENT_IX_TAG
+            || NAMES.contains_key(&(program, Kind::Instruction, discriminator))
+    }
+}
+
+/// Accounts of `create` / `create_v2` we read
+#[derive(Debug, Clone)]
+pub struct CreateAccounts {
+    pub mint: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub associated_bonding_curve: Pubkey,
+    /// The signer paying for the creation
+    pub user: Pubkey,
+}
+
+impl CreateAccounts {
+    /// `create`: mint, mint_authority, bonding_curve, associated_bonding_curve,
+    /// global, mpl_token_metadata, metadata, user, ...
+    fn v1(keys: &[String]) -> Option<Self> {
+        Some(Self {
+            mint: key(keys, 0)?,
+            bonding_curve: key(keys, 2)?,
+            associated_bonding_curve: key(keys, 3)?,
+            user: key(keys, 7)?,
+        })
+    }
+
+    /// `create_v2` keeps metadata in the mint: mint, mint_authority,
+    /// bonding_curve, associated_bonding_curve, global, user, ...
+    fn v2(keys: &[String]) -> Option<Self> {
+        Some(Self {
+            mint: key(keys, 0)?,
+            bonding_curve: key(keys, 2)?,
+            associated_bonding_curve: key(keys, 3)?,
+            user: key(keys, 5)?,
+        })
+    }
+}
+
+/// Accounts `buy`, `buy_exact_sol_in`, `sell` and `withdraw` share: global,
+/// fee_recipient (or last_withdraw), mint, bonding_curve,
+/// associated_bonding_curve, associated_user, user, ...
+#[derive(Debug, Clone)]
+pub struct CurveAccounts {
+    pub mint: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub associated_bonding_curve: Pubkey,
+    pub associated_user: Pubkey,
+    pub user: Pubkey,
+}
+
+impl CurveAccounts {
+    fn new(keys: &[String]) -> Option<Self> {
+        Some(Self {
+            mint: key(keys, 2)?,
+            bonding_curve: key(keys, 3)?,
+            associated_bonding_curve: key(keys, 4)?,
+            associated_user: key(keys, 5)?,
+            user: key(keys, 6)?,
+        })
+    }
+}
+
+/// Accounts of PumpSwap `create_pool`: pool, global_config, creator, base_mint,
+/// quote_mint, lp_mint, ...
+#[derive(Debug, Clone)]
+pub struct PoolAccounts {
+    pub pool: Pubkey,
+    pub creator: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub lp_mint: Pubkey,
+}
+
+impl PoolAccounts {
+    fn new(keys: &[String]) -> Option<Self> {
+        Some(Self {
+            pool: key(keys, 0)?,
+            creator: key(keys, 2)?,
+            base_mint: key(keys, 3)?,
+            quote_mint: key(keys, 4)?,
+            lp_mint: key(keys, 5)?,
+        })
+    }
+}
+
+fn key(keys: &[String], index: usize) -> Option<Pubkey> {
+    Pubkey::from_str(keys.get(index)?).ok()
+}
+
+/// An instruction of a known program in a transaction, with its account keys
+pub struct ProgramInstruction<'a> {
+    pub program: Program,
+    pub instruction: Instruction,
+    pub accounts: &'a [String],
+}
+
+impl ProgramInstruction<'_> {
+    pub fn create_accounts(&self) -> Option<CreateAccounts> {
+        match self.instruction {
+            Instruction::Create(_) => CreateAccounts::v1(self.accounts),
+            Instruction::CreateV2(_) => CreateAccounts::v2(self.accounts),
+            _ => None,
+        }
+    }
+
+    pub fn curve_accounts(&self) -> Option<CurveAccounts> {
+        match self.instruction {
+            Instruction::Buy(_)
+            | Instruction::BuyExactSolIn(_)
+            | Instruction::Sell(_)
+            | Instruction::Withdraw => CurveAccounts::new(self.accounts),
+            _ => None,
+        }
+    }
+
+    pub fn pool_accounts(&self) -> Option<PoolAccounts> {
+        match self.instruction {
+            Instruction::CreatePool(_) => PoolAccounts::new(self.accounts),
+            _ => None,
+        }
+    }
+}
+
+/// The pump.fun and PumpSwap instructions of a transaction, top level first,
+/// then CPIs
+pub struct Instructions<'a> {
+    decoded: Vec<ProgramInstruction<'a>>,
+    undecoded: Vec<Undecoded>,
+}
+
+impl<'a> Instructions<'a> {
+    pub fn decode(
+        tx: &'a EncodedConfirmedTransactionWithStatusMeta,
+        pump_fun_program: &str,
+    ) -> Self {
+        let mut instructions = Self {
+            decoded: Vec::new(),
+            undecoded: Vec::new(),
+        };
+        for instr in listener_helpers::all_instructions(tx) {
+            let UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(decoded)) = instr
+            else {
+                continue;
+            };
+            let Some(program) = Program::of(&decoded.program_id, pump_fun_program) else {
+                continue;
+            };
+            let Ok(data) = bs58::decode(&decoded.data).into_vec() else {
+                continue;
+            };
+            match Instruction::decode(program, &data) {
+                Some(instruction) => instructions.decoded.push(ProgramInstruction {
+                    program,
+                    instruction,
+                    accounts: &decoded.accounts,
+                }),
+                None => {
+                    let Some(discriminator) = data.first_chunk::<8>().copied() else {
+                        continue;
+                    };
+                    // Known instructions nothing reads are fine, a layout change is not
+                    if Instruction::decodes(program, discriminator)
+                        || !Instruction::is_known(program, discriminator)
+                    {
+                        instructions.undecoded.push(Undecoded {
+                            program,
+                            kind: Kind::Instruction,
+                            discriminator,
+                        });
+                    }
+                }
+            }
+        }
+        instructions
+    }
+
+    /// Log and count what did not decode; once per transaction
+    pub fn report_undecoded(&self) {
+        report(&self.undecoded);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ProgramInstruction<'a>> {
+        self.decoded.iter()
+    }
+
+    /// The first `create` / `create_v2` with its accounts
+    pub fn creation(&self) -> Option<(&CreateArgs, CreateAccounts)> {
+        self.iter().find_map(|ix| match &ix.instruction {
+            Instruction::Create(args) | Instruction::CreateV2(args) => {
+                Some((args, ix.create_accounts()?))
+            }
+            _ => None,
+        })
+    }
+
+    /// The first PumpSwap `create_pool` with its accounts
+    pub fn pool_creation(&self) -> Option<(&CreatePoolArgs, PoolAccounts)> {
+        self.iter().find_map(|ix| match &ix.instruction {
+            Instruction::CreatePool(args) => Some((args, ix.pool_accounts()?)),
+            _ => None,
+        })
+    }
+}
+
+/// Emitted by `create` / `create_v2`
+#[derive(Debug, Clone)]
+pub struct CreateEvent {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub mint: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub user: Pubkey,
+    /// Since creator fees
+    pub creator: Option<Pubkey>,
+    /// Unix seconds, since creator fees
+    pub timestamp: Option<i64>,
+}
+
+#[derive(BorshDeserialize)]
+struct CreateEventV1 {
+    name: String,
+    symbol: String,
+    uri: String,
+    mint: Pubkey,
+    bonding_curve: Pubkey,
+    user: Pubkey,
+}
+
+#[derive(BorshDeserialize)]
+struct CreateEventV2 {
+    v1: CreateEventV1,
+    creator: Pubkey,
+    timestamp: i64,
+}
+
+impl From<CreateEventV1> for CreateEvent {
+    fn from(v1: CreateEventV1) -> Self {
+        Self {
+            name: v1.name,
+            symbol: v1.symbol,
+            uri: v1.uri,
+            mint: v1.mint,
+            bonding_curve: v1.bonding_curve,
+            user: v1.user,
+            creator: None,
+            timestamp: None,
+        }
+    }
+}
+
+impl From<CreateEventV2> for CreateEvent {
+    fn from(v2: CreateEventV2) -> Self {
+        Self {
+            creator: Some(v2.creator),
+            timestamp: Some(v2.timestamp),
+            ..v2.v1.into()
+        }
+    }
+}
+
+/// Emitted for every buy and sell
+#[derive(Debug, Clone)]
+pub struct TradeEvent {
+    pub mint: Pubkey,
+    pub sol_amount: u64,
+    pub token_amount: u64,
+    pub is_buy: bool,
+    pub user: Pubkey,
+    /// Unix seconds
+    pub timestamp: i64,
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
+    /// Since creator fees
+    pub creator: Option<Pubkey>,
+    /// Protocol fee in lamports, since creator fees
+    pub fee: Option<u64>,
+    /// Creator fee in lamports, since creator fees
+    pub creator_fee: Option<u64>,
+    /// Top-level program of the instruction when it is not pump.fun itself,
+    /// e.g. an aggregator or a trading bot
+    pub invoked_by: Option<String>,
+}
+
+#[derive(BorshDeserialize)]
+struct TradeEventV1 {
+    mint: Pubkey,
+    sol_amount: u64,
+    token_amount: u64,
+    is_buy: bool,
+    user: Pubkey,
+    timestamp: i64,
+    virtual_sol_reserves: u64,
+    virtual_token_reserves: u64,
+}
+
+#[derive(BorshDeserialize)]
+struct TradeEventV2 {
+    v1: TradeEventV1,
+    _real_sol_reserves: u64,
+    _real_token_reserves: u64,
+    _fee_recipient: Pubkey,
+    _fee_basis_points: u64,
+    fee: u64,
+    creator: Pubkey,
+    _creator_fee_basis_points: u64,
+    creator_fee: u64,
+}
+
+impl From<TradeEventV1> for TradeEvent {
+    fn from(v1: TradeEventV1) -> Self {
+        Self {
+            mint: v1.mint,
+            sol_amount: v1.sol_amount,
+            token_amount: v1.token_amount,
+            is_buy: v1.is_buy,
+            user: v1.user,
+            timestamp: v1.timestamp,
+            virtual_sol_reserves: v1.virtual_sol_reserves,
+            virtual_token_reserves: v1.virtual_token_reserves,
+            creator: None,
+            fee: None,
+            creator_fee: None,
+            invoked_by: None,
+        }
+    }
+}
+
+impl From<TradeEventV2> for TradeEvent {
+    fn from(v2: TradeEventV2) -> Self {
+        Self {
+            creator: Some(v2.creator),
+            fee: Some(v2.fee),
+            creator_fee: Some(v2.creator_fee),
+            ..v2.v1.into()
+        }
+    }
+}
+
+/// Emitted by the trade that completes a bonding curve
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct CompleteEvent {
+    pub user: Pubkey,
+    pub mint: Pubkey,
+    pub bonding_curve: Pubkey,
+    /// Unix seconds
+    pub timestamp: i64,
+}
+
+/// The pump.fun events of a log, from the `Program data:` lines the bonding
+/// curve program wrote
+#[derive(Debug, Default)]
+pub struct Events {
+    pub creates: Vec<CreateEvent>,
+    pub trades: Vec<TradeEvent>,
+    pub completes: Vec<CompleteEvent>,
+    undecoded: Vec<Undecoded>,
+}
+
+impl Events {
+    pub fn decode(logs: &[String], pump_fun_program: &str) -> Self {
+        use base64::Engine;
+
+        let mut events = Self::default();
+        // Programs being executed, to tell whose `Program data:` a line is
+        let mut invoked: Vec<&str> = Vec::new();
+        for line in logs {
+            if let Some(data) = line.strip_prefix("Program data: ") {
+                let program = invoked
+                    .last()
+                    .and_then(|id| Program::of(id, pump_fun_program));
+                let caller = invoked.first().filter(|id| **id != pump_fun_program);
+                let data = base64::engine::general_purpose::STANDARD.decode(data);
+                if let (Some(program), Ok(data)) = (program, data) {
+                    events.push(program, &data, caller.copied());
+                }
+                continue;
+            }
+            let Some((program, status)) = line
+                .strip_prefix("Program ")
+                .and_then(|rest| rest.split_once(' '))
+                .filter(|(program, _)| !program.ends_with(':'))
+            else {
+                continue;
+            };
+            if status.starts_with("invoke") {
+                invoked.push(program);
+            } else if status == "success" || status.starts_with("failed") {
+                invoked.pop();
+            }
+        }
+        events
+    }
+
+    fn push(&mut self, program: Program, data: &[u8], caller: Option<&str>) {
+        let Some((discriminator, fields)) = data.split_first_chunk::<8>() else {
+            return;
+        };
+        let decoded = match (program, *discriminator) {
+            (Program::PumpFun, CREATE_EVENT) => {
+                versioned::<CreateEvent, CreateEventV2, CreateEventV1>(fields)
+                    .map(|event| self.creates.push(event))
+            }
+            (Program::PumpFun, TRADE_EVENT) => {
+                versioned::<TradeEvent, TradeEventV2, TradeEventV1>(fields).map(|event| {
+                    self.trades.push(TradeEvent {
+                        invoked_by: caller.map(str::to_string),
+                        ..event
+                    })
+                })
+            }
+            (Program::PumpFun, COMPLETE_EVENT) => CompleteEvent::deserialize(&mut &fields[..])
+                .ok()
+                .map(|event| self.completes.push(event)),
+            (program, discriminator) => NAMES
+                .contains_key(&(program, Kind::Event, discriminator))
+                .then_some(()),
+        };
+        if decoded.is_none() {
+            self.undecoded.push(Undecoded {
+                program,
+                kind: Kind::Event,
+                discriminator: *discriminator,
+            });
+        }
+    }
+
+    /// Log and count what did not decode; once per log
+    pub fn report_undecoded(&self) {
+        report(&self.undecoded);
+    }
+
+    /// The token the log creates
+    pub fn creation(&self) -> Option<&CreateEvent> {
+        self.creates.first()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::Engine;
+
+    use super::*;
+
+    const PUMP_FUN: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
+    const AGGREGATOR: &str = "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4";
+
+    /// Borsh encoding of hand-built instruction and event data
+    struct Data(Vec<u8>);
+
+    impl Data {
+        fn new(discriminator: [u8; 8]) -> Self {
+            Self(discriminator.to_vec())
+        }
+
+        fn string(mut self, value: &str) -> Self {
+            self.0
+                .extend_from_slice(&(value.len() as u32).to_le_bytes());
+            self.0.extend_from_slice(value.as_bytes());
+            self
+        }
+
+        fn pubkey(mut self, value: &Pubkey) -> Self {
+            self.0.extend_from_slice(value.as_ref());
+            self
+        }
+
+        fn u64(mut self, value: u64) -> Self {
+            self.0.extend_from_slice(&value.to_le_bytes());
+            self
+        }
+
+        fn i64(mut self, value: i64) -> Self {
+            self.0.extend_from_slice(&value.to_le_bytes());
+            self
+        }
+
+        fn bool(mut self, value: bool) -> Self {
+            self.0.push(u8::from(value));
+            self
+        }
+
+        fn log(&self) -> String {
+            format!(
+                "Program data: {}",
+                base64::engine::general_purpose::STANDARD.encode(&self.0)
+            )
+        }
+    }
+
+    /// A log of `program` writing `data`, invoked by `caller` if any
+    fn logs(caller: Option<&str>, data: &[Data]) -> Vec<String> {
+        let mut logs = Vec::new();
+        if let Some(caller) = caller {
+            logs.push(format!("Program {} invoke [1]", caller));
+        }
+        logs.push(format!("Program {} invoke [{}]", PUMP_FUN, logs.len() + 1));
+        logs.push("Program log: Instruction: Buy".to_string());
+        logs.extend(data.iter().map(Data::log));
+        logs.push(format!("Program {} success", PUMP_FUN));
+        if let Some(caller) = caller {
+            logs.push(format!("Program {} success", caller));
+        }
+        logs
+    }
+
+    struct Keys {
+        mint: Pubkey,
+        curve: Pubkey,
+        user: Pubkey,
+        creator: Pubkey,
+    }
+
+    fn keys() -> Keys {
+        Keys {
+            mint: Pubkey::new_unique(),
+            curve: Pubkey::new_unique(),
+            user: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+        }
+    }
+
+    fn create_event_v1(keys: &Keys) -> Data {
+        Data::new(CREATE_EVENT)
+            .string("Moon")
+            .string("MOON")
+            .string("https://ipfs.io/ipfs/moon")
+            .pubkey(&keys.mint)
+            .pubkey(&keys.curve)
+            .pubkey(&keys.user)
+    }
+
+    fn trade_event_v1(keys: &Keys) -> Data {
+        Data::new(TRADE_EVENT)
+            .pubkey(&keys.mint)
+            .u64(1_000_000_000)
+            .u64(34_612_903_225_806)
+            .bool(true)
+            .pubkey(&keys.user)
+            .i64(1_760_000_000)
+            .u64(31_000_000_000)
+            .u64(1_038_387_096_774_194)
+    }
+
+    fn trade_event_v2(keys: &Keys) -> Data {
+        trade_event_v1(keys)
+            .u64(1_000_000_000)
+            .u64(758_487_096_774_194)
+            .pubkey(&Pubkey::new_unique())
+            .u64(95)
+            .u64(9_500_000)
+            .pubkey(&keys.creator)
+            .u64(30)
+            .u64(3_000_000)
+    }
+
+    #[test]
+    fn decodes_create_trade_and_complete_events() {
+        let keys = keys();
+        let create = create_event_v1(&keys)
+            .pubkey(&keys.creator)
+            .i64(1_760_000_000);
+        let complete = Data::new(COMPLETE_EVENT)
+            .pubkey(&keys.user)
+            .pubkey(&keys.mint)
+            .pubkey(&keys.curve)
+            .i64(1_760_000_100);
+        let events = Events::decode(
+            &logs(None, &[create, trade_event_v2(&keys), complete]),
+            PUMP_FUN,
+        );
+
+        let create = events.creation().expect("create event");
+        assert_eq!(create.name, "Moon");
+        assert_eq!(create.symbol, "MOON");
+        assert_eq!(create.uri, "https://ipfs.io/ipfs/moon");
+        assert_eq!(create.mint, keys.mint);
+        assert_eq!(create.bonding_curve, keys.curve);
+        assert_eq!(create.user, keys.user);
+        assert_eq!(create.creator, Some(keys.creator));
+        assert_eq!(create.timestamp, Some(1_760_000_000));
+
+        let [trade] = events.trades.as_slice() else {
+            panic!("one trade event expected, got {:?}", events.trades);
+        };
+        assert_eq!(trade.mint, keys.mint);
+        assert_eq!(trade.sol_amount, 1_000_000_000);
+        assert_eq!(trade.token_amount, 34_612_903_225_806);
+        assert!(trade.is_buy);
+        assert_eq!(trade.user, keys.user);
+        assert_eq!(trade.timestamp, 1_760_000_000);
+        assert_eq!(trade.virtual_sol_reserves, 31_000_000_000);
+        assert_eq!(trade.virtual_token_reserves, 1_038_387_096_774_194);
+        assert_eq!(trade.creator, Some(keys.creator));
+        assert_eq!(trade.fee, Some(9_500_000));
+        assert_eq!(trade.creator_fee, Some(3_000_000));
+        assert_eq!(trade.invoked_by, None);
+
+        let [complete] = events.completes.as_slice() else {
+            panic!("one complete event expected, got {:?}", events.completes);
+        };
+        assert_eq!(complete.user, keys.user);
+        assert_eq!(complete.mint, keys.mint);
+        assert_eq!(complete.bonding_curve, keys.curve);
+        assert_eq!(complete.timestamp, 1_760_000_100);
+        assert!(events.undecoded.is_empty());
+    }
+
+    #[test]
+    fn trades_through_another_program_name_it() {
+        let keys = keys();
+        let events = Events::decode(&logs(Some(AGGREGATOR), &[trade_event_v2(&keys)]), PUMP_FUN);
+        assert_eq!(events.trades.len(), 1);
+        assert_eq!(events.trades[0].invoked_by.as_deref(), Some(AGGREGATOR));
+    }
+
+    #[test]
+    fn data_of_other_programs_is_ignored() {
+        let keys = keys();
+        let logs = vec![
+            format!("Program {} invoke [1]", AGGREGATOR),
+            trade_event_v2(&keys).log(),
+            format!("Program {} success", AGGREGATOR),
+        ];
+        let events = Events::decode(&logs, PUMP_FUN);
+        assert!(events.trades.is_empty());
+        assert!(events.undecoded.is_empty());
+    }
+
+    #[test]
+    fn decodes_instructions() {
+        let creator = Pubkey::new_unique();
+        let create = Data::new(CREATE)
+            .string("Moon")
+            .string("MOON")
+            .string("https://ipfs.io/ipfs/moon")
+            .pubkey(&creator);
+        let Some(Instruction::Create(args)) = Instruction::decode(Program::PumpFun, &create.0)
+        else {
+            panic!("create expected");
+        };
+        assert_eq!((args.name.as_str(), args.symbol.as_str()), ("Moon", "MOON"));
+        assert_eq!(args.creator, Some(creator));
+
+        let buy = Data::new(BUY).u64(1_000).u64(2_000);
+        let Some(Instruction::Buy(args)) = Instruction::decode(Program::PumpFun, &buy.0) else {
+            panic!("buy expected");
+        };
+        assert_eq!((args.amount, args.max_sol_cost), (1_000, 2_000));
+
+        let sell = Data::new(SELL).u64(3_000).u64(4_000);
+        let Some(Instruction::Sell(args)) = Instruction::decode(Program::PumpFun, &sell.0) else {
+            panic!("sell expected");
+        };
+        assert_eq!((args.amount, args.min_sol_output), (3_000, 4_000));
+
+        let withdraw = Data::new(WITHDRAW);
+        assert!(matches!(
+            Instruction::decode(Program::PumpFun, &withdraw.0),
+            Some(Instruction::Withdraw)
+        ));
+        // Discriminators are scoped to their program
+        assert!(Instruction::decode(Program::PumpAmm, &buy.0).is_none());
+    }
+
+    #[test]
+    fn falls_back_to_the_layouts_before_creator_fees() {
+        let keys = keys();
+        let events = Events::decode(
+            &logs(None, &[create_event_v1(&keys), trade_event_v1(&keys)]),
+            PUMP_FUN,
+        );
+
+        let create = events.creation().expect("create event");
+        assert_eq!(create.mint, keys.mint);
+        assert_eq!(create.user, keys.user);
+        assert_eq!(create.creator, None);
+        assert_eq!(create.timestamp, None);
+        let [trade] = events.trades.as_slice() else {
+            panic!("one trade event expected, got {:?}", events.trades);
+        };
+        assert_eq!(trade.sol_amount, 1_000_000_000);
+        assert_eq!(trade.virtual_token_reserves, 1_038_387_096_774_194);
+        assert_eq!(
+            (trade.creator, trade.fee, trade.creator_fee),
+            (None, None, None)
+        );
+        assert!(events.undecoded.is_empty());
+
+        let create = Data::new(CREATE_V2)
+            .string("Moon")
+            .string("MOON")
+            .string("https://ipfs.io/ipfs/moon");
+        let Some(Instruction::CreateV2(args)) = Instruction::decode(Program::PumpFun, &create.0)
+        else {
+            panic!("create_v2 expected");
+        };
+        assert_eq!(args.name, "Moon");
+        assert_eq!(args.creator, None);
+    }
+
+    #[test]
+    fn unknown_and_unfit_events_are_counted() {
+        let keys = keys();
+        let counter = metrics::IDL_UNDECODED.with_label_values(&["pump_fun", "event"]);
+        let before = counter.get();
+
+        let unknown = Data::new([1, 2, 3, 4, 5, 6, 7, 8]).u64(42);
+        // Ends inside the oldest layout
+        let mut truncated = trade_event_v1(&keys);
+        truncated.0.truncate(40);
+        // Known, but nothing reads it
+        let set_params = Data::new(discriminator("event", "SetParamsEvent")).u64(1);
+        let events = Events::decode(
+            &logs(
+                None,
+                &[unknown, truncated, set_params, trade_event_v2(&keys)],
+            ),
+            PUMP_FUN,
+        );
+
+        assert_eq!(events.trades.len(), 1);
+        assert_eq!(events.undecoded.len(), 2);
+        events.report_undecoded();
+        events.report_undecoded();
+        assert!(counter.get() >= before + 4);
+    }
+
+    #[test]
+    fn unknown_instructions_do_not_decode() {
+        assert!(Instruction::decode(Program::PumpFun, &[9; 16]).is_none());
+        assert!(!Instruction::is_known(Program::PumpFun, [9; 8]));
+        // Too short for its layout
+        assert!(Instruction::decode(Program::PumpFun, &Data::new(BUY).u64(1).0).is_none());
+        assert!(Instruction::decodes(Program::PumpFun, BUY));
+        assert!(Instruction::decode(Program::PumpFun, &[1, 2, 3]).is_none());
+    }
+}
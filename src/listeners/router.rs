@@ -0,0 +1,351 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use moka::future::Cache;
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient,
+    rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+    rpc_response::RpcLogsResponse,
+};
+use tracing::info;
+
+use crate::config::Config;
+use crate::error::ListenerError;
+use crate::event_bus::LogQueue;
+use crate::listeners::TokenListener;
+use crate::rpc_pool::RpcPool;
+use crate::{health, listener_helpers, metrics};
+
+const NAME: &str = "router";
+
+/// A listener taking its notifications from the shared subscription
+struct Route {
+    programs: Vec<String>,
+    listener: Arc<dyn TokenListener>,
+}
+
+/// The shared subscription (`ingestion.shared_subscription`): one WSS
+/// connection subscribed to the programs of every routed listener. The
+/// invoked programs of each notification are read once and it is queued for
+/// every listener of those programs that accepts it, so pump.fun, PumpSwap,
+/// LaunchLab and the pool listeners share a single socket.
+pub struct LogRouter {
+    config: Config,
+    rpc: Arc<RpcPool>,
+    queue: LogQueue,
+    routes: Vec<Route>,
+    /// A transaction invoking several programs arrives once per subscription
+    routed: Cache<String, ()>,
+}
+
+impl LogRouter {
+    /// `None` when none of `listeners` has programs to route
+    pub fn new(
+        config: Config,
+        rpc: Arc<RpcPool>,
+        queue: LogQueue,
+        listeners: &HashMap<&'static str, Arc<dyn TokenListener>>,
+    ) -> Option<Self> {
+        let routes: Vec<Route> = listeners
+            .values()
+            .map(|listener| Route {
+                programs: listener.programs(),
+                listener: listener.clone(),
+            })
+            .filter(|route| !route.programs.is_empty())
+            .collect();
+        if routes.is_empty() {
+            return None;
+        }
+        Some(Self {
+            config,
+            rpc,
+            queue,
+            routes,
+            routed: Cache::builder()
+                .max_capacity(100_000)
+                .time_to_live(Duration::from_secs(60))
+                .build(),
+        })
+    }
+
+    async fn listen(&self) -> Result<()> {
+        let pubsub = PubsubClient::new(self.rpc.wss_url(NAME)).await?;
+        let mut keepalive = listener_helpers::Keepalive::subscribe(
+            &pubsub,
+            NAME,
+            self.config.ingestion.keepalive_timeout_secs,
+        )
+        .await;
+
+        let programs: BTreeSet<&str> = self
+            .routes
+            .iter()
+            .flat_map(|route| route.programs.iter().map(String::as_str))
+            .collect();
+        // `Mentions` takes a single program, so subscribe once per program
+        let mut streams = Vec::with_capacity(programs.len());
+        let mut unsubscribes = Vec::with_capacity(programs.len());
+        for program in &programs {
+            let (stream, unsubscribe) = pubsub
+                .logs_subscribe(
+                    RpcTransactionLogsFilter::Mentions(vec![program.to_string()]),
+                    RpcTransactionLogsConfig {
+                        commitment: Some(self.rpc.commitment()),
+                    },
+                )
+                .await?;
+            streams.push(stream);
+            unsubscribes.push(unsubscribe);
+        }
+        let mut stream = futures::stream::select_all(streams);
+
+        info!(
+            "Shared subscription to {} program(s) for {} listener(s)",
+            programs.len(),
+            self.routes.len()
+        );
+
+        let silence_timeout =
+            Duration::from_secs(self.config.ingestion.stream_silence_timeout_secs);
+
+        let outcome = loop {
+            let next =
+                listener_helpers::next_log(&mut stream, &mut keepalive, NAME, silence_timeout)
+                    .await;
+            let (slot, rpc_log) = match next {
+                Ok(Some(next)) => next,
+                other => break other.map(|_| ()),
+            };
+            self.route(slot, rpc_log).await;
+        };
+
+        for unsubscribe in unsubscribes {
+            unsubscribe().await;
+        }
+        keepalive.close().await;
+        outcome
+    }
+
+    /// Queue `log` for each listener of a program it invokes
+    async fn route(&self, slot: u64, log: RpcLogsResponse) {
+        if self.routed.contains_key(&log.signature) {
+            metrics::DROPPED_MESSAGES
+                .with_label_values(&[NAME, "duplicate"])
+                .inc();
+            return;
+        }
+        self.routed.insert(log.signature.clone(), ()).await;
+
+        let invoked = invoked_programs(&log.logs);
+        let targets: Vec<&'static str> = self
+            .routes
+            .iter()
+            .filter(|route| route.programs.iter().any(|p| invoked.contains(p.as_str())))
+            .filter(|route| route.listener.accepts(&log))
+            .map(|route| route.listener.name())
+            .collect();
+
+        let Some((last, rest)) = targets.split_last() else {
+            return;
+        };
+        for listener in rest {
+            health::record_log(listener);
+            self.queue.push(listener, slot, log.clone()).await;
+        }
+        health::record_log(last);
+        self.queue.push(last, slot, log).await;
+    }
+}
+
+/// Program ids of the `Program <id> invoke [n]` lines
+fn invoked_programs(logs: &[String]) -> HashSet<&str> {
+    logs.iter()
+        .filter_map(|line| {
+            let (program, rest) = line.strip_prefix("Program ")?.split_once(' ')?;
+            rest.starts_with("invoke").then_some(program)
+        })
+        .collect()
+}
+
+#[async_trait]
+impl TokenListener for LogRouter {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    async fn run(&self) -> Result<()> {
+        self.listen().await
+    }
+
+    /// Never called: routed logs are queued under the listener they are for
+    async fn handle_log(&self, _slot: u64, _log: &RpcLogsResponse) -> Result<(), ListenerError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUMP_FUN: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
+    const PUMP_AMM: &str = "pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA";
+    const LAUNCHLAB: &str = "LanMV9sAd7wArD4vJFi2qDdfnVhFxYSUg6eADduJ3uj";
+
+    /// A listener that routes `programs` and accepts what `accepts` says
+    struct Stub {
+        name: &'static str,
+        programs: Vec<String>,
+        accepts: bool,
+    }
+
+    #[async_trait]
+    impl TokenListener for Stub {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        async fn run(&self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn handle_log(
+            &self,
+            _slot: u64,
+            _log: &RpcLogsResponse,
+        ) -> Result<(), ListenerError> {
+            Ok(())
+        }
+
+        fn programs(&self) -> Vec<String> {
+            self.programs.clone()
+        }
+
+        fn accepts(&self, log: &RpcLogsResponse) -> bool {
+            self.accepts && log.err.is_none()
+        }
+    }
+
+    fn stub(name: &'static str, programs: &[&str], accepts: bool) -> Arc<dyn TokenListener> {
+        Arc::new(Stub {
+            name,
+            programs: programs.iter().map(|p| p.to_string()).collect(),
+            accepts,
+        })
+    }
+
+    fn router(queue: &LogQueue, listeners: &[Arc<dyn TokenListener>]) -> Option<LogRouter> {
+        let config = Config::load().expect("config loads");
+        let rpc = Arc::new(RpcPool::new(&config.network, &config.rate_limit).expect("rpc pool"));
+        let listeners = listeners
+            .iter()
+            .map(|listener| (listener.name(), listener.clone()))
+            .collect();
+        LogRouter::new(config, rpc, queue.clone(), &listeners)
+    }
+
+    fn queue() -> LogQueue {
+        LogQueue::new(&Config::load().expect("config loads").ingestion)
+    }
+
+    fn log(signature: &str, lines: &[&str]) -> RpcLogsResponse {
+        RpcLogsResponse {
+            signature: signature.to_string(),
+            err: None,
+            logs: lines.iter().map(|line| line.to_string()).collect(),
+        }
+    }
+
+    /// The queued jobs as (listener, signature), sorted
+    async fn routed(queue: &LogQueue) -> Vec<(&'static str, String)> {
+        queue.close();
+        let mut routed = Vec::new();
+        while let Some(job) = queue.pop().await {
+            routed.push((job.listener, job.log.signature));
+        }
+        routed.sort();
+        routed
+    }
+
+    #[test]
+    fn invoked_programs_come_from_invoke_lines() {
+        let logs: Vec<String> = [
+            format!("Program {} invoke [1]", PUMP_FUN),
+            "Program log: Instruction: Buy".to_string(),
+            format!("Program {} invoke [2]", PUMP_AMM),
+            format!("Program {} success", PUMP_AMM),
+            format!("Program log: {}", LAUNCHLAB),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(invoked_programs(&logs), HashSet::from([PUMP_FUN, PUMP_AMM]));
+    }
+
+    #[tokio::test]
+    async fn logs_go_to_the_listeners_of_invoked_programs() {
+        let queue = queue();
+        let router = router(
+            &queue,
+            &[
+                stub("pumpfun", &[PUMP_FUN], true),
+                stub("pumpswap", &[PUMP_AMM], true),
+                stub("launchlab", &[LAUNCHLAB], true),
+                stub("watchlist", &[], true),
+            ],
+        )
+        .expect("routes");
+
+        let pump_fun_invoke = format!("Program {} invoke [1]", PUMP_FUN);
+        let amm_invoke = format!("Program {} invoke [2]", PUMP_AMM);
+        router
+            .route(1, log("migration", &[&pump_fun_invoke, &amm_invoke]))
+            .await;
+        router.route(2, log("buy", &[&pump_fun_invoke])).await;
+        // Mentioned without being invoked
+        let mention = format!("Program log: {}", LAUNCHLAB);
+        router.route(3, log("mention", &[&mention])).await;
+        // Each subscription delivers a transaction invoking several programs
+        router
+            .route(1, log("migration", &[&pump_fun_invoke, &amm_invoke]))
+            .await;
+
+        assert_eq!(
+            routed(&queue).await,
+            [
+                ("pumpfun", "buy".to_string()),
+                ("pumpfun", "migration".to_string()),
+                ("pumpswap", "migration".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn listeners_can_decline_logs() {
+        let queue = queue();
+        let router = router(
+            &queue,
+            &[
+                stub("pumpfun", &[PUMP_FUN], true),
+                stub("launchlab", &[LAUNCHLAB], false),
+            ],
+        )
+        .expect("routes");
+
+        let launchlab_invoke = format!("Program {} invoke [1]", LAUNCHLAB);
+        router.route(1, log("declined", &[&launchlab_invoke])).await;
+        let mut failed = log("failed", &[&format!("Program {} invoke [1]", PUMP_FUN)]);
+        failed.err = Some(solana_sdk::transaction::TransactionError::AccountNotFound.into());
+        router.route(2, failed).await;
+
+        assert!(routed(&queue).await.is_empty());
+    }
+
+    #[test]
+    fn no_router_without_programs() {
+        let queue = queue();
+        assert!(router(&queue, &[stub("watchlist", &[], true)]).is_none());
+    }
+}
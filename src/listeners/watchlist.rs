@@ -0,0 +1,187 @@
+use crate::processor::Processor;
+use crate::{listener_helpers, metrics};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+
+use crate::config::Config;
+use crate::error::ListenerError;
+use crate::event_bus::LogQueue;
+use crate::listeners::pumpfun::{idl::Events, mentions};
+use crate::listeners::TokenListener;
+use crate::models::{WalletLaunch, WalletTrade};
+use crate::rpc_pool::RpcPool;
+use moka::future::Cache;
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient,
+    rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+    rpc_response::RpcLogsResponse,
+};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+
+/// Subscribes to the transactions of `watchlist.wallets` and publishes their
+/// pump.fun launches (`WatchedWalletLaunch`) and trades (`WatchedWalletTrade`)
+pub struct WatchlistListener {
+    config: Config,
+    processor: Processor,
+    rpc: Arc<RpcPool>,
+    queue: LogQueue,
+    /// Labels by wallet
+    wallets: HashMap<Pubkey, Option<String>>,
+    /// A transaction of several watched wallets arrives once per subscription
+    seen: Cache<String, ()>,
+}
+
+impl WatchlistListener {
+    pub fn new(
+        config: Config,
+        processor: Processor,
+        rpc: Arc<RpcPool>,
+        queue: LogQueue,
+    ) -> Result<Self> {
+        let wallets = config
+            .watchlist
+            .wallets
+            .iter()
+            .map(|wallet| {
+                let address = Pubkey::from_str(&wallet.address)
+                    .with_context(|| format!("Invalid watchlist wallet {}", wallet.address))?;
+                Ok((address, wallet.label.clone()))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            config,
+            processor,
+            rpc,
+            queue,
+            wallets,
+            seen: Cache::builder()
+                .max_capacity(10_000)
+                .time_to_live(Duration::from_secs(600))
+                .build(),
+        })
+    }
+
+    async fn listen(&self) -> Result<()> {
+        let pubsub = PubsubClient::new(self.rpc.wss_url(self.name())).await?;
+        let mut keepalive = listener_helpers::Keepalive::subscribe(
+            &pubsub,
+            self.name(),
+            self.config.ingestion.keepalive_timeout_secs,
+        )
+        .await;
+
+        // `Mentions` takes a single account, so subscribe once per wallet
+        let mut streams = Vec::with_capacity(self.wallets.len());
+        let mut unsubscribes = Vec::with_capacity(self.wallets.len());
+        for wallet in self.wallets.keys() {
+            let (stream, unsubscribe) = pubsub
+                .logs_subscribe(
+                    RpcTransactionLogsFilter::Mentions(vec![wallet.to_string()]),
+                    RpcTransactionLogsConfig {
+                        commitment: Some(self.rpc.commitment()),
+                    },
+                )
+                .await?;
+            streams.push(stream);
+            unsubscribes.push(unsubscribe);
+        }
+        let mut stream = futures::stream::select_all(streams);
+
+        info!("Subscribed to {} watched wallet(s)", self.wallets.len());
+
+        // Watched wallets can be quiet for hours, silence says nothing about the connection
+        let outcome = loop {
+            let next = listener_helpers::next_log(
+                &mut stream,
+                &mut keepalive,
+                self.name(),
+                Duration::ZERO,
+            )
+            .await;
+            let (slot, rpc_log) = match next {
+                Ok(Some(next)) => next,
+                other => break other.map(|_| ()),
+            };
+
+            let pump_fun = self
+                .config
+                .programs
+                .pump_fun
+                .iter()
+                .any(|program| mentions(&rpc_log, program));
+            if rpc_log.err.is_none() && pump_fun {
+                self.queue.push(self.name(), slot, rpc_log).await;
+            }
+        };
+
+        for unsubscribe in unsubscribes {
+            unsubscribe().await;
+        }
+        keepalive.close().await;
+        outcome
+    }
+
+    async fn process_log(&self, log: &RpcLogsResponse) -> Result<()> {
+        if self.seen.contains_key(&log.signature) {
+            metrics::DROPPED_MESSAGES
+                .with_label_values(&["watchlist", "duplicate"])
+                .inc();
+            return Ok(());
+        }
+        self.seen.insert(log.signature.clone(), ()).await;
+
+        // The pumpfun listener reports what does not decode
+        let events = Events::decode(&log.logs, self.config.programs.pump_fun_program());
+        if let Some(event) = events.creation() {
+            if let Some(label) = self.wallets.get(&event.user) {
+                self.processor
+                    .process_wallet_launch(WalletLaunch {
+                        wallet: event.user.to_string(),
+                        label: label.clone(),
+                        signature: log.signature.clone(),
+                        mint: event.mint.to_string(),
+                        name: event.name.clone(),
+                        symbol: event.symbol.clone(),
+                        uri: event.uri.clone(),
+                    })
+                    .await?;
+            }
+        }
+
+        for trade in &events.trades {
+            let Some(label) = self.wallets.get(&trade.user) else {
+                continue;
+            };
+            self.processor
+                .process_wallet_trade(WalletTrade {
+                    label: label.clone(),
+                    signature: log.signature.clone(),
+                    trade: trade.to_trade(&self.config.trade_attribution),
+                })
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TokenListener for WatchlistListener {
+    fn name(&self) -> &'static str {
+        "watchlist"
+    }
+
+    async fn run(&self) -> Result<()> {
+        self.listen().await
+    }
+
+    async fn handle_log(&self, _slot: u64, log: &RpcLogsResponse) -> Result<(), ListenerError> {
+        Ok(self.process_log(log).await?)
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use aho_corasick::AhoCorasick;
+
+/// Something the lines of a log notification show, one bit of [`LogFlags`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LogFlag {
+    /// A program reported `success`
+    Success,
+    /// A token mint or launch was created
+    Create,
+    /// A pool was created
+    CreatePool,
+    Buy,
+    Sell,
+    Swap,
+    /// The pump.fun bonding curve program was invoked
+    PumpFun,
+    /// The PumpSwap AMM program was invoked
+    PumpAmm,
+}
+
+impl LogFlag {
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// The [`LogFlag`]s found in a log notification
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct LogFlags(u32);
+
+impl LogFlags {
+    pub fn contains(self, flag: LogFlag) -> bool {
+        self.0 & flag.bit() != 0
+    }
+
+    pub fn insert(&mut self, flag: LogFlag) {
+        self.0 |= flag.bit();
+    }
+
+    /// Whether any of the buy, sell or swap flags is set
+    pub fn is_trade(self) -> bool {
+        self.contains(LogFlag::Buy) || self.contains(LogFlag::Sell) || self.contains(LogFlag::Swap)
+    }
+}
+
+/// Classifies log notifications in a single pass over their lines, matching
+/// every substring pattern at once with Aho-Corasick instead of one scan per
+/// pattern. Build one per listener and keep it.
+pub(crate) struct LogClassifier {
+    matcher: AhoCorasick,
+    flags: Vec<LogFlag>,
+    lines: HashMap<String, LogFlag>,
+}
+
+impl LogClassifier {
+    pub fn builder() -> LogClassifierBuilder {
+        LogClassifierBuilder::default()
+    }
+
+    pub fn classify(&self, logs: &[String]) -> LogFlags {
+        let mut flags = LogFlags::default();
+        for line in logs {
+            if let Some(flag) = self.lines.get(line.as_str()) {
+                flags.insert(*flag);
+            }
+            for found in self.matcher.find_overlapping_iter(line.as_str()) {
+                flags.insert(self.flags[found.pattern().as_usize()]);
+            }
+        }
+        flags
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct LogClassifierBuilder {
+    patterns: Vec<String>,
+    flags: Vec<LogFlag>,
+    lines: HashMap<String, LogFlag>,
+}
+
+impl LogClassifierBuilder {
+    /// Set `flag` when a line contains `pattern`
+    pub fn contains(mut self, pattern: impl Into<String>, flag: LogFlag) -> Self {
+        self.patterns.push(pattern.into());
+        self.flags.push(flag);
+        self
+    }
+
+    /// Set `flag` when a line is exactly `line`, for names that prefix others
+    /// (`Instruction: Initialize` vs `Instruction: InitializeAccount3`)
+    pub fn line(mut self, line: impl Into<String>, flag: LogFlag) -> Self {
+        self.lines.insert(line.into(), flag);
+        self
+    }
+
+    pub fn build(self) -> LogClassifier {
+        LogClassifier {
+            matcher: AhoCorasick::new(&self.patterns).expect("log patterns fit the automaton"),
+            flags: self.flags,
+            lines: self.lines,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(classifier: &LogClassifier, lines: &[&str]) -> LogFlags {
+        let logs: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        classifier.classify(&logs)
+    }
+
+    #[test]
+    fn overlapping_patterns_all_match() {
+        let classifier = LogClassifier::builder()
+            .contains("Instruction: Swap", LogFlag::Swap)
+            .contains("SwapTob", LogFlag::Buy)
+            .contains("success", LogFlag::Success)
+            .build();
+
+        let flags = classify(&classifier, &["Program log: Instruction: SwapTob"]);
+        assert!(flags.contains(LogFlag::Swap));
+        assert!(flags.contains(LogFlag::Buy));
+        assert!(!flags.contains(LogFlag::Success));
+        assert!(flags.is_trade());
+    }
+
+    #[test]
+    fn flags_add_up_across_lines() {
+        let classifier = LogClassifier::builder()
+            .contains("Instruction: Sell", LogFlag::Sell)
+            .contains("success", LogFlag::Success)
+            .build();
+
+        let flags = classify(
+            &classifier,
+            &["Program log: Instruction: Sell", "Program x success"],
+        );
+        let mut expected = LogFlags::default();
+        expected.insert(LogFlag::Sell);
+        expected.insert(LogFlag::Success);
+        assert_eq!(flags, expected);
+        assert_eq!(classify(&classifier, &[]), LogFlags::default());
+    }
+
+    #[test]
+    fn lines_match_exactly() {
+        let classifier = LogClassifier::builder()
+            .line("Program log: Instruction: Initialize", LogFlag::Create)
+            .build();
+
+        let initialize = classify(&classifier, &["Program log: Instruction: Initialize"]);
+        assert!(initialize.contains(LogFlag::Create));
+        for line in [
+            "Program log: Instruction: InitializeAccount3",
+            " Program log: Instruction: Initialize",
+        ] {
+            assert_eq!(
+                classify(&classifier, &[line]),
+                LogFlags::default(),
+                "{}",
+                line
+            );
+        }
+    }
+}
@@ -1,45 +1,119 @@
-
-mod listener_helpers;
-mod listeners;
-mod config;
-mod processor;
-mod  token_helper;
-mod housekeeping_util;
-pub mod models;
+mod cli;
 
 use std::sync::Arc;
+
 use anyhow::Result;
-use tokio::sync::Semaphore;
-use tracing::log::info;
-use crate::config::Config;
+use clap::Parser;
+#[cfg(feature = "test-utils")]
+use pumpfun_ingestion::mock_rpc;
+use pumpfun_ingestion::config::Config;
+use pumpfun_ingestion::dry_run::DryRunReporter;
+use pumpfun_ingestion::fixtures::{FixtureRecorder, ReplaySender};
+use pumpfun_ingestion::rpc_pool::RpcTransport;
+use pumpfun_ingestion::sinks::EventSink;
+use pumpfun_ingestion::{app, backfill, housekeeping_util, outbound, processor, reprocess, selftest};
+#[cfg(feature = "test-utils")]
+use tracing::info;
+
+use crate::cli::{Cli, Command};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing
-    housekeeping_util::init_logging();
-
-    housekeeping_util::spawn_log_cleaner( 1);
-    info!("Starting Ingestion Service");
-    let limiter = Arc::new(Semaphore::new(8));
+    let cli = Cli::parse();
 
     // Load config
-    let config = Config::load()?;
-
-       // Create processor
-    let processor = processor::Processor::new( config.clone());
-
-    // Start listeners
-
-    let pumpfun_listener = listeners::pumpfun::PumpFunListener::new(config.clone(), processor.clone(),limiter.clone());
+    let mut config = Config::load()?;
+    if cli.stdout {
+        config.enable_stdout_output();
+    }
+    if cli.dry_run || config.dry_run {
+        config.enable_dry_run();
+    }
 
+    // Initialize tracing
+    let _telemetry = housekeeping_util::init_logging(&config.logging, &config.telemetry)?;
+    outbound::init(&config.outbound)?;
 
-    // Run  in parallel
-    tokio::select! {
-        result = pumpfun_listener.start() => {
-            tracing::error!("PumpFun listener stopped: {:?}", result);
+    match cli.command() {
+        Command::Run { record } => {
+            let recorder = match record {
+                Some(dir) => Some(Arc::new(FixtureRecorder::create(&dir)?)),
+                None => None,
+            };
+            app::run(config, recorder).await
+        }
+        Command::Inspect { signature } => app::inspect(&config, &signature).await,
+        Command::Backfill {
+            from_slot,
+            to_slot,
+            since,
+            until,
+            output,
+        } => {
+            let range = backfill::BackfillRange {
+                from_slot,
+                to_slot,
+                since,
+                until,
+            };
+            let output = match output {
+                Some(path) => backfill::BackfillOutput::file(&path).await?,
+                None => backfill::BackfillOutput::Publish(Box::new(
+                    processor::Processor::new(config.clone()).await?,
+                )),
+            };
+            backfill::run(&config, range, output).await
+        }
+        Command::Reprocess {
+            stream,
+            file,
+            since,
+            until,
+            channel,
+            output,
+            changed_only,
+        } => {
+            let source = match stream {
+                Some(key) => reprocess::ReprocessSource::Stream(key),
+                None => reprocess::ReprocessSource::Files(file),
+            };
+            let options = reprocess::ReprocessOptions {
+                since,
+                until,
+                changed_only,
+            };
+            let output: Arc<dyn EventSink> = match output {
+                _ if config.dry_run => Arc::new(DryRunReporter),
+                Some(path) => reprocess::file_output(&path).await?,
+                None => reprocess::redis_output(&config, channel).await?,
+            };
+            reprocess::run(&config, source, options, output).await
+        }
+        Command::Replay { file, rpc_fixtures } => {
+            let transport = match rpc_fixtures {
+                Some(path) => RpcTransport::Replay(ReplaySender::load(&path)?),
+                None => RpcTransport::Http,
+            };
+            app::replay(&config, &file, &transport).await
+        }
+        Command::SelfTest => selftest::run(&config).await,
+        #[cfg(feature = "test-utils")]
+        Command::MockRpc {
+            fixtures,
+            http_port,
+            ws_port,
+        } => {
+            let server = match fixtures {
+                Some(dir) => mock_rpc::MockRpcServer::from_fixtures(&dir)?,
+                None => mock_rpc::MockRpcServer::new(),
+            };
+            let handle = server.spawn(http_port, ws_port).await?;
+            info!(
+                "Mock RPC running, set network.rpc_http_url = {:?} and network.rpc_wss_url = {:?}",
+                handle.http_url, handle.ws_url
+            );
+            app::shutdown_signal().await;
+            Ok(())
         }
-
     }
-
-    Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,573 @@
+use std::sync::LazyLock;
+use prometheus::{
+    register_counter_vec, register_gauge, register_gauge_vec, register_histogram,
+    register_histogram_vec, register_int_counter_vec, register_int_gauge, register_int_gauge_vec,
+    CounterVec, Encoder, Gauge, GaugeVec, Histogram, HistogramVec, IntCounterVec, IntGauge,
+    IntGaugeVec, TextEncoder,
+};
+
+/// Tokens handed to the processor, labelled by source (pumpfun, onchain, ...)
+pub static TOKENS_DISCOVERED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_tokens_discovered_total",
+        "Number of tokens discovered",
+        &["source"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Tokens rejected by the publish filter, labelled by the rule that rejected them
+pub static TOKENS_FILTERED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_tokens_filtered_total",
+        "Number of discovered tokens not published because of a filter rule",
+        &["reason"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Failed RPC calls, labelled by RPC method
+pub static RPC_ERRORS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_rpc_errors_total",
+        "Number of failed RPC calls",
+        &["method"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Log messages that were received but not turned into an event
+pub static DROPPED_MESSAGES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_dropped_messages_total",
+        "Number of log messages dropped before publishing",
+        &["component", "reason"]
+    )
+    .expect("metric can be registered")
+});
+
+/// pump.fun / PumpSwap instructions and events the IDL decoder has no layout for
+pub static IDL_UNDECODED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_idl_undecoded_total",
+        "Number of pump.fun instructions and events with an unknown discriminator or layout",
+        &["program", "kind"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Trades that went through an aggregator or bot program, `other` when it is not
+/// in `trade_attribution.routers`
+pub static ROUTED_TRADES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_routed_trades_total",
+        "Number of trades made through a router program",
+        &["router"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Dead-lettered logs, labelled by outcome: queued, recovered or failed (retries exhausted)
+pub static DEAD_LETTERS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_dead_letters_total",
+        "Number of failed logs handled by the dead-letter queue",
+        &["listener", "outcome"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Events a sink gave up on after exhausting its retries
+pub static PUBLISH_FAILURES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_publish_failures_total",
+        "Number of events that failed to publish",
+        &["sink"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Time between `Token::discovered_at` and the event being delivered by a sink
+pub static DISCOVERY_TO_PUBLISH_SECONDS: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "pumpfun_discovery_to_publish_seconds",
+        "Latency from token discovery to event publish",
+        &["sink"],
+        vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0]
+    )
+    .expect("metric can be registered")
+});
+
+pub static RPC_FETCH_SECONDS: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "pumpfun_rpc_fetch_seconds",
+        "Latency of RPC fetches",
+        &["method"],
+        vec![0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]
+    )
+    .expect("metric can be registered")
+});
+
+/// Listener sessions that ended and were scheduled for a restart
+pub static LISTENER_RESTARTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_listener_restarts_total",
+        "Number of listener restarts after a failed or ended session",
+        &["listener"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Consecutive failed sessions of each listener, the backoff attempt
+pub static LISTENER_FAILURES: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "pumpfun_listener_consecutive_failures",
+        "Consecutive failed sessions of a listener, reset after a stable one",
+        &["listener"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Connections found half-open or closed by the WSS keepalive
+pub static WSS_KEEPALIVE_FAILURES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_wss_keepalive_failures_total",
+        "Number of WSS connections dropped because keepalive notifications stopped",
+        &["listener"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Subscriptions reopened right away after losing a healthy connection
+pub static RESUBSCRIBES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_listener_resubscribes_total",
+        "Number of listener subscriptions reopened without backoff after a lost connection",
+        &["listener"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Subscriptions torn down by the watchdog after going silent
+pub static STREAM_STALLS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_stream_stalls_total",
+        "Number of log subscriptions restarted because no message arrived in time",
+        &["listener"]
+    )
+    .expect("metric can be registered")
+});
+
+/// 1 while an HTTP RPC endpoint is in rotation, 0 while it is cooling down after failures
+pub static RPC_ENDPOINT_HEALTHY: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "pumpfun_rpc_endpoint_healthy",
+        "Whether an RPC endpoint is currently used",
+        &["endpoint"]
+    )
+    .expect("metric can be registered")
+});
+
+/// 429 responses, labelled by RPC endpoint host
+pub static RPC_RATE_LIMITED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_rpc_rate_limited_total",
+        "Number of RPC requests throttled by the endpoint",
+        &["endpoint"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Current adaptive request budget of each RPC endpoint
+pub static RPC_RATE_LIMIT: LazyLock<GaugeVec> = LazyLock::new(|| {
+    register_gauge_vec!(
+        "pumpfun_rpc_rate_limit_requests_per_second",
+        "Requests per second currently allowed against an RPC endpoint",
+        &["endpoint"]
+    )
+    .expect("metric can be registered")
+});
+
+/// HTTP RPC requests sent, labelled by RPC method and endpoint host
+pub static RPC_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_rpc_requests_total",
+        "Number of RPC requests sent to an endpoint",
+        &["method", "endpoint"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Estimated provider credits spent, weighted per method by `[rpc_budget]`
+pub static RPC_CREDITS: LazyLock<CounterVec> = LazyLock::new(|| {
+    register_counter_vec!(
+        "pumpfun_rpc_credits_total",
+        "Estimated provider credits spent on RPC requests",
+        &["endpoint"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Estimated credits spent since midnight UTC, across endpoints
+pub static RPC_CREDITS_TODAY: LazyLock<Gauge> = LazyLock::new(|| {
+    register_gauge!(
+        "pumpfun_rpc_credits_today",
+        "Estimated provider credits spent on RPC requests today (UTC)"
+    )
+    .expect("metric can be registered")
+});
+
+/// Log notifications waiting in the internal queue between listeners and workers
+pub static LOG_QUEUE_DEPTH: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "pumpfun_log_queue_depth",
+        "Number of log notifications queued for processing",
+        &["listener"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Log notifications dropped by the queue overflow policy, by what they carried
+pub static LOG_QUEUE_DROPPED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_log_queue_dropped_total",
+        "Number of log notifications dropped because the internal queue was full",
+        &["listener", "kind"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Time a log notification spent queued before a worker picked it up
+pub static LOG_QUEUE_WAIT_SECONDS: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "pumpfun_log_queue_wait_seconds",
+        "Time log notifications spend in the internal queue",
+        &["listener"],
+        vec![0.001, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]
+    )
+    .expect("metric can be registered")
+});
+
+/// Workers currently processing a log notification
+pub static WORKERS_BUSY: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "pumpfun_workers_busy",
+        "Number of log processing workers currently busy"
+    )
+    .expect("metric can be registered")
+});
+
+/// `wash_score` of every token whose early trades were analysed
+pub static WASH_SCORE: LazyLock<Histogram> = LazyLock::new(|| {
+    register_histogram!(
+        "pumpfun_wash_score",
+        "Share of the early volume of tokens traded back and forth, in percent",
+        vec![1.0, 5.0, 10.0, 25.0, 50.0, 75.0, 100.0]
+    )
+    .expect("metric can be registered")
+});
+
+/// Sell simulations of suspicious tokens, labelled by result (sellable, blocked, inconclusive)
+pub static SELL_SIMULATIONS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_sell_simulations_total",
+        "Number of simulated sells of tokens that could be honeypots",
+        &["result"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Authority changes seen on tracked tokens after discovery, labelled by change
+/// (mint_revoked, freeze_revoked, supply_minted, holders_frozen)
+pub static AUTHORITY_CHANGES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_authority_changes_total",
+        "Number of mint or freeze authority changes on tracked tokens",
+        &["change"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Lifecycle transitions, labelled by the state entered
+pub static TOKEN_STATE_CHANGES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_token_state_changes_total",
+        "Number of token lifecycle transitions",
+        &["state"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Tracked tokens dropped for not trading, labelled by source
+pub static TOKENS_ABANDONED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_tokens_abandoned_total",
+        "Number of tracked tokens abandoned after a window without trades",
+        &["source"]
+    )
+    .expect("metric can be registered")
+});
+
+/// OHLCV candles published, labelled by length in seconds
+pub static CANDLES_PUBLISHED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_candles_published_total",
+        "Number of OHLCV candles published",
+        &["interval"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Tokens that became king of the hill, labelled by source
+pub static KINGS_OF_THE_HILL: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_king_of_the_hill_total",
+        "Number of tokens reaching a king-of-the-hill threshold",
+        &["source"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Tracked tokens reaching `min_tx_count_for_active_pair` trades, labelled by source
+pub static ACTIVE_PAIRS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_active_pairs_total",
+        "Number of tracked tokens published as active pairs",
+        &["source"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Launches and trades of watched wallets, labelled by kind
+pub static WATCHED_WALLET_EVENTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_watched_wallet_events_total",
+        "Number of launches and trades by watched wallets",
+        &["kind"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Creator wallets added to the blacklist, labelled by origin (api, rug)
+pub static CREATORS_BLACKLISTED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_creators_blacklisted_total",
+        "Number of creator wallets added to the rugger blacklist",
+        &["origin"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Events waiting in the write-ahead log of a sink
+pub static WAL_EVENTS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "pumpfun_wal_events",
+        "Number of events buffered on disk until their sink recovers",
+        &["sink"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Buffered events delivered once their sink recovered
+pub static WAL_REPLAYED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_wal_replayed_total",
+        "Number of events replayed from the write-ahead log",
+        &["sink"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Rows written to ClickHouse, labelled by table
+pub static CLICKHOUSE_ROWS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_clickhouse_rows_total",
+        "Number of rows inserted into ClickHouse",
+        &["table"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Hourly archive objects uploaded, labelled by outcome (ok, error)
+pub static ARCHIVE_UPLOADS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_archive_uploads_total",
+        "Number of hourly event archives uploaded to object storage",
+        &["outcome"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Open gRPC streams, labelled by method
+#[cfg(feature = "grpc")]
+pub static GRPC_SUBSCRIBERS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "pumpfun_grpc_subscribers",
+        "Number of gRPC clients currently subscribed",
+        &["method"]
+    )
+    .expect("metric can be registered")
+});
+
+/// 1 while this instance holds the `leader.key` lease
+pub static LEADER: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "pumpfun_leader",
+        "Whether this instance is the leader publishing events"
+    )
+    .expect("metric can be registered")
+});
+
+/// Shards of `sharding.shards` this instance enriches
+pub static SHARDS_OWNED: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "pumpfun_shards_owned",
+        "Number of enrichment shards claimed by this instance"
+    )
+    .expect("metric can be registered")
+});
+
+/// Simulated positions closed, labelled by exit (take_profit, stop_loss, max_hold)
+pub static PAPER_TRADES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_paper_trades_total",
+        "Number of paper trading positions closed",
+        &["exit"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Simulated PnL in SOL, `realized` by closed positions and `unrealized` of open ones
+pub static PAPER_PNL_SOL: LazyLock<GaugeVec> = LazyLock::new(|| {
+    register_gauge_vec!(
+        "pumpfun_paper_pnl_sol",
+        "Paper trading profit and loss in SOL",
+        &["kind"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Buy attempts of the execution module, labelled by outcome
+/// (sent, failed, simulated, simulation_failed, rate_limited)
+pub static SNIPES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_snipes_total",
+        "Number of buys attempted by the execution module",
+        &["outcome"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Positions of the execution module currently held
+pub static POSITIONS_OPEN: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "pumpfun_positions_open",
+        "Number of positions held by the execution module"
+    )
+    .expect("metric can be registered")
+});
+
+/// Positions of the execution module closed, labelled by exit
+/// (take_profit, stop_loss, trailing_stop, max_hold)
+pub static POSITIONS_CLOSED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_positions_closed_total",
+        "Number of positions sold by the execution module",
+        &["exit"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Sells of the execution module, labelled by outcome (sent, dry_run, failed);
+/// failed sells are retried
+pub static POSITION_SELLS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_position_sells_total",
+        "Number of sells attempted by the execution module",
+        &["outcome"]
+    )
+    .expect("metric can be registered")
+});
+
+/// PnL of the execution module in SOL, `realized` by closed positions and
+/// `unrealized` of open ones
+pub static POSITION_PNL_SOL: LazyLock<GaugeVec> = LazyLock::new(|| {
+    register_gauge_vec!(
+        "pumpfun_position_pnl_sol",
+        "Profit and loss of executed positions in SOL",
+        &["kind"]
+    )
+    .expect("metric can be registered")
+});
+
+/// DexScreener lookups by `result`: `ok`, `empty`, `cache_hit`, `error` or
+/// `circuit_open`
+pub static DEXSCREENER_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_dexscreener_requests_total",
+        "DexScreener pair lookups",
+        &["result"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Birdeye token overviews by `result`: `ok`, `unknown` or `error`
+pub static BIRDEYE_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_birdeye_requests_total",
+        "Birdeye token overview lookups",
+        &["result"]
+    )
+    .expect("metric can be registered")
+});
+
+/// RugCheck reports by `result`: `ok`, `unknown` or `error`
+pub static RUGCHECK_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_rugcheck_requests_total",
+        "RugCheck report lookups",
+        &["result"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Enrichment lookups skipped because they recently failed or found nothing
+pub static NEGATIVE_CACHE_HITS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_negative_cache_hits_total",
+        "Lookups skipped after a recent miss",
+        &["lookup"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Token info cache lookups by `result`: `hit` or `miss`
+pub static TOKEN_INFO_CACHE: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_token_info_cache_total",
+        "Token info cache lookups",
+        &["result"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Re-enrichments of recently discovered tokens that were published, labelled
+/// by what moved (holders, top_holders, liquidity, curve_progress, score)
+pub static REENRICHMENTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "pumpfun_reenrichments_total",
+        "Number of re-enrichment changes on recently discovered tokens",
+        &["change"]
+    )
+    .expect("metric can be registered")
+});
+
+/// Render every registered metric in the Prometheus text format
+pub fn gather() -> String {
+    let mut buffer = Vec::new();
+    let encoder = TextEncoder::new();
+    if let Err(e) = encoder.encode(&prometheus::gather(), &mut buffer) {
+        tracing::error!("Failed to encode metrics: {}", e);
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
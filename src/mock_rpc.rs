@@ -0,0 +1,382 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::extract::State;
+use axum::routing::post;
+use axum::{Json, Router};
+use base64::Engine;
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use solana_sdk::transaction::Transaction;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::warn;
+
+use crate::fixtures::{self, RecordedLog, ReplaySender};
+
+/// JSON-RPC "method not found"
+const METHOD_NOT_FOUND: i64 = -32601;
+/// JSON-RPC "internal error", what a flaky node answers
+const INTERNAL_ERROR: i64 = -32603;
+
+/// Canned Solana RPC node for integration tests and CI: an HTTP JSON-RPC
+/// endpoint plus a websocket endpoint whose `logsSubscribe` streams the
+/// configured notifications, so the whole listener → processor → sink path
+/// runs without a real node.
+///
+/// `getTransaction` and `getAccountInfo` / `getMultipleAccounts` are answered
+/// from the canned transactions and accounts by their first parameter, other
+/// methods from their canned answer; any other request is looked up in the
+/// recorded `rpc.ndjson`, if one was loaded. `sendTransaction` accepts every
+/// transaction.
+#[derive(Default)]
+pub struct MockRpcServer {
+    transactions: HashMap<String, Value>,
+    accounts: HashMap<String, Value>,
+    answers: HashMap<String, Value>,
+    logs: Vec<RecordedLog>,
+    recorded: Option<ReplaySender>,
+    /// Calls of a method still to be failed
+    failures: HashMap<String, AtomicUsize>,
+    slot: u64,
+}
+
+/// Addresses of a running [`MockRpcServer`]
+pub struct MockRpcHandle {
+    pub http_url: String,
+    pub ws_url: String,
+    tasks: Vec<tokio::task::JoinHandle<()>>,
+}
+
+impl Drop for MockRpcHandle {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+impl MockRpcServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve a `run --record` directory: its RPC answers and its log notifications
+    pub fn from_fixtures(dir: &Path) -> Result<Self> {
+        let mut server = Self::new();
+
+        let rpc = dir.join(fixtures::RPC_FILE);
+        if rpc.exists() {
+            server.recorded = Some(ReplaySender::load(&rpc)?);
+        }
+
+        let logs = dir.join(fixtures::LOGS_FILE);
+        if logs.exists() {
+            let raw = std::fs::read_to_string(&logs)
+                .with_context(|| format!("Failed to read {}", logs.display()))?;
+            for (index, line) in raw.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let log: RecordedLog = serde_json::from_str(line).with_context(|| {
+                    format!("{}:{}: not a recorded log", logs.display(), index + 1)
+                })?;
+                server.logs.push(log);
+            }
+        }
+
+        Ok(server)
+    }
+
+    /// `getTransaction` result for `signature`, as the node would encode it
+    #[allow(dead_code)]
+    pub fn with_transaction(mut self, signature: &str, transaction: Value) -> Self {
+        self.transactions.insert(signature.to_string(), transaction);
+        self
+    }
+
+    /// Account (`{"lamports", "owner", "data", ...}`) returned for `pubkey`
+    #[allow(dead_code)]
+    pub fn with_account(mut self, pubkey: &str, account: Value) -> Self {
+        self.accounts.insert(pubkey.to_string(), account);
+        self
+    }
+
+    /// `result` of every `method` call
+    #[allow(dead_code)]
+    pub fn with_answer(mut self, method: &str, result: Value) -> Self {
+        self.answers.insert(method.to_string(), result);
+        self
+    }
+
+    /// Log notification streamed to every `logsSubscribe` subscriber
+    #[allow(dead_code)]
+    pub fn with_log(mut self, log: RecordedLog) -> Self {
+        self.logs.push(log);
+        self
+    }
+
+    /// Fail the first `count` calls of `method` with an internal error
+    #[allow(dead_code)]
+    pub fn with_failures(mut self, method: &str, count: usize) -> Self {
+        self.failures
+            .insert(method.to_string(), AtomicUsize::new(count));
+        self
+    }
+
+    /// Bind both endpoints on `127.0.0.1` (port 0 picks free ports) and serve in the background
+    pub async fn spawn(self, http_port: u16, ws_port: u16) -> Result<MockRpcHandle> {
+        let http = TcpListener::bind(("127.0.0.1", http_port))
+            .await
+            .context("Failed to bind mock RPC HTTP port")?;
+        let ws = TcpListener::bind(("127.0.0.1", ws_port))
+            .await
+            .context("Failed to bind mock RPC websocket port")?;
+        let http_addr = http.local_addr()?;
+        let ws_addr = ws.local_addr()?;
+
+        let server = Arc::new(self);
+        let app = Router::new()
+            .route("/", post(handle_http))
+            .with_state(server.clone());
+
+        let http_task = tokio::spawn(async move {
+            if let Err(e) = axum::serve(http, app).await {
+                warn!("Mock RPC HTTP server stopped: {}", e);
+            }
+        });
+        let ws_task = tokio::spawn(accept_websockets(ws, server));
+
+        Ok(MockRpcHandle {
+            http_url: format!("http://{}", http_addr),
+            ws_url: format!("ws://{}", ws_addr),
+            tasks: vec![http_task, ws_task],
+        })
+    }
+
+    fn context(&self, value: Value) -> Value {
+        json!({ "context": { "slot": self.slot }, "value": value })
+    }
+
+    fn answer(&self, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+        let failing = self.failures.get(method).is_some_and(|left| {
+            left.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| {
+                left.checked_sub(1)
+            })
+            .is_ok()
+        });
+        if failing {
+            return Err((INTERNAL_ERROR, format!("mock RPC failed {}", method)));
+        }
+        let first = params.get(0).and_then(Value::as_str);
+
+        let canned = match (method, first) {
+            ("getTransaction", Some(signature)) => self.transactions.get(signature).cloned(),
+            ("getAccountInfo", Some(pubkey)) => self
+                .accounts
+                .get(pubkey)
+                .map(|account| self.context(account.clone())),
+            ("getMultipleAccounts", _) => params.get(0).and_then(Value::as_array).and_then(|keys| {
+                let accounts: Vec<Value> = keys
+                    .iter()
+                    .map(|key| {
+                        key.as_str()
+                            .and_then(|key| self.accounts.get(key))
+                            .cloned()
+                            .unwrap_or(Value::Null)
+                    })
+                    .collect();
+                // Only answer when every key is canned, recordings cover the rest
+                accounts.iter().all(|a| !a.is_null()).then(|| self.context(json!(accounts)))
+            }),
+            ("sendTransaction", Some(encoded)) => sent_signature(encoded),
+            ("getHealth", _) => Some(json!("ok")),
+            ("getSlot", _) => Some(json!(self.slot)),
+            _ => self.answers.get(method).cloned(),
+        };
+
+        canned
+            .or_else(|| self.recorded.as_ref()?.answer(method, params))
+            .or_else(|| match method {
+                // Unknown transactions and accounts simply don't exist yet
+                "getTransaction" => Some(Value::Null),
+                "getAccountInfo" => Some(self.context(Value::Null)),
+                _ => None,
+            })
+            .ok_or_else(|| (METHOD_NOT_FOUND, format!("mock RPC has no answer for {}", method)))
+    }
+}
+
+/// Signature of a base64 `sendTransaction` parameter
+fn sent_signature(encoded: &str) -> Option<Value> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .ok()?;
+    let transaction: Transaction = bincode::deserialize(&bytes).ok()?;
+    Some(json!(transaction.signatures.first()?.to_string()))
+}
+
+async fn handle_http(
+    State(server): State<Arc<MockRpcServer>>,
+    Json(request): Json<Value>,
+) -> Json<Value> {
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = request.get("method").and_then(Value::as_str).unwrap_or_default();
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+    Json(match server.answer(method, &params) {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => {
+            json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+        }
+    })
+}
+
+async fn accept_websockets(listener: TcpListener, server: Arc<MockRpcServer>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                tokio::spawn(serve_websocket(stream, peer, server.clone()));
+            }
+            Err(e) => warn!("Mock RPC websocket accept failed: {}", e),
+        }
+    }
+}
+
+/// Answer `logsSubscribe` with a subscription id, then stream every canned log
+async fn serve_websocket(stream: TcpStream, peer: SocketAddr, server: Arc<MockRpcServer>) {
+    let mut ws = match tokio_tungstenite::accept_async(stream).await {
+        Ok(ws) => ws,
+        Err(e) => {
+            warn!("Mock RPC websocket handshake with {} failed: {}", peer, e);
+            return;
+        }
+    };
+
+    let mut next_subscription: u64 = 0;
+    while let Some(Ok(message)) = ws.next().await {
+        let Message::Text(text) = message else {
+            continue;
+        };
+        let Ok(request) = serde_json::from_str::<Value>(&text) else {
+            continue;
+        };
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+
+        let replies: Vec<Value> = match request.get("method").and_then(Value::as_str) {
+            Some("logsSubscribe") => {
+                next_subscription += 1;
+                let subscription = next_subscription;
+                std::iter::once(json!({ "jsonrpc": "2.0", "id": id, "result": subscription }))
+                    .chain(server.logs.iter().map(|recorded| {
+                        json!({
+                            "jsonrpc": "2.0",
+                            "method": "logsNotification",
+                            "params": {
+                                "subscription": subscription,
+                                "result": {
+                                    "context": { "slot": recorded.slot },
+                                    "value": recorded.log,
+                                },
+                            },
+                        })
+                    }))
+                    .collect()
+            }
+            Some(method) if method.ends_with("Unsubscribe") => {
+                vec![json!({ "jsonrpc": "2.0", "id": id, "result": true })]
+            }
+            method => vec![json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {
+                    "code": METHOD_NOT_FOUND,
+                    "message": format!("mock RPC does not support {:?}", method),
+                },
+            })],
+        };
+
+        for reply in replies {
+            if ws.send(Message::text(reply.to_string())).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::config::SinkConfig;
+    use crate::dedup::SignatureDedup;
+    use crate::event_bus::LogQueue;
+    use crate::fixtures::tests as fixture;
+    use crate::listeners::pumpfun::PumpFunListener;
+    use crate::listeners::TokenListener;
+    use crate::models::{Envelope, Event};
+    use crate::processor::Processor;
+    use crate::rpc_pool::RpcPool;
+
+    /// The recorded create log, streamed by the mock node's `logsSubscribe`, is
+    /// queued by the pump.fun listener and published to the sink as `TokenDiscovered`
+    #[tokio::test]
+    async fn create_log_reaches_the_sink() -> Result<()> {
+        let node = MockRpcServer::from_fixtures(&fixture::fixtures_dir())?
+            .spawn(0, 0)
+            .await?;
+        let events = std::env::temp_dir().join(format!("mock_rpc_{}.ndjson", std::process::id()));
+        let _ = std::fs::remove_file(&events);
+
+        let mut config = fixture::config()?;
+        config.network.rpc_http_url = node.http_url.clone();
+        config.network.rpc_http_urls = Vec::new();
+        config.network.rpc_wss_url = node.ws_url.clone();
+        config.network.rpc_wss_urls = Vec::new();
+        config.sinks = vec![SinkConfig::File {
+            path: events.display().to_string(),
+        }];
+        let rpc = Arc::new(RpcPool::new(&config.network, &config.rate_limit)?);
+        let processor = Processor::new(config.clone()).await?;
+        let dedup = Arc::new(SignatureDedup::new(&config.dedup, &config.database).await?);
+        let queue = LogQueue::new(&config.ingestion);
+        let listener = Arc::new(PumpFunListener::new(
+            config,
+            processor.clone(),
+            rpc,
+            dedup,
+            queue.clone(),
+            None,
+        ));
+
+        let subscription = tokio::spawn({
+            let listener = listener.clone();
+            async move { listener.run().await }
+        });
+        let job = tokio::time::timeout(Duration::from_secs(30), queue.pop())
+            .await
+            .context("No log queued within 30s")?
+            .context("Queue closed")?;
+        subscription.abort();
+        assert_eq!(job.log.signature, fixture::CREATE_SIGNATURE);
+        assert_eq!(job.slot, fixture::create_log()?.slot);
+
+        listener.process_log(job.slot, &job.log).await?;
+        assert!(processor.flush(Duration::from_secs(5)).await);
+        let published = std::fs::read_to_string(&events)?;
+        std::fs::remove_file(&events)?;
+        let line = published.lines().next().context("Nothing published")?;
+        let envelope: Envelope = serde_json::from_str(line)?;
+        let Event::TokenDiscovered(token) = envelope.payload else {
+            panic!("Expected token_discovered, got {}", line);
+        };
+        assert_eq!(token.mint_address, fixture::CREATE_MINT);
+        assert!(token.pump_fun_bonding_curve.is_some());
+        Ok(())
+    }
+}
@@ -7,7 +7,6 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use thiserror::Error;
-use tokio::signal::unix::Signal;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Token {
@@ -15,6 +14,9 @@ pub struct Token {
     pub created_at: DateTime<Utc>,
     pub discovered_at: DateTime<Utc>,
     pub source: TokenSource,
+    /// Wallet that created the token (fee payer of the creation transaction)
+    #[serde(default)]
+    pub creator: Option<String>,
 
     pub name: Option<String>,
     pub symbol: Option<String>,
@@ -23,26 +25,193 @@ pub struct Token {
     pub total_supply: BigDecimal,
     pub holder_count: Option<u32>,
     pub top_10_holder_percentage: Option<BigDecimal>,
+    /// Share of the supply the creator bought in the creation transaction
+    #[serde(default)]
+    pub dev_buy_percentage: Option<BigDecimal>,
 
+    /// SOL in the bonding curve, or in the PumpSwap pool after graduation
     pub liquidity_sol: Option<BigDecimal>,
+    /// SOL per whole token, from the same curve or pool
+    #[serde(default)]
+    pub price_sol: Option<BigDecimal>,
+    /// `price_sol` at the SOL/USD price of `[price]`
+    #[serde(default)]
+    pub price_usd: Option<BigDecimal>,
+    #[serde(default)]
+    pub market_cap_usd: Option<BigDecimal>,
+    /// Share of the pump.fun bonding curve's tokens sold, 100 once graduated
+    #[serde(default)]
+    pub bonding_curve_progress: Option<BigDecimal>,
+    /// When the token reached a king-of-the-hill threshold (`trending.enabled`)
+    #[serde(default)]
+    pub king_of_the_hill_at: Option<DateTime<Utc>>,
+    /// LP tokens of the pool the token graduated to are burned or locked (`lp.enabled`)
     pub liquidity_locked: Option<bool>,
     pub lp_burned: Option<bool>,
 
     pub mint_authority_disabled: bool,
     pub freeze_authority_disabled: bool,
 
+    /// Token-2022 extension names, e.g. "TransferFeeConfig"
+    #[serde(default)]
+    pub mint_extensions: Vec<String>,
+    pub transfer_fee_bps: Option<u16>,
+    #[serde(default)]
+    pub has_transfer_hook: bool,
+    #[serde(default)]
+    pub has_permanent_delegate: bool,
+
     pub raydium_pool: Option<Pubkey>,
     pub pump_fun_bonding_curve: Option<Pubkey>,
+    /// Raydium LaunchLab (letsbonk.fun) pool state account
+    #[serde(default)]
+    pub launchlab_pool: Option<String>,
     pub orca_pool: Option<String>,
     pub meteora_pool: Option<String>,
+    /// PumpSwap AMM pool the token trades in after leaving its bonding curve
+    #[serde(default)]
+    pub pumpswap_pool: Option<String>,
     pub four_meme_pool: Option<String>,
     pub base_pair: Option<String>,
     pub bsc_pair: Option<String>,
     pub score: Option<i32>,
     pub risk_level: Option<RiskLevel>,
+    /// Where the creator's SOL came from, with `funding.enabled`
+    #[serde(default)]
+    pub creator_funding: Option<CreatorFunding>,
+    /// Largest holder accounts frozen by the freeze authority, from the authority monitor
+    #[serde(default)]
+    pub frozen_holders: Option<u32>,
+    /// A simulated sell went through (`honeypot.enabled`); `None` when not simulated
+    #[serde(default)]
+    pub sellable: Option<bool>,
+    /// Share of the volume of the first `wash.window_secs` that went back and
+    /// forth between the same or related wallets, 0-100 (`wash.enabled`)
+    #[serde(default)]
+    pub wash_score: Option<BigDecimal>,
+    /// Off-chain metadata JSON (name, image, socials) from the creation
+    #[serde(default)]
+    pub metadata_uri: Option<String>,
+    /// Social links of the metadata, checked after publishing (`socials.enabled`)
+    #[serde(default)]
+    pub socials: Vec<SocialLink>,
+    /// Well-known or recently graduated token this one copies, as "Name (SYMBOL)"
+    #[serde(default)]
+    pub impersonation_of: Option<String>,
+    /// The creator is on the rugger blacklist (`blacklist.enabled`)
+    #[serde(default)]
+    pub creator_blacklisted: bool,
+    /// Several wallets bought in the creation transaction or slot, see [`LaunchSnapshot`]
+    #[serde(default)]
+    pub bundled: bool,
+    /// Wallets other than the creator that bought within `launch.window_slots`,
+    /// set once that window has passed
+    #[serde(default)]
+    pub sniper_count: Option<u32>,
+    /// Published from a `processed` notification, a `TokenConfirmed` or `TokenRetracted` follows
+    #[serde(default)]
+    pub provisional: bool,
+    /// Rolling trade activity, refreshed every `volume.interval_secs` (`volume.enabled`)
+    #[serde(default)]
+    pub activity: Option<TradeActivity>,
+    /// rugcheck.xyz report, fetched shortly after discovery (`rugcheck.enabled`)
+    #[serde(default)]
+    pub rugcheck: Option<RugCheckReport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RugCheckReport {
+    /// RugCheck's own score, normalised to 0-100 where higher is riskier
+    pub score: Option<u32>,
+    pub risks: Vec<RugCheckRisk>,
+    /// The report as RugCheck returned it
+    pub raw: serde_json::Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RugCheckRisk {
+    pub name: String,
+    /// `danger`, `warn` or `info`
+    pub level: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+impl RugCheckReport {
+    /// Risks at `level`
+    pub fn count(&self, level: &str) -> usize {
+        self.risks.iter().filter(|risk| risk.level == level).count()
+    }
+}
+
+/// Trades of a token over the last minute and the last five minutes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TradeActivity {
+    pub volume_1m_sol: BigDecimal,
+    pub volume_5m_sol: BigDecimal,
+    /// Distinct buying wallets
+    pub buyers_1m: u32,
+    pub buyers_5m: u32,
+    pub buys_5m: u32,
+    pub sells_5m: u32,
+    /// `buys_5m / sells_5m`, `None` without sells
+    pub buy_sell_ratio_5m: Option<BigDecimal>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SocialLink {
+    pub kind: SocialKind,
+    pub url: String,
+    /// Answered with a real page: not an error, a parked domain or an unknown Telegram name
+    pub live: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SocialKind {
+    Twitter,
+    Telegram,
+    Website,
 }
 
+/// The last SOL transfer into a token creator's wallet before the launch
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatorFunding {
+    pub source: FundingSource,
+    /// Sender of the transfer, `None` if none was found in the scanned history
+    pub funder: Option<String>,
+    pub lamports: u64,
+    pub funded_at: Option<DateTime<Utc>>,
+    /// The wallet's whole history is recent (`funding.fresh_wallet_hours`)
+    pub fresh_wallet: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FundingSource {
+    Cex,
+    Bridge,
+    /// A wallet that launched tokens itself
+    Deployer,
+    Unknown,
+}
+
+impl fmt::Display for FundingSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                FundingSource::Cex => "cex",
+                FundingSource::Bridge => "bridge",
+                FundingSource::Deployer => "deployer",
+                FundingSource::Unknown => "unknown",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum RiskLevel {
     Low,
@@ -70,6 +239,11 @@ pub enum TokenSource {
 
     Pumpfun,
 
+    /// Raydium LaunchLab, the program behind letsbonk.fun
+    LaunchLab,
+
+    /// Meteora dynamic bonding curve launches
+    Meteora,
 
     OnChain,
 }
@@ -82,6 +256,8 @@ impl fmt::Display for TokenSource {
                 TokenSource::OnChain => "Onchain",
 
                 TokenSource::Pumpfun => "Pumpfun",
+                TokenSource::LaunchLab => "LaunchLab",
+                TokenSource::Meteora => "Meteora",
 
             }
         )
@@ -94,6 +270,8 @@ impl FromStr for TokenSource {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
              "pumpfun" => Ok(TokenSource::Pumpfun),
+            "launchlab" | "launch-lab" | "letsbonk" => Ok(TokenSource::LaunchLab),
+            "meteora" => Ok(TokenSource::Meteora),
 
             "onchain" | "on-chain" => Ok(TokenSource::OnChain),
             _ => Err(TokenSourceParseError::InvalidSource(s.to_string())),
@@ -111,5 +289,425 @@ pub enum TokenSourceParseError {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Event {
     TokenDiscovered(Token),
-    
+    /// A previously discovered token gained new data, e.g. a DEX pool
+    TokenUpdated(Token),
+    /// A tracked token left its bonding curve for the AMM pool `pool`
+    TokenGraduated {
+        token: Token,
+        pool: String,
+    },
+    /// A trade of a tracked token (`events.trades`)
+    TradeExecuted(Trade),
+    /// Periodic state of a listener (`events.heartbeat_secs`), so consumers
+    /// notice a stale feed
+    ListenerStatus(ListenerHeartbeat),
+    /// The creation transaction of a provisional token reached the confirmation commitment
+    TokenConfirmed(Token),
+    /// The creation transaction of a provisional token failed or was dropped
+    TokenRetracted { token: Token, reason: String },
+    /// Every buy in the first slots of a launch (`launch.snapshot_event`)
+    LaunchSnapshot(LaunchSnapshot),
+    /// A token moved to the next stage of its life (`lifecycle.enabled`)
+    TokenStateChanged(TokenStateChange),
+    /// A bonding curve token stopped trading (`abandoned.enabled`) and is no longer tracked;
+    /// `last_trade_at` is `None` if it never traded after discovery
+    TokenAbandoned {
+        token: Token,
+        last_trade_at: Option<DateTime<Utc>>,
+    },
+    /// A closed OHLCV period of a tracked token (`candles.enabled`)
+    Candle(Candle),
+    /// A bonding curve token reached a king-of-the-hill threshold (`trending.enabled`)
+    KingOfTheHill(Token),
+    /// A tracked token reached `ingestion.min_tx_count_for_active_pair` trades
+    ActivePair { token: Token, tx_count: u32 },
+    /// A watched wallet bought or sold a pump.fun token (`watchlist.enabled`)
+    WatchedWalletTrade(WalletTrade),
+    /// A watched wallet created a pump.fun token (`watchlist.enabled`)
+    WatchedWalletLaunch(WalletLaunch),
+    /// The execution module bought a token (`execution.enabled`)
+    PositionOpened(Position),
+    /// The execution module sold a position
+    PositionClosed(ClosedPosition),
+}
+
+/// Heartbeat of one listener's subscription
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListenerHeartbeat {
+    /// Listener name, e.g. `pumpfun`
+    pub source: String,
+    /// Newest slot the listener handled
+    pub last_slot: Option<u64>,
+    /// Milliseconds since its last notification, or since the subscription
+    /// started if none arrived yet
+    pub lag_ms: i64,
+    pub connected: bool,
+}
+
+/// Stages of a token's life, in order; `Abandoned` and `Rugged` end it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenState {
+    Discovered,
+    /// Trading on its bonding curve
+    Bonding,
+    /// The bonding curve completed
+    Graduated,
+    /// Trading in an AMM pool
+    PoolListed,
+    Abandoned,
+    Rugged,
+}
+
+impl TokenState {
+    fn rank(self) -> u8 {
+        match self {
+            TokenState::Discovered => 0,
+            TokenState::Bonding => 1,
+            TokenState::Graduated => 2,
+            TokenState::PoolListed => 3,
+            TokenState::Abandoned | TokenState::Rugged => 4,
+        }
+    }
+
+    pub fn is_final(self) -> bool {
+        matches!(self, TokenState::Abandoned | TokenState::Rugged)
+    }
+
+    /// Stages may be skipped (a missed curve completion) but never revisited
+    pub fn can_become(self, next: TokenState) -> bool {
+        !self.is_final() && next.rank() > self.rank()
+    }
+}
+
+impl fmt::Display for TokenState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                TokenState::Discovered => "discovered",
+                TokenState::Bonding => "bonding",
+                TokenState::Graduated => "graduated",
+                TokenState::PoolListed => "pool_listed",
+                TokenState::Abandoned => "abandoned",
+                TokenState::Rugged => "rugged",
+            }
+        )
+    }
+}
+
+impl FromStr for TokenState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "discovered" => Ok(TokenState::Discovered),
+            "bonding" => Ok(TokenState::Bonding),
+            "graduated" => Ok(TokenState::Graduated),
+            "pool_listed" => Ok(TokenState::PoolListed),
+            "abandoned" => Ok(TokenState::Abandoned),
+            "rugged" => Ok(TokenState::Rugged),
+            _ => Err(format!("Unknown token state {:?}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenStateChange {
+    pub mint: String,
+    /// `None` for a token entering the lifecycle
+    pub from: Option<TokenState>,
+    pub to: TokenState,
+    pub at: DateTime<Utc>,
+}
+
+/// A buy or sell of a token, as decoded from its program's logs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trade {
+    pub mint: String,
+    pub wallet: String,
+    /// Lamports paid or received
+    pub sol_amount: u64,
+    /// Raw token amount bought or sold
+    pub token_amount: u64,
+    pub is_buy: bool,
+    /// Block time
+    pub at: DateTime<Utc>,
+    /// Aggregator or bot program the trade went through, by its
+    /// `trade_attribution.routers` name or else its program id
+    #[serde(default)]
+    pub router: Option<String>,
+}
+
+/// A trade by a wallet of `watchlist.wallets`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletTrade {
+    pub label: Option<String>,
+    pub signature: String,
+    pub trade: Trade,
+}
+
+/// A pump.fun token created by a wallet of `watchlist.wallets`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletLaunch {
+    pub wallet: String,
+    pub label: Option<String>,
+    pub signature: String,
+    pub mint: String,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+/// A token bought by the execution module (`execution.enabled`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Position {
+    pub mint: String,
+    pub symbol: Option<String>,
+    /// `None` for a dry-run buy
+    pub signature: Option<String>,
+    pub dry_run: bool,
+    /// Raw token amount bought
+    pub token_amount: u64,
+    pub decimals: u8,
+    /// SOL spent
+    pub cost_sol: BigDecimal,
+    /// SOL paid per whole token
+    pub entry_price_sol: BigDecimal,
+    pub opened_at: DateTime<Utc>,
+}
+
+/// Why a position was sold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitReason {
+    TakeProfit,
+    StopLoss,
+    /// The price fell `positions.trailing_stop_percentage` below its peak
+    TrailingStop,
+    MaxHold,
+}
+
+impl fmt::Display for ExitReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ExitReason::TakeProfit => "take_profit",
+            ExitReason::StopLoss => "stop_loss",
+            ExitReason::TrailingStop => "trailing_stop",
+            ExitReason::MaxHold => "max_hold",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A position sold by the execution module
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClosedPosition {
+    #[serde(flatten)]
+    pub position: Position,
+    pub exit: ExitReason,
+    /// `None` for a dry-run sell
+    pub sell_signature: Option<String>,
+    /// The trade price that triggered the exit
+    pub exit_price_sol: BigDecimal,
+    /// SOL the sell was quoted to return, before fees
+    pub proceeds_sol: BigDecimal,
+    pub pnl_sol: BigDecimal,
+    pub pnl_percentage: BigDecimal,
+    pub closed_at: DateTime<Utc>,
+}
+
+/// Open, high, low and close prices and volume of a token over `interval_secs`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Candle {
+    pub mint: String,
+    pub interval_secs: u64,
+    pub open_time: DateTime<Utc>,
+    /// Prices in SOL per whole token
+    pub open: BigDecimal,
+    pub high: BigDecimal,
+    pub low: BigDecimal,
+    pub close: BigDecimal,
+    pub volume_sol: BigDecimal,
+    /// Raw token amount traded
+    pub volume_tokens: u128,
+    pub buys: u32,
+    pub sells: u32,
+}
+
+/// The buyers of a token right after its creation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchSnapshot {
+    pub mint: String,
+    pub creator: Option<String>,
+    pub creation_slot: u64,
+    pub window_slots: u64,
+    /// In slot order; the creator's own buys included
+    pub buys: Vec<LaunchBuy>,
+    pub sniper_count: u32,
+    pub bundled: bool,
+    /// Distinct wallets of `buys`, in the order they first bought
+    #[serde(default)]
+    pub buyers: Vec<LaunchBuyer>,
+    /// Most buyers funded by one wallet, with `funding.enabled`
+    #[serde(default)]
+    pub same_funder_buyers: Option<u32>,
+    /// The creator's wallet still holds some of the token, `None` if it could not be read
+    #[serde(default)]
+    pub dev_holds: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchBuyer {
+    pub wallet: String,
+    /// Raw token amount bought over `buys`
+    pub token_amount: u64,
+    /// Share of the supply in the wallet's token account when the snapshot was
+    /// taken, `None` if it could not be read
+    #[serde(default)]
+    pub holding_percentage: Option<BigDecimal>,
+    /// Last wallet that sent it SOL, with `funding.enabled`
+    #[serde(default)]
+    pub funder: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchBuy {
+    pub wallet: String,
+    pub slot: u64,
+    /// Lamports paid
+    pub sol_amount: u64,
+    /// Raw token amount received
+    pub token_amount: u64,
+    /// `sol_amount` in USD, with `[price]` enabled
+    #[serde(default)]
+    pub usd_amount: Option<BigDecimal>,
+}
+
+/// Version of the published JSON, bumped on incompatible changes to `Envelope` or `Event`
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// What sinks publish: an event with what consumers need to order and deduplicate it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Envelope {
+    pub version: u32,
+    /// 1, 2, 3... across everything published, see [`crate::envelope::Sequencer`]
+    pub seq: u64,
+    /// Slot and signature of the transaction the event came from, `None` for
+    /// events of background jobs (refreshes, sweeps, candles...)
+    pub slot: Option<u64>,
+    pub signature: Option<String>,
+    pub emitted_at: DateTime<Utc>,
+    pub payload: Event,
+}
+
+impl Envelope {
+    pub fn event_type(&self) -> &'static str {
+        self.payload.event_type()
+    }
+}
+
+impl Event {
+    /// Stable snake_case name used for routing and as the stream `type` field
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            Event::TokenDiscovered(_) => "token_discovered",
+            Event::TokenUpdated(_) => "token_updated",
+            Event::TokenGraduated { .. } => "token_graduated",
+            Event::TradeExecuted(_) => "trade_executed",
+            Event::ListenerStatus(_) => "listener_status",
+            Event::TokenConfirmed(_) => "token_confirmed",
+            Event::TokenRetracted { .. } => "token_retracted",
+            Event::LaunchSnapshot(_) => "launch_snapshot",
+            Event::TokenStateChanged(_) => "token_state_changed",
+            Event::TokenAbandoned { .. } => "token_abandoned",
+            Event::Candle(_) => "candle",
+            Event::KingOfTheHill(_) => "king_of_the_hill",
+            Event::ActivePair { .. } => "active_pair",
+            Event::WatchedWalletTrade(_) => "watched_wallet_trade",
+            Event::WatchedWalletLaunch(_) => "watched_wallet_launch",
+            Event::PositionOpened(_) => "position_opened",
+            Event::PositionClosed(_) => "position_closed",
+        }
+    }
+
+    /// Mint of the token the event is about
+    pub fn mint(&self) -> &str {
+        match self {
+            Event::LaunchSnapshot(snapshot) => &snapshot.mint,
+            Event::TokenStateChanged(change) => &change.mint,
+            Event::Candle(candle) => &candle.mint,
+            Event::TradeExecuted(trade) => &trade.mint,
+            Event::WatchedWalletTrade(trade) => &trade.trade.mint,
+            Event::WatchedWalletLaunch(launch) => &launch.mint,
+            Event::PositionOpened(position) => &position.mint,
+            Event::PositionClosed(closed) => &closed.position.mint,
+            event => event
+                .token()
+                .map(|token| token.mint_address.as_str())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The token the event carries in full
+    pub fn token(&self) -> Option<&Token> {
+        match self {
+            Event::TokenDiscovered(token)
+            | Event::TokenUpdated(token)
+            | Event::TokenGraduated { token, .. }
+            | Event::TokenConfirmed(token)
+            | Event::TokenRetracted { token, .. }
+            | Event::TokenAbandoned { token, .. }
+            | Event::KingOfTheHill(token)
+            | Event::ActivePair { token, .. } => Some(token),
+            Event::TradeExecuted(_)
+            | Event::ListenerStatus(_)
+            | Event::LaunchSnapshot(_)
+            | Event::TokenStateChanged(_)
+            | Event::Candle(_)
+            | Event::WatchedWalletTrade(_)
+            | Event::WatchedWalletLaunch(_)
+            | Event::PositionOpened(_)
+            | Event::PositionClosed(_) => None,
+        }
+    }
+
+    /// Launchpad of the token the event is about, `None` when the event only
+    /// carries a mint
+    pub fn source(&self) -> Option<TokenSource> {
+        match self {
+            // Trades, launch snapshots, watched wallets and positions only follow pump.fun
+            Event::TradeExecuted(_)
+            | Event::LaunchSnapshot(_)
+            | Event::WatchedWalletTrade(_)
+            | Event::WatchedWalletLaunch(_)
+            | Event::PositionOpened(_)
+            | Event::PositionClosed(_) => Some(TokenSource::Pumpfun),
+            event => event.token().map(|token| token.source.clone()),
+        }
+    }
+
+    /// Discovery time of the token the event is about, used for latency metrics
+    pub fn discovered_at(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Event::TokenDiscovered(token) => Some(token.discovered_at),
+            Event::TokenUpdated(_)
+            | Event::TokenGraduated { .. }
+            | Event::TradeExecuted(_)
+            | Event::ListenerStatus(_)
+            | Event::TokenConfirmed(_)
+            | Event::TokenRetracted { .. }
+            | Event::LaunchSnapshot(_)
+            | Event::TokenStateChanged(_)
+            | Event::TokenAbandoned { .. }
+            | Event::Candle(_)
+            | Event::KingOfTheHill(_)
+            | Event::ActivePair { .. }
+            | Event::WatchedWalletTrade(_)
+            | Event::WatchedWalletLaunch(_)
+            | Event::PositionOpened(_)
+            | Event::PositionClosed(_) => None,
+        }
+    }
 }
\ No newline at end of file
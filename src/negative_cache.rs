@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use moka::future::Cache;
+
+use crate::config::NegativeCacheConfig;
+use crate::metrics;
+
+/// Keys of a lookup that recently failed or found nothing, skipped until
+/// `negative_cache.ttl_secs` have passed so dead tokens are not asked about
+/// again and again
+pub struct NegativeCache {
+    lookup: &'static str,
+    /// `None` with `ttl_secs = 0`
+    misses: Option<Cache<String, ()>>,
+}
+
+impl NegativeCache {
+    pub fn new(lookup: &'static str, config: &NegativeCacheConfig) -> Self {
+        let misses = (config.ttl_secs > 0).then(|| {
+            Cache::builder()
+                .max_capacity(config.max_entries)
+                .time_to_live(Duration::from_secs(config.ttl_secs))
+                .build()
+        });
+        Self { lookup, misses }
+    }
+
+    /// Whether `key` missed within the TTL
+    pub fn contains(&self, key: &str) -> bool {
+        let Some(misses) = &self.misses else {
+            return false;
+        };
+        let hit = misses.contains_key(key);
+        if hit {
+            metrics::NEGATIVE_CACHE_HITS
+                .with_label_values(&[self.lookup])
+                .inc();
+        }
+        hit
+    }
+
+    /// Remember that `key` failed or found nothing
+    pub async fn insert(&self, key: &str) {
+        if let Some(misses) = &self.misses {
+            misses.insert(key.to_string(), ()).await;
+        }
+    }
+}
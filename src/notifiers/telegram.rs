@@ -0,0 +1,136 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use reqwest::Client;
+use serde_json::json;
+
+use crate::config::TelegramConfig;
+use crate::models::Token;
+use crate::outbound;
+use crate::retry::{self, CircuitBreaker, RetryPolicy};
+use crate::token_helper::is_transient_http_error;
+
+const TELEGRAM_API: &str = "https://api.telegram.org";
+
+/// Sends token alerts to a Telegram chat through the bot API
+pub struct TelegramNotifier {
+    config: TelegramConfig,
+    client: Client,
+    breaker: CircuitBreaker,
+}
+
+impl TelegramNotifier {
+    pub fn new(config: TelegramConfig) -> Result<Self> {
+        let client = outbound::client_builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .context("Failed to build Telegram HTTP client")?;
+
+        Ok(Self {
+            config,
+            client,
+            breaker: CircuitBreaker::new("Telegram", 5, Duration::from_secs(300)),
+        })
+    }
+
+    /// Whether the token passes the configured alert thresholds
+    pub fn matches(&self, token: &Token) -> bool {
+        let thresholds = &self.config;
+
+        if let Some(min_score) = thresholds.min_score {
+            if token.score.unwrap_or(0) < min_score {
+                return false;
+            }
+        }
+        if thresholds.require_mint_authority_disabled && !token.mint_authority_disabled {
+            return false;
+        }
+        if thresholds.require_freeze_authority_disabled && !token.freeze_authority_disabled {
+            return false;
+        }
+
+        true
+    }
+
+    pub async fn notify(&self, token: &Token) -> Result<()> {
+        if !self.breaker.is_closed(Instant::now()) {
+            bail!("Telegram circuit open, skipping alert for {}", token.mint_address);
+        }
+
+        let url = format!("{}/bot{}/sendMessage", TELEGRAM_API, self.config.bot_token);
+        let body = json!({
+            "chat_id": self.config.chat_id,
+            "text": format_message(token),
+            "parse_mode": "HTML",
+            "disable_web_page_preview": true,
+        });
+
+        let result = retry::retry(
+            &RetryPolicy::default(),
+            "Telegram sendMessage",
+            is_transient_http_error,
+            // The URL carries the bot token, keep it out of logs
+            || async {
+                self.client
+                    .post(&url)
+                    .json(&body)
+                    .send()
+                    .await
+                    .and_then(reqwest::Response::error_for_status)
+                    .map_err(reqwest::Error::without_url)
+            },
+        )
+        .await;
+
+        match result {
+            Ok(_) => {
+                self.breaker.record_success();
+                Ok(())
+            }
+            Err(e) => {
+                if is_transient_http_error(&e) {
+                    self.breaker.record_failure();
+                }
+                Err(e).context("Telegram rejected the message")
+            }
+        }
+    }
+}
+
+fn format_message(token: &Token) -> String {
+    let name = escape_html(token.name.as_deref().unwrap_or("Unknown"));
+    let symbol = escape_html(token.symbol.as_deref().unwrap_or("UNK"));
+    let mint = &token.mint_address;
+
+    let score = token
+        .score
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| "n/a".to_string());
+    let risk = token
+        .risk_level
+        .as_ref()
+        .map(|r| r.to_string())
+        .unwrap_or_else(|| "n/a".to_string());
+
+    let flag = |disabled: bool| if disabled { "disabled ✅" } else { "enabled ⚠️" };
+
+    format!(
+        "🚀 <b>{name}</b> (${symbol}) on {source}\n\
+         Mint: <code>{mint}</code>\n\
+         Score: <b>{score}</b> ({risk} risk)\n\
+         Mint authority: {mint_auth}\n\
+         Freeze authority: {freeze_auth}\n\n\
+         <a href=\"https://pump.fun/coin/{mint}\">pump.fun</a> | \
+         <a href=\"https://dexscreener.com/solana/{mint}\">DexScreener</a> | \
+         <a href=\"https://solscan.io/token/{mint}\">Solscan</a>",
+        source = token.source,
+        mint_auth = flag(token.mint_authority_disabled),
+        freeze_auth = flag(token.freeze_authority_disabled),
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
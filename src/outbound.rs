@@ -0,0 +1,101 @@
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result};
+use tracing::info;
+
+use crate::config::OutboundConfig;
+
+/// `[outbound]` as read at startup
+#[derive(Default)]
+struct Outbound {
+    proxy_url: Option<String>,
+    no_proxy: Option<String>,
+    /// PEM certificates trusted on top of the built-in roots
+    ca_bundle: Vec<u8>,
+}
+
+static OUTBOUND: OnceLock<Outbound> = OnceLock::new();
+
+/// Apply `[outbound]` to every HTTP client built from now on; only the first
+/// call counts
+pub fn init(config: &OutboundConfig) -> Result<()> {
+    let ca_bundle = match &config.ca_bundle {
+        Some(path) => {
+            let pem = std::fs::read(path)
+                .with_context(|| format!("Failed to read outbound.ca_bundle {}", path))?;
+            let certs = reqwest::Certificate::from_pem_bundle(&pem)
+                .with_context(|| format!("Invalid outbound.ca_bundle {}", path))?;
+            info!(
+                "Trusting {} extra CA certificate(s) from {}",
+                certs.len(),
+                path
+            );
+            pem
+        }
+        None => Vec::new(),
+    };
+    if let Some(url) = &config.proxy_url {
+        reqwest::Proxy::all(url).context("Invalid outbound.proxy_url")?;
+        info!("Outbound HTTP goes through a proxy");
+    }
+
+    let _ = OUTBOUND.set(Outbound {
+        proxy_url: config.proxy_url.clone(),
+        no_proxy: config.no_proxy.clone(),
+        ca_bundle,
+    });
+    Ok(())
+}
+
+fn outbound() -> &'static Outbound {
+    OUTBOUND.get_or_init(Outbound::default)
+}
+
+/// `reqwest::Client::builder()` with the proxy and CA bundle of `[outbound]`
+pub fn client_builder() -> reqwest::ClientBuilder {
+    let outbound = outbound();
+    let mut builder = reqwest::Client::builder();
+    // Both were parsed by `init`
+    if let Some(proxy) = outbound
+        .proxy_url
+        .as_ref()
+        .and_then(|url| reqwest::Proxy::all(url).ok())
+    {
+        let no_proxy = outbound
+            .no_proxy
+            .as_deref()
+            .and_then(reqwest::NoProxy::from_string);
+        builder = builder.proxy(proxy.no_proxy(no_proxy));
+    }
+    for cert in reqwest::Certificate::from_pem_bundle(&outbound.ca_bundle).unwrap_or_default() {
+        builder = builder.add_root_certificate(cert);
+    }
+    builder
+}
+
+/// The same for the HTTP client of Solana RPC requests, which uses a newer reqwest
+pub fn rpc_client_builder() -> reqwest_rpc::ClientBuilder {
+    let outbound = outbound();
+    let mut builder = reqwest_rpc::Client::builder();
+    if let Some(proxy) = outbound
+        .proxy_url
+        .as_ref()
+        .and_then(|url| reqwest_rpc::Proxy::all(url).ok())
+    {
+        let no_proxy = outbound
+            .no_proxy
+            .as_deref()
+            .and_then(reqwest_rpc::NoProxy::from_string);
+        builder = builder.proxy(proxy.no_proxy(no_proxy));
+    }
+    for cert in reqwest_rpc::Certificate::from_pem_bundle(&outbound.ca_bundle).unwrap_or_default() {
+        builder = builder.add_root_certificate(cert);
+    }
+    builder
+}
+
+/// Whether RPC clients need a client of their own instead of solana-client's default
+pub fn is_configured() -> bool {
+    let outbound = outbound();
+    outbound.proxy_url.is_some() || !outbound.ca_bundle.is_empty()
+}
@@ -0,0 +1,335 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bigdecimal::{BigDecimal, FromPrimitive, ToPrimitive, Zero};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, info};
+
+use crate::config::PaperTradeConfig;
+use crate::metrics;
+use crate::models::Token;
+
+/// Digits kept in reported SOL amounts
+const SOL_DIGITS: i64 = 9;
+/// Digits kept in reported percentages
+const PERCENTAGE_DIGITS: i64 = 2;
+/// Closed positions listed in the report, newest first
+const RECENT_CLOSED: usize = 100;
+
+/// Simulated buys of `papertrade.buy_sol` into every token passing the filters,
+/// valued at the price of each trade on its bonding curve and sold on
+/// take-profit, stop-loss or after `max_hold_secs`
+pub struct PaperTrader {
+    buy_sol: BigDecimal,
+    /// Share left after a fee, `1 - fee_percentage / 100`
+    after_fee: BigDecimal,
+    take_profit: BigDecimal,
+    stop_loss: BigDecimal,
+    max_hold: chrono::Duration,
+    max_open: usize,
+    book: Mutex<Book>,
+}
+
+#[derive(Default)]
+struct Book {
+    open: HashMap<String, Position>,
+    closed: VecDeque<ClosedPosition>,
+    wins: u64,
+    losses: u64,
+    realized_pnl_sol: BigDecimal,
+}
+
+struct Position {
+    symbol: Option<String>,
+    opened_at: DateTime<Utc>,
+    /// The token's price when published, else the first trade's
+    entry_price_sol: Option<BigDecimal>,
+    price_sol: Option<BigDecimal>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Exit {
+    TakeProfit,
+    StopLoss,
+    MaxHold,
+}
+
+impl Exit {
+    fn as_str(self) -> &'static str {
+        match self {
+            Exit::TakeProfit => "take_profit",
+            Exit::StopLoss => "stop_loss",
+            Exit::MaxHold => "max_hold",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenPosition {
+    pub mint: String,
+    pub symbol: Option<String>,
+    pub opened_at: DateTime<Utc>,
+    pub entry_price_sol: Option<BigDecimal>,
+    pub price_sol: Option<BigDecimal>,
+    pub pnl_sol: Option<BigDecimal>,
+    pub pnl_percentage: Option<BigDecimal>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClosedPosition {
+    pub mint: String,
+    pub symbol: Option<String>,
+    pub opened_at: DateTime<Utc>,
+    pub closed_at: DateTime<Utc>,
+    pub entry_price_sol: BigDecimal,
+    pub exit_price_sol: BigDecimal,
+    pub exit: Exit,
+    pub pnl_sol: BigDecimal,
+    pub pnl_percentage: BigDecimal,
+}
+
+/// What `GET /papertrade` returns
+#[derive(Debug, Clone, Serialize)]
+pub struct PaperReport {
+    pub buy_sol: BigDecimal,
+    pub open: Vec<OpenPosition>,
+    pub closed: Vec<ClosedPosition>,
+    pub wins: u64,
+    pub losses: u64,
+    pub win_rate_percentage: Option<BigDecimal>,
+    pub realized_pnl_sol: BigDecimal,
+    pub unrealized_pnl_sol: BigDecimal,
+}
+
+impl PaperTrader {
+    /// Build the trader and start closing expired positions and logging the
+    /// summary every `report_interval_secs`
+    pub fn spawn(config: &PaperTradeConfig) -> Arc<Self> {
+        let decimal = |value: f64| BigDecimal::from_f64(value).unwrap_or_default();
+        let trader = Arc::new(Self {
+            buy_sol: decimal(config.buy_sol),
+            after_fee: BigDecimal::from(1) - decimal(config.fee_percentage) / 100,
+            take_profit: decimal(config.take_profit_percentage),
+            stop_loss: -decimal(config.stop_loss_percentage),
+            max_hold: chrono::Duration::seconds(config.max_hold_secs as i64),
+            max_open: config.max_open_positions,
+            book: Mutex::new(Book::default()),
+        });
+        info!(
+            "Paper trading {} SOL per token, take profit {}%, stop loss {}%",
+            config.buy_sol, config.take_profit_percentage, config.stop_loss_percentage
+        );
+
+        let reporter = trader.clone();
+        let interval = Duration::from_secs(config.report_interval_secs.max(1));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            // The first tick fires right away, nothing bought yet
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                reporter.close_expired(Utc::now());
+                reporter.log_summary();
+            }
+        });
+
+        trader
+    }
+
+    /// Buy `token`, a no-op if it is already held or too many positions are open
+    pub fn open(&self, token: &Token) {
+        let mut book = self.book.lock().expect("papertrade lock poisoned");
+        if book.open.contains_key(&token.mint_address) {
+            return;
+        }
+        if book.open.len() >= self.max_open {
+            debug!(
+                "{} paper positions open, not buying {}",
+                book.open.len(),
+                token.mint_address
+            );
+            return;
+        }
+        let price = token.price_sol.clone().filter(|price| !price.is_zero());
+        book.open.insert(
+            token.mint_address.clone(),
+            Position {
+                symbol: token.symbol.clone(),
+                opened_at: Utc::now(),
+                entry_price_sol: price.clone(),
+                price_sol: price,
+            },
+        );
+    }
+
+    /// Forget the position in a token that turned out not to exist
+    pub fn cancel(&self, mint: &str) {
+        let mut book = self.book.lock().expect("papertrade lock poisoned");
+        book.open.remove(mint);
+    }
+
+    /// `mint` just traded at `price_sol`; sell if that hits take-profit or stop-loss
+    pub fn on_price(&self, mint: &str, price_sol: &BigDecimal) {
+        if price_sol.is_zero() {
+            return;
+        }
+        let mut book = self.book.lock().expect("papertrade lock poisoned");
+        let Some(position) = book.open.get_mut(mint) else {
+            return;
+        };
+        let entry = position
+            .entry_price_sol
+            .get_or_insert_with(|| price_sol.clone())
+            .clone();
+        position.price_sol = Some(price_sol.clone());
+
+        let pnl_percentage = self.pnl_percentage(&entry, price_sol);
+        let exit = if pnl_percentage >= self.take_profit {
+            Exit::TakeProfit
+        } else if pnl_percentage <= self.stop_loss {
+            Exit::StopLoss
+        } else {
+            return;
+        };
+        self.close(&mut book, mint, exit);
+    }
+
+    /// Sell positions held longer than `max_hold_secs` at their last price
+    fn close_expired(&self, now: DateTime<Utc>) {
+        let mut book = self.book.lock().expect("papertrade lock poisoned");
+        let expired: Vec<String> = book
+            .open
+            .iter()
+            .filter(|(_, position)| now - position.opened_at >= self.max_hold)
+            .map(|(mint, _)| mint.clone())
+            .collect();
+        for mint in expired {
+            self.close(&mut book, &mint, Exit::MaxHold);
+        }
+    }
+
+    fn close(&self, book: &mut Book, mint: &str, exit: Exit) {
+        let Some(position) = book.open.remove(mint) else {
+            return;
+        };
+        let (Some(entry), Some(price)) = (position.entry_price_sol, position.price_sol) else {
+            debug!("Paper position in {} never got a price, dropping it", mint);
+            return;
+        };
+
+        let pnl_percentage = self.pnl_percentage(&entry, &price);
+        let pnl_sol = self.pnl_sol(&pnl_percentage);
+        if pnl_sol > BigDecimal::zero() {
+            book.wins += 1;
+        } else {
+            book.losses += 1;
+        }
+        book.realized_pnl_sol += &pnl_sol;
+        metrics::PAPER_TRADES
+            .with_label_values(&[exit.as_str()])
+            .inc();
+        info!(
+            "Paper sold {} ({}) on {}: {} SOL ({}%)",
+            position.symbol.as_deref().unwrap_or("UNKNOWN"),
+            mint,
+            exit.as_str(),
+            pnl_sol,
+            pnl_percentage.round(PERCENTAGE_DIGITS)
+        );
+
+        book.closed.push_front(ClosedPosition {
+            mint: mint.to_string(),
+            symbol: position.symbol,
+            opened_at: position.opened_at,
+            closed_at: Utc::now(),
+            entry_price_sol: entry,
+            exit_price_sol: price,
+            exit,
+            pnl_sol,
+            pnl_percentage: pnl_percentage.round(PERCENTAGE_DIGITS),
+        });
+        book.closed.truncate(RECENT_CLOSED);
+    }
+
+    /// Change in value from buying at `entry` to selling at `price`, fees included
+    fn pnl_percentage(&self, entry: &BigDecimal, price: &BigDecimal) -> BigDecimal {
+        (&self.after_fee * &self.after_fee * price / entry - 1) * 100
+    }
+
+    fn pnl_sol(&self, pnl_percentage: &BigDecimal) -> BigDecimal {
+        let pnl: BigDecimal = &self.buy_sol * pnl_percentage;
+        (pnl / BigDecimal::from(100)).round(SOL_DIGITS)
+    }
+
+    pub fn report(&self) -> PaperReport {
+        let book = self.book.lock().expect("papertrade lock poisoned");
+        let mut unrealized_pnl_sol = BigDecimal::zero();
+        let mut open: Vec<OpenPosition> = book
+            .open
+            .iter()
+            .map(|(mint, position)| {
+                let pnl_percentage = match (&position.entry_price_sol, &position.price_sol) {
+                    (Some(entry), Some(price)) => Some(self.pnl_percentage(entry, price)),
+                    _ => None,
+                };
+                let pnl_sol = pnl_percentage.as_ref().map(|pnl| self.pnl_sol(pnl));
+                if let Some(pnl_sol) = &pnl_sol {
+                    unrealized_pnl_sol += pnl_sol;
+                }
+                OpenPosition {
+                    mint: mint.clone(),
+                    symbol: position.symbol.clone(),
+                    opened_at: position.opened_at,
+                    entry_price_sol: position.entry_price_sol.clone(),
+                    price_sol: position.price_sol.clone(),
+                    pnl_sol,
+                    pnl_percentage: pnl_percentage.map(|pnl| pnl.round(PERCENTAGE_DIGITS)),
+                }
+            })
+            .collect();
+        open.sort_by_key(|position| position.opened_at);
+
+        let trades = book.wins + book.losses;
+        PaperReport {
+            buy_sol: self.buy_sol.clone(),
+            open,
+            closed: book.closed.iter().cloned().collect(),
+            wins: book.wins,
+            losses: book.losses,
+            win_rate_percentage: (trades > 0).then(|| {
+                (BigDecimal::from(book.wins * 100) / BigDecimal::from(trades))
+                    .round(PERCENTAGE_DIGITS)
+            }),
+            realized_pnl_sol: book.realized_pnl_sol.clone(),
+            unrealized_pnl_sol,
+        }
+    }
+
+    fn log_summary(&self) {
+        let report = self.report();
+        metrics::PAPER_PNL_SOL
+            .with_label_values(&["realized"])
+            .set(to_f64(&report.realized_pnl_sol));
+        metrics::PAPER_PNL_SOL
+            .with_label_values(&["unrealized"])
+            .set(to_f64(&report.unrealized_pnl_sol));
+        info!(
+            "Paper trading: {} open, {} won / {} lost ({:?}%), realized {} SOL, unrealized {} SOL",
+            report.open.len(),
+            report.wins,
+            report.losses,
+            report.win_rate_percentage,
+            report.realized_pnl_sol,
+            report.unrealized_pnl_sol
+        );
+    }
+}
+
+fn to_f64(value: &BigDecimal) -> f64 {
+    value.to_f64().unwrap_or_default()
+}
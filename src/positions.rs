@@ -0,0 +1,324 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use bigdecimal::{BigDecimal, FromPrimitive, ToPrimitive, Zero};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::time::MissedTickBehavior;
+use tracing::{info, info_span, Instrument};
+
+use crate::config::PositionsConfig;
+use crate::execution::{Fill, Sale};
+use crate::metrics;
+use crate::models::{ClosedPosition, ExitReason, Position, Token, Trade};
+use crate::processor::Processor;
+
+const LAMPORTS_DECIMALS: i64 = 9;
+/// Significant digits kept in prices
+const PRICE_DIGITS: u64 = 10;
+/// Digits kept in reported percentages
+const PERCENTAGE_DIGITS: i64 = 2;
+
+/// Positions bought by the execution module, valued at the price of each trade
+/// on their bonding curve and sold on take-profit, stop-loss, trailing stop or
+/// after `max_hold_secs`
+pub struct Positions {
+    take_profit: BigDecimal,
+    stop_loss: BigDecimal,
+    trailing_stop: Option<BigDecimal>,
+    max_hold: Option<chrono::Duration>,
+    book: Mutex<Book>,
+}
+
+#[derive(Default)]
+struct Book {
+    open: HashMap<String, Held>,
+    wins: u64,
+    losses: u64,
+    realized_pnl_sol: BigDecimal,
+}
+
+struct Held {
+    position: Position,
+    price_sol: BigDecimal,
+    peak_price_sol: BigDecimal,
+    /// A sell is on its way, see [`Positions::unsold`]
+    selling: bool,
+}
+
+/// A position to sell
+#[derive(Debug, Clone)]
+pub struct Exit {
+    pub position: Position,
+    pub reason: ExitReason,
+    pub price_sol: BigDecimal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenPosition {
+    #[serde(flatten)]
+    pub position: Position,
+    pub price_sol: BigDecimal,
+    pub peak_price_sol: BigDecimal,
+    pub value_sol: BigDecimal,
+    pub pnl_sol: BigDecimal,
+    pub pnl_percentage: BigDecimal,
+}
+
+/// What `GET /positions` returns
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionsReport {
+    pub open: Vec<OpenPosition>,
+    pub wins: u64,
+    pub losses: u64,
+    pub realized_pnl_sol: BigDecimal,
+    pub unrealized_pnl_sol: BigDecimal,
+}
+
+impl Positions {
+    pub fn new(config: &PositionsConfig) -> Self {
+        let decimal = |value: f64| BigDecimal::from_f64(value).unwrap_or_default();
+        Self {
+            take_profit: decimal(config.take_profit_percentage),
+            stop_loss: -decimal(config.stop_loss_percentage),
+            trailing_stop: config.trailing_stop_percentage.map(decimal),
+            max_hold: (config.max_hold_secs > 0)
+                .then(|| chrono::Duration::seconds(config.max_hold_secs as i64)),
+            book: Mutex::new(Book::default()),
+        }
+    }
+
+    /// Hold what `fill` bought of `token`
+    pub fn open(&self, token: &Token, fill: &Fill) -> Position {
+        let cost_sol = BigDecimal::new(fill.cost_lamports.into(), LAMPORTS_DECIMALS);
+        let entry_price_sol = price(&cost_sol, fill.token_amount, token.decimals);
+        let position = Position {
+            mint: token.mint_address.clone(),
+            symbol: token.symbol.clone(),
+            signature: fill.signature.clone(),
+            dry_run: fill.signature.is_none(),
+            token_amount: fill.token_amount,
+            decimals: token.decimals,
+            cost_sol,
+            entry_price_sol: entry_price_sol.clone(),
+            opened_at: Utc::now(),
+        };
+        let mut book = self.book.lock().expect("positions lock poisoned");
+        book.open.insert(
+            position.mint.clone(),
+            Held {
+                position: position.clone(),
+                price_sol: entry_price_sol.clone(),
+                peak_price_sol: entry_price_sol,
+                selling: false,
+            },
+        );
+        metrics::POSITIONS_OPEN.set(book.open.len() as i64);
+        position
+    }
+
+    /// Reprice the position in the traded token, if any; the exit to take if
+    /// that triggers one
+    pub fn on_trade(&self, trade: &Trade) -> Option<Exit> {
+        if trade.token_amount == 0 {
+            return None;
+        }
+        let mut book = self.book.lock().expect("positions lock poisoned");
+        let held = book.open.get_mut(&trade.mint)?;
+        let volume_sol = BigDecimal::new(trade.sol_amount.into(), LAMPORTS_DECIMALS);
+        let price_sol = price(&volume_sol, trade.token_amount, held.position.decimals);
+        if price_sol.is_zero() {
+            return None;
+        }
+        if price_sol > held.peak_price_sol {
+            held.peak_price_sol = price_sol.clone();
+        }
+        held.price_sol = price_sol;
+        self.take_exit(held, Utc::now())
+    }
+
+    /// Positions held past `max_hold_secs`, or whose earlier sell failed
+    pub fn due(&self, now: DateTime<Utc>) -> Vec<Exit> {
+        let mut book = self.book.lock().expect("positions lock poisoned");
+        book.open
+            .values_mut()
+            .filter_map(|held| self.take_exit(held, now))
+            .collect()
+    }
+
+    /// The exit `held` is due for, marking it as being sold
+    fn take_exit(&self, held: &mut Held, now: DateTime<Utc>) -> Option<Exit> {
+        if held.selling {
+            return None;
+        }
+        let pnl_percentage = percentage(&held.position.entry_price_sol, &held.price_sol);
+        let reason =
+            if pnl_percentage >= self.take_profit {
+                ExitReason::TakeProfit
+            } else if pnl_percentage <= self.stop_loss {
+                ExitReason::StopLoss
+            } else if self.trailing_stop.as_ref().is_some_and(|trailing| {
+                -percentage(&held.peak_price_sol, &held.price_sol) >= *trailing
+            }) {
+                ExitReason::TrailingStop
+            } else if self
+                .max_hold
+                .is_some_and(|max_hold| now - held.position.opened_at >= max_hold)
+            {
+                ExitReason::MaxHold
+            } else {
+                return None;
+            };
+        held.selling = true;
+        Some(Exit {
+            position: held.position.clone(),
+            reason,
+            price_sol: held.price_sol.clone(),
+        })
+    }
+
+    /// `exit` went through as `sale`
+    pub fn sold(&self, exit: Exit, sale: &Sale) -> ClosedPosition {
+        let proceeds_sol = BigDecimal::new(sale.proceeds_lamports.into(), LAMPORTS_DECIMALS);
+        let pnl_sol = &proceeds_sol - &exit.position.cost_sol;
+        let pnl_percentage = if exit.position.cost_sol.is_zero() {
+            BigDecimal::zero()
+        } else {
+            (&pnl_sol * BigDecimal::from(100) / &exit.position.cost_sol).round(PERCENTAGE_DIGITS)
+        };
+
+        let mut book = self.book.lock().expect("positions lock poisoned");
+        book.open.remove(&exit.position.mint);
+        if pnl_sol > BigDecimal::zero() {
+            book.wins += 1;
+        } else {
+            book.losses += 1;
+        }
+        book.realized_pnl_sol += &pnl_sol;
+        metrics::POSITIONS_OPEN.set(book.open.len() as i64);
+        metrics::POSITIONS_CLOSED
+            .with_label_values(&[&exit.reason.to_string()])
+            .inc();
+
+        ClosedPosition {
+            position: exit.position,
+            exit: exit.reason,
+            sell_signature: sale.signature.clone(),
+            exit_price_sol: exit.price_sol,
+            proceeds_sol,
+            pnl_sol,
+            pnl_percentage,
+            closed_at: Utc::now(),
+        }
+    }
+
+    /// The sell of `mint` failed, try again at the next trade or check
+    pub fn unsold(&self, mint: &str) {
+        let mut book = self.book.lock().expect("positions lock poisoned");
+        if let Some(held) = book.open.get_mut(mint) {
+            held.selling = false;
+        }
+    }
+
+    pub fn report(&self) -> PositionsReport {
+        let book = self.book.lock().expect("positions lock poisoned");
+        let mut unrealized_pnl_sol = BigDecimal::zero();
+        let mut open: Vec<OpenPosition> = book
+            .open
+            .values()
+            .map(|held| {
+                let position = &held.position;
+                let value_sol = (&held.price_sol
+                    * BigDecimal::new(position.token_amount.into(), i64::from(position.decimals)))
+                .round(LAMPORTS_DECIMALS);
+                let pnl_sol = &value_sol - &position.cost_sol;
+                unrealized_pnl_sol += &pnl_sol;
+                OpenPosition {
+                    position: position.clone(),
+                    price_sol: held.price_sol.clone(),
+                    peak_price_sol: held.peak_price_sol.clone(),
+                    value_sol,
+                    pnl_sol,
+                    pnl_percentage: percentage(&position.entry_price_sol, &held.price_sol)
+                        .round(PERCENTAGE_DIGITS),
+                }
+            })
+            .collect();
+        open.sort_by_key(|open| open.position.opened_at);
+
+        PositionsReport {
+            open,
+            wins: book.wins,
+            losses: book.losses,
+            realized_pnl_sol: book.realized_pnl_sol.clone(),
+            unrealized_pnl_sol,
+        }
+    }
+
+    pub fn log_summary(&self) {
+        let report = self.report();
+        metrics::POSITION_PNL_SOL
+            .with_label_values(&["realized"])
+            .set(report.realized_pnl_sol.to_f64().unwrap_or_default());
+        metrics::POSITION_PNL_SOL
+            .with_label_values(&["unrealized"])
+            .set(report.unrealized_pnl_sol.to_f64().unwrap_or_default());
+        info!(
+            "Positions: {} open, {} won / {} lost, realized {} SOL, unrealized {} SOL",
+            report.open.len(),
+            report.wins,
+            report.losses,
+            report.realized_pnl_sol,
+            report.unrealized_pnl_sol
+        );
+    }
+}
+
+/// SOL per whole token
+fn price(sol: &BigDecimal, token_amount: u64, decimals: u8) -> BigDecimal {
+    if token_amount == 0 {
+        return BigDecimal::zero();
+    }
+    (sol / BigDecimal::new(token_amount.into(), i64::from(decimals))).with_prec(PRICE_DIGITS)
+}
+
+/// Change from `from` to `to`, in percent
+fn percentage(from: &BigDecimal, to: &BigDecimal) -> BigDecimal {
+    if from.is_zero() {
+        return BigDecimal::zero();
+    }
+    (to / from - 1) * 100
+}
+
+/// Sells positions held past `positions.max_hold_secs`, retries failed sells
+/// and logs the PnL summary every `report_interval_secs`
+pub struct PositionMonitor {
+    processor: Processor,
+    interval: Duration,
+}
+
+impl PositionMonitor {
+    /// Start the monitor task
+    pub fn spawn(processor: Processor, config: &PositionsConfig) {
+        let monitor = Self {
+            processor,
+            interval: Duration::from_secs(config.report_interval_secs.max(1)),
+        };
+        tokio::spawn(monitor.run());
+    }
+
+    async fn run(self) {
+        let mut ticker = tokio::time::interval(self.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // The first tick fires right away, nothing is held yet
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            self.processor
+                .check_positions()
+                .instrument(info_span!("positions"))
+                .await;
+        }
+    }
+}
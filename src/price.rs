@@ -0,0 +1,82 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use bigdecimal::{BigDecimal, FromPrimitive};
+use reqwest::Client;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+use crate::config::PriceConfig;
+use crate::outbound;
+
+const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+/// A price older than this is dropped when it cannot be refreshed
+const MAX_STALE: Duration = Duration::from_secs(600);
+
+/// SOL/USD from the Jupiter price API, fetched at most once per `cache_secs`
+/// however many tokens ask for it
+pub struct SolPriceFeed {
+    client: Client,
+    url: String,
+    cache_for: Duration,
+    last: Mutex<Option<(Instant, BigDecimal)>>,
+}
+
+impl SolPriceFeed {
+    pub fn new(config: &PriceConfig) -> Result<Self> {
+        let client = outbound::client_builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .context("Failed to build the price feed HTTP client")?;
+        Ok(Self {
+            client,
+            url: config.url.clone(),
+            cache_for: Duration::from_secs(config.cache_secs),
+            last: Mutex::new(None),
+        })
+    }
+
+    /// The current price, or the last one seen within `MAX_STALE` when the API fails
+    pub async fn sol_usd(&self) -> Option<BigDecimal> {
+        // Held while fetching, so concurrent callers wait for one request
+        let mut last = self.last.lock().await;
+        if let Some((at, price)) = last.as_ref() {
+            if at.elapsed() < self.cache_for {
+                return Some(price.clone());
+            }
+        }
+
+        match self.fetch().await {
+            Ok(price) => {
+                debug!("SOL/USD {}", price);
+                *last = Some((Instant::now(), price.clone()));
+                Some(price)
+            }
+            Err(e) => {
+                warn!("SOL/USD price unavailable: {:#}", e);
+                last.as_ref()
+                    .filter(|(at, _)| at.elapsed() < MAX_STALE)
+                    .map(|(_, price)| price.clone())
+            }
+        }
+    }
+
+    async fn fetch(&self) -> Result<BigDecimal> {
+        let body: serde_json::Value = self
+            .client
+            .get(&self.url)
+            .query(&[("ids", SOL_MINT)])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("Price response is not JSON")?;
+        let price = body
+            .get(SOL_MINT)
+            .and_then(|sol| sol.get("usdPrice"))
+            .and_then(|price| price.as_f64())
+            .context("No usdPrice for SOL in the price response")?;
+        BigDecimal::from_f64(price).context("Invalid SOL price")
+    }
+}
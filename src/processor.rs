@@ -1,62 +1,1165 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 use anyhow::Result;
+use moka::future::Cache;
+use chrono::{DateTime, Utc};
 
- use tracing::{info, error};
-use crate::config::Config;
-use crate::models::{Event, Token};
+use tracing::{debug, info, info_span, warn, Instrument, Span};
+use crate::config::{Config, FilterConfig};
+use crate::blacklist::CreatorBlacklist;
+use crate::filters::TokenFilter;
+use crate::funding::FundingTracer;
+use crate::honeypot::SellSimulator;
+use crate::liquidity::LpChecker;
+use crate::impersonation::ImpersonationDetector;
+use crate::socials::SocialChecker;
+use crate::price::SolPriceFeed;
+use crate::birdeye::Birdeye;
+use crate::rugcheck::RugCheck;
+use crate::token_cache::TokenInfoCache;
+use crate::token_helper::{self, TokenInfo};
+use bigdecimal::{BigDecimal, ToPrimitive};
+use crate::notifiers::telegram::TelegramNotifier;
+use crate::dry_run::DryRunReporter;
+use crate::sinks::{self, EventSink, SinkFanout};
+use crate::{metrics, scoring};
+use crate::aggregator::CandleAggregator;
+use crate::broadcast::Broadcast;
+use crate::clickhouse::ClickHouseWriter;
+use crate::envelope::Sequencer;
+use crate::leader::LeaderElection;
+use crate::sharding::Shards;
+use crate::lifecycle::Lifecycle;
+use crate::trending::{Mover, Trending};
+use crate::papertrade::{PaperReport, PaperTrader};
+use crate::execution::{Fill, Sniper};
+use crate::positions::{Exit, Positions, PositionsReport};
+use crate::volume::VolumeTracker;
+use crate::wash::WashDetector;
+use crate::models::{
+    Candle, ClosedPosition, Event, LaunchSnapshot, ListenerHeartbeat, Token, TokenState, Trade,
+    WalletLaunch, WalletTrade,
+};
+use solana_sdk::pubkey::Pubkey;
+
+/// Significant digits kept in USD prices
+const PRICE_DIGITS: u64 = 10;
+const LAMPORTS_DECIMALS: i64 = 9;
 
 #[derive(Clone)]
 pub struct Processor {
-config: Config
+    sinks: SinkFanout,
+    sequencer: Arc<Sequencer>,
+    /// Swapped as a whole when the config file changes, see [`Processor::reload`]
+    rules: Arc<RwLock<Arc<Rules>>>,
+    /// Recently published tokens, keyed by mint, so pool listeners can link and update them
+    tracked: Cache<String, Token>,
+    /// Latest trade and trade count of each tracked token, see [`Processor::process_trade`]
+    trade_stats: Cache<String, TradeStats>,
+    min_tx_count: u32,
+    /// Publish `TradeExecuted` for trades of tracked tokens (`events.trades`)
+    publish_trades: bool,
+    /// Traces creator funding before scoring, see [`Processor::with_funding`]
+    funding: Option<Arc<FundingTracer>>,
+    blacklist: Option<Arc<CreatorBlacklist>>,
+    lifecycle: Option<Arc<Lifecycle>>,
+    /// Checks LP burns / locks after graduation, see [`Processor::check_lp`]
+    lp: Option<Arc<LpChecker>>,
+    /// Simulates a sell of suspicious tokens before scoring, see [`Processor::with_honeypot`]
+    honeypot: Option<Arc<SellSimulator>>,
+    /// Analyses the early trades of tracked tokens, see [`Processor::with_wash`]
+    wash: Option<Arc<WashDetector>>,
+    impersonation: Arc<ImpersonationDetector>,
+    socials: Option<Arc<SocialChecker>>,
+    price: Option<Arc<SolPriceFeed>>,
+    /// Looks tokens up once they are a few minutes old, see [`Processor::spawn_birdeye_lookup`]
+    birdeye: Option<Arc<Birdeye>>,
+    rugcheck: Option<Arc<RugCheck>>,
+    /// Metadata of published mints, see [`Processor::token_info`]
+    token_cache: Option<Arc<TokenInfoCache>>,
+    candles: Option<Arc<CandleAggregator>>,
+    volume: Option<Arc<VolumeTracker>>,
+    trending: Option<Arc<Trending>>,
+    clickhouse: Option<Arc<ClickHouseWriter>>,
+    papertrade: Option<Arc<PaperTrader>>,
+    /// Buys tokens scoring high enough, see [`Processor::with_sniper`]
+    sniper: Option<Arc<Sniper>>,
+    /// What the sniper bought, sold on the exits of `[positions]`
+    positions: Option<Arc<Positions>>,
+    broadcast: Broadcast,
+    /// Only publish while holding the lease, see [`Processor::with_leader`]
+    leader: Option<Arc<LeaderElection>>,
+    /// Which mints this instance enriches, see [`Processor::owns_shard`]
+    shards: Option<Arc<Shards>>,
+}
+
+#[derive(Clone)]
+struct TradeStats {
+    last_trade_at: DateTime<Utc>,
+    tx_count: u32,
+}
+
+/// The parts of the config that can change while running
+struct Rules {
+    filters: FilterConfig,
+    filter: TokenFilter,
+    telegram: Option<Arc<TelegramNotifier>>,
+    /// Report alerts instead of sending them, see [`Config::enable_dry_run`]
+    dry_run: bool,
+}
+
+impl Rules {
+    fn new(config: &Config, dry_run: bool) -> Result<Self> {
+        let telegram = match &config.notifiers.telegram {
+            Some(telegram) if telegram.enabled => {
+                info!("Telegram alerts enabled for chat {}", telegram.chat_id);
+                Some(Arc::new(TelegramNotifier::new(telegram.clone())?))
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            filters: config.filters.clone(),
+            filter: TokenFilter::new(&config.filters)?,
+            telegram,
+            dry_run,
+        })
+    }
 }
 
 impl Processor {
-    pub fn new(config: Config) -> Self {
-        Self {config  }
+    pub async fn new(config: Config) -> Result<Self> {
+        let sinks = sinks::build_sinks(&config).await?;
+        if sinks.is_empty() {
+            anyhow::bail!("No event sinks configured, add at least one [[sinks]] entry");
+        }
+        let sequencer = Sequencer::new(&config.sequence, &config.database).await?;
+        Ok(Self::with_sinks(&config, sinks)?.with_sequencer(sequencer))
+    }
+
+    /// Publish to `sinks` instead of the ones configured in `[[sinks]]`
+    pub fn with_sinks(config: &Config, sinks: Vec<Arc<dyn EventSink>>) -> Result<Self> {
+        let tracked = Cache::builder()
+            .max_capacity(config.ingestion.tracked_token_max_entries)
+            .time_to_live(Duration::from_secs(config.ingestion.tracked_token_ttl_secs))
+            .build();
+        let trade_stats = Cache::builder()
+            .max_capacity(config.ingestion.tracked_token_max_entries)
+            .time_to_live(Duration::from_secs(config.ingestion.tracked_token_ttl_secs))
+            .build();
+
+        let socials = if config.socials.enabled {
+            Some(Arc::new(SocialChecker::new(
+                &config.socials,
+                &config.negative_cache,
+            )?))
+        } else {
+            None
+        };
+
+        let price = if config.price.enabled {
+            Some(Arc::new(SolPriceFeed::new(&config.price)?))
+        } else {
+            None
+        };
+
+        // Birdeye only stands in for the on-chain liquidity reads
+        let birdeye = match (config.birdeye.enabled, config.liquidity.refresh) {
+            (true, false) => Some(Arc::new(Birdeye::new(
+                &config.birdeye,
+                &config.negative_cache,
+            )?)),
+            (true, true) => {
+                info!("Birdeye enrichment off, liquidity.refresh reads liquidity on-chain");
+                None
+            }
+            _ => None,
+        };
+
+        let rugcheck = if config.rugcheck.enabled {
+            Some(Arc::new(RugCheck::new(
+                &config.rugcheck,
+                &config.negative_cache,
+            )?))
+        } else {
+            None
+        };
+
+        let candles = config
+            .candles
+            .enabled
+            .then(|| Arc::new(CandleAggregator::new(&config.candles)));
+
+        let volume = config
+            .volume
+            .enabled
+            .then(|| Arc::new(VolumeTracker::new()));
+
+        let trending = config
+            .trending
+            .enabled
+            .then(|| Arc::new(Trending::new(&config.trending)));
+
+        let clickhouse = if config.clickhouse.enabled {
+            Some(ClickHouseWriter::spawn(&config.clickhouse)?)
+        } else {
+            None
+        };
+
+        let papertrade = config
+            .papertrade
+            .enabled
+            .then(|| PaperTrader::spawn(&config.papertrade));
+        let positions = config
+            .execution
+            .enabled
+            .then(|| Arc::new(Positions::new(&config.positions)));
+
+        Ok(Self {
+            sinks: SinkFanout::spawn(sinks, &config.sink_options),
+            sequencer: Arc::new(Sequencer::in_memory()),
+            rules: Arc::new(RwLock::new(Arc::new(Rules::new(config, config.dry_run)?))),
+            tracked,
+            trade_stats,
+            min_tx_count: config.ingestion.min_tx_count_for_active_pair,
+            publish_trades: config.events.trades,
+            funding: None,
+            blacklist: None,
+            lifecycle: None,
+            honeypot: None,
+            wash: None,
+            lp: None,
+            impersonation: Arc::new(ImpersonationDetector::new(&config.impersonation)),
+            socials,
+            price,
+            birdeye,
+            rugcheck,
+            token_cache: None,
+            candles,
+            volume,
+            trending,
+            clickhouse,
+            papertrade,
+            sniper: None,
+            positions,
+            broadcast: Broadcast::new(config.grpc.buffer),
+            leader: None,
+            shards: None,
+        })
+    }
+
+    /// Number events with `sequencer`, in memory when built with [`Processor::with_sinks`]
+    pub fn with_sequencer(mut self, sequencer: Sequencer) -> Self {
+        self.sequencer = Arc::new(sequencer);
+        self
     }
 
+    /// Look every creator up in `blacklist` before scoring, and let listeners flag ruggers
+    pub fn with_blacklist(mut self, blacklist: Option<Arc<CreatorBlacklist>>) -> Self {
+        self.blacklist = blacklist;
+        self
+    }
+
+    pub fn blacklist(&self) -> Option<Arc<CreatorBlacklist>> {
+        self.blacklist.clone()
+    }
+
+    /// Follow published tokens through their lifecycle, publishing `TokenStateChanged`
+    pub fn with_lifecycle(mut self, lifecycle: Option<Arc<Lifecycle>>) -> Self {
+        self.lifecycle = lifecycle;
+        self
+    }
+
+    pub fn lifecycle(&self) -> Option<Arc<Lifecycle>> {
+        self.lifecycle.clone()
+    }
+
+    /// Keep processing on a standby instance but publish, alert and record
+    /// trades only while `leader` holds the lease
+    pub fn with_leader(mut self, leader: Option<Arc<LeaderElection>>) -> Self {
+        self.leader = leader;
+        self
+    }
+
+    fn is_leader(&self) -> bool {
+        self.leader.as_ref().is_none_or(|leader| leader.is_leader())
+    }
+
+    /// Share enrichment with other instances through `shards`
+    pub fn with_shards(mut self, shards: Option<Arc<Shards>>) -> Self {
+        self.shards = shards;
+        self
+    }
+
+    pub fn is_sharded(&self) -> bool {
+        self.shards.is_some()
+    }
+
+    /// Whether this instance handles the logs of `key` (a mint, or a signature
+    /// when the mint is only known after fetching); always without sharding
+    pub fn owns_shard(&self, key: &str) -> bool {
+        self.shards.as_ref().is_none_or(|shards| shards.owns(key))
+    }
+
+    /// Trace where each creator's SOL came from before scoring, when `tracer` is set
+    pub fn with_funding(mut self, tracer: Option<Arc<FundingTracer>>) -> Self {
+        self.funding = tracer;
+        self
+    }
 
-    pub async fn process_token_discovered(&self, token: Token) -> Result<()> {
+    /// Check that suspicious tokens can be sold before scoring, when `simulator` is set
+    pub fn with_honeypot(mut self, simulator: Option<Arc<SellSimulator>>) -> Self {
+        self.honeypot = simulator;
+        self
+    }
 
+    /// Rescore tracked tokens once their early trades are analysed, when `detector` is set
+    pub fn with_wash(mut self, detector: Option<Arc<WashDetector>>) -> Self {
+        self.wash = detector;
+        self
+    }
 
+    /// Check the LP tokens of the pools tokens graduate to, when `checker` is set
+    pub fn with_lp_checker(mut self, checker: Option<Arc<LpChecker>>) -> Self {
+        self.lp = checker;
+        self
+    }
 
+    /// Buy tokens passing the filters with `execution.min_score`, on the leader
+    /// only, when `sniper` is set
+    pub fn with_sniper(mut self, sniper: Option<Arc<Sniper>>) -> Self {
+        self.sniper = sniper;
+        self
+    }
+
+    /// Remember the metadata of published tokens in `cache`, when set
+    pub fn with_token_cache(mut self, cache: Option<Arc<TokenInfoCache>>) -> Self {
+        self.token_cache = cache;
+        self
+    }
+
+    /// Name, symbol and metadata URI of `mint`, from the token cache when it was
+    /// published before, else looked up for `listener`
+    pub async fn token_info(&self, listener: &str, mint: &str) -> Result<TokenInfo> {
+        if let Some(cache) = &self.token_cache {
+            if let Some(info) = cache.get(mint).await {
+                return Ok(info);
+            }
+        }
+        token_helper::fetch_token_info(listener, mint, "solana").await
+    }
+
+    /// Apply new `[filters]` and `[notifiers]` settings to every clone of this processor.
+    /// On error the current rules stay in place.
+    pub fn reload(&self, config: &Config) -> Result<()> {
+        // Whether this is a dry run was settled at startup
+        let rules = Arc::new(Rules::new(config, self.rules().dry_run)?);
+        *self.rules.write().unwrap_or_else(|e| e.into_inner()) = rules;
+        Ok(())
+    }
+
+    /// The `[filters]` in effect
+    pub fn filters(&self) -> FilterConfig {
+        self.rules().filters.clone()
+    }
+
+    /// Replace the `[filters]` of every clone of this processor, until the next reload.
+    /// On error the current filters stay in place.
+    pub fn set_filters(&self, filters: FilterConfig) -> Result<()> {
+        let filter = TokenFilter::new(&filters)?;
+        let mut rules = self.rules.write().unwrap_or_else(|e| e.into_inner());
+        *rules = Arc::new(Rules {
+            filters,
+            filter,
+            telegram: rules.telegram.clone(),
+            dry_run: rules.dry_run,
+        });
+        Ok(())
+    }
+
+    fn rules(&self) -> Arc<Rules> {
+        self.rules.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+
+    pub async fn process_token_discovered(&self, mut token: Token) -> Result<()> {
+        Span::current().record("mint", token.mint_address.as_str());
+        if let (Some(funding), Some(creator)) = (&self.funding, &token.creator) {
+            token.creator_funding = funding.trace(creator).await;
+        }
+        if let (Some(blacklist), Some(creator)) = (&self.blacklist, &token.creator) {
+            token.creator_blacklisted = blacklist.contains(creator).await;
+        }
+        if let Some(honeypot) = &self.honeypot {
+            if SellSimulator::is_suspicious(&token) {
+                token.sellable = honeypot.sellable(&token).await;
+            }
+        }
+        token.impersonation_of = self.impersonation.check(&token);
+        self.price_in_usd(&mut token).await;
+        scoring::apply(&mut token);
 
         info!(
-            "New token discovered: {} ({}) from {:?}",
+            "New token discovered: {} ({}) from {:?}, score {:?} ({:?})",
             token.symbol.as_deref().unwrap_or("UNKNOWN"),
             token.mint_address,
-            token.source
+            token.source,
+            token.score,
+            token.risk_level
         );
 
 
+        metrics::TOKENS_DISCOVERED
+            .with_label_values(&[&token.source.to_string()])
+            .inc();
+
+        let rules = self.rules();
+        if let Some(reason) = rules.filter.check(&token) {
+            info!("Token {} filtered out: {}", token.mint_address, reason);
+            metrics::TOKENS_FILTERED.with_label_values(&[reason]).inc();
+            return Ok(());
+        }
+
+        if self.is_leader() {
+            Self::notify(&rules, &token);
+            self.snipe(&token);
+        }
+        if let Some(papertrade) = &self.papertrade {
+            papertrade.open(&token);
+        }
+
+        self.tracked
+            .insert(token.mint_address.clone(), token.clone())
+            .await;
+        if let Some(cache) = &self.token_cache {
+            cache.insert(&token).await;
+        }
+
+        let metadata_uri = token.metadata_uri.clone();
+        let mint = token.mint_address.clone();
+
+        let crowned = self.crown(&mut token);
+
         // Publish event
-        self.publish_event(Event::TokenDiscovered(token)).await?;
+        self.publish_event(Event::TokenDiscovered(token.clone())).await?;
+        self.transition(&mint, TokenState::Discovered).await?;
+        if crowned {
+            self.publish_king_of_the_hill(token).await?;
+        }
+
+        if let Some(birdeye) = &self.birdeye {
+            self.spawn_birdeye_lookup(birdeye.clone(), mint.clone());
+        }
+        if let Some(rugcheck) = &self.rugcheck {
+            self.spawn_rugcheck_lookup(rugcheck.clone(), mint.clone());
+        }
+        if let (Some(socials), Some(uri)) = (&self.socials, metadata_uri) {
+            self.spawn_social_check(socials.clone(), mint, uri);
+        }
 
         Ok(())
     }
-    pub async fn process_token_graduated(
-        &self,
-        token_address: String,
-        pair_address: String,
-    ) -> Result<()> {
-        // Update database with DEX pair information
-        info!("Token {} graduated to pair {}", token_address, pair_address);
-        // Your database update logic here
+
+    /// Check the metadata's social links off the publishing path, then rescore
+    /// the token and publish it again with `socials` filled in
+    fn spawn_social_check(&self, checker: Arc<SocialChecker>, mint: String, uri: String) {
+        let processor = self.clone();
+        let span = info_span!("socials", mint = %mint);
+        tokio::spawn(
+            async move {
+                let links = match checker.verify(&uri).await {
+                    Ok(links) if !links.is_empty() => links,
+                    Ok(_) => return,
+                    Err(e) => {
+                        warn!("Social links of {} unavailable: {:#}", mint, e);
+                        return;
+                    }
+                };
+                let Some(mut token) = processor.tracked_token(&mint).await else {
+                    return;
+                };
+                token.socials = links;
+                scoring::apply(&mut token);
+                if let Err(e) = processor.process_token_updated(token).await {
+                    warn!("Failed to publish social links of {}: {:#}", mint, e);
+                }
+            }
+            .instrument(span),
+        );
+    }
+
+    /// Fill price, liquidity and holders of a token from Birdeye once it is
+    /// `birdeye.delay_secs` old, then rescore it and publish it again
+    fn spawn_birdeye_lookup(&self, birdeye: Arc<Birdeye>, mint: String) {
+        let processor = self.clone();
+        let span = info_span!("birdeye", mint = %mint);
+        tokio::spawn(
+            async move {
+                tokio::time::sleep(birdeye.delay()).await;
+                let overview = match birdeye.overview(&mint).await {
+                    Ok(Some(overview)) => overview,
+                    Ok(None) => return,
+                    Err(e) => {
+                        warn!("Birdeye lookup of {} failed: {:#}", mint, e);
+                        return;
+                    }
+                };
+                let Some(mut token) = processor.tracked_token(&mint).await else {
+                    return;
+                };
+                let sol_usd = match &processor.price {
+                    Some(feed) => feed.sol_usd().await,
+                    None => None,
+                };
+                overview.apply(&mut token, sol_usd.as_ref());
+                scoring::apply(&mut token);
+                if let Err(e) = processor.process_token_updated(token).await {
+                    warn!("Failed to publish Birdeye data of {}: {:#}", mint, e);
+                }
+            }
+            .instrument(span),
+        );
+    }
+
+    /// Attach the RugCheck report of a token once RugCheck had `rugcheck.delay_secs`
+    /// to index it, then rescore it and publish it again
+    fn spawn_rugcheck_lookup(&self, rugcheck: Arc<RugCheck>, mint: String) {
+        let processor = self.clone();
+        let span = info_span!("rugcheck", mint = %mint);
+        tokio::spawn(
+            async move {
+                tokio::time::sleep(rugcheck.delay()).await;
+                let report = match rugcheck.report(&mint).await {
+                    Ok(Some(report)) => report,
+                    Ok(None) => return,
+                    Err(e) => {
+                        warn!("RugCheck report of {} unavailable: {:#}", mint, e);
+                        return;
+                    }
+                };
+                let Some(mut token) = processor.tracked_token(&mint).await else {
+                    return;
+                };
+                info!(
+                    "RugCheck report of {}: score {:?}, {} dangers, {} warnings",
+                    mint,
+                    report.score,
+                    report.count("danger"),
+                    report.count("warn")
+                );
+                token.rugcheck = Some(report);
+                scoring::apply(&mut token);
+                if let Err(e) = processor.process_token_updated(token).await {
+                    warn!("Failed to publish RugCheck report of {}: {:#}", mint, e);
+                }
+            }
+            .instrument(span),
+        );
+    }
+
+    /// Measure the wash trading among the early trades of a token off the trade
+    /// path, then rescore it and publish it again with `wash_score`
+    fn spawn_wash_analysis(&self, detector: Arc<WashDetector>, mint: String, trades: Vec<Trade>) {
+        let processor = self.clone();
+        let span = info_span!("wash", mint = %mint);
+        tokio::spawn(
+            async move {
+                let report = detector.analyze(&trades).await;
+                metrics::WASH_SCORE.observe(report.score.to_f64().unwrap_or(0.0));
+                let Some(mut token) = processor.tracked_token(&mint).await else {
+                    return;
+                };
+                info!(
+                    "Wash score of {}: {}% of {} SOL over {} trades, {} self-traders, {} related groups",
+                    mint,
+                    report.score,
+                    BigDecimal::new(report.volume_lamports.into(), LAMPORTS_DECIMALS),
+                    report.trades,
+                    report.self_traders,
+                    report.related_groups
+                );
+                token.wash_score = Some(report.score);
+                scoring::apply(&mut token);
+                if let Err(e) = processor.process_token_updated(token).await {
+                    warn!("Failed to publish wash score of {}: {:#}", mint, e);
+                }
+            }
+            .instrument(span),
+        );
+    }
+
+    /// A published token still within the tracking window
+    pub async fn tracked_token(&self, mint: &str) -> Option<Token> {
+        self.tracked.get(mint).await
+    }
+
+    /// Every published token still within the tracking window
+    pub fn tracked_tokens(&self) -> Vec<Token> {
+        self.tracked.iter().map(|(_, token)| token).collect()
+    }
+
+    /// Publish new data for a tracked token and remember it as the latest state
+    pub async fn process_token_updated(&self, mut token: Token) -> Result<()> {
+        Span::current().record("mint", token.mint_address.as_str());
+        self.price_in_usd(&mut token).await;
+        info!("Token updated: {}", token.mint_address);
+        let crowned = self.crown(&mut token);
+
+        self.tracked
+            .insert(token.mint_address.clone(), token.clone())
+            .await;
+
+        self.publish_event(Event::TokenUpdated(token.clone())).await?;
+        if crowned {
+            self.publish_king_of_the_hill(token).await?;
+        }
         Ok(())
     }
 
+    /// Record the token's price for the top movers, and set `king_of_the_hill_at`
+    /// if it just reached a threshold
+    fn crown(&self, token: &mut Token) -> bool {
+        let Some(trending) = &self.trending else {
+            return false;
+        };
+        trending.record(token);
+        if token.king_of_the_hill_at.is_some() || !trending.is_king_of_the_hill(token) {
+            return false;
+        }
+        token.king_of_the_hill_at = Some(Utc::now());
+        true
+    }
 
-    async fn publish_event(&self, event: Event) -> Result<()> {
-        // Publish to Redis pub/sub for other services to consume
-        let client = redis::Client::open(self.config.database.redis_url.clone())?;
-        let mut conn = client.get_async_connection().await?;
-
-        let event_json = serde_json::to_string(&event)?;
-        let _: () = redis::cmd("PUBLISH")
-            .arg("events")
-            .arg(event_json)
-            .query_async(&mut conn)
+    async fn publish_king_of_the_hill(&self, token: Token) -> Result<()> {
+        info!(
+            "Token {} is king of the hill: curve {:?}%, market cap {:?} USD",
+            token.mint_address, token.bonding_curve_progress, token.market_cap_usd
+        );
+        metrics::KINGS_OF_THE_HILL
+            .with_label_values(&[&token.source.to_string()])
+            .inc();
+        self.publish_event(Event::KingOfTheHill(token)).await
+    }
+
+    /// Tracked tokens whose price moved the most, `None` unless `trending.enabled`
+    pub fn top_movers(&self) -> Option<Vec<Mover>> {
+        let trending = self.trending.as_ref()?;
+        Some(trending.top_movers(&self.tracked_tokens()))
+    }
+
+    /// A provisional token's creation transaction is now confirmed
+    pub async fn process_token_confirmed(&self, mut token: Token) -> Result<()> {
+        info!("Token confirmed: {}", token.mint_address);
+        token.provisional = false;
+
+        self.tracked
+            .insert(token.mint_address.clone(), token.clone())
+            .await;
+
+        self.publish_event(Event::TokenConfirmed(token)).await
+    }
+
+    /// A provisional token's creation never landed, consumers should discard it
+    pub async fn process_token_retracted(&self, token: Token, reason: &str) -> Result<()> {
+        warn!("Token {} retracted: {}", token.mint_address, reason);
+        self.tracked.invalidate(&token.mint_address).await;
+        if let Some(papertrade) = &self.papertrade {
+            papertrade.cancel(&token.mint_address);
+        }
+
+        self.publish_event(Event::TokenRetracted {
+            token,
+            reason: reason.to_string(),
+        })
+        .await
+    }
+
+    /// A decoded trade, written to ClickHouse if enabled. A trade of a tracked
+    /// token also means it is not abandoned, and goes into its rolling activity,
+    /// candles and paper position
+    pub async fn process_trade(&self, trade: &Trade) -> Result<()> {
+        if self.is_leader() {
+            if let Some(clickhouse) = &self.clickhouse {
+                clickhouse.add_trade(trade);
+            }
+            self.broadcast.send_trade(trade);
+        }
+        if let Some(exit) = self
+            .positions
+            .as_ref()
+            .and_then(|positions| positions.on_trade(trade))
+        {
+            self.sell(exit);
+        }
+        let Some(token) = self.tracked.get(&trade.mint).await else {
+            return Ok(());
+        };
+        if let Some(wash) = &self.wash {
+            for (mint, trades) in wash.record(trade, token.created_at) {
+                self.spawn_wash_analysis(wash.clone(), mint, trades);
+            }
+        }
+        if self.publish_trades {
+            self.publish_event(Event::TradeExecuted(trade.clone()))
+                .await?;
+        }
+        let stats = self
+            .trade_stats
+            .entry(trade.mint.clone())
+            .and_upsert_with(|stats| async move {
+                TradeStats {
+                    last_trade_at: Utc::now(),
+                    tx_count: stats.map_or(0, |stats| stats.into_value().tx_count) + 1,
+                }
+            })
+            .await
+            .into_value();
+        // Exactly once, the count keeps growing past the threshold
+        if self.min_tx_count > 0 && stats.tx_count == self.min_tx_count {
+            self.process_active_pair(token.clone(), stats.tx_count).await?;
+        }
+        if let Some(volume) = &self.volume {
+            volume.record(trade);
+        }
+
+        if trade.token_amount == 0 {
+            return Ok(());
+        }
+        let volume_sol = BigDecimal::new(trade.sol_amount.into(), LAMPORTS_DECIMALS);
+        let price_sol = (&volume_sol
+            / BigDecimal::new(trade.token_amount.into(), i64::from(token.decimals)))
+        .with_prec(PRICE_DIGITS);
+        if let Some(papertrade) = &self.papertrade {
+            papertrade.on_price(&trade.mint, &price_sol);
+        }
+
+        let Some(candles) = &self.candles else {
+            return Ok(());
+        };
+        for candle in candles.add(trade, &price_sol, &volume_sol) {
+            self.publish_candle(candle).await?;
+        }
+        Ok(())
+    }
+
+    /// Buy `token` in the background if the sniper wants it, and hold the position
+    fn snipe(&self, token: &Token) {
+        let Some(sniper) = self.sniper.clone() else {
+            return;
+        };
+        if !sniper.qualifies(token) {
+            return;
+        }
+        let processor = self.clone();
+        let token = token.clone();
+        let span = info_span!("execution", mint = %token.mint_address);
+        tokio::spawn(
+            async move {
+                let Some(fill) = sniper.snipe(&token).await else {
+                    return;
+                };
+                if let Err(e) = processor.process_position_opened(&token, &fill).await {
+                    warn!(
+                        "Failed to publish position in {}: {:#}",
+                        token.mint_address, e
+                    );
+                }
+            }
+            .instrument(span),
+        );
+    }
+
+    async fn process_position_opened(&self, token: &Token, fill: &Fill) -> Result<()> {
+        let Some(positions) = &self.positions else {
+            return Ok(());
+        };
+        let position = positions.open(token, fill);
+        info!(
+            "Holding {} tokens of {} bought for {} SOL at {} SOL",
+            position.token_amount, position.mint, position.cost_sol, position.entry_price_sol
+        );
+        self.publish_event(Event::PositionOpened(position)).await
+    }
+
+    /// Sell the position of `exit` in the background, publishing `PositionClosed`
+    /// once sold; a failed sell is retried by the next trade or check
+    fn sell(&self, exit: Exit) {
+        let (Some(sniper), Some(positions)) = (self.sniper.clone(), self.positions.clone()) else {
+            return;
+        };
+        let processor = self.clone();
+        let span = info_span!("execution", mint = %exit.position.mint);
+        tokio::spawn(
+            async move {
+                let mint = exit.position.mint.clone();
+                let sale = match sniper.sell(&exit.position).await {
+                    Ok(sale) => sale,
+                    Err(e) => {
+                        warn!("Sell of {} on {} failed: {:#}", mint, exit.reason, e);
+                        metrics::POSITION_SELLS.with_label_values(&["failed"]).inc();
+                        positions.unsold(&mint);
+                        return;
+                    }
+                };
+                let outcome = if sale.signature.is_some() {
+                    "confirmed"
+                } else {
+                    "dry_run"
+                };
+                metrics::POSITION_SELLS.with_label_values(&[outcome]).inc();
+                let closed = positions.sold(exit, &sale);
+                if let Err(e) = processor.process_position_closed(closed).await {
+                    warn!("Failed to publish sale of {}: {:#}", mint, e);
+                }
+            }
+            .instrument(span),
+        );
+    }
+
+    async fn process_position_closed(&self, closed: ClosedPosition) -> Result<()> {
+        info!(
+            "Sold {} on {}: {} SOL ({}%)",
+            closed.position.mint, closed.exit, closed.pnl_sol, closed.pnl_percentage
+        );
+        self.publish_event(Event::PositionClosed(closed)).await
+    }
+
+    /// Sell positions held too long or whose sell failed, then log the PnL summary
+    pub async fn check_positions(&self) {
+        let Some(positions) = &self.positions else {
+            return;
+        };
+        for exit in positions.due(Utc::now()) {
+            self.sell(exit);
+        }
+        positions.log_summary();
+    }
+
+    /// Held positions and PnL of the execution module, `None` unless `execution.enabled`
+    pub fn positions_report(&self) -> Option<PositionsReport> {
+        self.positions.as_ref().map(|positions| positions.report())
+    }
+
+    /// Simulated positions and PnL, `None` unless `papertrade.enabled`
+    pub fn paper_report(&self) -> Option<PaperReport> {
+        self.papertrade.as_ref().map(|papertrade| papertrade.report())
+    }
+
+    /// Published events and decoded trades, for in-process subscribers
+    pub fn broadcast(&self) -> Broadcast {
+        self.broadcast.clone()
+    }
+
+    pub fn volume(&self) -> Option<Arc<VolumeTracker>> {
+        self.volume.clone()
+    }
+
+    /// Publish the candles whose period is over
+    pub async fn flush_candles(&self) -> Result<()> {
+        let Some(candles) = &self.candles else {
+            return Ok(());
+        };
+        for candle in candles.close(Utc::now()) {
+            self.publish_candle(candle).await?;
+        }
+        Ok(())
+    }
+
+    async fn publish_candle(&self, candle: Candle) -> Result<()> {
+        debug!(
+            "{}s candle of {} at {}: close {}, {} trades",
+            candle.interval_secs,
+            candle.mint,
+            candle.open_time,
+            candle.close,
+            candle.buys + candle.sells
+        );
+        metrics::CANDLES_PUBLISHED
+            .with_label_values(&[&candle.interval_secs.to_string()])
+            .inc();
+        self.publish_event(Event::Candle(candle)).await
+    }
+
+    /// When a tracked token last traded, `None` if not since it was published
+    pub async fn last_trade(&self, mint: &str) -> Option<DateTime<Utc>> {
+        self.trade_stats
+            .get(mint)
+            .await
+            .map(|stats| stats.last_trade_at)
+    }
+
+    /// A tracked token stopped trading: stop tracking it and tell consumers to do the same
+    pub async fn process_token_abandoned(
+        &self,
+        token: Token,
+        last_trade_at: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        info!(
+            "Token {} abandoned, last trade {:?}",
+            token.mint_address, last_trade_at
+        );
+        metrics::TOKENS_ABANDONED
+            .with_label_values(&[&token.source.to_string()])
+            .inc();
+
+        // While still tracked, so the transition is published
+        self.transition(&token.mint_address, TokenState::Abandoned)
             .await?;
+        self.tracked.invalidate(&token.mint_address).await;
+        self.trade_stats.invalidate(&token.mint_address).await;
+
+        self.publish_event(Event::TokenAbandoned {
+            token,
+            last_trade_at,
+        })
+        .await
+    }
+
+    /// Move a published token to `to` and publish `TokenStateChanged`, unless it
+    /// is already there or further along. Untracked tokens are ignored.
+    pub async fn transition(&self, mint: &str, to: TokenState) -> Result<()> {
+        let Some(lifecycle) = &self.lifecycle else {
+            return Ok(());
+        };
+        if !self.tracked.contains_key(mint) {
+            return Ok(());
+        }
+        let change = match lifecycle.advance(mint, to).await {
+            Ok(Some(change)) => change,
+            Ok(None) => return Ok(()),
+            Err(e) => {
+                warn!("Lifecycle of {} not updated: {:#}", mint, e);
+                return Ok(());
+            }
+        };
+        info!(
+            "Token {} is now {} (was {:?})",
+            mint,
+            change.to,
+            change.from.map(|from| from.to_string())
+        );
+        metrics::TOKEN_STATE_CHANGES
+            .with_label_values(&[&change.to.to_string()])
+            .inc();
+        self.publish_event(Event::TokenStateChanged(change)).await
+    }
 
+    /// A tracked token left its bonding curve; new tokens copying it are impersonations
+    pub async fn note_graduated(&self, token: &Token) {
+        self.impersonation.add_trending(token).await;
+    }
+
+    /// Find out in the background whether the LP tokens of `pool`, the PumpSwap
+    /// pool a tracked token just graduated to, get burned or locked, and publish the result
+    pub fn check_lp(&self, mint: &str, pool: &str) {
+        let (Some(checker), Ok(pool)) = (self.lp.clone(), Pubkey::from_str(pool)) else {
+            return;
+        };
+        let processor = self.clone();
+        let mint = mint.to_string();
+        let span = info_span!("lp_check", mint = %mint);
+        tokio::spawn(
+            async move {
+                tokio::time::sleep(checker.delay()).await;
+                let status = match checker.check(&pool).await {
+                    Ok(Some(status)) => status,
+                    Ok(None) => return,
+                    Err(e) => {
+                        warn!("LP check of {} failed: {:#}", mint, e);
+                        return;
+                    }
+                };
+                let Some(mut token) = processor.tracked_token(&mint).await else {
+                    return;
+                };
+                info!(
+                    "LP of {}: burned {}, locked {}",
+                    mint, status.burned, status.locked
+                );
+                token.lp_burned = Some(status.burned);
+                token.liquidity_locked = Some(status.locked);
+                scoring::apply(&mut token);
+                if let Err(e) = processor.process_token_updated(token).await {
+                    warn!("Failed to publish LP status of {}: {:#}", mint, e);
+                }
+            }
+            .instrument(span),
+        );
+    }
+
+    /// Put the creator of `token` on the blacklist after it rugged
+    pub async fn flag_rugger(&self, token: &Token, reason: &str) -> Result<()> {
+        let (Some(blacklist), Some(creator)) = (&self.blacklist, &token.creator) else {
+            return Ok(());
+        };
+        if blacklist.flag(creator, "rug").await? {
+            warn!(
+                "Blacklisted creator {} of {}: {}",
+                creator, token.mint_address, reason
+            );
+        }
         Ok(())
     }
+
+    /// Set `price_usd` and `market_cap_usd` from `price_sol`, when a price feed is configured
+    async fn price_in_usd(&self, token: &mut Token) {
+        let (Some(feed), Some(price_sol)) = (&self.price, &token.price_sol) else {
+            return;
+        };
+        let Some(sol_usd) = feed.sol_usd().await else {
+            return;
+        };
+        let price_usd = (price_sol * &sol_usd).with_prec(PRICE_DIGITS);
+        let supply = &token.total_supply / BigDecimal::new(1.into(), -i64::from(token.decimals));
+        token.market_cap_usd = Some((&price_usd * supply).round(2));
+        token.price_usd = Some(price_usd);
+    }
+
+    /// A tracked token has seen enough trades to be worth reacting to
+    async fn process_active_pair(&self, token: Token, tx_count: u32) -> Result<()> {
+        info!(
+            "Token {} is an active pair after {} trades",
+            token.mint_address, tx_count
+        );
+        metrics::ACTIVE_PAIRS
+            .with_label_values(&[&token.source.to_string()])
+            .inc();
+        self.publish_event(Event::ActivePair { token, tx_count }).await
+    }
+
+    /// A trade by a watched wallet
+    pub async fn process_wallet_trade(&self, trade: WalletTrade) -> Result<()> {
+        Span::current().record("mint", trade.trade.mint.as_str());
+        info!(
+            "Watched wallet {} ({}) {} {}",
+            trade.trade.wallet,
+            trade.label.as_deref().unwrap_or("unlabelled"),
+            if trade.trade.is_buy { "bought" } else { "sold" },
+            trade.trade.mint
+        );
+        metrics::WATCHED_WALLET_EVENTS
+            .with_label_values(&["trade"])
+            .inc();
+        self.publish_event(Event::WatchedWalletTrade(trade)).await
+    }
+
+    /// A token launched by a watched wallet
+    pub async fn process_wallet_launch(&self, launch: WalletLaunch) -> Result<()> {
+        Span::current().record("mint", launch.mint.as_str());
+        info!(
+            "Watched wallet {} ({}) launched {} ({})",
+            launch.wallet,
+            launch.label.as_deref().unwrap_or("unlabelled"),
+            launch.symbol,
+            launch.mint
+        );
+        metrics::WATCHED_WALLET_EVENTS
+            .with_label_values(&["launch"])
+            .inc();
+        self.publish_event(Event::WatchedWalletLaunch(launch)).await
+    }
+
+    /// Early buyers of a launch whose snipe window has closed
+    pub async fn process_launch_snapshot(&self, mut snapshot: LaunchSnapshot) -> Result<()> {
+        Span::current().record("mint", snapshot.mint.as_str());
+        if let Some(funding) = &self.funding {
+            let wallets: Vec<String> = snapshot
+                .buyers
+                .iter()
+                .map(|buyer| buyer.wallet.clone())
+                .collect();
+            let funders = funding.funders(wallets, usize::MAX).await;
+            let mut funded: HashMap<&str, u32> = HashMap::new();
+            for funder in funders.values().flatten() {
+                *funded.entry(funder.as_str()).or_default() += 1;
+            }
+            snapshot.same_funder_buyers = Some(funded.values().copied().max().unwrap_or(0));
+            for buyer in &mut snapshot.buyers {
+                buyer.funder = funders.get(&buyer.wallet).cloned().flatten();
+            }
+        }
+        if let Some(feed) = &self.price {
+            if let Some(sol_usd) = feed.sol_usd().await {
+                for buy in &mut snapshot.buys {
+                    let sol = BigDecimal::new(buy.sol_amount.into(), LAMPORTS_DECIMALS);
+                    buy.usd_amount = Some((sol * &sol_usd).round(2));
+                }
+            }
+        }
+        info!(
+            "Launch snapshot for {}: {} buyers, {} snipers, bundled: {}, dev holds: {:?}",
+            snapshot.mint,
+            snapshot.buyers.len(),
+            snapshot.sniper_count,
+            snapshot.bundled,
+            snapshot.dev_holds
+        );
+        self.publish_event(Event::LaunchSnapshot(snapshot)).await
+    }
+
+    /// A tracked token left its bonding curve for the AMM pool `pool`
+    pub async fn process_token_graduated(&self, token: Token, pool: &str) -> Result<()> {
+        info!("Token {} graduated to pool {}", token.mint_address, pool);
+        self.publish_event(Event::TokenGraduated {
+            token,
+            pool: pool.to_string(),
+        })
+        .await
+    }
+
+    /// Publish a listener heartbeat
+    pub async fn process_listener_status(&self, status: ListenerHeartbeat) -> Result<()> {
+        self.publish_event(Event::ListenerStatus(status)).await
+    }
+
+
+    /// Fire alerts in the background so a slow bot API never delays publishing
+    fn notify(rules: &Rules, token: &Token) {
+        if let Some(telegram) = &rules.telegram {
+            if telegram.matches(token) {
+                if rules.dry_run {
+                    DryRunReporter::notification("telegram", token);
+                    return;
+                }
+                let telegram = telegram.clone();
+                let token = token.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = telegram.notify(&token).await {
+                        warn!("Telegram alert for {} failed: {:#}", token.mint_address, e);
+                    }
+                });
+            }
+        }
+    }
+
+    /// Wait for the sinks to deliver everything already published
+    pub async fn flush(&self, timeout: Duration) -> bool {
+        if let Some(clickhouse) = &self.clickhouse {
+            clickhouse.flush().await;
+        }
+        self.sinks.flush(timeout).await
+    }
+
+    async fn publish_event(&self, event: Event) -> Result<()> {
+        if !self.is_leader() {
+            metrics::DROPPED_MESSAGES
+                .with_label_values(&["leader", "standby"])
+                .inc();
+            return Ok(());
+        }
+        let envelope = self.sequencer.wrap(event).await?;
+        if let Some(clickhouse) = &self.clickhouse {
+            clickhouse.add_event(&envelope);
+        }
+        self.broadcast.send_event(&envelope);
+        let _span = info_span!(
+            "publish",
+            event = envelope.event_type(),
+            seq = envelope.seq
+        )
+        .entered();
+        Ok(self.sinks.publish(envelope)?)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,290 @@
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::rpc_request::RpcError;
+use tracing::warn;
+
+use crate::config::RateLimitConfig;
+use crate::metrics;
+
+/// Slowest refill, so a zero rate in the config cannot stall the bucket
+const MIN_RATE: f64 = 0.01;
+
+static RETRY_AFTER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)retry[- ]after\D{0,3}(\d+)").expect("valid retry-after regex")
+});
+
+/// A 429 given as the HTTP status or error code, not any number containing it
+static STATUS_429: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)\b(?:status|code|http|error)\W{0,3}429\b"#).expect("valid status regex")
+});
+
+/// Token bucket for one RPC endpoint that adapts to the provider's limits.
+///
+/// The refill rate is halved on every 429 (and the bucket paused for the
+/// Retry-After hint, if any) and climbs back linearly towards the configured
+/// maximum while requests keep succeeding.
+pub struct RateLimiter {
+    endpoint: String,
+    max_rate: f64,
+    min_rate: f64,
+    recovery_per_sec: f64,
+    default_pause: Duration,
+    state: Mutex<BucketState>,
+}
+
+struct BucketState {
+    rate: f64,
+    tokens: f64,
+    last_refill: Instant,
+    last_increase: Instant,
+    paused_until: Option<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(endpoint: &str, config: &RateLimitConfig) -> Self {
+        let min_rate = config.min_requests_per_sec.max(MIN_RATE);
+        let max_rate = config
+            .endpoints
+            .get(endpoint)
+            .copied()
+            .unwrap_or(config.max_requests_per_sec)
+            .max(min_rate);
+        let now = Instant::now();
+
+        metrics::RPC_RATE_LIMIT
+            .with_label_values(&[endpoint])
+            .set(max_rate);
+
+        Self {
+            endpoint: endpoint.to_string(),
+            max_rate,
+            min_rate,
+            recovery_per_sec: config.recovery_per_sec,
+            default_pause: Duration::from_millis(config.default_retry_after_ms),
+            state: Mutex::new(BucketState {
+                rate: max_rate,
+                tokens: max_rate.max(1.0),
+                last_refill: now,
+                last_increase: now,
+                paused_until: None,
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BucketState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Whether the endpoint asked us to back off and the pause has not expired
+    pub fn is_paused(&self, now: Instant) -> bool {
+        self.lock().paused_until.is_some_and(|until| now < until)
+    }
+
+    /// Wait for a request slot
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut state = self.lock();
+                let now = Instant::now();
+
+                match state.paused_until {
+                    Some(until) if now < until => until - now,
+                    _ => {
+                        state.paused_until = None;
+                        let rate = state.rate.max(MIN_RATE);
+                        let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+                        state.tokens = (state.tokens + elapsed * rate).min(rate.max(1.0));
+                        state.last_refill = now;
+
+                        if state.tokens >= 1.0 {
+                            state.tokens -= 1.0;
+                            return;
+                        }
+                        Duration::from_secs_f64((1.0 - state.tokens) / rate)
+                    }
+                }
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Additive increase while the endpoint keeps answering
+    pub fn on_success(&self) {
+        let mut state = self.lock();
+        if state.rate >= self.max_rate {
+            return;
+        }
+
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.last_increase).as_secs_f64();
+        state.rate = (state.rate + elapsed * self.recovery_per_sec).min(self.max_rate);
+        state.last_increase = now;
+
+        metrics::RPC_RATE_LIMIT
+            .with_label_values(&[self.endpoint.as_str()])
+            .set(state.rate);
+    }
+
+    /// Multiplicative decrease and a pause after a 429
+    pub fn on_rate_limited(&self, retry_after: Option<Duration>) {
+        let mut state = self.lock();
+        let now = Instant::now();
+        let pause = retry_after.unwrap_or(self.default_pause);
+
+        state.rate = (state.rate / 2.0).max(self.min_rate).max(MIN_RATE);
+        state.tokens = 0.0;
+        state.last_increase = now;
+        state.paused_until = Some(now + pause);
+
+        warn!(
+            "RPC endpoint {} rate limited, slowing to {:.1} req/s and pausing {:?}",
+            self.endpoint, state.rate, pause
+        );
+        metrics::RPC_RATE_LIMITED
+            .with_label_values(&[self.endpoint.as_str()])
+            .inc();
+        metrics::RPC_RATE_LIMIT
+            .with_label_values(&[self.endpoint.as_str()])
+            .set(state.rate);
+    }
+}
+
+/// `Some(retry_after)` if the error is the provider throttling us
+pub fn rate_limit_hint(error: &ClientError) -> Option<Option<Duration>> {
+    let throttled = match error.kind() {
+        ClientErrorKind::Reqwest(e) => e.status().is_some_and(|s| s.as_u16() == 429),
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, message, .. }) => {
+            *code == 429 || *code == -32429 || is_rate_limit_message(message)
+        }
+        _ => false,
+    } || is_rate_limit_message(&error.to_string());
+
+    if !throttled {
+        return None;
+    }
+
+    let retry_after = RETRY_AFTER
+        .captures(&error.to_string())
+        .and_then(|c| c[1].parse::<u64>().ok())
+        .map(Duration::from_secs);
+    Some(retry_after)
+}
+
+fn is_rate_limit_message(message: &str) -> bool {
+    let lowercase = message.to_ascii_lowercase();
+    STATUS_429.is_match(message)
+        || lowercase.contains("too many requests")
+        || lowercase.contains("rate limit")
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_client::rpc_request::RpcResponseErrorData;
+
+    use super::*;
+
+    fn rpc_error(code: i64, message: &str) -> ClientError {
+        ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            code,
+            message: message.to_string(),
+            data: RpcResponseErrorData::Empty,
+        })
+        .into()
+    }
+
+    fn custom_error(message: &str) -> ClientError {
+        ClientErrorKind::Custom(message.to_string()).into()
+    }
+
+    fn limiter() -> RateLimiter {
+        let config = RateLimitConfig {
+            max_requests_per_sec: 10.0,
+            min_requests_per_sec: 1.0,
+            recovery_per_sec: 2.0,
+            default_retry_after_ms: 500,
+            ..RateLimitConfig::default()
+        };
+        RateLimiter::new("rate-limit-test", &config)
+    }
+
+    #[test]
+    fn rate_limit_codes_are_throttling() {
+        assert_eq!(rate_limit_hint(&rpc_error(429, "error")), Some(None));
+        assert_eq!(rate_limit_hint(&rpc_error(-32429, "error")), Some(None));
+        assert_eq!(
+            rate_limit_hint(&rpc_error(
+                -32005,
+                "Too many requests for a specific RPC call"
+            )),
+            Some(None)
+        );
+        assert_eq!(
+            rate_limit_hint(&custom_error("HTTP status client error (429) for url")),
+            Some(None)
+        );
+    }
+
+    #[test]
+    fn retry_after_hint_is_parsed() {
+        assert_eq!(
+            rate_limit_hint(&rpc_error(429, "rate limited, retry after 3 seconds")),
+            Some(Some(Duration::from_secs(3)))
+        );
+        assert_eq!(
+            rate_limit_hint(&custom_error("Too Many Requests, Retry-After: 12")),
+            Some(Some(Duration::from_secs(12)))
+        );
+    }
+
+    #[test]
+    fn numbers_containing_429_are_not_throttling() {
+        assert_eq!(
+            rate_limit_hint(&rpc_error(
+                -32602,
+                "Invalid param: could not find account 7429xPq"
+            )),
+            None
+        );
+        assert_eq!(
+            rate_limit_hint(&custom_error(
+                "Blockhash 4291yC8F not found at slot 342900001"
+            )),
+            None
+        );
+        assert_eq!(rate_limit_hint(&rpc_error(-32000, "Node is behind")), None);
+    }
+
+    #[test]
+    fn rate_halves_down_to_the_minimum() {
+        let limiter = limiter();
+        limiter.on_rate_limited(Some(Duration::from_secs(1)));
+        assert_eq!(limiter.lock().rate, 5.0);
+        assert!(limiter.is_paused(Instant::now()));
+        assert!(!limiter.is_paused(Instant::now() + Duration::from_secs(2)));
+
+        for _ in 0..5 {
+            limiter.on_rate_limited(None);
+        }
+        assert_eq!(limiter.lock().rate, 1.0);
+    }
+
+    #[test]
+    fn rate_recovers_linearly_up_to_the_maximum() {
+        let limiter = limiter();
+        limiter.on_rate_limited(None);
+        limiter.on_rate_limited(None);
+        assert_eq!(limiter.lock().rate, 2.5);
+
+        limiter.lock().last_increase -= Duration::from_secs(2);
+        limiter.on_success();
+        let rate = limiter.lock().rate;
+        assert!((6.5..7.0).contains(&rate), "rate {}", rate);
+
+        limiter.lock().last_increase -= Duration::from_secs(10);
+        limiter.on_success();
+        assert_eq!(limiter.lock().rate, 10.0);
+    }
+}
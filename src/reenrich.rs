@@ -0,0 +1,275 @@
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use bigdecimal::{BigDecimal, ToPrimitive, Zero};
+use chrono::Utc;
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::pubkey::Pubkey;
+use spl_token_2022::extension::StateWithExtensions;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, info, info_span, warn, Instrument};
+
+use crate::config::ReenrichConfig;
+use crate::liquidity::{self, Reserves};
+use crate::models::Token;
+use crate::processor::Processor;
+use crate::rpc_pool::RpcPool;
+use crate::{metrics, scoring, token_helper};
+
+/// Offset and length of `amount` in a token account, after mint and owner
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 32 * 2;
+const TOKEN_ACCOUNT_AMOUNT_LEN: usize = 8;
+const TOP_HOLDERS: usize = 10;
+
+/// Revisits tokens discovered within `window_secs` every `interval_secs`:
+/// holders, liquidity and curve progress are read again, the token rescored
+/// and published (`TokenUpdated`) when any of it moved.
+pub struct Reenricher {
+    rpc: Arc<RpcPool>,
+    processor: Processor,
+    config: ReenrichConfig,
+}
+
+impl Reenricher {
+    /// Start the re-enrichment task
+    pub fn spawn(rpc: Arc<RpcPool>, processor: Processor, config: &ReenrichConfig) {
+        let reenricher = Self {
+            rpc,
+            processor,
+            config: config.clone(),
+        };
+        tokio::spawn(reenricher.run());
+    }
+
+    async fn run(self) {
+        let mut ticker =
+            tokio::time::interval(Duration::from_secs(self.config.interval_secs.max(1)));
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // The first tick fires right away, nothing is tracked yet
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            self.refresh_all().instrument(info_span!("reenrich")).await;
+        }
+    }
+
+    async fn refresh_all(&self) {
+        let since = Utc::now() - chrono::Duration::seconds(self.config.window_secs as i64);
+        let recent: Vec<Token> = self
+            .processor
+            .tracked_tokens()
+            .into_iter()
+            .filter(|token| token.discovered_at >= since)
+            .collect();
+        debug!("Re-enriching {} tokens", recent.len());
+
+        for token in &recent {
+            if let Err(e) = self.refresh(token).await {
+                warn!("Re-enrichment of {} failed: {:#}", token.mint_address, e);
+            }
+        }
+    }
+
+    async fn refresh(&self, before: &Token) -> Result<()> {
+        let mint = Pubkey::from_str(&before.mint_address).context("Invalid mint")?;
+
+        let mut after = before.clone();
+        let (holder_count, top_10) = self.holders(before, &mint).await?;
+        if holder_count.is_some() {
+            after.holder_count = holder_count;
+        }
+        if top_10.is_some() {
+            after.top_10_holder_percentage = top_10;
+        }
+        if let Some(reserves) = self.reserves(before).await? {
+            reserves.apply(&mut after);
+        }
+        scoring::apply(&mut after);
+
+        let changes = self.changes(before, &after);
+        if changes.is_empty() {
+            return Ok(());
+        }
+        for change in &changes {
+            metrics::REENRICHMENTS.with_label_values(&[change]).inc();
+        }
+        info!("Re-enriched {}: {}", mint, changes.join(", "));
+
+        // Apply to the latest state, other tasks may have updated the token meanwhile
+        let Some(mut token) = self.processor.tracked_token(&before.mint_address).await else {
+            return Ok(());
+        };
+        token.holder_count = after.holder_count;
+        token.top_10_holder_percentage = after.top_10_holder_percentage;
+        token.liquidity_sol = after.liquidity_sol;
+        token.price_sol = after.price_sol;
+        token.bonding_curve_progress = after.bonding_curve_progress;
+        scoring::apply(&mut token);
+        self.processor.process_token_updated(token).await
+    }
+
+    /// Holder count (with `count_holders`) and top 10 share of the supply,
+    /// leaving out the bonding curve's or pool's own token account
+    async fn holders(
+        &self,
+        token: &Token,
+        mint: &Pubkey,
+    ) -> Result<(Option<u32>, Option<BigDecimal>)> {
+        let largest = self
+            .rpc
+            .call("getTokenLargestAccounts", |client| async move {
+                client.get_token_largest_accounts(mint).await
+            })
+            .await
+            .context("Failed to list token holders")?;
+        let keys: Vec<Pubkey> = largest
+            .iter()
+            .filter_map(|holder| Pubkey::from_str(&holder.address).ok())
+            .collect();
+
+        // (token program, owner, amount)
+        let holdings: Vec<(Pubkey, Pubkey, u64)> = token_helper::load_accounts(&self.rpc, &keys)
+            .await?
+            .into_iter()
+            .flatten()
+            .filter_map(|account| {
+                let state =
+                    StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+                        .ok()?;
+                Some((account.owner, state.base.owner, state.base.amount))
+            })
+            .collect();
+        let Some(program) = holdings.first().map(|(program, _, _)| *program) else {
+            return Ok((None, None));
+        };
+
+        let pool = token
+            .pumpswap_pool
+            .as_deref()
+            .and_then(|pool| Pubkey::from_str(pool).ok());
+        let is_reserve = |owner: &Pubkey| {
+            token.pump_fun_bonding_curve.as_ref() == Some(owner) || pool.as_ref() == Some(owner)
+        };
+        let mut amounts: Vec<u64> = holdings
+            .iter()
+            .filter(|(_, owner, _)| !is_reserve(owner))
+            .map(|(_, _, amount)| *amount)
+            .collect();
+        amounts.sort_unstable_by(|a, b| b.cmp(a));
+        let top: u64 = amounts.iter().take(TOP_HOLDERS).sum();
+        let top_10 = (!token.total_supply.is_zero()).then(|| {
+            (BigDecimal::from(top) * BigDecimal::from(100) / &token.total_supply).round(2)
+        });
+
+        if !self.config.count_holders {
+            return Ok((None, top_10));
+        }
+        let reserves = holdings
+            .iter()
+            .filter(|(_, owner, amount)| is_reserve(owner) && *amount > 0)
+            .count();
+        let holder_count = self
+            .count_holders(&program, mint)
+            .await?
+            .saturating_sub(reserves);
+        Ok((Some(holder_count as u32), top_10))
+    }
+
+    /// Token accounts of `mint` with a balance, read through `getProgramAccounts`
+    /// with only their amount sliced out
+    async fn count_holders(&self, program: &Pubkey, mint: &Pubkey) -> Result<usize> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                0,
+                mint.as_ref(),
+            ))]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                data_slice: Some(UiDataSliceConfig {
+                    offset: TOKEN_ACCOUNT_AMOUNT_OFFSET,
+                    length: TOKEN_ACCOUNT_AMOUNT_LEN,
+                }),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        let accounts = self
+            .rpc
+            .call("getProgramAccounts", |client| {
+                let config = config.clone();
+                async move {
+                    client
+                        .get_program_ui_accounts_with_config(program, config)
+                        .await
+                }
+            })
+            .await
+            .context("Failed to count token holders")?;
+        Ok(accounts
+            .iter()
+            .filter_map(|(_, account)| account.data.decode())
+            .filter_map(|amount| amount.try_into().ok().map(u64::from_le_bytes))
+            .filter(|amount| *amount > 0)
+            .count())
+    }
+
+    /// Current reserves: the PumpSwap pool's once graduated, the bonding curve's before
+    async fn reserves(&self, token: &Token) -> Result<Option<Reserves>> {
+        if let Some(pool) = token
+            .pumpswap_pool
+            .as_deref()
+            .and_then(|pool| Pubkey::from_str(pool).ok())
+        {
+            return liquidity::pool_reserves(&self.rpc, &pool, token.decimals).await;
+        }
+        let Some(curve) = token.pump_fun_bonding_curve else {
+            return Ok(None);
+        };
+        Ok(token_helper::load_accounts(&self.rpc, &[curve])
+            .await?
+            .remove(0)
+            .and_then(|curve| Reserves::from_curve(&curve.data, token.decimals)))
+    }
+
+    /// Metric labels of what moved enough to publish
+    fn changes(&self, before: &Token, after: &Token) -> Vec<&'static str> {
+        let mut changes = Vec::new();
+        let holders = |token: &Token| token.holder_count.map(BigDecimal::from);
+        if self.moved(holders(before), holders(after)) {
+            changes.push("holders");
+        }
+        if self.moved(
+            before.top_10_holder_percentage.clone(),
+            after.top_10_holder_percentage.clone(),
+        ) {
+            changes.push("top_holders");
+        }
+        if self.moved(before.liquidity_sol.clone(), after.liquidity_sol.clone()) {
+            changes.push("liquidity");
+        }
+        if before.bonding_curve_progress != after.bonding_curve_progress {
+            changes.push("curve_progress");
+        }
+        if before.score != after.score || before.risk_level != after.risk_level {
+            changes.push("score");
+        }
+        changes
+    }
+
+    fn moved(&self, before: Option<BigDecimal>, after: Option<BigDecimal>) -> bool {
+        let Some(after) = after.and_then(|after| after.to_f64()) else {
+            return false;
+        };
+        let Some(before) = before.and_then(|before| before.to_f64()) else {
+            return true;
+        };
+        if before == 0.0 {
+            return after != 0.0;
+        }
+        ((after - before) / before).abs() * 100.0 >= self.config.min_change_percentage
+    }
+}
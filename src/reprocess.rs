@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use flate2::read::MultiGzDecoder;
+use redis::aio::ConnectionManager;
+use tracing::{info, warn};
+
+use crate::config::{Config, OutputConfig};
+use crate::filters::TokenFilter;
+use crate::models::{Envelope, Event, Token};
+use crate::scoring;
+use crate::sinks::file::FileSink;
+use crate::sinks::redis::RedisSink;
+use crate::sinks::EventSink;
+
+/// Where the published events to reprocess are read from
+pub enum ReprocessSource {
+    /// Entries of a Redis stream (`output.mode = "stream"`)
+    Stream(String),
+    /// NDJSON envelopes, gzipped when the name ends in `.gz`: file sink output
+    /// or downloaded archive objects
+    Files(Vec<PathBuf>),
+}
+
+/// Which events to reprocess and what to emit
+#[derive(Debug, Clone, Default)]
+pub struct ReprocessOptions {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// Only emit events whose score or risk level changed
+    pub changed_only: bool,
+}
+
+impl ReprocessOptions {
+    fn in_range(&self, emitted_at: DateTime<Utc>) -> bool {
+        self.since.is_none_or(|since| emitted_at >= since)
+            && self.until.is_none_or(|until| emitted_at <= until)
+    }
+}
+
+#[derive(Default)]
+struct Counts {
+    read: usize,
+    changed: usize,
+    filtered: usize,
+    emitted: usize,
+}
+
+/// Re-runs the current `[scoring]` and `[filters]` over published token events
+/// and emits the corrected envelopes
+struct Reprocessor {
+    filter: TokenFilter,
+    options: ReprocessOptions,
+    output: Arc<dyn EventSink>,
+    counts: Counts,
+}
+
+impl Reprocessor {
+    async fn handle(&mut self, mut envelope: Envelope) -> Result<()> {
+        if !self.options.in_range(envelope.emitted_at) {
+            return Ok(());
+        }
+        let discovered = matches!(envelope.payload, Event::TokenDiscovered(_));
+        let Some(token) = token_mut(&mut envelope.payload) else {
+            return Ok(());
+        };
+        self.counts.read += 1;
+
+        let before = (token.score, token.risk_level.clone());
+        scoring::apply(token);
+        let changed = before != (token.score, token.risk_level.clone());
+        if changed {
+            self.counts.changed += 1;
+        }
+        // Filters only ever applied to discoveries
+        if discovered {
+            if let Some(reason) = self.filter.check(token) {
+                info!("Token {} now filtered out: {}", token.mint_address, reason);
+                self.counts.filtered += 1;
+                return Ok(());
+            }
+        }
+        if self.options.changed_only && !changed {
+            return Ok(());
+        }
+
+        self.output.send(&envelope).await?;
+        self.counts.emitted += 1;
+        Ok(())
+    }
+}
+
+/// The token an event carries, to rescore it in place
+fn token_mut(event: &mut Event) -> Option<&mut Token> {
+    match event {
+        Event::TokenDiscovered(token)
+        | Event::TokenUpdated(token)
+        | Event::TokenGraduated { token, .. }
+        | Event::TokenConfirmed(token)
+        | Event::TokenRetracted { token, .. }
+        | Event::TokenAbandoned { token, .. }
+        | Event::KingOfTheHill(token)
+        | Event::ActivePair { token, .. } => Some(token),
+        _ => None,
+    }
+}
+
+/// Publish corrected events to `channel` (`reprocess.channel` unless given),
+/// in the configured output mode
+pub async fn redis_output(config: &Config, channel: Option<String>) -> Result<Arc<dyn EventSink>> {
+    let channel = channel.unwrap_or_else(|| config.reprocess.channel.clone());
+    info!("Emitting reprocessed events to {}", channel);
+    let output = OutputConfig {
+        default_channel: channel,
+        channels: HashMap::new(),
+        channel_template: None,
+        ..config.output.clone()
+    };
+    Ok(Arc::new(
+        RedisSink::new(&config.database.redis_url, output).await?,
+    ))
+}
+
+/// Append corrected events to an NDJSON file
+pub async fn file_output(path: &Path) -> Result<Arc<dyn EventSink>> {
+    let path = path.to_str().context("Output path is not valid UTF-8")?;
+    Ok(Arc::new(FileSink::new(path).await?))
+}
+
+pub async fn run(
+    config: &Config,
+    source: ReprocessSource,
+    options: ReprocessOptions,
+    output: Arc<dyn EventSink>,
+) -> Result<()> {
+    let mut reprocessor = Reprocessor {
+        filter: TokenFilter::new(&config.filters)?,
+        options,
+        output,
+        counts: Counts::default(),
+    };
+
+    match source {
+        ReprocessSource::Stream(key) => read_stream(config, &key, &mut reprocessor).await?,
+        ReprocessSource::Files(paths) => {
+            for path in paths {
+                read_file(&path, &mut reprocessor).await?;
+            }
+        }
+    }
+
+    let counts = &reprocessor.counts;
+    info!(
+        "Reprocessed {} token events: {} rescored differently, {} now filtered out, {} emitted",
+        counts.read, counts.changed, counts.filtered, counts.emitted
+    );
+    Ok(())
+}
+
+/// Page through `key` with XRANGE, oldest first, within `since` / `until`
+async fn read_stream(config: &Config, key: &str, reprocessor: &mut Reprocessor) -> Result<()> {
+    let client = redis::Client::open(config.database.redis_url.as_str())
+        .context("Invalid database.redis_url")?;
+    let mut conn = ConnectionManager::new(client)
+        .await
+        .context("Failed to connect to Redis")?;
+
+    // Entry ids start with their millisecond timestamp
+    let mut start = match reprocessor.options.since {
+        Some(since) => format!("{}-0", since.timestamp_millis()),
+        None => "-".to_string(),
+    };
+    let end = match reprocessor.options.until {
+        Some(until) => until.timestamp_millis().to_string(),
+        None => "+".to_string(),
+    };
+
+    loop {
+        let entries: Vec<(String, Vec<String>)> = redis::cmd("XRANGE")
+            .arg(key)
+            .arg(&start)
+            .arg(&end)
+            .arg("COUNT")
+            .arg(config.reprocess.batch_size.max(1))
+            .query_async(&mut conn)
+            .await
+            .with_context(|| format!("Failed to read stream {}", key))?;
+        let Some((last, _)) = entries.last() else {
+            break;
+        };
+        start = next_id(last).with_context(|| format!("Unexpected stream entry id {}", last))?;
+        let full = entries.len() >= config.reprocess.batch_size.max(1);
+
+        for (id, fields) in entries {
+            let data = fields
+                .chunks(2)
+                .find(|pair| pair[0] == "data")
+                .and_then(|pair| pair.get(1));
+            let Some(data) = data else {
+                continue;
+            };
+            match serde_json::from_str::<Envelope>(data) {
+                Ok(envelope) => reprocessor.handle(envelope).await?,
+                Err(e) => warn!("Skipping unreadable entry {} of {}: {}", id, key, e),
+            }
+        }
+        if !full {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// The smallest entry id after `id`, as XRANGE has no exclusive start before Redis 6.2
+fn next_id(id: &str) -> Option<String> {
+    let (ms, seq) = id.split_once('-')?;
+    let seq: u64 = seq.parse().ok()?;
+    Some(format!("{}-{}", ms, seq + 1))
+}
+
+async fn read_file(path: &Path, reprocessor: &mut Reprocessor) -> Result<()> {
+    let file =
+        std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let reader: Box<dyn Read> = if path.extension().is_some_and(|ext| ext == "gz") {
+        Box::new(MultiGzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+
+    for (index, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line.with_context(|| format!("Failed to read {}", path.display()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Envelope>(&line) {
+            Ok(envelope) => reprocessor.handle(envelope).await?,
+            Err(e) => warn!(
+                "Skipping unreadable line {} of {}: {}",
+                index + 1,
+                path.display(),
+                e
+            ),
+        }
+    }
+    Ok(())
+}
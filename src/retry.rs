@@ -0,0 +1,139 @@
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tracing::{info, warn};
+
+/// Exponential backoff with jitter.
+///
+/// Attempt `n` (1-based) waits a random duration between half and all of
+/// `initial_backoff * 2^(n-1)`, capped at `max_backoff`, so concurrent callers
+/// retrying the same failure spread out instead of hitting the endpoint together.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first one
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let ceiling = self
+            .initial_backoff
+            .saturating_mul(1 << exponent)
+            .min(self.max_backoff);
+        ceiling.mul_f64(rand::random_range(0.5..=1.0))
+    }
+}
+
+/// Run `op` until it succeeds, fails with an error `is_retryable` rejects, or
+/// the policy runs out of attempts. The last error is returned.
+pub async fn retry<T, E, F, Fut, R>(
+    policy: &RetryPolicy,
+    what: &str,
+    is_retryable: R,
+    mut op: F,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    R: Fn(&E) -> bool,
+    E: std::fmt::Display,
+{
+    let mut attempt: u32 = 1;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < policy.max_attempts && is_retryable(&e) => {
+                let delay = policy.delay(attempt);
+                warn!(
+                    "{} failed (attempt {}/{}), retrying in {:?}: {}",
+                    what, attempt, policy.max_attempts, delay, e
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Stops calls to a failing dependency for a while.
+///
+/// After `failure_threshold` consecutive failures the breaker opens for a
+/// cooldown that doubles on every further failure (up to `max_cooldown`).
+/// Once the cooldown has passed calls are let through again; the first success
+/// closes the breaker, another failure reopens it.
+pub struct CircuitBreaker {
+    name: String,
+    failure_threshold: u32,
+    max_cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    pub fn new(name: impl Into<String>, failure_threshold: u32, max_cooldown: Duration) -> Self {
+        Self {
+            name: name.into(),
+            failure_threshold: failure_threshold.max(1),
+            max_cooldown,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BreakerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Whether calls may go through at `now`
+    pub fn is_closed(&self, now: Instant) -> bool {
+        self.lock().open_until.is_none_or(|until| now >= until)
+    }
+
+    /// Returns `true` if this success closed an open breaker
+    pub fn record_success(&self) -> bool {
+        let mut state = self.lock();
+        state.consecutive_failures = 0;
+        let was_open = state.open_until.take().is_some();
+        if was_open {
+            info!("{} recovered, circuit closed", self.name);
+        }
+        was_open
+    }
+
+    /// Returns `true` if this failure (re)opened the breaker
+    pub fn record_failure(&self) -> bool {
+        let mut state = self.lock();
+        state.consecutive_failures += 1;
+        if state.consecutive_failures < self.failure_threshold {
+            return false;
+        }
+
+        let exponent = (state.consecutive_failures - self.failure_threshold).min(16);
+        let cooldown = Duration::from_secs(1 << exponent).min(self.max_cooldown);
+        warn!(
+            "{} failed {} times in a row, circuit open for {:?}",
+            self.name, state.consecutive_failures, cooldown
+        );
+        state.open_until = Some(Instant::now() + cooldown);
+        true
+    }
+}
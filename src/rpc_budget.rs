@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::{NaiveDate, Utc};
+use tracing::warn;
+
+use crate::config::RpcBudgetConfig;
+use crate::metrics;
+
+/// Counts HTTP RPC requests per method and endpoint and estimates the provider
+/// credits they cost from `[rpc_budget]` weights, warning once a day when
+/// `daily_credits` is exceeded
+pub struct RpcBudget {
+    weights: HashMap<String, f64>,
+    default_weight: f64,
+    daily_credits: u64,
+    today: Mutex<Usage>,
+}
+
+struct Usage {
+    day: NaiveDate,
+    credits: f64,
+    warned: bool,
+}
+
+impl Usage {
+    fn new(day: NaiveDate) -> Self {
+        Self {
+            day,
+            credits: 0.0,
+            warned: false,
+        }
+    }
+}
+
+impl RpcBudget {
+    pub fn new(config: &RpcBudgetConfig) -> Self {
+        Self {
+            weights: config.weights.clone(),
+            default_weight: config.default_weight,
+            daily_credits: config.daily_credits,
+            today: Mutex::new(Usage::new(Utc::now().date_naive())),
+        }
+    }
+
+    /// Note one request of `method` sent to `endpoint`
+    pub fn record(&self, method: &str, endpoint: &str) {
+        let weight = self
+            .weights
+            .get(method)
+            .copied()
+            .unwrap_or(self.default_weight);
+        metrics::RPC_REQUESTS
+            .with_label_values(&[method, endpoint])
+            .inc();
+        metrics::RPC_CREDITS
+            .with_label_values(&[endpoint])
+            .inc_by(weight);
+
+        let mut usage = self.today.lock().unwrap_or_else(|e| e.into_inner());
+        let day = Utc::now().date_naive();
+        if usage.day != day {
+            *usage = Usage::new(day);
+        }
+        usage.credits += weight;
+        metrics::RPC_CREDITS_TODAY.set(usage.credits);
+
+        if self.daily_credits > 0 && usage.credits > self.daily_credits as f64 && !usage.warned {
+            usage.warned = true;
+            warn!(
+                "RPC credit budget exceeded: about {:.0} of {} daily credits spent today",
+                usage.credits, self.daily_credits
+            );
+        }
+    }
+}
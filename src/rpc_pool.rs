@@ -0,0 +1,401 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use solana_client::client_error::{ClientError, ClientErrorKind, Result as ClientResult};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_custom_error::JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY;
+use solana_client::rpc_client::RpcClientConfig;
+use solana_client::rpc_request::RpcError;
+use solana_commitment_config::CommitmentConfig;
+use solana_rpc_client::http_sender::HttpSender;
+use tracing::{info, info_span, warn, Instrument};
+
+use crate::config::{NetworkConfig, RateLimitConfig, RpcBudgetConfig};
+use crate::fixtures::{FixtureRecorder, RecordingSender, ReplaySender};
+use crate::metrics;
+use crate::outbound;
+use crate::rate_limit::{self, RateLimiter};
+use crate::retry::{self, CircuitBreaker, RetryPolicy};
+use crate::rpc_budget::RpcBudget;
+
+/// Weight of the newest sample in the latency / error moving averages
+const EWMA_ALPHA: f64 = 0.2;
+/// Consecutive failures before an endpoint is taken out of rotation
+const FAILURES_BEFORE_COOLDOWN: u32 = 3;
+const MAX_COOLDOWN: Duration = Duration::from_secs(60);
+/// Endpoints scoring within this factor of the best one share the load
+const SCORE_SPREAD: f64 = 2.0;
+
+/// The configured HTTP and WSS RPC endpoints.
+///
+/// HTTP calls go through [`RpcPool::call`], which spreads them over the
+/// healthiest endpoints, scores each endpoint on latency and error rate, and
+/// moves on to another endpoint when one fails at the transport level.
+/// Every endpoint also has its own adaptive [`RateLimiter`], so a provider
+/// answering 429 is slowed down (and skipped while paused) without starving the others.
+/// Each listener is given a WSS URL round-robin and keeps it until the
+/// supervisor moves it on with [`RpcPool::rotate_wss`].
+pub struct RpcPool {
+    endpoints: Vec<Endpoint>,
+    wss_urls: Vec<String>,
+    next_http: AtomicUsize,
+    next_wss: AtomicUsize,
+    /// Index of the WSS URL each listener subscribes through
+    wss_assigned: Mutex<HashMap<String, usize>>,
+    commitment: CommitmentConfig,
+    /// Ceiling on one request to one endpoint, on top of the HTTP client's own
+    timeout: Duration,
+    retry: RetryPolicy,
+    /// Requests and credits spent, see [`RpcPool::with_budget`]
+    budget: RpcBudget,
+}
+
+struct Endpoint {
+    label: String,
+    client: Arc<RpcClient>,
+    limiter: RateLimiter,
+    breaker: CircuitBreaker,
+    health: Mutex<Health>,
+}
+
+struct Health {
+    latency_ms: f64,
+    error_rate: f64,
+}
+
+impl Health {
+    /// Lower is better
+    fn score(&self) -> f64 {
+        self.latency_ms.max(1.0) * (1.0 + 10.0 * self.error_rate)
+    }
+}
+
+/// How the pool's HTTP clients reach a node
+pub enum RpcTransport {
+    Http,
+    /// Talk to the node and record every exchange as a fixture
+    Record(Arc<FixtureRecorder>),
+    /// Answer from recorded fixtures, nothing goes over the network
+    Replay(ReplaySender),
+}
+
+impl RpcTransport {
+    fn client(
+        &self,
+        url: String,
+        timeout: Duration,
+        commitment: CommitmentConfig,
+    ) -> Result<RpcClient> {
+        Ok(match self {
+            RpcTransport::Http => http_client(url, timeout, commitment)?,
+            RpcTransport::Record(recorder) => RpcClient::new_sender(
+                RecordingSender::new(http_client(url, timeout, commitment)?, recorder.clone()),
+                RpcClientConfig::with_commitment(commitment),
+            ),
+            RpcTransport::Replay(sender) => RpcClient::new_sender(
+                sender.clone(),
+                RpcClientConfig::with_commitment(commitment),
+            ),
+        })
+    }
+}
+
+/// solana-client's own HTTP client, or one going through `[outbound]`
+pub(crate) fn http_client(
+    url: String,
+    timeout: Duration,
+    commitment: CommitmentConfig,
+) -> Result<RpcClient> {
+    if !outbound::is_configured() {
+        return Ok(RpcClient::new_with_timeout_and_commitment(
+            url, timeout, commitment,
+        ));
+    }
+    let client = outbound::rpc_client_builder()
+        .default_headers(HttpSender::default_headers())
+        .timeout(timeout)
+        .pool_idle_timeout(timeout)
+        .build()
+        .context("Failed to build RPC HTTP client")?;
+    Ok(RpcClient::new_sender(
+        HttpSender::new_with_client(url, client),
+        RpcClientConfig::with_commitment(commitment),
+    ))
+}
+
+impl RpcPool {
+    pub fn new(network: &NetworkConfig, rate_limit: &RateLimitConfig) -> Result<Self> {
+        Self::with_transport(network, rate_limit, &RpcTransport::Http)
+    }
+
+    pub fn with_transport(
+        network: &NetworkConfig,
+        rate_limit: &RateLimitConfig,
+        transport: &RpcTransport,
+    ) -> Result<Self> {
+        let commitment = network.commitment_config()?;
+        let timeout = Duration::from_secs(network.rpc_timeout_secs);
+
+        let http_urls = network.http_urls();
+        let wss_urls = network.wss_urls();
+        if http_urls.is_empty() || wss_urls.is_empty() {
+            bail!("At least one HTTP and one WSS RPC endpoint must be configured");
+        }
+
+        let mut labels: Vec<String> = Vec::with_capacity(http_urls.len());
+        let endpoints = http_urls
+            .into_iter()
+            .map(|url| {
+                let mut label = endpoint_label(&url);
+                if labels.contains(&label) {
+                    // Same provider with different keys/paths
+                    label = format!("{}#{}", label, labels.len());
+                }
+                labels.push(label.clone());
+                metrics::RPC_ENDPOINT_HEALTHY
+                    .with_label_values(&[label.as_str()])
+                    .set(1);
+                Ok(Endpoint {
+                    limiter: RateLimiter::new(&label, rate_limit),
+                    breaker: CircuitBreaker::new(
+                        format!("RPC endpoint {}", label),
+                        FAILURES_BEFORE_COOLDOWN,
+                        MAX_COOLDOWN,
+                    ),
+                    label,
+                    client: Arc::new(transport.client(url, timeout, commitment)?),
+                    health: Mutex::new(Health {
+                        latency_ms: 0.0,
+                        error_rate: 0.0,
+                    }),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        info!(
+            "RPC pool with {} HTTP and {} WSS endpoint(s)",
+            endpoints.len(),
+            wss_urls.len()
+        );
+
+        Ok(Self {
+            endpoints,
+            wss_urls,
+            next_http: AtomicUsize::new(0),
+            next_wss: AtomicUsize::new(0),
+            wss_assigned: Mutex::new(HashMap::new()),
+            commitment,
+            timeout,
+            retry: RetryPolicy::default(),
+            budget: RpcBudget::new(&RpcBudgetConfig::default()),
+        })
+    }
+
+    /// Weigh requests with `[rpc_budget]` and warn past its daily budget
+    pub fn with_budget(mut self, config: &RpcBudgetConfig) -> Self {
+        self.budget = RpcBudget::new(config);
+        self
+    }
+
+    pub fn commitment(&self) -> CommitmentConfig {
+        self.commitment
+    }
+
+    /// WSS endpoint `listener` subscribes through
+    pub fn wss_url(&self, listener: &str) -> &str {
+        let mut assigned = self.wss_assigned.lock().unwrap_or_else(|e| e.into_inner());
+        let index = *assigned
+            .entry(listener.to_string())
+            .or_insert_with(|| self.next_wss.fetch_add(1, Ordering::Relaxed) % self.wss_urls.len());
+        &self.wss_urls[index]
+    }
+
+    /// Move `listener` to the next WSS endpoint; its 1-based position, `None`
+    /// when there is no other endpoint
+    pub fn rotate_wss(&self, listener: &str) -> Option<usize> {
+        if self.wss_urls.len() < 2 {
+            return None;
+        }
+        let mut assigned = self.wss_assigned.lock().unwrap_or_else(|e| e.into_inner());
+        let index = assigned.entry(listener.to_string()).or_default();
+        *index = (*index + 1) % self.wss_urls.len();
+        Some(*index + 1)
+    }
+
+    /// Run one RPC request against the best available endpoint.
+    ///
+    /// Waits for the endpoint's rate limiter first. Throttling (429) and
+    /// transport failures (timeouts, HTTP errors, unhealthy nodes) are retried
+    /// on the remaining endpoints, and the whole pool is retried with backoff
+    /// when every endpoint failed; any other error is returned as-is because a
+    /// different endpoint would answer the same way.
+    pub async fn call<T, F, Fut>(&self, method: &str, request: F) -> ClientResult<T>
+    where
+        F: Fn(Arc<RpcClient>) -> Fut,
+        Fut: Future<Output = ClientResult<T>>,
+    {
+        retry::retry(
+            &self.retry,
+            method,
+            |e| is_endpoint_failure(e) || rate_limit::rate_limit_hint(e).is_some(),
+            || self.call_once(method, &request),
+        )
+        .instrument(info_span!("rpc", method))
+        .await
+    }
+
+    /// One pass over the endpoints
+    async fn call_once<T, F, Fut>(&self, method: &str, request: &F) -> ClientResult<T>
+    where
+        F: Fn(Arc<RpcClient>) -> Fut,
+        Fut: Future<Output = ClientResult<T>>,
+    {
+        let mut tried: Vec<usize> = Vec::with_capacity(self.endpoints.len());
+
+        loop {
+            let index = self.pick(&tried);
+            let endpoint = &self.endpoints[index];
+
+            endpoint.limiter.acquire().await;
+
+            let timer = metrics::RPC_FETCH_SECONDS
+                .with_label_values(&[method])
+                .start_timer();
+            let started = Instant::now();
+            // A request outliving the timeout counts as a transport failure of the endpoint
+            let result = tokio::time::timeout(self.timeout, request(endpoint.client.clone()))
+                .await
+                .unwrap_or_else(|_| {
+                    Err(ClientErrorKind::Io(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        format!("{} timed out after {:?}", method, self.timeout),
+                    ))
+                    .into())
+                });
+            timer.observe_duration();
+            self.budget.record(method, &endpoint.label);
+
+            let throttled = result.as_ref().err().and_then(rate_limit::rate_limit_hint);
+
+            match result {
+                Err(e) if throttled.is_some() => {
+                    endpoint.limiter.on_rate_limited(throttled.flatten());
+                    tried.push(index);
+                    if tried.len() >= self.endpoints.len() {
+                        return Err(e);
+                    }
+                }
+                Err(e) if is_endpoint_failure(&e) => {
+                    self.record_failure(index);
+                    tried.push(index);
+                    if tried.len() >= self.endpoints.len() {
+                        return Err(e);
+                    }
+                    warn!(
+                        "{} via {} failed, trying another endpoint: {}",
+                        method, endpoint.label, e
+                    );
+                }
+                other => {
+                    endpoint.limiter.on_success();
+                    self.record_success(index, started.elapsed());
+                    return other;
+                }
+            }
+        }
+    }
+
+    /// Round-robin over the available endpoints whose score is close to the best.
+    /// Endpoints in cooldown or paused by a 429 only get picked when nothing else is left.
+    fn pick(&self, exclude: &[usize]) -> usize {
+        let now = Instant::now();
+        let healths: Vec<(usize, bool, f64)> = self
+            .endpoints
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !exclude.contains(i))
+            .map(|(i, endpoint)| {
+                let health = endpoint.health.lock().unwrap_or_else(|e| e.into_inner());
+                let available = endpoint.breaker.is_closed(now) && !endpoint.limiter.is_paused(now);
+                (i, available, health.score())
+            })
+            .collect();
+
+        let available: Vec<&(usize, bool, f64)> = healths.iter().filter(|h| h.1).collect();
+        if available.is_empty() {
+            // Everything is cooling down: fall back to the best of the rest
+            return healths
+                .iter()
+                .min_by(|a, b| a.2.total_cmp(&b.2))
+                .map(|h| h.0)
+                .unwrap_or(0);
+        }
+
+        let best = available
+            .iter()
+            .map(|h| h.2)
+            .fold(f64::INFINITY, f64::min);
+        let candidates: Vec<usize> = available
+            .iter()
+            .filter(|h| h.2 <= best * SCORE_SPREAD)
+            .map(|h| h.0)
+            .collect();
+
+        candidates[self.next_http.fetch_add(1, Ordering::Relaxed) % candidates.len()]
+    }
+
+    fn record_success(&self, index: usize, latency: Duration) {
+        let endpoint = &self.endpoints[index];
+        let mut health = endpoint.health.lock().unwrap_or_else(|e| e.into_inner());
+
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        health.latency_ms = if health.latency_ms == 0.0 {
+            latency_ms
+        } else {
+            EWMA_ALPHA * latency_ms + (1.0 - EWMA_ALPHA) * health.latency_ms
+        };
+        health.error_rate *= 1.0 - EWMA_ALPHA;
+
+        if endpoint.breaker.record_success() {
+            metrics::RPC_ENDPOINT_HEALTHY
+                .with_label_values(&[endpoint.label.as_str()])
+                .set(1);
+        }
+    }
+
+    fn record_failure(&self, index: usize) {
+        let endpoint = &self.endpoints[index];
+        let mut health = endpoint.health.lock().unwrap_or_else(|e| e.into_inner());
+
+        health.error_rate = EWMA_ALPHA + (1.0 - EWMA_ALPHA) * health.error_rate;
+
+        if endpoint.breaker.record_failure() {
+            metrics::RPC_ENDPOINT_HEALTHY
+                .with_label_values(&[endpoint.label.as_str()])
+                .set(0);
+        }
+    }
+}
+
+pub(crate) fn is_endpoint_failure(error: &ClientError) -> bool {
+    match error.kind() {
+        ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_) => true,
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => {
+            *code == JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY
+        }
+        _ => false,
+    }
+}
+
+/// Host of an endpoint URL, so API keys in paths or query strings never end up in logs or metrics
+pub(crate) fn endpoint_label(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_string))
+        .unwrap_or_else(|| "unknown".to_string())
+}
@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Client, StatusCode};
+
+use crate::config::{NegativeCacheConfig, RateLimitConfig, RugCheckConfig};
+use crate::metrics;
+use crate::models::{RugCheckReport, RugCheckRisk};
+use crate::negative_cache::NegativeCache;
+use crate::outbound;
+use crate::rate_limit::RateLimiter;
+
+/// rugcheck.xyz API client for tokens `delay_secs` old, held under
+/// `requests_per_sec`
+pub struct RugCheck {
+    http: Client,
+    url: String,
+    delay: Duration,
+    limiter: RateLimiter,
+    misses: NegativeCache,
+}
+
+impl RugCheck {
+    pub fn new(config: &RugCheckConfig, negative: &NegativeCacheConfig) -> Result<Self> {
+        let http = outbound::client_builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .context("Failed to build RugCheck HTTP client")?;
+        let host = reqwest::Url::parse(&config.url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_else(|| config.url.clone());
+        let limiter = RateLimiter::new(
+            &host,
+            &RateLimitConfig {
+                max_requests_per_sec: config.requests_per_sec,
+                min_requests_per_sec: config.requests_per_sec / 10.0,
+                ..RateLimitConfig::default()
+            },
+        );
+        Ok(Self {
+            http,
+            url: config.url.trim_end_matches('/').to_string(),
+            delay: Duration::from_secs(config.delay_secs),
+            limiter,
+            misses: NegativeCache::new("rugcheck", negative),
+        })
+    }
+
+    /// How long after discovery tokens are looked up
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// The report summary of `mint`, `None` when RugCheck has none (yet)
+    pub async fn report(&self, mint: &str) -> Result<Option<RugCheckReport>> {
+        if self.misses.contains(mint) {
+            return Ok(None);
+        }
+        let result = self.fetch(mint).await;
+        let outcome = match &result {
+            Ok(Some(_)) => "ok",
+            Ok(None) => "unknown",
+            Err(_) => "error",
+        };
+        if outcome != "ok" {
+            self.misses.insert(mint).await;
+        }
+        metrics::RUGCHECK_REQUESTS
+            .with_label_values(&[outcome])
+            .inc();
+        result
+    }
+
+    async fn fetch(&self, mint: &str) -> Result<Option<RugCheckReport>> {
+        self.limiter.acquire().await;
+        let response = self
+            .http
+            .get(format!("{}/v1/tokens/{}/report/summary", self.url, mint))
+            .send()
+            .await
+            .context("RugCheck request failed")?;
+        match response.status() {
+            StatusCode::NOT_FOUND => {
+                self.limiter.on_success();
+                return Ok(None);
+            }
+            StatusCode::TOO_MANY_REQUESTS => {
+                let retry_after = response
+                    .headers()
+                    .get(RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse().ok())
+                    .map(Duration::from_secs);
+                self.limiter.on_rate_limited(retry_after);
+            }
+            _ => self.limiter.on_success(),
+        }
+
+        let raw: serde_json::Value = response
+            .error_for_status()
+            .context("RugCheck request failed")?
+            .json()
+            .await
+            .context("Invalid RugCheck response")?;
+        let risks = raw
+            .get("risks")
+            .filter(|risks| !risks.is_null())
+            .cloned()
+            .map(serde_json::from_value::<Vec<RugCheckRisk>>)
+            .transpose()
+            .context("Invalid RugCheck risks")?
+            .unwrap_or_default();
+        let score = raw
+            .get("score_normalised")
+            .and_then(serde_json::Value::as_u64)
+            .map(|score| score.min(100) as u32);
+        Ok(Some(RugCheckReport { score, risks, raw }))
+    }
+}
@@ -0,0 +1,126 @@
+use bigdecimal::ToPrimitive;
+
+use crate::models::{FundingSource, RiskLevel, Token};
+
+const MAX_SCORE: i32 = 100;
+/// Dev buys up to this share of the supply are common and not penalized
+const FREE_DEV_BUY_PERCENTAGE: f64 = 5.0;
+const HIGH_RISK_DEV_BUY_PERCENTAGE: f64 = 20.0;
+/// RugCheck `danger` flags that make a token high risk on their own
+const HIGH_RISK_RUGCHECK_DANGERS: usize = 2;
+/// Share of the early volume traded back and forth that makes a token high risk
+const HIGH_RISK_WASH_SCORE: f64 = 50.0;
+
+/// Score a token from 0 (avoid) to 100 (clean) using the on-chain facts
+/// collected at discovery time.
+pub fn score_token(token: &Token) -> (i32, RiskLevel) {
+    let mut score = MAX_SCORE;
+
+    if !token.mint_authority_disabled {
+        score -= 30;
+    }
+    if !token.freeze_authority_disabled {
+        score -= 30;
+    }
+    if token.has_permanent_delegate {
+        score -= 40;
+    }
+    if token.has_transfer_hook {
+        score -= 50;
+    }
+    if let Some(bps) = token.transfer_fee_bps {
+        // 1% fee costs 10 points, capped so fees alone never dominate
+        score -= (i32::from(bps) / 10).min(30);
+    }
+    if let Some(funding) = &token.creator_funding {
+        // A deployer handing SOL to a fresh wallet is how serial launchers hide
+        if funding.source == FundingSource::Deployer {
+            score -= 30;
+        }
+        if funding.fresh_wallet {
+            score -= 10;
+        }
+    }
+    let dev_buy = dev_buy_percentage(token);
+    if dev_buy > FREE_DEV_BUY_PERCENTAGE {
+        // 2 points per percent of supply above the allowance
+        score -= ((dev_buy - FREE_DEV_BUY_PERCENTAGE) * 2.0).min(40.0) as i32;
+    }
+
+    if token.creator_blacklisted {
+        score -= 50;
+    }
+    if token.impersonation_of.is_some() {
+        score -= 25;
+    }
+    if token.sellable == Some(false) {
+        score -= 60;
+    }
+    // Graduated, and the LP tokens can still be redeemed: the liquidity can be pulled
+    if token.liquidity_locked == Some(false) {
+        score -= 30;
+    }
+    let frozen_holders = token.frozen_holders.unwrap_or(0);
+    if frozen_holders > 0 {
+        score -= 50;
+    }
+    // Advertising socials that do not exist is worse than having none
+    let dead_links = token.socials.iter().filter(|link| !link.live).count() as i32;
+    score -= (dead_links * 10).min(20);
+    // RugCheck repeats some of the checks above, hence the caps
+    let (rugcheck_dangers, rugcheck_warnings) = token.rugcheck.as_ref().map_or((0, 0), |report| {
+        (report.count("danger"), report.count("warn"))
+    });
+    score -= (rugcheck_dangers as i32 * 15).min(45);
+    score -= (rugcheck_warnings as i32 * 5).min(15);
+    // Volume going back and forth fakes demand: a point per 2% of it
+    let wash_score = token
+        .wash_score
+        .as_ref()
+        .and_then(|p| p.to_f64())
+        .unwrap_or(0.0);
+    score -= (wash_score / 2.0).min(30.0) as i32;
+
+    let score = score.clamp(0, MAX_SCORE);
+
+    // A transfer hook or permanent delegate can block or claw back sells:
+    // classic honeypot, regardless of everything else
+    // So does a creator holding a large share of the supply from the start,
+    // or one that rugged before, a sell that fails in simulation, holders
+    // already frozen by the freeze authority, several RugCheck dangers or
+    // early volume that is mostly wash trading
+    let risk_level = if token.creator_blacklisted
+        || token.sellable == Some(false)
+        || frozen_holders > 0
+        || token.has_transfer_hook
+        || token.has_permanent_delegate
+        || dev_buy >= HIGH_RISK_DEV_BUY_PERCENTAGE
+        || rugcheck_dangers >= HIGH_RISK_RUGCHECK_DANGERS
+        || wash_score >= HIGH_RISK_WASH_SCORE
+    {
+        RiskLevel::High
+    } else if score >= 70 {
+        RiskLevel::Low
+    } else if score >= 40 {
+        RiskLevel::Medium
+    } else {
+        RiskLevel::High
+    };
+
+    (score, risk_level)
+}
+
+fn dev_buy_percentage(token: &Token) -> f64 {
+    token
+        .dev_buy_percentage
+        .as_ref()
+        .and_then(|p| p.to_f64())
+        .unwrap_or(0.0)
+}
+
+/// Fill in `score` and `risk_level` on the token
+pub fn apply(token: &mut Token) {
+    let (score, risk_level) = score_token(token);
+    token.score = Some(score);
+    token.risk_level = Some(risk_level);
+}
@@ -0,0 +1,201 @@
+use std::future::Future;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use futures::StreamExt;
+use redis::aio::ConnectionManager;
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use tracing::{error, info};
+
+use crate::config::{Config, DatabaseBackend};
+use crate::rpc_pool::{endpoint_label, http_client};
+
+/// Owners of deployed programs: the upgradeable, v2, deprecated and v4 BPF
+/// loaders, and the native loader of builtins
+const LOADERS: [&str; 5] = [
+    "BPFLoaderUpgradeab1e11111111111111111111111",
+    "BPFLoader2111111111111111111111111111111111",
+    "BPFLoader1111111111111111111111111111111111",
+    "LoaderV411111111111111111111111111111111111",
+    "NativeLoader1111111111111111111111111111111",
+];
+
+/// Outcome of one check, for the startup report
+struct Check {
+    name: String,
+    result: Result<String>,
+}
+
+/// Check before subscribing that every HTTP endpoint answers `getVersion`,
+/// every WSS endpoint streams a slot subscription, Redis answers `PING` when
+/// something uses it and the program ids of the enabled listeners are
+/// deployed programs. Every check is logged and the failed ones are listed in
+/// the error.
+pub async fn run(config: &Config) -> Result<()> {
+    let timeout = Duration::from_secs(config.self_test.timeout_secs.max(1));
+    let commitment = config.network.commitment_config()?;
+    let mut checks = Vec::new();
+    // The program accounts are read from the first endpoint that answers
+    let mut reachable = None;
+
+    for (index, url) in config.network.http_urls().into_iter().enumerate() {
+        let name = format!("rpc #{} ({})", index + 1, endpoint_label(&url));
+        let client = http_client(url, timeout, commitment)?;
+        let result = within(timeout, async {
+            let version = client.get_version().await.map_err(without_url)?;
+            Ok(format!("solana-core {}", version.solana_core))
+        })
+        .await;
+        if result.is_ok() && reachable.is_none() {
+            reachable = Some(client);
+        }
+        checks.push(Check { name, result });
+    }
+
+    for (index, url) in config.network.wss_urls().into_iter().enumerate() {
+        checks.push(Check {
+            name: format!("wss #{} ({})", index + 1, endpoint_label(&url)),
+            result: within(timeout, check_wss(&url)).await,
+        });
+    }
+
+    if config.database.backend == DatabaseBackend::Redis && config.uses_redis() {
+        checks.push(Check {
+            name: "redis".to_string(),
+            result: within(timeout, check_redis(&config.database.redis_url)).await,
+        });
+    }
+
+    let programs = programs(config);
+    if !programs.is_empty() {
+        let result = match &reachable {
+            Some(client) => within(timeout, check_programs(client, &programs)).await,
+            None => Err(anyhow!("no HTTP RPC endpoint to read them from")),
+        };
+        checks.push(Check {
+            name: "programs".to_string(),
+            result,
+        });
+    }
+
+    let mut failed = Vec::new();
+    for check in &checks {
+        match &check.result {
+            Ok(detail) => info!("Self-test {}: ok, {}", check.name, detail),
+            Err(e) => {
+                error!("Self-test {}: {:#}", check.name, e);
+                failed.push(format!("{}: {:#}", check.name, e));
+            }
+        }
+    }
+    if !failed.is_empty() {
+        bail!(
+            "Startup self-test failed ({} of {} checks): {}",
+            failed.len(),
+            checks.len(),
+            failed.join("; ")
+        );
+    }
+    info!("Self-test passed ({} checks)", checks.len());
+    Ok(())
+}
+
+async fn within<T>(timeout: Duration, check: impl Future<Output = Result<T>>) -> Result<T> {
+    tokio::time::timeout(timeout, check)
+        .await
+        .map_err(|_| anyhow!("no answer within {}s", timeout.as_secs()))?
+}
+
+/// The endpoint URL of transport errors may carry an API key
+fn without_url(error: ClientError) -> anyhow::Error {
+    match *error.kind {
+        ClientErrorKind::Reqwest(e) => anyhow!(e.without_url()),
+        kind => anyhow!(kind),
+    }
+}
+
+/// Subscribe to slots and wait for the first notification
+async fn check_wss(url: &str) -> Result<String> {
+    let pubsub = PubsubClient::new(url).await.context("connect")?;
+    let (mut slots, unsubscribe) = pubsub.slot_subscribe().await.context("slotSubscribe")?;
+    let slot = slots.next().await.map(|info| info.slot);
+    unsubscribe().await;
+    let slot = slot.context("subscription closed before the first slot")?;
+    Ok(format!("slot {}", slot))
+}
+
+async fn check_redis(url: &str) -> Result<String> {
+    let client = redis::Client::open(url).context("Invalid database.redis_url")?;
+    let mut conn = ConnectionManager::new(client).await?;
+    let pong: String = redis::cmd("PING").query_async(&mut conn).await?;
+    Ok(pong)
+}
+
+/// Program ids of the enabled listeners, with the key they are configured under
+fn programs(config: &Config) -> Vec<(&'static str, &str)> {
+    let ingestion = &config.ingestion;
+    let programs = &config.programs;
+    let mut ids = Vec::new();
+    if ingestion.track_pumpfun {
+        ids.extend(
+            programs
+                .pump_fun
+                .iter()
+                .map(|id| ("programs.pump_fun", id.as_str())),
+        );
+    }
+    if ingestion.track_launchlab {
+        ids.push(("programs.launchlab", programs.launchlab.as_str()));
+    }
+    if ingestion.track_orca {
+        ids.push(("programs.orca_whirlpool", programs.orca_whirlpool.as_str()));
+    }
+    if ingestion.track_meteora {
+        ids.push(("programs.meteora_dlmm", programs.meteora_dlmm.as_str()));
+        ids.push(("programs.meteora_dbc", programs.meteora_dbc.as_str()));
+    }
+    if ingestion.track_onchain {
+        ids.push(("programs.token_program", programs.token_program.as_str()));
+        ids.push((
+            "programs.token_2022_program",
+            programs.token_2022_program.as_str(),
+        ));
+    }
+    ids
+}
+
+/// Every id must be an executable account owned by a loader
+async fn check_programs(client: &RpcClient, programs: &[(&str, &str)]) -> Result<String> {
+    let keys = programs
+        .iter()
+        .map(|(key, id)| {
+            Pubkey::from_str(id).map_err(|e| anyhow!("{} {} is not a pubkey: {}", key, id, e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let accounts = client
+        .get_multiple_accounts(&keys)
+        .await
+        .map_err(without_url)?;
+
+    let mut problems = Vec::new();
+    for ((key, id), account) in programs.iter().zip(accounts) {
+        let owner = account.as_ref().map(|account| account.owner.to_string());
+        let problem = match (&account, owner) {
+            (None, _) => "does not exist".to_string(),
+            (Some(account), _) if !account.executable => "is not executable".to_string(),
+            (_, Some(owner)) if !LOADERS.contains(&owner.as_str()) => {
+                format!("is owned by {}, not a loader", owner)
+            }
+            _ => continue,
+        };
+        problems.push(format!("{} {} {}", key, id, problem));
+    }
+    if !problems.is_empty() {
+        bail!("{}", problems.join(", "));
+    }
+    Ok(format!("{} deployed", keys.len()))
+}
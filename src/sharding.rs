@@ -0,0 +1,197 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use redis::aio::ConnectionManager;
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use crate::config::ShardingConfig;
+use crate::leader::default_instance_id;
+use crate::metrics;
+
+/// Heartbeats this instance, then keeps, releases or claims shard leases so it
+/// holds its fair share, `ceil(shards / live instances)`; returns the shards held
+const BALANCE_SCRIPT: &str = r#"
+local prefix, id = KEYS[1], ARGV[1]
+local lease, now, shards = tonumber(ARGV[2]), tonumber(ARGV[3]), tonumber(ARGV[4])
+local members = prefix .. ':members'
+redis.call('ZADD', members, now, id)
+redis.call('ZREMRANGEBYSCORE', members, '-inf', now - lease)
+redis.call('PEXPIRE', members, lease)
+local share = math.ceil(shards / redis.call('ZCARD', members))
+local held = {}
+for shard = 0, shards - 1 do
+    local key = prefix .. ':' .. shard
+    if redis.call('GET', key) == id then
+        if #held < share then
+            redis.call('PEXPIRE', key, lease)
+            table.insert(held, shard)
+        else
+            redis.call('DEL', key)
+        end
+    end
+end
+for shard = 0, shards - 1 do
+    if #held >= share then
+        break
+    end
+    if redis.call('SET', prefix .. ':' .. shard, id, 'NX', 'PX', lease) then
+        table.insert(held, shard)
+    end
+end
+return held
+"#;
+
+const RELEASE_SCRIPT: &str = r#"
+local prefix, id = KEYS[1], ARGV[1]
+redis.call('ZREM', prefix .. ':members', id)
+for shard = 0, tonumber(ARGV[2]) - 1 do
+    local key = prefix .. ':' .. shard
+    if redis.call('GET', key) == id then
+        redis.call('DEL', key)
+    end
+end
+return 0
+"#;
+
+/// Splits the RPC-heavy part of processing between instances: every instance
+/// subscribes to the logs, but a log keyed to a mint (or signature) is only
+/// handled by the instance owning its shard.
+///
+/// Shards are leased in Redis under `sharding.key_prefix` for `lease_secs` and
+/// rebalanced every `renew_interval_secs` as instances come and go; shards of a
+/// dead instance are taken over once their lease expires.
+pub struct Shards {
+    conn: ConnectionManager,
+    prefix: String,
+    id: String,
+    lease: Duration,
+    renew_interval: Duration,
+    owned: Vec<AtomicBool>,
+    /// Held during each rebalance, set once released
+    released: Mutex<bool>,
+}
+
+impl Shards {
+    pub async fn new(config: &ShardingConfig, redis_url: &str) -> Result<Self> {
+        let client = redis::Client::open(redis_url).context("Invalid redis_url for sharding")?;
+        let conn = ConnectionManager::new(client)
+            .await
+            .context("Failed to connect to Redis for sharding")?;
+        let id = config
+            .instance_id
+            .clone()
+            .unwrap_or_else(default_instance_id);
+        info!(
+            "Sharding enrichment over {} shards under {} as {}",
+            config.shards, config.key_prefix, id
+        );
+        metrics::SHARDS_OWNED.set(0);
+
+        Ok(Self {
+            conn,
+            prefix: config.key_prefix.clone(),
+            id,
+            lease: Duration::from_secs(config.lease_secs),
+            renew_interval: Duration::from_secs(config.renew_interval_secs),
+            owned: (0..config.shards).map(|_| AtomicBool::new(false)).collect(),
+            released: Mutex::new(false),
+        })
+    }
+
+    /// Whether this instance handles `key` (a mint address or signature)
+    pub fn owns(&self, key: &str) -> bool {
+        let hash = Sha256::digest(key.as_bytes());
+        let hash = u64::from_be_bytes(hash[..8].try_into().expect("digest has 32 bytes"));
+        self.owned[(hash % self.owned.len() as u64) as usize].load(Ordering::Relaxed)
+    }
+
+    /// Rebalance until [`Shards::release`]
+    pub fn spawn(self: &Arc<Self>) {
+        let shards = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(shards.renew_interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut lease_until = None;
+            loop {
+                ticker.tick().await;
+                let released = shards.released.lock().await;
+                if *released {
+                    return;
+                }
+                let attempt = Instant::now();
+                match shards.balance().await {
+                    Ok(held) => {
+                        lease_until = Some(attempt + shards.lease);
+                        shards.set_owned(&held);
+                    }
+                    Err(e) => {
+                        warn!("Shard rebalance under {} failed: {:#}", shards.prefix, e);
+                        // Leases may run out before the next attempt
+                        let next_attempt = Instant::now() + shards.renew_interval;
+                        if lease_until.is_some_and(|until| next_attempt >= until) {
+                            lease_until = None;
+                            shards.set_owned(&[]);
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    async fn balance(&self) -> Result<Vec<u32>> {
+        let mut conn = self.conn.clone();
+        redis::cmd("EVAL")
+            .arg(BALANCE_SCRIPT)
+            .arg(1)
+            .arg(&self.prefix)
+            .arg(&self.id)
+            .arg(self.lease.as_millis() as u64)
+            .arg(Utc::now().timestamp_millis())
+            .arg(self.owned.len())
+            .query_async(&mut conn)
+            .await
+            .context("Failed to rebalance shards")
+    }
+
+    fn set_owned(&self, held: &[u32]) {
+        let mut changed = false;
+        for (shard, owned) in self.owned.iter().enumerate() {
+            let own = held.contains(&(shard as u32));
+            changed |= owned.swap(own, Ordering::Relaxed) != own;
+        }
+        if changed {
+            info!(
+                "{} now enriches {} of {} shards",
+                self.id,
+                held.len(),
+                self.owned.len()
+            );
+        }
+        metrics::SHARDS_OWNED.set(held.len() as i64);
+    }
+
+    /// Stop rebalancing and free this instance's shards for the others right away
+    pub async fn release(&self) {
+        let mut released = self.released.lock().await;
+        *released = true;
+        self.set_owned(&[]);
+        let mut conn = self.conn.clone();
+        let result: redis::RedisResult<u32> = redis::cmd("EVAL")
+            .arg(RELEASE_SCRIPT)
+            .arg(1)
+            .arg(&self.prefix)
+            .arg(&self.id)
+            .arg(self.owned.len())
+            .query_async(&mut conn)
+            .await;
+        if let Err(e) = result {
+            warn!("Failed to release shards, they expire in time: {}", e);
+        }
+    }
+}
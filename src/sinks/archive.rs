@@ -0,0 +1,273 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Url};
+use sha2::{Digest, Sha256};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info, warn};
+
+use crate::config::ArchiveConfig;
+use crate::metrics;
+use crate::models::Envelope;
+use crate::outbound;
+use crate::sinks::EventSink;
+
+/// Spool files are named after the hour their events were received in
+const HOUR_FORMAT: &str = "%Y%m%d%H";
+/// Time left for writes that picked an hour just before it ended
+const UPLOAD_GRACE: chrono::Duration = chrono::Duration::minutes(1);
+const UPLOAD_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Appends events to an hourly NDJSON spool file, and uploads every finished
+/// hour gzipped to S3-compatible storage. Spool files outlive restarts and are
+/// only removed once uploaded.
+pub struct ArchiveSink {
+    name: String,
+    spool_dir: PathBuf,
+    /// The spool file being written and its hour
+    current: Mutex<Option<(String, File)>>,
+}
+
+impl ArchiveSink {
+    pub async fn new(config: &ArchiveConfig) -> Result<Self> {
+        tokio::fs::create_dir_all(&config.spool_dir)
+            .await
+            .with_context(|| format!("Failed to create archive spool {}", config.spool_dir))?;
+
+        let uploader = Arc::new(Uploader::new(config)?);
+        let spool_dir = PathBuf::from(&config.spool_dir);
+        let spool = spool_dir.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(UPLOAD_CHECK_INTERVAL);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = upload_finished_hours(&spool, &uploader).await {
+                    error!("Failed to archive events: {:#}", e);
+                }
+            }
+        });
+
+        Ok(Self {
+            name: format!("archive:{}", config.bucket),
+            spool_dir,
+            current: Mutex::new(None),
+        })
+    }
+}
+
+#[async_trait]
+impl EventSink for ArchiveSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn send(&self, event: &Envelope) -> Result<()> {
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+
+        let hour = Utc::now().format(HOUR_FORMAT).to_string();
+        let mut current = self.current.lock().await;
+        if current.as_ref().is_none_or(|(open, _)| *open != hour) {
+            let path = self.spool_dir.join(format!("{}.ndjson", hour));
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .await
+                .with_context(|| format!("Failed to open {}", path.display()))?;
+            *current = Some((hour, file));
+        }
+        let (_, file) = current.as_mut().expect("spool file opened above");
+        file.write_all(&line).await?;
+        file.flush().await?;
+        Ok(())
+    }
+}
+
+async fn upload_finished_hours(spool_dir: &Path, uploader: &Uploader) -> Result<()> {
+    let mut entries = tokio::fs::read_dir(spool_dir).await?;
+    let mut finished = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let Some(hour) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".ndjson"))
+            .and_then(parse_hour)
+        else {
+            continue;
+        };
+        if hour + chrono::Duration::hours(1) + UPLOAD_GRACE <= Utc::now() {
+            finished.push((hour, path));
+        }
+    }
+    finished.sort();
+
+    for (hour, path) in finished {
+        let raw = tokio::fs::read(&path).await?;
+        if !raw.is_empty() {
+            let body = tokio::task::spawn_blocking(move || {
+                let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+                gzip.write_all(&raw)?;
+                gzip.finish()
+            })
+            .await??;
+            let key = hour.format("%Y/%m/%d/%H.ndjson.gz").to_string();
+            if let Err(e) = uploader.put(&key, body).await {
+                metrics::ARCHIVE_UPLOADS.with_label_values(&["error"]).inc();
+                warn!(
+                    "Failed to upload {}, retrying later: {:#}",
+                    path.display(),
+                    e
+                );
+                // Keep hours in order
+                return Ok(());
+            }
+            metrics::ARCHIVE_UPLOADS.with_label_values(&["ok"]).inc();
+            info!("Archived {} as {}", path.display(), key);
+        }
+        tokio::fs::remove_file(&path).await?;
+    }
+    Ok(())
+}
+
+fn parse_hour(name: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(&format!("{}0000", name), "%Y%m%d%H%M%S")
+        .ok()
+        .map(|hour| hour.and_utc())
+}
+
+/// PUTs objects signed with AWS Signature Version 4
+struct Uploader {
+    client: Client,
+    bucket: String,
+    region: String,
+    endpoint: Url,
+    path_style: bool,
+    prefix: String,
+    access_key_id: String,
+    secret_access_key: String,
+}
+
+impl Uploader {
+    fn new(config: &ArchiveConfig) -> Result<Self> {
+        let endpoint = config
+            .endpoint
+            .clone()
+            .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", config.region));
+        let access_key_id = config
+            .access_key_id
+            .clone()
+            .or_else(|| std::env::var("AWS_ACCESS_KEY_ID").ok())
+            .context("Archive sink needs access_key_id or AWS_ACCESS_KEY_ID")?;
+        let secret_access_key = config
+            .secret_access_key
+            .clone()
+            .or_else(|| std::env::var("AWS_SECRET_ACCESS_KEY").ok())
+            .context("Archive sink needs secret_access_key or AWS_SECRET_ACCESS_KEY")?;
+
+        Ok(Self {
+            client: outbound::client_builder()
+                .timeout(Duration::from_secs(300))
+                .build()
+                .context("Failed to build archive HTTP client")?,
+            bucket: config.bucket.clone(),
+            region: config.region.clone(),
+            endpoint: Url::parse(&endpoint)
+                .with_context(|| format!("Invalid archive endpoint {}", endpoint))?,
+            path_style: config.path_style,
+            prefix: config.prefix.trim_matches('/').to_string(),
+            access_key_id,
+            secret_access_key,
+        })
+    }
+
+    async fn put(&self, key: &str, body: Vec<u8>) -> Result<()> {
+        let key = if self.prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}/{}", self.prefix, key)
+        };
+        let mut url = self.endpoint.clone();
+        if self.path_style {
+            url.set_path(&format!("/{}/{}", self.bucket, key));
+        } else {
+            let host = url.host_str().context("Archive endpoint has no host")?;
+            url.set_host(Some(&format!("{}.{}", self.bucket, host)))?;
+            url.set_path(&format!("/{}", key));
+        }
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex::encode(Sha256::digest(&body));
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "PUT\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            url.path(),
+            host,
+            payload_hash,
+            amz_date,
+            signed_headers,
+            payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let mut key_bytes = hmac(
+            format!("AWS4{}", self.secret_access_key).as_bytes(),
+            date.as_bytes(),
+        );
+        for part in [self.region.as_str(), "s3", "aws4_request"] {
+            key_bytes = hmac(&key_bytes, part.as_bytes());
+        }
+        let signature = hex::encode(hmac(&key_bytes, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id, scope, signed_headers, signature
+        );
+
+        let response = self
+            .client
+            .put(url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("authorization", authorization)
+            .header("content-type", "application/gzip")
+            .body(body)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let message = response.text().await.unwrap_or_default();
+            bail!("Storage returned {}: {}", status, message.trim());
+        }
+        Ok(())
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
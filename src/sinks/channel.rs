@@ -0,0 +1,66 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::Stream;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::models::Envelope;
+use crate::sinks::EventSink;
+
+/// Hands events to an in-process [`EventStream`]
+pub struct ChannelSink {
+    tx: mpsc::Sender<Envelope>,
+}
+
+impl ChannelSink {
+    /// Sink and the receiving end of its `capacity`-sized channel
+    pub fn new(capacity: usize) -> (Self, mpsc::Receiver<Envelope>) {
+        let (tx, rx) = mpsc::channel(capacity.max(1));
+        (Self { tx }, rx)
+    }
+}
+
+#[async_trait]
+impl EventSink for ChannelSink {
+    fn name(&self) -> &str {
+        "channel"
+    }
+
+    async fn send(&self, event: &Envelope) -> Result<()> {
+        self.tx
+            .send(event.clone())
+            .await
+            .map_err(|_| anyhow::anyhow!("event stream was dropped"))
+    }
+}
+
+/// Events of a [`ChannelSink`]; dropping the stream stops the tasks feeding it
+pub struct EventStream {
+    rx: mpsc::Receiver<Envelope>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl EventStream {
+    pub fn new(rx: mpsc::Receiver<Envelope>, tasks: Vec<JoinHandle<()>>) -> Self {
+        Self { rx, tasks }
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Envelope;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Envelope>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
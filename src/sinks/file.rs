@@ -0,0 +1,55 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use crate::models::Envelope;
+use crate::sinks::EventSink;
+
+/// Appends events as NDJSON to a local file
+pub struct FileSink {
+    name: String,
+    file: Mutex<File>,
+}
+
+impl FileSink {
+    pub async fn new(path: &str) -> Result<Self> {
+        if let Some(parent) = Path::new(path).parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("Failed to create directory for {}", path))?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .with_context(|| format!("Failed to open event file {}", path))?;
+
+        Ok(Self {
+            name: format!("file:{}", path),
+            file: Mutex::new(file),
+        })
+    }
+}
+
+#[async_trait]
+impl EventSink for FileSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn send(&self, event: &Envelope) -> Result<()> {
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+
+        let mut file = self.file.lock().await;
+        file.write_all(&line).await?;
+        file.flush().await?;
+        Ok(())
+    }
+}
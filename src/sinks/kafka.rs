@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::ClientConfig;
+
+use crate::models::Envelope;
+use crate::sinks::EventSink;
+
+/// Produces events to a Kafka topic, keyed by event type
+pub struct KafkaSink {
+    topic: String,
+    producer: FutureProducer,
+}
+
+impl KafkaSink {
+    pub fn new(brokers: &str, topic: &str) -> Result<Self> {
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("message.timeout.ms", "5000")
+            .create()
+            .context("Failed to create Kafka producer")?;
+
+        Ok(Self {
+            topic: topic.to_string(),
+            producer,
+        })
+    }
+}
+
+#[async_trait]
+impl EventSink for KafkaSink {
+    fn name(&self) -> &str {
+        "kafka"
+    }
+
+    async fn send(&self, event: &Envelope) -> Result<()> {
+        let payload = serde_json::to_string(event)?;
+        let record = FutureRecord::to(&self.topic)
+            .key(event.event_type())
+            .payload(&payload);
+
+        self.producer
+            .send(record, Duration::from_secs(5))
+            .await
+            .map_err(|(e, _)| anyhow!("Kafka delivery failed: {}", e))?;
+        Ok(())
+    }
+}
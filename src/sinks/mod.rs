@@ -0,0 +1,286 @@
+pub mod archive;
+pub mod channel;
+pub mod file;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub mod redis;
+pub mod sqlite;
+pub mod stdout;
+pub mod wal;
+pub mod webhook;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info, info_span, warn, Instrument, Span};
+
+use crate::config::{Config, DatabaseBackend, SinkConfig, SinkOptions};
+use crate::dry_run::DryRunReporter;
+use crate::error::ListenerError;
+use crate::metrics;
+use crate::models::Envelope;
+use crate::retry::RetryPolicy;
+use crate::sinks::wal::Wal;
+
+/// A destination for published events
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn send(&self, event: &Envelope) -> Result<()>;
+
+    /// Called once an event has exhausted its retries
+    async fn dead_letter(&self, _event: &Envelope, _error: &anyhow::Error) {}
+}
+
+/// Build every sink listed in `[[sinks]]`
+pub async fn build_sinks(config: &Config) -> Result<Vec<Arc<dyn EventSink>>> {
+    if config.dry_run {
+        info!("Dry run: events are reported on stdout instead of the configured sinks");
+        return Ok(vec![Arc::new(DryRunReporter)]);
+    }
+    let mut sinks: Vec<Arc<dyn EventSink>> = Vec::new();
+    if config.database.backend == DatabaseBackend::Sqlite {
+        sinks.push(Arc::new(sqlite::SqliteSink::new(&config.database.sqlite_path)?));
+        info!("Enabled event sink: sqlite");
+    }
+
+    for sink in &config.sinks {
+        let built: Arc<dyn EventSink> = match sink {
+            SinkConfig::Redis => Arc::new(
+                redis::RedisSink::new(&config.database.redis_url, config.output.clone()).await?,
+            ),
+            SinkConfig::Stdout => Arc::new(stdout::StdoutSink),
+            SinkConfig::File { path } => Arc::new(file::FileSink::new(path).await?),
+            SinkConfig::Webhook {
+                url,
+                timeout_secs,
+                secret,
+                dead_letter_path,
+            } => Arc::new(webhook::WebhookSink::new(
+                url,
+                *timeout_secs,
+                secret.clone(),
+                dead_letter_path.clone(),
+            )?),
+            SinkConfig::Archive(archive) => Arc::new(archive::ArchiveSink::new(archive).await?),
+            #[cfg(feature = "kafka")]
+            SinkConfig::Kafka { brokers, topic } => Arc::new(kafka::KafkaSink::new(brokers, topic)?),
+            #[cfg(not(feature = "kafka"))]
+            SinkConfig::Kafka { brokers, topic } => anyhow::bail!(
+                "Kafka sink ({} / {}) configured but the binary was built without the `kafka` feature",
+                brokers,
+                topic
+            ),
+        };
+        info!("Enabled event sink: {}", built.name());
+        sinks.push(built);
+    }
+
+    Ok(sinks)
+}
+
+/// An event travels with the span it was published in, so its delivery is traced too
+type Delivery = (Arc<Envelope>, Span);
+
+/// Fans every event out to all sinks.
+///
+/// Each sink gets its own bounded queue and worker task, so a slow or failing
+/// sink only ever delays (or drops) its own deliveries. With `sink_options.wal`
+/// a sink that keeps failing has its events buffered on disk instead.
+#[derive(Clone)]
+pub struct SinkFanout {
+    queues: Vec<(String, mpsc::Sender<Delivery>)>,
+    /// Deliveries queued or in progress across all sinks
+    pending: Arc<AtomicUsize>,
+}
+
+impl SinkFanout {
+    pub fn spawn(sinks: Vec<Arc<dyn EventSink>>, options: &SinkOptions) -> Self {
+        let mut queues = Vec::with_capacity(sinks.len());
+        let pending = Arc::new(AtomicUsize::new(0));
+
+        for sink in sinks {
+            let (tx, rx) = mpsc::channel(options.queue_size.max(1));
+            queues.push((sink.name().to_string(), tx));
+            tokio::spawn(run_sink(sink, rx, options.clone(), pending.clone()));
+        }
+
+        Self { queues, pending }
+    }
+
+    /// Wait until every queued event has been delivered (or given up on).
+    /// Returns `false` if deliveries were still pending after `timeout`.
+    pub async fn flush(&self, timeout: Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        while self.pending.load(Ordering::Acquire) > 0 {
+            if tokio::time::Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        true
+    }
+
+    /// Queue an event on every sink; never waits on a sink.
+    /// Sinks that rejected it are reported once all the others have it.
+    pub fn publish(&self, event: Envelope) -> Result<(), ListenerError> {
+        let event = Arc::new(event);
+        let mut rejected = Vec::new();
+
+        for (name, queue) in &self.queues {
+            self.pending.fetch_add(1, Ordering::AcqRel);
+            if let Err(e) = queue.try_send((event.clone(), Span::current())) {
+                self.pending.fetch_sub(1, Ordering::AcqRel);
+                warn!("Sink {} queue rejected {} event: {}", name, event.event_type(), e);
+                metrics::DROPPED_MESSAGES
+                    .with_label_values(&[name.as_str(), "sink_queue_full"])
+                    .inc();
+                rejected.push(name.as_str());
+            }
+        }
+
+        if rejected.is_empty() {
+            Ok(())
+        } else {
+            Err(ListenerError::SinkFailure {
+                sink: rejected.join(", "),
+                message: format!("queue rejected {} event", event.event_type()),
+            })
+        }
+    }
+}
+
+async fn run_sink(
+    sink: Arc<dyn EventSink>,
+    mut rx: mpsc::Receiver<Delivery>,
+    options: SinkOptions,
+    pending: Arc<AtomicUsize>,
+) {
+    let name = sink.name().to_string();
+    let backoff = RetryPolicy {
+        max_attempts: options.max_retries + 1,
+        initial_backoff: Duration::from_millis(options.retry_backoff_ms),
+        max_backoff: Duration::from_secs(60),
+    };
+
+    let mut wal = if options.wal.enabled {
+        match Wal::open(&options.wal, &name).await {
+            Ok(wal) => Some(wal),
+            Err(e) => {
+                error!("Sink {} runs without a write-ahead log: {:#}", name, e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let mut replay =
+        tokio::time::interval(Duration::from_secs(options.wal.replay_interval_secs.max(1)));
+    replay.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            delivery = rx.recv() => {
+                let Some((event, published_in)) = delivery else {
+                    break;
+                };
+                let span = info_span!(
+                    parent: &published_in,
+                    "sink",
+                    sink = %name,
+                    event = event.event_type()
+                );
+                match wal.as_mut() {
+                    // Nothing overtakes buffered events
+                    Some(wal) if !wal.is_empty() => {
+                        if let Err(e) = wal.append(&event).await {
+                            give_up(sink.as_ref(), &name, &event, e).instrument(span).await;
+                        }
+                    }
+                    wal => {
+                        deliver(sink.as_ref(), &name, &event, &options, &backoff, wal)
+                            .instrument(span)
+                            .await
+                    }
+                }
+                pending.fetch_sub(1, Ordering::AcqRel);
+            }
+            _ = replay.tick(), if wal.as_ref().is_some_and(|wal| !wal.is_empty()) => {
+                if let Some(wal) = wal.as_mut() {
+                    if let Err(e) = wal.replay(sink.as_ref()).await {
+                        error!("Failed to replay the write-ahead log of sink {}: {:#}", name, e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn deliver(
+    sink: &dyn EventSink,
+    name: &str,
+    event: &Envelope,
+    options: &SinkOptions,
+    backoff: &RetryPolicy,
+    wal: Option<&mut Wal>,
+) {
+    let mut attempt: u32 = 0;
+
+    loop {
+        match sink.send(event).await {
+            Ok(()) => {
+                if let Some(discovered_at) = event.payload.discovered_at() {
+                    let latency = chrono::Utc::now().signed_duration_since(discovered_at);
+                    metrics::DISCOVERY_TO_PUBLISH_SECONDS
+                        .with_label_values(&[name])
+                        .observe(latency.num_milliseconds().max(0) as f64 / 1000.0);
+                }
+                break;
+            }
+            Err(e) if attempt < options.max_retries => {
+                attempt += 1;
+                let delay = backoff.delay(attempt);
+                warn!(
+                    "Sink {} failed to send {} (attempt {}/{}), retrying in {:?}: {}",
+                    name,
+                    event.event_type(),
+                    attempt,
+                    options.max_retries,
+                    delay,
+                    e
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => {
+                if let Some(wal) = wal {
+                    match wal.append(event).await {
+                        Ok(()) => {
+                            warn!(
+                                "Sink {} is unavailable, buffering events on disk: {}",
+                                name, e
+                            );
+                            break;
+                        }
+                        Err(wal_error) => warn!("{:#}", wal_error),
+                    }
+                }
+                give_up(sink, name, event, e).await;
+                break;
+            }
+        }
+    }
+}
+
+async fn give_up(sink: &dyn EventSink, name: &str, event: &Envelope, e: anyhow::Error) {
+    error!("Sink {} gave up on {} event: {}", name, event.event_type(), e);
+    metrics::PUBLISH_FAILURES
+        .with_label_values(&[name])
+        .inc();
+    sink.dead_letter(event, &e).await;
+}
@@ -0,0 +1,66 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+
+use crate::config::{OutputConfig, OutputMode};
+use crate::models::Envelope;
+use crate::sinks::EventSink;
+
+/// Publishes events to Redis, as pub/sub messages or stream entries
+pub struct RedisSink {
+    output: OutputConfig,
+    conn: ConnectionManager,
+}
+
+impl RedisSink {
+    pub async fn new(redis_url: &str, output: OutputConfig) -> Result<Self> {
+        let client = redis::Client::open(redis_url).context("Invalid database.redis_url")?;
+        // ConnectionManager reconnects on its own after a dropped connection
+        let conn = ConnectionManager::new(client)
+            .await
+            .context("Failed to connect to Redis")?;
+
+        Ok(Self { output, conn })
+    }
+}
+
+#[async_trait]
+impl EventSink for RedisSink {
+    fn name(&self) -> &str {
+        "redis"
+    }
+
+    async fn send(&self, event: &Envelope) -> Result<()> {
+        let mut conn = self.conn.clone();
+
+        let event_type = event.event_type();
+        let channel = self.output.channel_for(&event.payload);
+        let event_json = serde_json::to_string(event)?;
+
+        match self.output.mode {
+            OutputMode::PubSub => {
+                let _: () = redis::cmd("PUBLISH")
+                    .arg(channel.as_ref())
+                    .arg(event_json)
+                    .query_async(&mut conn)
+                    .await?;
+            }
+            OutputMode::Stream => {
+                let _: String = redis::cmd("XADD")
+                    .arg(channel.as_ref())
+                    .arg("MAXLEN")
+                    .arg("~")
+                    .arg(self.output.stream_maxlen)
+                    .arg("*")
+                    .arg("type")
+                    .arg(event_type)
+                    .arg("data")
+                    .arg(event_json)
+                    .query_async(&mut conn)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
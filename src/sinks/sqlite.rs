@@ -0,0 +1,30 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::models::Envelope;
+use crate::sinks::EventSink;
+use crate::sqlite::SqliteStore;
+
+/// Keeps published tokens and trades in the `database.backend = "sqlite"` database
+pub struct SqliteSink {
+    store: SqliteStore,
+}
+
+impl SqliteSink {
+    pub fn new(path: &str) -> Result<Self> {
+        Ok(Self {
+            store: SqliteStore::open(path)?,
+        })
+    }
+}
+
+#[async_trait]
+impl EventSink for SqliteSink {
+    fn name(&self) -> &str {
+        "sqlite"
+    }
+
+    async fn send(&self, event: &Envelope) -> Result<()> {
+        self.store.save_event(event).await
+    }
+}
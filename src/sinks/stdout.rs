@@ -0,0 +1,25 @@
+use std::io::Write;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::models::Envelope;
+use crate::sinks::EventSink;
+
+/// Writes one JSON event per line to stdout (NDJSON)
+pub struct StdoutSink;
+
+#[async_trait]
+impl EventSink for StdoutSink {
+    fn name(&self) -> &str {
+        "stdout"
+    }
+
+    async fn send(&self, event: &Envelope) -> Result<()> {
+        let line = serde_json::to_string(event)?;
+        let mut stdout = std::io::stdout().lock();
+        writeln!(stdout, "{}", line)?;
+        stdout.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info, warn};
+
+use crate::config::WalConfig;
+use crate::metrics;
+use crate::models::Envelope;
+use crate::sinks::EventSink;
+
+/// Events a sink could not take, appended as NDJSON to `{dir}/{sink}.ndjson`
+/// and replayed oldest first once the sink is back. The file outlives restarts.
+pub struct Wal {
+    sink: String,
+    path: PathBuf,
+    max_bytes: u64,
+    /// Events and bytes in the file
+    len: usize,
+    bytes: u64,
+}
+
+impl Wal {
+    pub async fn open(config: &WalConfig, sink: &str) -> Result<Self> {
+        tokio::fs::create_dir_all(&config.dir)
+            .await
+            .with_context(|| format!("Failed to create WAL directory {}", config.dir))?;
+        // Sink names hold `:` and `/` (`file:data/events.ndjson`)
+        let file_name: String = sink
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let path = Path::new(&config.dir).join(format!("{}.ndjson", file_name));
+
+        let contents = match tokio::fs::read(&path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read WAL {}", path.display()))
+            }
+        };
+
+        let wal = Self {
+            sink: sink.to_string(),
+            path,
+            max_bytes: config.max_bytes,
+            len: count_events(&contents),
+            bytes: contents.len() as u64,
+        };
+        if !wal.is_empty() {
+            info!(
+                "Sink {} has {} event(s) left in {} to replay",
+                sink,
+                wal.len,
+                wal.path.display()
+            );
+        }
+        wal.update_gauge();
+        Ok(wal)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub async fn append(&mut self, event: &Envelope) -> Result<()> {
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+        if self.bytes + line.len() as u64 > self.max_bytes {
+            metrics::DROPPED_MESSAGES
+                .with_label_values(&[self.sink.as_str(), "wal_full"])
+                .inc();
+            bail!(
+                "WAL {} reached {} bytes",
+                self.path.display(),
+                self.max_bytes
+            );
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .with_context(|| format!("Failed to open WAL {}", self.path.display()))?;
+        file.write_all(&line).await?;
+        file.flush().await?;
+
+        self.len += 1;
+        self.bytes += line.len() as u64;
+        self.update_gauge();
+        Ok(())
+    }
+
+    /// Send buffered events to `sink` in order, once each, stopping at the first
+    /// failure; returns how many were delivered
+    pub async fn replay(&mut self, sink: &dyn EventSink) -> Result<usize> {
+        let contents = tokio::fs::read(&self.path)
+            .await
+            .with_context(|| format!("Failed to read WAL {}", self.path.display()))?;
+
+        let mut consumed = 0;
+        let mut replayed = 0;
+        for line in contents.split_inclusive(|b| *b == b'\n') {
+            let json = line.strip_suffix(b"\n").unwrap_or(line);
+            if !json.is_empty() {
+                match serde_json::from_slice::<Envelope>(json) {
+                    Ok(event) => {
+                        if let Err(e) = sink.send(&event).await {
+                            debug!("Sink {} still unavailable: {:#}", self.sink, e);
+                            break;
+                        }
+                        replayed += 1;
+                    }
+                    Err(e) => warn!("Skipping unreadable WAL entry of {}: {}", self.sink, e),
+                }
+            }
+            consumed += line.len();
+        }
+
+        let remaining = &contents[consumed..];
+        if remaining.is_empty() {
+            tokio::fs::remove_file(&self.path).await?;
+        } else if consumed > 0 {
+            let tmp = self.path.with_extension("ndjson.tmp");
+            tokio::fs::write(&tmp, remaining).await?;
+            tokio::fs::rename(&tmp, &self.path).await?;
+        }
+
+        self.len = count_events(remaining);
+        self.bytes = remaining.len() as u64;
+        self.update_gauge();
+        metrics::WAL_REPLAYED
+            .with_label_values(&[self.sink.as_str()])
+            .inc_by(replayed as u64);
+        if replayed > 0 {
+            info!(
+                "Replayed {} buffered event(s) to sink {}, {} left",
+                replayed, self.sink, self.len
+            );
+        }
+        Ok(replayed)
+    }
+
+    fn update_gauge(&self) {
+        metrics::WAL_EVENTS
+            .with_label_values(&[self.sink.as_str()])
+            .set(self.len as i64);
+    }
+}
+
+fn count_events(contents: &[u8]) -> usize {
+    contents
+        .split(|b| *b == b'\n')
+        .filter(|line| !line.is_empty())
+        .count()
+}
@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde_json::json;
+use sha2::Sha256;
+use tokio::io::AsyncWriteExt;
+use tracing::{error, warn};
+
+use crate::models::Envelope;
+use crate::outbound;
+use crate::rpc_pool::endpoint_label;
+use crate::sinks::EventSink;
+
+/// Hex HMAC-SHA256 of the raw body, as `sha256=<hex>`
+pub const SIGNATURE_HEADER: &str = "X-Pumpfun-Signature";
+/// Unix seconds the request was signed at, included in the signed payload
+pub const TIMESTAMP_HEADER: &str = "X-Pumpfun-Timestamp";
+
+/// POSTs every event as JSON to a URL, optionally signed with a shared secret
+pub struct WebhookSink {
+    name: String,
+    url: String,
+    client: Client,
+    secret: Option<String>,
+    dead_letter_path: Option<String>,
+}
+
+impl WebhookSink {
+    pub fn new(
+        url: &str,
+        timeout_secs: u64,
+        secret: Option<String>,
+        dead_letter_path: Option<String>,
+    ) -> Result<Self> {
+        let client = outbound::client_builder()
+            .timeout(Duration::from_secs(timeout_secs))
+            .build()
+            .context("Failed to build webhook HTTP client")?;
+
+        Ok(Self {
+            // The path and query may carry tokens, keep them out of metric labels and logs
+            name: format!("webhook:{}", endpoint_label(url)),
+            url: url.to_string(),
+            client,
+            secret,
+            dead_letter_path,
+        })
+    }
+}
+
+/// Signature over `"{timestamp}.{body}"` so a captured request can't be replayed later
+fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[async_trait]
+impl EventSink for WebhookSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn send(&self, event: &Envelope) -> Result<()> {
+        let body = serde_json::to_vec(event)?;
+
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+
+        if let Some(secret) = &self.secret {
+            let timestamp = chrono::Utc::now().timestamp();
+            request = request
+                .header(TIMESTAMP_HEADER, timestamp)
+                .header(SIGNATURE_HEADER, sign(secret, timestamp, &body));
+        }
+
+        request
+            .body(body)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(reqwest::Error::without_url)?;
+        Ok(())
+    }
+
+    async fn dead_letter(&self, event: &Envelope, error: &anyhow::Error) {
+        let Some(path) = &self.dead_letter_path else {
+            warn!(
+                "Dropping undeliverable {} event for {}",
+                event.event_type(),
+                self.name
+            );
+            return;
+        };
+
+        let entry = json!({
+            "failed_at": chrono::Utc::now(),
+            "url": self.url,
+            "error": error.to_string(),
+            "event": event,
+        });
+
+        let result = async {
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .await?;
+            file.write_all(format!("{}\n", entry).as_bytes()).await?;
+            file.flush().await
+        }
+        .await;
+
+        if let Err(e) = result {
+            error!("Failed to write webhook dead letter to {}: {}", path, e);
+        }
+    }
+}
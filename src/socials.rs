@@ -0,0 +1,187 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use reqwest::{redirect, Client, Method, Response};
+use tracing::debug;
+
+use crate::config::{NegativeCacheConfig, SocialsConfig};
+use crate::models::{SocialKind, SocialLink};
+use crate::negative_cache::NegativeCache;
+use crate::outbound;
+
+const MAX_REDIRECTS: usize = 5;
+/// Enough of a page to spot a parking notice or the Telegram channel title
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// Hosts that only serve parked or for-sale pages
+const PARKING_HOSTS: &[&str] = &[
+    "sedoparking.com",
+    "parkingcrew.net",
+    "bodis.com",
+    "afternic.com",
+    "dan.com",
+    "hugedomains.com",
+    "above.com",
+    "parklogic.com",
+    "undeveloped.com",
+];
+
+const PARKED_MARKERS: &[&str] = &[
+    "domain is for sale",
+    "buy this domain",
+    "domain may be for sale",
+    "this domain is parked",
+    "parked free",
+    "domain parking",
+];
+
+/// Present on t.me pages of existing users, groups and channels only
+const TELEGRAM_TITLE_MARKER: &str = "tgme_page_title";
+
+/// Reads the twitter / telegram / website links of a token's metadata JSON and
+/// checks which of them lead anywhere
+pub struct SocialChecker {
+    client: Client,
+    /// Metadata that could not be read or has no links
+    misses: NegativeCache,
+}
+
+impl SocialChecker {
+    pub fn new(config: &SocialsConfig, negative: &NegativeCacheConfig) -> Result<Self> {
+        let client = outbound::client_builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .redirect(redirect::Policy::limited(MAX_REDIRECTS))
+            .user_agent("Mozilla/5.0 (compatible; pumpfun-ingestion)")
+            .build()
+            .context("Failed to build the social link HTTP client")?;
+        Ok(Self {
+            client,
+            misses: NegativeCache::new("metadata", negative),
+        })
+    }
+
+    /// Links found in the metadata at `uri`, each with whether it is live
+    pub async fn verify(&self, uri: &str) -> Result<Vec<SocialLink>> {
+        if self.misses.contains(uri) {
+            return Ok(Vec::new());
+        }
+        let links = self.links(uri).await;
+        if links.as_ref().map_or(true, Vec::is_empty) {
+            self.misses.insert(uri).await;
+        }
+        links
+    }
+
+    async fn links(&self, uri: &str) -> Result<Vec<SocialLink>> {
+        let metadata: serde_json::Value = self
+            .client
+            .get(gateway_url(uri))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("Metadata is not JSON")?;
+
+        let mut links = Vec::new();
+        for (kind, key) in [
+            (SocialKind::Twitter, "twitter"),
+            (SocialKind::Telegram, "telegram"),
+            (SocialKind::Website, "website"),
+        ] {
+            let url = metadata
+                .get(key)
+                .or_else(|| metadata.get("extensions").and_then(|e| e.get(key)))
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|url| !url.is_empty());
+            let Some(url) = url else {
+                continue;
+            };
+
+            let url = link_url(kind, url);
+            let live = self.is_live(kind, &url).await;
+            debug!("{:?} link {} live: {}", kind, url, live);
+            links.push(SocialLink { kind, url, live });
+        }
+        Ok(links)
+    }
+
+    async fn is_live(&self, kind: SocialKind, url: &str) -> bool {
+        // Telegram answers 200 for any name, only the page tells whether it exists
+        let response = match kind {
+            SocialKind::Telegram | SocialKind::Website => self.request(Method::GET, url).await,
+            SocialKind::Twitter => match self.request(Method::HEAD, url).await {
+                Some(response) if response.status() != reqwest::StatusCode::METHOD_NOT_ALLOWED => {
+                    Some(response)
+                }
+                _ => self.request(Method::GET, url).await,
+            },
+        };
+        let Some(response) = response else {
+            return false;
+        };
+        if !response.status().is_success() {
+            return false;
+        }
+
+        let host = response.url().host_str().unwrap_or_default().to_lowercase();
+        if PARKING_HOSTS
+            .iter()
+            .any(|parking| host == *parking || host.ends_with(&format!(".{}", parking)))
+        {
+            return false;
+        }
+
+        match kind {
+            SocialKind::Twitter => true,
+            SocialKind::Telegram => read_head(response).await.contains(TELEGRAM_TITLE_MARKER),
+            SocialKind::Website => {
+                let body = read_head(response).await.to_lowercase();
+                !PARKED_MARKERS.iter().any(|marker| body.contains(marker))
+            }
+        }
+    }
+
+    async fn request(&self, method: Method, url: &str) -> Option<Response> {
+        match self.client.request(method, url).send().await {
+            Ok(response) => Some(response),
+            Err(e) => {
+                debug!("Social link {} unreachable: {}", url, e);
+                None
+            }
+        }
+    }
+}
+
+/// The first `MAX_BODY_BYTES` of the body, lossily decoded
+async fn read_head(mut response: Response) -> String {
+    let mut body = Vec::new();
+    while body.len() < MAX_BODY_BYTES {
+        match response.chunk().await {
+            Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+            _ => break,
+        }
+    }
+    String::from_utf8_lossy(&body).into_owned()
+}
+
+/// `ipfs://` URIs through a public gateway
+fn gateway_url(uri: &str) -> String {
+    match uri.strip_prefix("ipfs://") {
+        Some(cid) => format!("https://ipfs.io/ipfs/{}", cid),
+        None => uri.to_string(),
+    }
+}
+
+/// Metadata often carries bare domains like `example.com` or handles like `@name`
+fn link_url(kind: SocialKind, url: &str) -> String {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        return url.to_string();
+    }
+    match (kind, url.strip_prefix('@')) {
+        (SocialKind::Twitter, Some(handle)) => format!("https://x.com/{}", handle),
+        (SocialKind::Telegram, Some(handle)) => format!("https://t.me/{}", handle),
+        _ => format!("https://{}", url),
+    }
+}
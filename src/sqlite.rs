@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use tracing::info;
+
+use crate::cursor::CursorPosition;
+use crate::models::{Envelope, Event, Trade};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS tokens (
+    mint TEXT PRIMARY KEY,
+    source TEXT NOT NULL,
+    name TEXT,
+    symbol TEXT,
+    creator TEXT,
+    score INTEGER,
+    discovered_at TEXT NOT NULL,
+    last_event TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS trades (
+    signature TEXT NOT NULL,
+    mint TEXT NOT NULL,
+    wallet TEXT NOT NULL,
+    is_buy INTEGER NOT NULL,
+    sol_amount INTEGER NOT NULL,
+    token_amount INTEGER NOT NULL,
+    at TEXT NOT NULL,
+    UNIQUE (signature, mint, wallet, is_buy, token_amount)
+);
+CREATE INDEX IF NOT EXISTS trades_mint ON trades (mint, at);
+CREATE TABLE IF NOT EXISTS signatures (
+    signature TEXT PRIMARY KEY,
+    seen_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS signatures_seen_at ON signatures (seen_at);
+CREATE TABLE IF NOT EXISTS cursor (
+    listener TEXT PRIMARY KEY,
+    slot INTEGER NOT NULL,
+    signature TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS sequences (
+    key TEXT PRIMARY KEY,
+    value INTEGER NOT NULL
+);
+";
+
+/// The `database.backend = "sqlite"` store: tokens and trades as published,
+/// dedup signatures, the slot cursor and event sequence numbers, in one file
+/// for single-node deployments without Redis.
+///
+/// Each component opens its own connection; WAL mode lets them share the file.
+#[derive(Clone)]
+pub struct SqliteStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStore {
+    pub fn open(path: &str) -> Result<Self> {
+        if let Some(dir) = Path::new(path)
+            .parent()
+            .filter(|d| !d.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open SQLite database {}", path))?;
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        conn.execute_batch(SCHEMA)
+            .with_context(|| format!("Failed to create the tables of {}", path))?;
+        info!("SQLite database ready at {}", path);
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Run `f` on the connection off the async workers
+    async fn run<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || f(&store.lock()))
+            .await
+            .context("SQLite task panicked")?
+            .context("SQLite query failed")
+    }
+
+    /// Keep the token an event carries, and the trade of trade events
+    pub async fn save_event(&self, event: &Envelope) -> Result<()> {
+        if let Some(token) = event.payload.token() {
+            let data = serde_json::to_string(token)?;
+            let token = token.clone();
+            let event_type = event.event_type();
+            let updated_at = event.emitted_at.to_rfc3339();
+            return self
+                .run(move |conn| {
+                    conn.execute(
+                        "INSERT INTO tokens
+                            (mint, source, name, symbol, creator, score, discovered_at,
+                             last_event, updated_at, data)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                         ON CONFLICT (mint) DO UPDATE SET
+                            name = excluded.name,
+                            symbol = excluded.symbol,
+                            creator = excluded.creator,
+                            score = excluded.score,
+                            last_event = excluded.last_event,
+                            updated_at = excluded.updated_at,
+                            data = excluded.data",
+                        params![
+                            token.mint_address,
+                            token.source.to_string(),
+                            token.name,
+                            token.symbol,
+                            token.creator,
+                            token.score,
+                            token.discovered_at.to_rfc3339(),
+                            event_type,
+                            updated_at,
+                            data,
+                        ],
+                    )
+                    .map(drop)
+                })
+                .await;
+        }
+
+        let trade = match &event.payload {
+            Event::TradeExecuted(trade) => trade.clone(),
+            Event::WatchedWalletTrade(trade) => trade.trade.clone(),
+            _ => return Ok(()),
+        };
+        let Some(signature) = event.signature.clone() else {
+            return Ok(());
+        };
+        self.insert_trade(signature, trade).await
+    }
+
+    async fn insert_trade(&self, signature: String, trade: Trade) -> Result<()> {
+        self.run(move |conn| {
+            conn.execute(
+                "INSERT OR IGNORE INTO trades
+                    (signature, mint, wallet, is_buy, sol_amount, token_amount, at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    signature,
+                    trade.mint,
+                    trade.wallet,
+                    trade.is_buy,
+                    trade.sol_amount as i64,
+                    trade.token_amount as i64,
+                    trade.at.to_rfc3339(),
+                ],
+            )
+            .map(drop)
+        })
+        .await
+    }
+
+    /// Record `signature`; `true` unless it was already recorded within `ttl`
+    pub async fn first_seen(&self, signature: &str, ttl: Duration) -> Result<bool> {
+        let signature = signature.to_string();
+        let now = Utc::now().timestamp();
+        let expired = now - ttl.as_secs() as i64;
+        self.run(move |conn| {
+            conn.execute(
+                "DELETE FROM signatures WHERE signature = ?1 AND seen_at < ?2",
+                params![signature, expired],
+            )?;
+            let inserted = conn.execute(
+                "INSERT OR IGNORE INTO signatures (signature, seen_at) VALUES (?1, ?2)",
+                params![signature, now],
+            )?;
+            Ok(inserted == 1)
+        })
+        .await
+    }
+
+    pub async fn forget(&self, signature: &str) -> Result<()> {
+        let signature = signature.to_string();
+        self.run(move |conn| {
+            conn.execute(
+                "DELETE FROM signatures WHERE signature = ?1",
+                params![signature],
+            )
+            .map(drop)
+        })
+        .await
+    }
+
+    /// Drop signatures older than `ttl`
+    pub async fn prune_signatures(&self, ttl: Duration) -> Result<usize> {
+        let expired = Utc::now().timestamp() - ttl.as_secs() as i64;
+        self.run(move |conn| {
+            conn.execute(
+                "DELETE FROM signatures WHERE seen_at < ?1",
+                params![expired],
+            )
+        })
+        .await
+    }
+
+    pub async fn load_cursor(&self) -> Result<HashMap<String, CursorPosition>> {
+        self.run(|conn| {
+            let mut statement =
+                conn.prepare("SELECT listener, slot, signature, updated_at FROM cursor")?;
+            let rows = statement.query_map([], |row| {
+                let updated_at: String = row.get(3)?;
+                Ok((
+                    row.get::<_, String>(0)?,
+                    CursorPosition {
+                        slot: row.get::<_, i64>(1)? as u64,
+                        signature: row.get(2)?,
+                        updated_at: DateTime::parse_from_rfc3339(&updated_at)
+                            .map(|at| at.with_timezone(&Utc))
+                            .unwrap_or_else(|_| Utc::now()),
+                    },
+                ))
+            })?;
+            rows.collect()
+        })
+        .await
+    }
+
+    pub async fn save_cursor(&self, positions: HashMap<String, CursorPosition>) -> Result<()> {
+        self.run(move |conn| {
+            let tx = conn.unchecked_transaction()?;
+            for (listener, position) in &positions {
+                tx.execute(
+                    "INSERT INTO cursor (listener, slot, signature, updated_at)
+                     VALUES (?1, ?2, ?3, ?4)
+                     ON CONFLICT (listener) DO UPDATE SET
+                        slot = excluded.slot,
+                        signature = excluded.signature,
+                        updated_at = excluded.updated_at",
+                    params![
+                        listener,
+                        position.slot as i64,
+                        position.signature,
+                        position.updated_at.to_rfc3339(),
+                    ],
+                )?;
+            }
+            tx.commit()
+        })
+        .await
+    }
+
+    /// Increment the counter `key` (starting at 1) and return its new value
+    pub async fn next_sequence(&self, key: &str) -> Result<u64> {
+        let key = key.to_string();
+        self.run(move |conn| {
+            conn.query_row(
+                "INSERT INTO sequences (key, value) VALUES (?1, 1)
+                 ON CONFLICT (key) DO UPDATE SET value = value + 1
+                 RETURNING value",
+                params![key],
+                |row| row.get::<_, i64>(0),
+            )
+            .map(|value| value as u64)
+        })
+        .await
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::{watch, RwLock};
+use tokio::task::JoinSet;
+use tracing::{error, info, warn};
+
+use crate::config::IngestionConfig;
+use crate::error::ConnectionLost;
+use crate::listeners::TokenListener;
+use crate::metrics;
+use crate::retry::RetryPolicy;
+use crate::rpc_pool::RpcPool;
+
+/// A session that lasted at least this long is considered healthy and resets the backoff
+const STABLE_SESSION: Duration = Duration::from_secs(60);
+const STATUS_REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ListenerState {
+    Starting,
+    Running,
+    Backoff { attempt: u32, retry_in_secs: u64 },
+    /// Unsubscribed through the admin API until resumed
+    Paused,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ListenerStatus {
+    #[serde(flatten)]
+    pub state: ListenerState,
+    pub restarts: u64,
+    pub last_error: Option<String>,
+    pub since: DateTime<Utc>,
+}
+
+impl ListenerStatus {
+    fn new() -> Self {
+        Self {
+            state: ListenerState::Starting,
+            restarts: 0,
+            last_error: None,
+            since: Utc::now(),
+        }
+    }
+}
+
+pub type StatusMap = Arc<RwLock<HashMap<&'static str, ListenerStatus>>>;
+
+/// Pause switches of the registered listeners
+#[derive(Clone, Default)]
+pub struct ListenerControls(Arc<HashMap<&'static str, watch::Sender<bool>>>);
+
+impl ListenerControls {
+    /// Pause or resume `name`; `None` if no such listener, otherwise whether it changed
+    pub fn set_paused(&self, name: &str, paused: bool) -> Option<bool> {
+        let switch = self.0.get(name)?;
+        Some(switch.send_if_modified(|current| std::mem::replace(current, paused) != paused))
+    }
+
+    fn subscribe(&self, name: &str) -> watch::Receiver<bool> {
+        match self.0.get(name) {
+            Some(switch) => switch.subscribe(),
+            // Never paused
+            None => watch::channel(false).1,
+        }
+    }
+}
+
+/// How failed listeners are restarted
+#[derive(Clone)]
+struct RestartPolicy {
+    backoff: RetryPolicy,
+    /// Consecutive failures after which a listener moves to the next WSS endpoint, 0 never
+    failover_after: u32,
+    rpc: Option<Arc<RpcPool>>,
+}
+
+/// Runs every registered listener on its own task and restarts each one
+/// independently with exponential backoff and jitter, moving it to the next
+/// WSS endpoint after `ingestion.wss_failover_after` failures in a row.
+pub struct Supervisor {
+    listeners: Vec<Arc<dyn TokenListener>>,
+    restart: RestartPolicy,
+    status: StatusMap,
+    controls: ListenerControls,
+}
+
+impl Supervisor {
+    pub fn new(config: &IngestionConfig) -> Self {
+        Self {
+            listeners: Vec::new(),
+            restart: RestartPolicy {
+                backoff: RetryPolicy {
+                    max_attempts: u32::MAX,
+                    initial_backoff: Duration::from_secs(
+                        config.restart_backoff_initial_secs.max(1),
+                    ),
+                    max_backoff: Duration::from_secs(config.restart_backoff_max_secs.max(1)),
+                },
+                failover_after: config.wss_failover_after,
+                rpc: None,
+            },
+            status: Arc::new(RwLock::new(HashMap::new())),
+            controls: ListenerControls::default(),
+        }
+    }
+
+    /// Rotate failing listeners through the WSS endpoints of `rpc`
+    pub fn with_rpc(mut self, rpc: Arc<RpcPool>) -> Self {
+        self.restart.rpc = Some(rpc);
+        self
+    }
+
+    pub fn register(&mut self, listener: Arc<dyn TokenListener>) {
+        let mut controls = (*self.controls.0).clone();
+        controls.insert(listener.name(), watch::channel(false).0);
+        self.controls = ListenerControls(Arc::new(controls));
+        self.listeners.push(listener);
+    }
+
+    /// Registered listeners by name, for dispatching queued logs
+    pub fn handlers(&self) -> HashMap<&'static str, Arc<dyn TokenListener>> {
+        self.listeners
+            .iter()
+            .map(|listener| (listener.name(), listener.clone()))
+            .collect()
+    }
+
+    pub fn status(&self) -> StatusMap {
+        self.status.clone()
+    }
+
+    /// Take once every listener is registered
+    pub fn controls(&self) -> ListenerControls {
+        self.controls.clone()
+    }
+
+    pub async fn run(self) -> Result<()> {
+        if self.listeners.is_empty() {
+            anyhow::bail!("No listeners enabled, check the [ingestion] section of your config");
+        }
+
+        let mut tasks = JoinSet::new();
+        for listener in self.listeners {
+            self.status
+                .write()
+                .await
+                .insert(listener.name(), ListenerStatus::new());
+
+            tasks.spawn(supervise(
+                listener,
+                self.status.clone(),
+                self.controls.clone(),
+                self.restart.clone(),
+            ));
+        }
+
+        let status = self.status.clone();
+        tasks.spawn(async move {
+            loop {
+                tokio::time::sleep(STATUS_REPORT_INTERVAL).await;
+                for (name, s) in status.read().await.iter() {
+                    info!(
+                        "Listener {}: {:?} (restarts: {}, since {})",
+                        name, s.state, s.restarts, s.since
+                    );
+                }
+            }
+        });
+
+        // Supervised tasks loop forever, so getting here means one of them panicked
+        while let Some(result) = tasks.join_next().await {
+            if let Err(e) = result {
+                error!("Supervised task aborted: {}", e);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+async fn supervise(
+    listener: Arc<dyn TokenListener>,
+    status: StatusMap,
+    controls: ListenerControls,
+    restart: RestartPolicy,
+) {
+    let name = listener.name();
+    let mut paused = controls.subscribe(name);
+    let mut attempt: u32 = 0;
+
+    loop {
+        if *paused.borrow_and_update() {
+            set_state(&status, name, ListenerState::Paused, None).await;
+            info!("{} listener paused", name);
+            // `controls` keeps the sender alive, so this only returns on resume
+            let _ = paused.wait_for(|paused| !*paused).await;
+            info!("{} listener resumed", name);
+            attempt = 0;
+        }
+
+        set_state(&status, name, ListenerState::Running, None).await;
+        info!("Starting {} listener", name);
+
+        let started = tokio::time::Instant::now();
+        let result = tokio::select! {
+            result = listener.run() => result,
+            // Dropping the run future closes the subscription
+            _ = paused.wait_for(|paused| *paused) => continue,
+        };
+
+        let stable = started.elapsed() >= STABLE_SESSION;
+        if stable {
+            attempt = 0;
+            metrics::LISTENER_FAILURES.with_label_values(&[name]).set(0);
+        }
+
+        // A healthy connection that dropped is reopened at once; if that
+        // session fails quickly too, the usual backoff applies
+        if stable {
+            if let Err(e) = &result {
+                if e.is::<ConnectionLost>() {
+                    warn!("{} listener: {}, resubscribing", name, e);
+                    metrics::RESUBSCRIBES.with_label_values(&[name]).inc();
+                    let state = ListenerState::Backoff {
+                        attempt: 0,
+                        retry_in_secs: 0,
+                    };
+                    set_state(&status, name, state, Some(e.to_string())).await;
+                    continue;
+                }
+            }
+        }
+        attempt = attempt.saturating_add(1);
+        metrics::LISTENER_RESTARTS.with_label_values(&[name]).inc();
+        metrics::LISTENER_FAILURES
+            .with_label_values(&[name])
+            .set(i64::from(attempt));
+
+        let last_error = match result {
+            Ok(()) => {
+                warn!("{} listener stream ended", name);
+                None
+            }
+            Err(e) => {
+                error!("{} listener error: {:#}", name, e);
+                Some(e.to_string())
+            }
+        };
+
+        if restart.failover_after > 0 && attempt.is_multiple_of(restart.failover_after) {
+            if let Some(position) = restart.rpc.as_ref().and_then(|rpc| rpc.rotate_wss(name)) {
+                warn!(
+                    "{} listener failed {} times in a row, moving to WSS endpoint #{}",
+                    name, attempt, position
+                );
+            }
+        }
+
+        let delay = restart.backoff.delay(attempt);
+        set_state(
+            &status,
+            name,
+            ListenerState::Backoff {
+                attempt,
+                retry_in_secs: delay.as_secs(),
+            },
+            last_error,
+        )
+        .await;
+
+        tokio::time::sleep(delay).await;
+    }
+}
+
+async fn set_state(
+    status: &StatusMap,
+    name: &'static str,
+    state: ListenerState,
+    last_error: Option<String>,
+) {
+    let mut map = status.write().await;
+    let entry = map.entry(name).or_insert_with(ListenerStatus::new);
+
+    if matches!(state, ListenerState::Running)
+        && !matches!(entry.state, ListenerState::Starting | ListenerState::Paused)
+    {
+        entry.restarts += 1;
+    }
+    if last_error.is_some() {
+        entry.last_error = last_error;
+    }
+    entry.state = state;
+    entry.since = Utc::now();
+}
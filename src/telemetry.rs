@@ -0,0 +1,86 @@
+use anyhow::Result;
+use tracing::Subscriber;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+use crate::config::TelemetryConfig;
+
+pub type BoxedLayer<S> = Box<dyn Layer<S> + Send + Sync>;
+
+/// Keeps the OTLP exporter running; dropping it flushes the spans still buffered
+#[derive(Default)]
+pub struct TelemetryGuard {
+    #[cfg(feature = "otel")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed to flush OpenTelemetry spans: {}", e);
+            }
+        }
+    }
+}
+
+/// Layer exporting every span to `telemetry.otlp_endpoint`, `None` when export is disabled
+#[cfg(feature = "otel")]
+pub fn otlp_layer<S>(config: &TelemetryConfig) -> Result<(Option<BoxedLayer<S>>, TelemetryGuard)>
+where
+    S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
+{
+    use anyhow::Context;
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+    use opentelemetry_sdk::Resource;
+
+    if !config.enabled {
+        return Ok((None, TelemetryGuard::default()));
+    }
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(&config.otlp_endpoint)
+        .build()
+        .context("Failed to build the OTLP span exporter")?;
+
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio,
+        ))))
+        .with_resource(
+            Resource::builder()
+                .with_service_name(config.service_name.clone())
+                .build(),
+        )
+        .build();
+
+    let layer = tracing_opentelemetry::layer()
+        .with_tracer(provider.tracer("pumpfun_ingestion"))
+        .boxed();
+
+    Ok((
+        Some(layer),
+        TelemetryGuard {
+            provider: Some(provider),
+        },
+    ))
+}
+
+#[cfg(not(feature = "otel"))]
+pub fn otlp_layer<S>(config: &TelemetryConfig) -> Result<(Option<BoxedLayer<S>>, TelemetryGuard)>
+where
+    S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
+{
+    if config.enabled {
+        anyhow::bail!(
+            "OpenTelemetry export ({}) configured but the binary was built without the `otel` feature",
+            config.otlp_endpoint
+        );
+    }
+    Ok((None, TelemetryGuard::default()))
+}
@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use moka::future::Cache;
+use redis::aio::ConnectionManager;
+use tracing::{info, warn};
+
+use crate::config::TokenCacheConfig;
+use crate::metrics;
+use crate::models::Token;
+use crate::token_helper::{TokenInfo, UNKNOWN_NAME};
+
+const REDIS_KEY_PREFIX: &str = "tokeninfo:";
+
+/// Name, symbol, metadata URI and decimals of published mints, so later events
+/// about the same mint do not fetch its metadata again.
+///
+/// Kept in memory; with `use_redis` also in Redis, where every instance finds
+/// what the others resolved.
+pub struct TokenInfoCache {
+    local: Cache<String, TokenInfo>,
+    redis: Option<ConnectionManager>,
+    ttl: Duration,
+}
+
+impl TokenInfoCache {
+    pub async fn new(config: &TokenCacheConfig, redis_url: &str) -> Result<Self> {
+        let ttl = Duration::from_secs(config.ttl_secs);
+
+        let local = Cache::builder()
+            .max_capacity(config.max_entries)
+            .time_to_live(ttl)
+            .build();
+
+        let redis = if config.use_redis {
+            let client =
+                redis::Client::open(redis_url).context("Invalid redis_url for the token cache")?;
+            let manager = ConnectionManager::new(client)
+                .await
+                .context("Failed to connect to Redis for the token cache")?;
+            info!("Token info cache backed by Redis");
+            Some(manager)
+        } else {
+            None
+        };
+
+        Ok(Self { local, redis, ttl })
+    }
+
+    pub async fn get(&self, mint: &str) -> Option<TokenInfo> {
+        let info = match self.local.get(mint).await {
+            Some(info) => Some(info),
+            None => self.get_shared(mint).await,
+        };
+        let result = if info.is_some() { "hit" } else { "miss" };
+        metrics::TOKEN_INFO_CACHE.with_label_values(&[result]).inc();
+        info
+    }
+
+    async fn get_shared(&self, mint: &str) -> Option<TokenInfo> {
+        let redis = self.redis.as_ref()?;
+        let mut conn = redis.clone();
+        let result: redis::RedisResult<Option<String>> = redis::cmd("GET")
+            .arg(format!("{}{}", REDIS_KEY_PREFIX, mint))
+            .query_async(&mut conn)
+            .await;
+        let json = match result {
+            Ok(json) => json?,
+            Err(e) => {
+                warn!("Redis token cache lookup failed for {}: {}", mint, e);
+                return None;
+            }
+        };
+        let info: TokenInfo = serde_json::from_str(&json).ok()?;
+        self.local.insert(mint.to_string(), info.clone()).await;
+        Some(info)
+    }
+
+    /// Remember what was resolved about a published token
+    pub async fn insert(&self, token: &Token) {
+        let (Some(name), Some(symbol)) = (&token.name, &token.symbol) else {
+            return;
+        };
+        if name == UNKNOWN_NAME {
+            return;
+        }
+        let info = TokenInfo {
+            name: name.clone(),
+            symbol: symbol.clone(),
+            uri: token.metadata_uri.clone(),
+            decimals: Some(token.decimals),
+            pair: None,
+        };
+        self.local
+            .insert(token.mint_address.clone(), info.clone())
+            .await;
+
+        let Some(redis) = &self.redis else {
+            return;
+        };
+        let json = match serde_json::to_string(&info) {
+            Ok(json) => json,
+            Err(e) => {
+                warn!(
+                    "Failed to serialize token info of {}: {}",
+                    token.mint_address, e
+                );
+                return;
+            }
+        };
+        let mut conn = redis.clone();
+        let result: redis::RedisResult<()> = redis::cmd("SET")
+            .arg(format!("{}{}", REDIS_KEY_PREFIX, token.mint_address))
+            .arg(json)
+            .arg("EX")
+            .arg(self.ttl.as_secs())
+            .query_async(&mut conn)
+            .await;
+        if let Err(e) = result {
+            warn!(
+                "Failed to cache token info of {} in Redis: {}",
+                token.mint_address, e
+            );
+        }
+    }
+}
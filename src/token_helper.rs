@@ -1,9 +1,6 @@
-use std::str::FromStr;
 use anyhow::{anyhow, Result};
-use reqwest::Client;
-use serde_json::json;
-use solana_client::rpc_client::RpcClient;
-use solana_client::rpc_config::RpcTransactionConfig;
+use solana_commitment_config::CommitmentConfig;
+use solana_sdk::account::Account;
 use solana_sdk::pubkey::Pubkey;
 use solana_program::program_option::COption;
 use spl_token::solana_program::program_pack::Pack;
@@ -11,8 +8,18 @@ use spl_token::solana_program::program_pack::Pack;
 use spl_token::state::Mint as LegacyMint;
 
 // SPL Token-2022
-use spl_token_2022::state::Mint as Mint2022;
-use tracing::{info, warn};
+use spl_token_2022::extension::permanent_delegate::PermanentDelegate;
+use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use spl_token_2022::extension::transfer_hook::TransferHook;
+use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument, warn};
+
+use crate::dexscreener::{self, Pair};
+use crate::rpc_pool::RpcPool;
+
+/// `getMultipleAccounts` accepts at most this many accounts per call
+const MAX_ACCOUNTS_PER_CALL: usize = 100;
 
 #[derive(Debug)]
 pub enum MintProgramType {
@@ -22,18 +29,37 @@ pub enum MintProgramType {
 
 #[derive(Debug)]
 pub struct MintInfo {
+    #[allow(dead_code)]
     pub program: MintProgramType,
     pub decimals: u8,
     pub supply: u64,
     pub mint_authority: COption<Pubkey>,
     pub freeze_authority: COption<Pubkey>,
+    /// Token-2022 extensions, always empty for legacy SPL mints
+    pub extensions: MintExtensions,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct MintExtensions {
+    pub types: Vec<ExtensionType>,
+    /// Current (newer) transfer fee in basis points
+    pub transfer_fee_basis_points: Option<u16>,
+    pub transfer_hook_program: Option<Pubkey>,
+    pub permanent_delegate: Option<Pubkey>,
 }
 
 /// Load and parse a mint account from chain safely
-pub fn load_mint_info(rpc: &RpcClient, mint: &Pubkey) -> anyhow::Result<Option<MintInfo>> {
-    let account = match rpc.get_account(mint) {
+pub async fn load_mint_info(rpc: &RpcPool, mint: &Pubkey) -> anyhow::Result<Option<MintInfo>> {
+    let result = rpc
+        .call("getAccountInfo", |client| async move { client.get_account(mint).await })
+        .await;
+
+    let account = match result {
         Ok(acc) => acc,
         Err(e) => {
+            crate::metrics::RPC_ERRORS
+                .with_label_values(&["getAccountInfo"])
+                .inc();
             warn!("Failed to load mint account {}: {:?}", mint, e);
             return Ok(None);
         }
@@ -41,64 +67,155 @@ pub fn load_mint_info(rpc: &RpcClient, mint: &Pubkey) -> anyhow::Result<Option<M
 
     info!("Solana Account in load mint helper {:?}", account);
 
+    Ok(parse_mint_account(mint, &account))
+}
+
+/// Fetch accounts with `getMultipleAccounts`, in the order given, in as many
+/// round trips as [`MAX_ACCOUNTS_PER_CALL`] requires
+pub async fn load_accounts(rpc: &RpcPool, keys: &[Pubkey]) -> Result<Vec<Option<Account>>> {
+    let mut accounts = Vec::with_capacity(keys.len());
+    for chunk in keys.chunks(MAX_ACCOUNTS_PER_CALL) {
+        let loaded = rpc
+            .call("getMultipleAccounts", |client| async move {
+                client.get_multiple_accounts(chunk).await
+            })
+            .await
+            .map_err(|e| {
+                crate::metrics::RPC_ERRORS
+                    .with_label_values(&["getMultipleAccounts"])
+                    .inc();
+                anyhow!("Failed to load accounts {:?}: {}", chunk, e)
+            })?;
+        accounts.extend(loaded);
+    }
+    Ok(accounts)
+}
+
+/// [`load_accounts`] at an explicit commitment, e.g. `processed` for accounts created moments ago
+pub async fn load_accounts_with_commitment(
+    rpc: &RpcPool,
+    keys: &[Pubkey],
+    commitment: CommitmentConfig,
+) -> Result<Vec<Option<Account>>> {
+    rpc.call("getMultipleAccounts", |client| async move {
+        client
+            .get_multiple_accounts_with_commitment(keys, commitment)
+            .await
+            .map(|response| response.value)
+    })
+    .await
+    .map_err(|e| {
+        crate::metrics::RPC_ERRORS
+            .with_label_values(&["getMultipleAccounts"])
+            .inc();
+        anyhow!("Failed to load accounts {:?}: {}", keys, e)
+    })
+}
+
+/// Decode an SPL Token or Token-2022 mint account
+pub fn parse_mint_account(mint: &Pubkey, account: &Account) -> Option<MintInfo> {
     if account.owner == spl_token::ID {
         let mint_data = LegacyMint::unpack(&account.data);
         match mint_data {
-            Ok(mint) => Ok(Some(MintInfo {
+            Ok(mint) => Some(MintInfo {
                 program: MintProgramType::Token,
                 decimals: mint.decimals,
                 supply: mint.supply,
                 mint_authority: mint.mint_authority,
                 freeze_authority: mint.freeze_authority,
-            })),
+                extensions: MintExtensions::default(),
+            }),
             Err(e) => {
                 warn!("Failed to unpack legacy SPL mint {}: {:?}", mint, e);
-                Ok(None)
+                None
             }
         }
     } else if account.owner == spl_token_2022::ID {
-        let mint_data = spl_token_2022::state::Mint::unpack(&account.data);
-        match mint_data {
-            Ok(mint) => Ok(Some(MintInfo {
-                program: MintProgramType::Token2022,
-                decimals: mint.decimals,
-                supply: mint.supply,
-                mint_authority: mint.mint_authority,
-                freeze_authority: mint.freeze_authority,
-            })),
+        match parse_spl_token_2022_mint(&account.data) {
+            Ok(mint_info) => Some(mint_info),
             Err(e) => {
                 warn!("Failed to unpack SPL-2022 mint {}: {:?}", mint, e);
-                Ok(None)
+                None
             }
         }
     } else {
         warn!("Account {} is not a valid SPL token mint (custom program)", mint);
-        Ok(None)
+        None
     }
 }
 
 
 /// Helper to parse legacy SPL Token Mint manually
+#[allow(dead_code)]
 fn parse_spl_token_mint(data: &[u8]) -> Result<LegacyMint> {
     let mint = LegacyMint::unpack(data)
         .map_err(|e| anyhow!("Failed to unpack legacy SPL mint: {}", e))?;
     Ok(mint)
 }
 
-/// Helper to parse SPL-2022 Mint manually
-fn parse_spl_token_2022_mint(data: &[u8]) -> Result<spl_token_2022::state::Mint> {
-    let mint = spl_token_2022::state::Mint::unpack(data)
+/// Helper to parse SPL-2022 Mint including its extensions.
+/// Plain `Mint::unpack` rejects any account longer than the base mint layout.
+fn parse_spl_token_2022_mint(data: &[u8]) -> Result<MintInfo> {
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(data)
         .map_err(|e| anyhow!("Failed to unpack SPL-2022 mint: {}", e))?;
-    Ok(mint)
+
+    let types = state
+        .get_extension_types()
+        .map_err(|e| anyhow!("Failed to read SPL-2022 extensions: {}", e))?;
+
+    let extensions = MintExtensions {
+        transfer_fee_basis_points: state
+            .get_extension::<TransferFeeConfig>()
+            .ok()
+            .map(|fee| u16::from(fee.newer_transfer_fee.transfer_fee_basis_points)),
+        transfer_hook_program: state
+            .get_extension::<TransferHook>()
+            .ok()
+            .and_then(|hook| Option::<Pubkey>::from(hook.program_id)),
+        permanent_delegate: state
+            .get_extension::<PermanentDelegate>()
+            .ok()
+            .and_then(|delegate| Option::<Pubkey>::from(delegate.delegate)),
+        types,
+    };
+
+    Ok(MintInfo {
+        program: MintProgramType::Token2022,
+        decimals: state.base.decimals,
+        supply: state.base.supply,
+        mint_authority: state.base.mint_authority,
+        freeze_authority: state.base.freeze_authority,
+        extensions,
+    })
 }
 
 
-#[derive(Debug)]
+/// Timeouts, connection errors, 429 and 5xx are worth another attempt
+pub fn is_transient_http_error(error: &reqwest::Error) -> bool {
+    match error.status() {
+        Some(status) => status.as_u16() == 429 || status.is_server_error(),
+        None => error.is_timeout() || error.is_connect() || error.is_request(),
+    }
+}
+
+/// Name of tokens whose metadata could not be found
+pub const UNKNOWN_NAME: &str = "Unknown";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenInfo {
     pub name: String,
     pub symbol: String,
+    /// Off-chain metadata JSON
+    #[serde(default)]
+    pub uri: Option<String>,
+    #[serde(default)]
+    pub decimals: Option<u8>,
+    /// The token's most liquid DexScreener pair, once it has one; never cached
+    #[serde(skip)]
+    pub pair: Option<Pair>,
 }
 
+#[instrument(name = "token_info", skip_all)]
 pub async fn fetch_token_info(
 listener:&str,
     mint_address: &str,
@@ -106,46 +223,26 @@ listener:&str,
 ) -> Result<TokenInfo> {
 
 
-    let client = Client::new();
-
-    let mut name = "Unknown".to_string();
+    let mut name = UNKNOWN_NAME.to_string();
     let mut symbol = "UNK".to_string();
 
 
 
     // 3️⃣ Fallback to DexScreener
-    if name == "Unknown" {
-        let dex_url = format!(
-            "https://api.dexscreener.com/tokens/v1/{}/{}",
-            chain_id, mint_address
-        );
-        info!("Fetching dex url: {} for listener: {}", dex_url ,listener);
-
-        if let Ok(resp) = client.get(&dex_url).send().await {
-            if resp.status().is_success() {
-
-                let respo_text=resp.text().await?;
-                // info!("Dex API response: {:?}", &respo_text);
-                if let Ok(json) = serde_json::from_str::<serde_json::Value>(&respo_text) {
-                    if let Some(arr) = json.as_array() {
-                        if let Some(pair) = arr.first() {
-                            name = pair
-                                .get("baseToken")
-                                .and_then(|t| t.get("name"))
-                                .and_then(|v| v.as_str())
-                                .unwrap_or(&name)
-                                .to_string();
-
-                            symbol = pair
-                                .get("baseToken")
-                                .and_then(|t| t.get("symbol"))
-                                .and_then(|v| v.as_str())
-                                .unwrap_or(&symbol)
-                                .to_string();
-                        }
-                    }
-                }
-            }
+    info!("Fetching DexScreener pairs of {} for listener: {}", mint_address, listener);
+    let pair = match dexscreener::shared().best_pair(chain_id, mint_address).await {
+        Ok(pair) => pair,
+        Err(e) => {
+            warn!("{:#}", e);
+            None
+        }
+    };
+    if let Some(pair) = &pair {
+        if !pair.base_token.name.is_empty() {
+            name = pair.base_token.name.clone();
+        }
+        if !pair.base_token.symbol.is_empty() {
+            symbol = pair.base_token.symbol.clone();
         }
     }
 
@@ -154,5 +251,8 @@ listener:&str,
 
         name,
         symbol,
+        uri: None,
+        decimals: None,
+        pair,
     })
-}
\ No newline at end of file
+}
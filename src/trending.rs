@@ -0,0 +1,126 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use bigdecimal::{BigDecimal, FromPrimitive, Zero};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+use crate::config::TrendingConfig;
+use crate::models::Token;
+
+/// Digits kept in `change_percentage`
+const CHANGE_DIGITS: i64 = 2;
+
+/// Price samples of a token within the window, oldest first
+type PriceHistory = VecDeque<(DateTime<Utc>, BigDecimal)>;
+
+/// King-of-the-hill detection and the rolling list of tracked tokens whose
+/// price moved the most within `trending.window_secs`
+pub struct Trending {
+    koth_curve_progress: Option<BigDecimal>,
+    koth_market_cap_usd: Option<BigDecimal>,
+    window: Duration,
+    top: usize,
+    prices: Mutex<HashMap<String, PriceHistory>>,
+}
+
+/// A tracked token and its price change over the window
+#[derive(Debug, Clone, Serialize)]
+pub struct Mover {
+    pub mint: String,
+    pub symbol: Option<String>,
+    pub price_sol: BigDecimal,
+    pub change_percentage: BigDecimal,
+    pub since: DateTime<Utc>,
+}
+
+impl Trending {
+    pub fn new(config: &TrendingConfig) -> Self {
+        Self {
+            koth_curve_progress: config.koth_curve_progress.and_then(BigDecimal::from_f64),
+            koth_market_cap_usd: config.koth_market_cap_usd.and_then(BigDecimal::from_f64),
+            window: Duration::seconds(config.window_secs as i64),
+            top: config.top,
+            prices: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether `token` has reached a king-of-the-hill threshold; a token still
+    /// on its bonding curve only, graduated tokens are past the hill
+    pub fn is_king_of_the_hill(&self, token: &Token) -> bool {
+        if token.pumpswap_pool.is_some() {
+            return false;
+        }
+        let progress = matches!(
+            (&self.koth_curve_progress, &token.bonding_curve_progress),
+            (Some(min), Some(progress)) if progress >= min
+        );
+        let market_cap = matches!(
+            (&self.koth_market_cap_usd, &token.market_cap_usd),
+            (Some(min), Some(market_cap)) if market_cap >= min
+        );
+        progress || market_cap
+    }
+
+    /// Add the current price of `token` to its history
+    pub fn record(&self, token: &Token) {
+        let Some(price) = &token.price_sol else {
+            return;
+        };
+        let now = Utc::now();
+        let mut prices = self.prices.lock().expect("trending lock poisoned");
+        let samples = prices.entry(token.mint_address.clone()).or_default();
+        if samples.back().is_some_and(|(_, last)| last == price) {
+            return;
+        }
+        samples.push_back((now, price.clone()));
+        while samples
+            .front()
+            .is_some_and(|(at, _)| now - *at > self.window)
+        {
+            samples.pop_front();
+        }
+    }
+
+    /// Tokens in `tracked` with the largest price changes over the window, up or down
+    pub fn top_movers(&self, tracked: &[Token]) -> Vec<Mover> {
+        let now = Utc::now();
+        let mut prices = self.prices.lock().expect("trending lock poisoned");
+        let symbols: HashMap<&str, Option<&String>> = tracked
+            .iter()
+            .map(|token| (token.mint_address.as_str(), token.symbol.as_ref()))
+            .collect();
+        // Forget tokens no longer tracked or without a recent price
+        prices.retain(|mint, samples| {
+            while samples
+                .front()
+                .is_some_and(|(at, _)| now - *at > self.window)
+            {
+                samples.pop_front();
+            }
+            symbols.contains_key(mint.as_str()) && !samples.is_empty()
+        });
+
+        let mut movers: Vec<Mover> = prices
+            .iter()
+            .filter_map(|(mint, samples)| {
+                let (since, first) = samples.front()?;
+                let (_, last) = samples.back()?;
+                if first.is_zero() {
+                    return None;
+                }
+                Some(Mover {
+                    mint: mint.clone(),
+                    symbol: symbols.get(mint.as_str()).copied().flatten().cloned(),
+                    price_sol: last.clone(),
+                    change_percentage: ((last - first) * BigDecimal::from(100) / first)
+                        .round(CHANGE_DIGITS),
+                    since: *since,
+                })
+            })
+            .collect();
+        movers.sort_by_key(|mover| std::cmp::Reverse(mover.change_percentage.abs()));
+        movers.truncate(self.top);
+        movers
+    }
+}
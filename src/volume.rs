@@ -0,0 +1,139 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, info_span, warn, Instrument};
+
+use crate::config::VolumeConfig;
+use crate::models::{Trade, TradeActivity};
+use crate::processor::Processor;
+
+const LAMPORTS_DECIMALS: i64 = 9;
+const SHORT_WINDOW: chrono::Duration = chrono::Duration::minutes(1);
+const LONG_WINDOW: chrono::Duration = chrono::Duration::minutes(5);
+/// Digits kept in `buy_sell_ratio_5m`
+const RATIO_DIGITS: i64 = 4;
+
+/// Recent trades of tracked tokens, turned into rolling 1m / 5m activity
+#[derive(Default)]
+pub struct VolumeTracker {
+    trades: Mutex<HashMap<String, VecDeque<Trade>>>,
+}
+
+impl VolumeTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, trade: &Trade) {
+        let mut trades = self.trades.lock().expect("volume lock poisoned");
+        trades
+            .entry(trade.mint.clone())
+            .or_default()
+            .push_back(trade.clone());
+    }
+
+    /// Activity of `mint` over the windows ending at `now`, zero if it did not trade
+    pub fn activity(&self, mint: &str, now: DateTime<Utc>) -> TradeActivity {
+        let trades = self.trades.lock().expect("volume lock poisoned");
+        let recent = trades.get(mint).into_iter().flatten();
+
+        let mut short = Window::default();
+        let mut long = Window::default();
+        for trade in recent.filter(|trade| now - trade.at < LONG_WINDOW) {
+            long.add(trade);
+            if now - trade.at < SHORT_WINDOW {
+                short.add(trade);
+            }
+        }
+
+        TradeActivity {
+            volume_1m_sol: BigDecimal::new(short.lamports.into(), LAMPORTS_DECIMALS),
+            volume_5m_sol: BigDecimal::new(long.lamports.into(), LAMPORTS_DECIMALS),
+            buyers_1m: short.buyers.len() as u32,
+            buyers_5m: long.buyers.len() as u32,
+            buys_5m: long.buys,
+            sells_5m: long.sells,
+            buy_sell_ratio_5m: (long.sells > 0).then(|| {
+                (BigDecimal::from(long.buys) / BigDecimal::from(long.sells)).round(RATIO_DIGITS)
+            }),
+        }
+    }
+
+    /// Forget trades older than the longest window
+    fn prune(&self, now: DateTime<Utc>) {
+        let mut trades = self.trades.lock().expect("volume lock poisoned");
+        trades.retain(|_, recent| {
+            while recent
+                .front()
+                .is_some_and(|trade| now - trade.at >= LONG_WINDOW)
+            {
+                recent.pop_front();
+            }
+            !recent.is_empty()
+        });
+    }
+
+    /// Publish `TokenUpdated` with fresh `activity` every `interval_secs`, for
+    /// the tracked tokens whose activity changed
+    pub fn spawn_publisher(processor: Processor, config: &VolumeConfig) {
+        let interval = Duration::from_secs(config.interval_secs.max(1));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            // The first tick fires right away, nothing traded yet
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                publish(&processor).instrument(info_span!("volume")).await;
+            }
+        });
+    }
+}
+
+async fn publish(processor: &Processor) {
+    let Some(tracker) = processor.volume() else {
+        return;
+    };
+    let now = Utc::now();
+    tracker.prune(now);
+
+    let mut updated = 0;
+    for mut token in processor.tracked_tokens() {
+        let activity = tracker.activity(&token.mint_address, now);
+        // Tokens that never traded, or are still idle, have nothing new
+        if token.activity.as_ref().unwrap_or(&TradeActivity::default()) == &activity {
+            continue;
+        }
+        token.activity = Some(activity);
+        let mint = token.mint_address.clone();
+        if let Err(e) = processor.process_token_updated(token).await {
+            warn!("Failed to publish trade activity of {}: {:#}", mint, e);
+        }
+        updated += 1;
+    }
+    debug!("Published trade activity of {} tokens", updated);
+}
+
+#[derive(Default)]
+struct Window<'a> {
+    lamports: u64,
+    buyers: HashSet<&'a str>,
+    buys: u32,
+    sells: u32,
+}
+
+impl<'a> Window<'a> {
+    fn add(&mut self, trade: &'a Trade) {
+        self.lamports += trade.sol_amount;
+        if trade.is_buy {
+            self.buys += 1;
+            self.buyers.insert(&trade.wallet);
+        } else {
+            self.sells += 1;
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+
+use crate::config::{Config, WashConfig};
+use crate::funding::FundingTracer;
+use crate::models::Trade;
+use crate::rpc_pool::RpcPool;
+
+/// The detector for `config`, `None` unless `wash.enabled`
+pub fn detector(config: &Config, rpc: &Arc<RpcPool>) -> Option<Arc<WashDetector>> {
+    config
+        .wash
+        .enabled
+        .then(|| Arc::new(WashDetector::new(rpc.clone(), config)))
+}
+
+/// Collects the trades of each tracked token over its first `wash.window_secs`
+/// and measures how much of that volume went back and forth between the same
+/// hands: a wallet on both sides (self-trades), or wallets with a common funder
+/// buying and selling among themselves (circular volume).
+///
+/// Windows only close as trades come in, so a token is analysed with the
+/// first trade of any token seen after its window.
+pub struct WashDetector {
+    config: WashConfig,
+    funding: FundingTracer,
+    windows: Mutex<HashMap<String, Window>>,
+}
+
+struct Window {
+    closes_at: DateTime<Utc>,
+    trades: Vec<Trade>,
+}
+
+/// What the early trades of a token show
+#[derive(Debug)]
+pub struct WashReport {
+    /// Share of the volume that went back and forth, 0-100
+    pub score: BigDecimal,
+    pub trades: usize,
+    pub volume_lamports: u64,
+    /// Wallets that bought and sold
+    pub self_traders: usize,
+    /// Wallets sharing a funder that, together, bought and sold
+    pub related_groups: usize,
+}
+
+impl WashDetector {
+    pub fn new(rpc: Arc<RpcPool>, config: &Config) -> Self {
+        Self {
+            config: config.wash.clone(),
+            funding: FundingTracer::new(rpc, config),
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Window>> {
+        self.windows.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Add a trade of a token created at `created_at` and return the trades of
+    /// the tokens whose window has closed since, by mint
+    pub fn record(&self, trade: &Trade, created_at: DateTime<Utc>) -> Vec<(String, Vec<Trade>)> {
+        let closes_at = created_at + chrono::Duration::seconds(self.config.window_secs as i64);
+        let mut windows = self.lock();
+        if trade.at <= closes_at {
+            let window = windows.entry(trade.mint.clone()).or_insert_with(|| Window {
+                closes_at,
+                trades: Vec::new(),
+            });
+            if window.trades.len() < self.config.max_trades {
+                window.trades.push(trade.clone());
+            }
+        }
+
+        let now = Utc::now();
+        let closed: Vec<String> = windows
+            .iter()
+            .filter(|(_, window)| window.closes_at < now)
+            .map(|(mint, _)| mint.clone())
+            .collect();
+        closed
+            .into_iter()
+            .filter_map(|mint| windows.remove_entry(&mint))
+            .map(|(mint, window)| (mint, window.trades))
+            .collect()
+    }
+
+    /// Measure the volume of `trades` that went back and forth
+    pub async fn analyze(&self, trades: &[Trade]) -> WashReport {
+        let sides = sides(trades);
+        let funders = self.funders(&sides).await;
+        report(trades, &sides, &funders)
+    }
+
+    /// Funders of the `max_funding_lookups` wallets that traded the most
+    async fn funders(&self, sides: &HashMap<&str, (u64, u64)>) -> HashMap<String, Option<String>> {
+        let mut wallets: Vec<(&str, u64)> = sides
+            .iter()
+            .map(|(wallet, (bought, sold))| (*wallet, bought + sold))
+            .collect();
+        wallets.sort_by_key(|(_, volume)| Reverse(*volume));
+        let wallets: Vec<String> = wallets
+            .into_iter()
+            .map(|(wallet, _)| wallet.to_string())
+            .collect();
+        self.funding
+            .funders(wallets, self.config.max_funding_lookups)
+            .await
+    }
+}
+
+/// Lamports bought and sold by each wallet
+fn sides(trades: &[Trade]) -> HashMap<&str, (u64, u64)> {
+    let mut sides: HashMap<&str, (u64, u64)> = HashMap::new();
+    for trade in trades {
+        let (bought, sold) = sides.entry(trade.wallet.as_str()).or_default();
+        if trade.is_buy {
+            *bought += trade.sol_amount;
+        } else {
+            *sold += trade.sol_amount;
+        }
+    }
+    sides
+}
+
+/// How much of the volume of `trades` went back and forth between a wallet
+/// and itself, or wallets with the same funder
+fn report(
+    trades: &[Trade],
+    sides: &HashMap<&str, (u64, u64)>,
+    funders: &HashMap<String, Option<String>>,
+) -> WashReport {
+    // A trader funding other traders heads their group, the others join
+    // their funder's or stand alone
+    let heads: HashSet<&str> = funders.values().flatten().map(String::as_str).collect();
+    let mut groups: HashMap<&str, (usize, u64, u64)> = HashMap::new();
+    for (wallet, (bought, sold)) in sides {
+        let head = if heads.contains(wallet) {
+            wallet
+        } else {
+            funders
+                .get(*wallet)
+                .and_then(Option::as_deref)
+                .unwrap_or(wallet)
+        };
+        let group = groups.entry(head).or_default();
+        group.0 += 1;
+        group.1 += bought;
+        group.2 += sold;
+    }
+
+    let volume: u64 = trades.iter().map(|trade| trade.sol_amount).sum();
+    // What a group sold back of what it bought, both legs counted
+    let wash: u64 = groups
+        .values()
+        .map(|(_, bought, sold)| 2 * bought.min(sold))
+        .sum();
+    let score = if volume == 0 {
+        BigDecimal::from(0)
+    } else {
+        (BigDecimal::from(wash) * BigDecimal::from(100) / BigDecimal::from(volume)).round(2)
+    };
+
+    WashReport {
+        score,
+        trades: trades.len(),
+        volume_lamports: volume,
+        self_traders: sides
+            .values()
+            .filter(|(bought, sold)| *bought > 0 && *sold > 0)
+            .count(),
+        related_groups: groups
+            .values()
+            .filter(|(wallets, bought, sold)| *wallets > 1 && *bought > 0 && *sold > 0)
+            .count(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+    fn trade(wallet: &str, is_buy: bool, sol: u64) -> Trade {
+        Trade {
+            mint: "mint".to_string(),
+            wallet: wallet.to_string(),
+            sol_amount: sol * LAMPORTS_PER_SOL,
+            token_amount: sol * 1_000_000,
+            is_buy,
+            at: Utc::now(),
+            router: None,
+        }
+    }
+
+    fn analyze(trades: &[Trade], funders: &[(&str, &str)]) -> WashReport {
+        let funders = funders
+            .iter()
+            .map(|(wallet, funder)| (wallet.to_string(), Some(funder.to_string())))
+            .collect();
+        report(trades, &sides(trades), &funders)
+    }
+
+    #[test]
+    fn self_trader_volume_is_wash() {
+        let trades = [
+            trade("alice", true, 1),
+            trade("bob", true, 2),
+            trade("alice", false, 1),
+        ];
+        let report = analyze(&trades, &[]);
+
+        assert_eq!(report.score, BigDecimal::from(50));
+        assert_eq!(report.trades, 3);
+        assert_eq!(report.volume_lamports, 4 * LAMPORTS_PER_SOL);
+        assert_eq!(report.self_traders, 1);
+        assert_eq!(report.related_groups, 0);
+    }
+
+    #[test]
+    fn wallets_with_a_shared_funder_trade_as_one() {
+        let trades = [
+            trade("alice", true, 1),
+            trade("bob", false, 1),
+            trade("carol", true, 2),
+        ];
+        let report = analyze(&trades, &[("alice", "funder"), ("bob", "funder")]);
+
+        assert_eq!(report.score, BigDecimal::from(50));
+        assert_eq!(report.self_traders, 0);
+        assert_eq!(report.related_groups, 1);
+    }
+
+    #[test]
+    fn trader_funding_others_heads_their_group() {
+        let trades = [trade("alice", true, 3), trade("bob", false, 1)];
+        let report = analyze(&trades, &[("bob", "alice")]);
+
+        assert_eq!(report.score, BigDecimal::from(50));
+        assert_eq!(report.related_groups, 1);
+    }
+
+    #[test]
+    fn clean_volume_scores_zero() {
+        let trades = [
+            trade("alice", true, 1),
+            trade("bob", true, 2),
+            trade("carol", false, 1),
+        ];
+        let report = analyze(&trades, &[("alice", "exchange"), ("carol", "other")]);
+
+        assert_eq!(report.score, BigDecimal::from(0));
+        assert_eq!(report.self_traders, 0);
+        assert_eq!(report.related_groups, 0);
+    }
+
+    #[test]
+    fn no_trades_score_zero() {
+        let report = analyze(&[], &[]);
+        assert_eq!(report.score, BigDecimal::from(0));
+        assert_eq!(report.volume_lamports, 0);
+    }
+}